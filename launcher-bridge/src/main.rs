@@ -64,6 +64,9 @@ struct BridgeCredentials {
     server_list_url: String,
     acts_map: HashMap<String, String>,
     pages_map: HashMap<String, String>,
+    /// Appended to the Tera.exe command line after `-LANGUAGEEXT`.
+    #[serde(default)]
+    extra_args: Vec<String>,
 }
 
 // ─── Global state ─────────────────────────────────────────────────────────────
@@ -556,6 +559,7 @@ fn main() {
     *SERVER_LIST_URL.write().unwrap() = creds.server_list_url;
     *PAGES_MAP.write().unwrap() = creds.pages_map;
     *ACTS_MAP.write().unwrap() = creds.acts_map;
+    let extra_args = creds.extra_args;
 
     // Set up server list channel
     let (tx, mut rx) = mpsc::channel::<(WPARAM, usize)>(32);
@@ -588,6 +592,7 @@ fn main() {
 
         let mut child = match Command::new(&game_path)
             .arg(format!("-LANGUAGEEXT={}", game_lang))
+            .args(&extra_args)
             .spawn()
         {
             Ok(c) => c,
@@ -1,18 +1,18 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
 // Standard library imports
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
 use std::env;
 use std::fs::{self, File};
 use std::io::{Read, Write};
 use std::path::{Path, PathBuf};
 use std::sync::{Arc, RwLock};
-use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
 use std::time::{Duration, Instant, SystemTime};
 
 // Third-party imports
 use dotenv::dotenv;
-use log::{LevelFilter, error, info};
+use log::{LevelFilter, error, info, warn};
 use tokio::sync::{watch, Mutex, mpsc};
 use tokio::io::AsyncWriteExt;
 use rayon::prelude::*;
@@ -21,13 +21,14 @@ use serde::{Deserialize, Serialize};
 use serde_json::{json};
 use tauri::{Manager};
 use tauri::api::dialog::FileDialogBuilder;
-use teralib::{get_game_status_receiver, run_game, reset_global_state, get_last_exit_info, get_last_crash_details, get_last_game_stderr};
-use teralib::config::get_config_value;
+use teralib::{get_game_status_receiver, run_game_with_env, reset_global_state, get_last_exit_info, get_last_crash_details, get_last_game_stderr, get_game_pid, terminate_game as terminate_game_process, TerminationOutcome, TerminationReport, run_additional_client, get_session_pid, terminate_session, is_elevated, relaunch_elevated, ProcessPriority, set_process_priority, set_process_affinity, has_visible_window, focus_process_window, set_capture_output, set_http_client, get_last_game_output, find_external_game_process};
+use teralib::config::{get_config_json, get_config_list, get_config_value, get_optional_config_value};
 use reqwest::Client;
 use lazy_static::lazy_static;
 use ini::Ini;
 use sha2::{Sha256, Digest};
 use futures_util::StreamExt;
+use futures_util::stream::FuturesUnordered;
 use indicatif::{ProgressBar, ProgressStyle};
 use walkdir::WalkDir;
 use reqwest::cookie::Jar;
@@ -55,8 +56,6 @@ lazy_static! {
 
   static ref SIGNUP_SESSION_CLIENT: Mutex<Option<Client>> = Mutex::new(None);
 
-  static ref LAUNCHER_BASE_URL: String = get_config_value("LAUNCHER_ACTION_URL");
-
   static ref GLOBAL_ACTS_MAP: RwLock<HashMap<String, String>> = RwLock::new(HashMap::new());
   static ref GLOBAL_PAGES_MAP: RwLock<HashMap<String, String>> = RwLock::new(HashMap::new());
 }
@@ -188,7 +187,188 @@ struct FileInfo {
   path: String,
   hash: String,
   size: u64,
+  /// Empty in local snapshots from `get_local_manifest`.
+  #[serde(default, skip_serializing_if = "String::is_empty")]
   url: String,
+  /// Modification time (unix seconds) applied to the file after download.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  mtime: Option<u64>,
+  /// Download order; lower values are fetched first. See `priority-rules.txt`.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  priority: Option<u32>,
+  /// Per-range hashes for large files, so a corrupted range can be fetched
+  /// again on its own.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  chunks: Option<Vec<FileChunk>>,
+  /// Set by the update check when the local copy differs from both the server
+  /// and the last verified version, i.e. the player changed it (mods, translations).
+  #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+  locally_modified: bool,
+  /// Algorithm that produced `hash` and the chunk hashes.
+  #[serde(default, skip_serializing_if = "HashAlgorithm::is_sha256")]
+  algo: HashAlgorithm,
+  /// xxh3 of the file, used by the update check to skip the full hash when it
+  /// matches. Downloads are always verified against `hash`.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  quick_hash: Option<String>,
+  /// Set by the update check for chunked files: bytes in the chunks that
+  /// differ locally, i.e. what the download will actually fetch.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  changed_bytes: Option<u64>,
+  /// Set by the update check: why the file needs updating.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  reason: Option<UpdateReason>,
+  /// Set by the update check when the local copy was hashed.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  local_hash: Option<String>,
+  /// Optional content the player can leave out (a voice pack, HD textures).
+  /// See `content-groups.txt`.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  group: Option<String>,
+  /// Set by the update check for files in `protected_files`: out of date, but
+  /// left alone by downloads unless `override_protected` is passed.
+  #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+  protected: bool,
+  /// Attributes applied after the file is downloaded and verified.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  attributes: Option<FileAttributes>,
+}
+
+impl FileInfo {
+  /// Bytes the update is expected to transfer for this file.
+  fn transfer_size(&self) -> u64 {
+    self.changed_bytes.unwrap_or(self.size)
+  }
+
+  /// Records why the update check queued the file. Locally modified files
+  /// are reported as such whatever the mismatch was.
+  fn pending_because(mut self, reason: UpdateReason, local_hash: Option<String>) -> Self {
+    self.reason = Some(if self.locally_modified { UpdateReason::LocallyModified } else { reason });
+    self.local_hash = local_hash;
+    self
+  }
+
+  /// Queues an up-to-date file whose attributes differ; nothing is downloaded.
+  fn attributes_only(mut self) -> Self {
+    self.changed_bytes = Some(0);
+    self.reason = Some(UpdateReason::AttributeMismatch);
+    self
+  }
+}
+
+/// File attributes recorded by `generate_hash_file`: the read-only flag, and
+/// the permission bits outside Windows. Only files with something notable
+/// (read-only, or executable) get them, and missing fields are left as is.
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+struct FileAttributes {
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  readonly: Option<bool>,
+  /// Unix permission bits, e.g. `0o755`. Ignored on Windows.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  mode: Option<u32>,
+}
+
+impl FileAttributes {
+  #[cfg(unix)]
+  fn notable(metadata: &fs::Metadata) -> Option<Self> {
+    use std::os::unix::fs::PermissionsExt;
+    let mode = metadata.permissions().mode() & 0o7777;
+    let readonly = metadata.permissions().readonly();
+    (readonly || mode & 0o111 != 0).then_some(FileAttributes {
+      readonly: readonly.then_some(true),
+      mode: Some(mode),
+    })
+  }
+
+  #[cfg(not(unix))]
+  fn notable(metadata: &fs::Metadata) -> Option<Self> {
+    metadata.permissions().readonly().then_some(FileAttributes { readonly: Some(true), mode: None })
+  }
+
+  fn matches(&self, metadata: &fs::Metadata) -> bool {
+    let permissions = metadata.permissions();
+    #[cfg(unix)]
+    {
+      use std::os::unix::fs::PermissionsExt;
+      if let Some(mode) = self.mode {
+        return permissions.mode() & 0o7777 == mode;
+      }
+    }
+    self.readonly.is_none_or(|readonly| permissions.readonly() == readonly)
+  }
+
+  fn apply(&self, path: &Path) -> Result<(), String> {
+    let mut permissions = fs::metadata(path).map_err(|e| e.to_string())?.permissions();
+    #[cfg(unix)]
+    {
+      use std::os::unix::fs::PermissionsExt;
+      if let Some(mode) = self.mode {
+        permissions.set_mode(mode);
+        return fs::set_permissions(path, permissions).map_err(|e| e.to_string());
+      }
+    }
+    if let Some(readonly) = self.readonly {
+      permissions.set_readonly(readonly);
+      fs::set_permissions(path, permissions).map_err(|e| e.to_string())?;
+    }
+    Ok(())
+  }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+enum UpdateReason {
+  Missing,
+  /// The file exists but couldn't be read.
+  Unreadable,
+  SizeMismatch,
+  HashMismatch,
+  LocallyModified,
+  /// Contents match, only the manifest `attributes` differ; fixed without a download.
+  AttributeMismatch,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+enum HashAlgorithm {
+  #[default]
+  Sha256,
+  Blake3,
+}
+
+impl HashAlgorithm {
+  fn is_sha256(&self) -> bool {
+    *self == HashAlgorithm::Sha256
+  }
+
+  fn parse(name: &str) -> Result<Self, String> {
+    match name.to_ascii_lowercase().as_str() {
+      "sha256" => Ok(HashAlgorithm::Sha256),
+      "blake3" => Ok(HashAlgorithm::Blake3),
+      other => Err(format!("Unsupported hash algorithm: {}", other)),
+    }
+  }
+
+  /// Algorithm for one manifest entry: its own `algo`, else the manifest's,
+  /// else sha256 for manifests written before the field existed.
+  fn for_entry(manifest_default: HashAlgorithm, entry: &serde_json::Value) -> Self {
+    entry["algo"].as_str()
+      .and_then(|name| HashAlgorithm::parse(name).ok())
+      .unwrap_or(manifest_default)
+  }
+
+  fn for_manifest(manifest: &serde_json::Value) -> Self {
+    manifest["algo"].as_str()
+      .and_then(|name| HashAlgorithm::parse(name).ok())
+      .unwrap_or_default()
+  }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+struct FileChunk {
+  offset: u64,
+  size: u64,
+  hash: String,
 }
 
 #[derive(Clone, Serialize)]
@@ -201,6 +381,10 @@ struct ProgressPayload {
   total_files: usize,
   elapsed_time: f64,
   current_file_index: usize,
+  /// True when the file was copied from a local source instead of downloaded.
+  copied_locally: bool,
+  /// Number of parallel transfers the session is using.
+  concurrency: usize,
 }
 
 #[derive(Clone, Serialize)]
@@ -211,6 +395,27 @@ struct FileCheckProgress {
   total_files: usize,
   elapsed_time: f64,
   files_to_update: usize,
+  /// Files hashed and found to match the server.
+  files_ok: usize,
+  /// Files accepted from the hash cache without reading them.
+  files_cached: usize,
+  /// Manifest entries left out by ignore rules or symlink handling.
+  files_ignored: usize,
+  /// Bytes read while hashing local files.
+  bytes_checked: u64,
+}
+
+/// Running totals of a file check behind the counters in `FileCheckProgress`.
+#[derive(Default)]
+struct FileCheckCounters {
+  ok: AtomicUsize,
+  cached: AtomicUsize,
+  bytes_checked: AtomicU64,
+  /// Files queued for update and the bytes they will transfer.
+  pending: AtomicUsize,
+  pending_size: AtomicU64,
+  quick_resolved: AtomicUsize,
+  no_mtime: AtomicUsize,
 }
 
 #[derive(Debug, Deserialize)]
@@ -228,21 +433,661 @@ struct LauncherUrls {
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 struct CachedFileInfo {
+  /// Hash of the file as it was at `last_modified`.
   hash: String,
   last_modified: SystemTime,
+  /// Entries written before this field existed were all sha256.
+  #[serde(default)]
+  algo: HashAlgorithm,
+  /// Last hash that matched the server, i.e. the version installed by the
+  /// last successful update. Differs from `hash` once the file is edited.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  verified_hash: Option<String>,
+}
+
+impl CachedFileInfo {
+  /// An entry for a file that matches the server.
+  fn verified(hash: String, last_modified: SystemTime, algo: HashAlgorithm) -> Self {
+    CachedFileInfo { verified_hash: Some(hash.clone()), hash, last_modified, algo }
+  }
+
+  /// The version to compare against when deciding whether the player changed
+  /// the file. Entries written before `verified_hash` existed only kept
+  /// verified hashes.
+  fn last_verified_hash(&self) -> &str {
+    self.verified_hash.as_deref().unwrap_or(&self.hash)
+  }
 }
 
 struct GameState {
-  status_receiver: Arc<Mutex<watch::Receiver<bool>>>,
+  status_receiver: watch::Receiver<bool>,
   is_launching: Arc<Mutex<bool>>,
+  phase: Arc<Mutex<GamePhase>>,
+  current_process: Arc<Mutex<Option<GameProcess>>>,
+  /// Account the first client was launched with.
+  primary_account: Arc<Mutex<String>>,
+  /// UUID of the first client's latest launch, sent with its events so the
+  /// UI can drop those of an earlier launch. Kept after the game exits.
+  launch_id: Arc<Mutex<Option<String>>>,
+  /// The last `MAX_GAME_SESSION_RECORDS` finished sessions, oldest first,
+  /// mirrored in game_sessions.json.
+  recent_sessions: Arc<Mutex<VecDeque<GameSessionRecord>>>,
+  /// When the first client's launch started; cleared once its process
+  /// exists. Watched by `launch_watchdog`.
+  launch_started_at: Arc<Mutex<Option<Instant>>>,
+  /// Clients started next to the first one with `allow_multiple_clients`,
+  /// by session id. The first client is always `PRIMARY_SESSION_ID`.
+  extra_sessions: Arc<Mutex<HashMap<u64, GameSession>>>,
+  /// The `GameRunning` operation lock, shared by all running clients so it
+  /// is released when the last one exits.
+  game_operation: Arc<Mutex<std::sync::Weak<OperationGuard>>>,
+}
+
+const PRIMARY_SESSION_ID: u64 = 0;
+static NEXT_SESSION_ID: AtomicU64 = AtomicU64::new(PRIMARY_SESSION_ID + 1);
+
+struct GameSession {
+  account: String,
+  launch_id: String,
+  phase: GamePhase,
+  started_at: SystemTime,
+}
+
+#[derive(Debug, Serialize)]
+struct GameSessionInfo {
+  session_id: u64,
+  launch_id: Option<String>,
+  account: String,
+  pid: Option<u32>,
+  phase: GamePhase,
+  /// Unix seconds.
+  started_at: u64,
+  elapsed_seconds: u64,
+}
+
+/// The game process of the current session, recorded when it starts running.
+struct GameProcess {
+  /// Filled in from teralib once the process is spawned; the running flag
+  /// is raised slightly earlier on Windows.
+  pid: Option<u32>,
+  started_at: SystemTime,
+}
+
+#[derive(Debug, Serialize)]
+struct GameProcessInfo {
+  pid: Option<u32>,
+  /// Unix seconds.
+  started_at: u64,
+  elapsed_seconds: u64,
+}
+
+impl GameState {
+  fn new(status_receiver: watch::Receiver<bool>) -> Self {
+    GameState {
+      status_receiver,
+      is_launching: Arc::new(Mutex::new(false)),
+      phase: Arc::new(Mutex::new(GamePhase::Idle)),
+      current_process: Arc::new(Mutex::new(None)),
+      primary_account: Arc::new(Mutex::new(String::new())),
+      launch_id: Arc::new(Mutex::new(None)),
+      launch_started_at: Arc::new(Mutex::new(None)),
+      recent_sessions: Arc::new(Mutex::new(VecDeque::new())),
+      extra_sessions: Arc::new(Mutex::new(HashMap::new())),
+      game_operation: Arc::new(Mutex::new(std::sync::Weak::new())),
+    }
+  }
+
+  /// Claims a launch: checks that nothing is launching or running, or that
+  /// another client may start next to it, and sets the launching flag. The
+  /// checks and the flag happen under one lock so a double-click on Play
+  /// can't start two launches, but the lock is released on return so the
+  /// slow checks that follow don't hold it. Returns whether this is an
+  /// additional client, which leaves the first client's flag alone.
+  async fn reserve_launch(&self) -> Result<bool, String> {
+    let mut is_launching = self.is_launching.lock().await;
+    let phase = *self.phase.lock().await;
+    if matches!(phase, GamePhase::RunningExternal { .. }) {
+      return Err("The game is already running (started outside the launcher)".to_string());
+    }
+    let running = *is_launching || self.is_game_running();
+    // With `allow_multiple_clients`, Play next to a running game starts
+    // another client instead of failing.
+    let additional = running && multiple_clients_allowed();
+    if *is_launching && (!additional || phase == GamePhase::Launching) {
+      println!("handle_launch_game: Already launching");
+      return Err("Game is already launching".to_string());
+    }
+    if running && !additional {
+      println!("handle_launch_game: Game already running");
+      return Err("Game is already running".to_string());
+    }
+    if !additional {
+      *is_launching = true;
+    }
+    Ok(additional)
+  }
+
+  /// A receiver of teralib's running flag for a new listener. Each listener
+  /// needs its own clone so `changed()` tracks what that listener has seen.
+  fn subscribe(&self) -> watch::Receiver<bool> {
+    self.status_receiver.clone()
+  }
+
+  fn is_game_running(&self) -> bool {
+    *self.status_receiver.borrow()
+  }
+
+  /// The shared `GameRunning` lock, taken if no client holds it yet.
+  async fn game_operation(&self, app_handle: &tauri::AppHandle) -> Result<Arc<OperationGuard>, String> {
+    let mut shared = self.game_operation.lock().await;
+    if let Some(guard) = shared.upgrade() {
+      return Ok(guard);
+    }
+    let guard = Arc::new(begin_operation(app_handle, LauncherOperation::GameRunning)?);
+    *shared = Arc::downgrade(&guard);
+    Ok(guard)
+  }
+}
+
+/// Steps of a launch, reported through `launch_stage` events so the UI can
+/// tell a slow launch from a stuck one.
+#[derive(Debug, Serialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+enum LaunchStage {
+  ValidatingAuth,
+  CheckingFiles,
+  SpawningProcess,
+  /// The process exists but hasn't shown its window yet.
+  WaitingForWindow,
+  InGame,
+}
+
+/// Emits `launch_stage` with the stage and when it began (Unix ms).
+fn emit_launch_stage(app_handle: &tauri::AppHandle, stage: LaunchStage) {
+  let timestamp = SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap_or_default().as_millis() as u64;
+  info!("Launch stage: {:?}", stage);
+  if let Err(e) = app_handle.emit_all("launch_stage", json!({ "stage": stage, "timestamp": timestamp })) {
+    error!("Failed to emit launch_stage event: {:?}", e);
+  }
+}
+
+/// Follows a launch from the process spawning to its window showing. Where
+/// windows can't be looked up, the game counts as in game once spawned.
+async fn track_launch_stages(app_handle: tauri::AppHandle) {
+  let pid = loop {
+    if let Some(pid) = get_game_pid() {
+      break pid;
+    }
+    tokio::time::sleep(Duration::from_millis(250)).await;
+  };
+  emit_launch_stage(&app_handle, LaunchStage::WaitingForWindow);
+  loop {
+    match has_visible_window(pid) {
+      Some(false) if get_game_pid() == Some(pid) => {}
+      Some(false) => return,
+      Some(true) | None => break,
+    }
+    tokio::time::sleep(Duration::from_millis(250)).await;
+  }
+  emit_launch_stage(&app_handle, LaunchStage::InGame);
+  if minimize_on_launch_enabled() {
+    minimize_launcher_window(&app_handle);
+  }
+}
+
+/// Whether the game's stdout and stderr are streamed into the launcher log
+/// (`capture_game_output`, on by default).
+fn capture_game_output_enabled() -> bool {
+  get_launcher_setting("capture_game_output")
+    .map(|v| v.eq_ignore_ascii_case("true") || v == "1")
+    .unwrap_or(true)
+}
+
+#[tauri::command]
+fn get_capture_game_output() -> bool {
+  capture_game_output_enabled()
+}
+
+/// Applies from the next launch.
+#[tauri::command]
+fn set_capture_game_output(enabled: bool) -> Result<(), String> {
+  save_launcher_setting("capture_game_output", &enabled.to_string())
+}
+
+/// Whether the launcher window is minimized once the game is up, and brought
+/// back when it exits. Off by default.
+fn minimize_on_launch_enabled() -> bool {
+  get_launcher_setting("minimize_on_launch")
+    .map(|v| v.eq_ignore_ascii_case("true") || v == "1")
+    .unwrap_or(false)
+}
+
+/// Set while the launcher window is minimized for the game, so only a window
+/// minimized by the launcher gets restored.
+static LAUNCHER_MINIMIZED_FOR_GAME: AtomicBool = AtomicBool::new(false);
+
+/// Minimized rather than hidden: the launcher has no tray icon to bring a
+/// hidden window back from.
+fn minimize_launcher_window(app_handle: &tauri::AppHandle) {
+  let Some(window) = app_handle.get_window("main") else { return };
+  match window.minimize() {
+    Ok(()) => {
+      LAUNCHER_MINIMIZED_FOR_GAME.store(true, Ordering::SeqCst);
+      info!("Launcher window minimized while the game runs");
+    }
+    Err(e) => error!("Failed to minimize the launcher window: {:?}", e),
+  }
+}
+
+/// Restores and focuses the launcher window if it was minimized for the game.
+fn restore_launcher_window<R: tauri::Runtime>(app_handle: &tauri::AppHandle<R>) {
+  if !LAUNCHER_MINIMIZED_FOR_GAME.swap(false, Ordering::SeqCst) {
+    return;
+  }
+  let Some(window) = app_handle.get_window("main") else { return };
+  if let Err(e) = window.unminimize().and_then(|_| window.set_focus()) {
+    error!("Failed to restore the launcher window: {:?}", e);
+  }
+}
+
+#[tauri::command]
+fn get_minimize_on_launch() -> bool {
+  minimize_on_launch_enabled()
+}
+
+/// Saves `minimize_on_launch` and applies it right away if the game is
+/// already running.
+#[tauri::command]
+async fn set_minimize_on_launch(
+  app_handle: tauri::AppHandle,
+  state: tauri::State<'_, GameState>,
+  enabled: bool,
+) -> Result<(), String> {
+  save_launcher_setting("minimize_on_launch", &enabled.to_string())?;
+  if !enabled {
+    restore_launcher_window(&app_handle);
+  } else if *state.phase.lock().await == GamePhase::Running {
+    minimize_launcher_window(&app_handle);
+  }
+  Ok(())
+}
+
+const DEFAULT_LAUNCH_TIMEOUT_SECS: u64 = 120;
+
+/// Seconds a launch may take before the game process exists, from the
+/// `launch_timeout_secs` setting; 0 turns the watchdog off.
+fn launch_timeout() -> Duration {
+  Duration::from_secs(
+    get_launcher_setting("launch_timeout_secs")
+      .and_then(|v| v.parse::<u64>().ok())
+      .unwrap_or(DEFAULT_LAUNCH_TIMEOUT_SECS),
+  )
+}
+
+/// Resolves with the timeout once the launch recorded in `launch_started_at`
+/// has run past it with no game process. Never resolves if the process
+/// shows up or the launch state is reset first.
+async fn launch_watchdog(launch_started_at: &Mutex<Option<Instant>>) -> Duration {
+  let timeout = launch_timeout();
+  if timeout.is_zero() {
+    return std::future::pending().await;
+  }
+  loop {
+    tokio::time::sleep(Duration::from_secs(1)).await;
+    let Some(started_at) = *launch_started_at.lock().await else {
+      return std::future::pending().await;
+    };
+    if get_game_pid().is_some() {
+      *launch_started_at.lock().await = None;
+      return std::future::pending().await;
+    }
+    if started_at.elapsed() >= timeout {
+      return timeout;
+    }
+  }
+}
+
+/// Whether Play starts another client while one is already running, for
+/// players with several accounts. Off by default.
+fn multiple_clients_allowed() -> bool {
+  get_launcher_setting("allow_multiple_clients")
+    .map(|v| v.eq_ignore_ascii_case("true") || v == "1")
+    .unwrap_or(false)
+}
+
+/// Where the game is in its lifecycle, finer-grained than `get_game_status`.
+#[derive(Debug, Serialize, Clone, Copy, PartialEq)]
+#[serde(tag = "state", rename_all = "snake_case")]
+enum GamePhase {
+  Idle,
+  Launching,
+  Running,
+  Exiting,
+  /// The game exited abnormally or failed to start; `exit_code` is `None`
+  /// when no process exit code is available.
+  Crashed { exit_code: Option<i32> },
+  /// A game started outside the launcher is running from the game
+  /// directory. See `watch_external_game`.
+  RunningExternal { pid: u32 },
+}
+
+impl GamePhase {
+  /// The old boolean status: whether the game is starting or playing.
+  fn is_active(self) -> bool {
+    matches!(self, GamePhase::Launching | GamePhase::Running | GamePhase::RunningExternal { .. })
+  }
+}
+
+const EXTERNAL_GAME_POLL_SECS: u64 = 5;
+
+/// Notices a Tera.exe started outside the launcher, e.g. from another
+/// shortcut, and treats it as the running game so Play and updates stay
+/// locked until it exits. Polls only while the launcher window is shown and
+/// none of its own clients run, or while such a game is being tracked. A
+/// result has to be seen twice in a row to count.
+async fn watch_external_game(app_handle: tauri::AppHandle) {
+  let game_state = app_handle.state::<GameState>();
+  let mut tracked: Option<(u32, Option<Arc<OperationGuard>>)> = None;
+  let mut last_seen: Option<Option<u32>> = None;
+  let mut tick = tokio::time::interval(Duration::from_secs(EXTERNAL_GAME_POLL_SECS));
+  loop {
+    tick.tick().await;
+    if tracked.is_none() {
+      let window_shown = app_handle.get_window("main")
+        .map(|w| w.is_visible().unwrap_or(false) && !w.is_minimized().unwrap_or(false))
+        .unwrap_or(false);
+      let own_game = *game_state.is_launching.lock().await
+        || game_state.phase.lock().await.is_active()
+        || !game_state.extra_sessions.lock().await.is_empty();
+      if !window_shown || own_game {
+        last_seen = None;
+        continue;
+      }
+    }
+
+    let Ok(game_path) = get_game_path() else { continue };
+    let found = tokio::task::spawn_blocking(move || find_external_game_process(&game_path))
+      .await
+      .unwrap_or(None);
+    if last_seen != Some(found) {
+      last_seen = Some(found);
+      continue;
+    }
+
+    match (found, tracked.is_some()) {
+      (Some(pid), false) => {
+        info!("Game started outside the launcher is running (PID {})", pid);
+        let operation = match game_state.game_operation(&app_handle).await {
+          Ok(guard) => Some(guard),
+          Err(e) => {
+            error!("Game started outside the launcher while {}", e);
+            None
+          }
+        };
+        tracked = Some((pid, operation));
+        *game_state.launch_id.lock().await = None;
+        set_game_phase(&app_handle, &game_state.phase, GamePhase::RunningExternal { pid }).await;
+      }
+      (None, true) => {
+        info!("Game started outside the launcher has exited");
+        tracked = None;
+        if matches!(*game_state.phase.lock().await, GamePhase::RunningExternal { .. }) {
+          set_game_phase(&app_handle, &game_state.phase, GamePhase::Idle).await;
+        }
+      }
+      _ => {}
+    }
+  }
+}
+
+/// Follows teralib's running flag for the lifetime of the app, so the frontend
+/// hears about every transition without polling. While a launch is in
+/// progress its task owns the exit phases; otherwise, e.g. when the game
+/// outlives a launcher reload or is closed externally, this sets them.
+async fn watch_game_status(app_handle: tauri::AppHandle) {
+  let game_state = app_handle.state::<GameState>();
+  let mut status_receiver = game_state.subscribe();
+  while status_receiver.changed().await.is_ok() {
+    let running = *status_receiver.borrow_and_update();
+    *game_state.current_process.lock().await = running.then(|| GameProcess {
+      pid: get_game_pid(),
+      started_at: SystemTime::now(),
+    });
+    let phase = *game_state.phase.lock().await;
+    if running && phase != GamePhase::Running {
+      set_game_phase(&app_handle, &game_state.phase, GamePhase::Running).await;
+    } else if !running && phase.is_active() && !*game_state.is_launching.lock().await {
+      set_game_phase(&app_handle, &game_state.phase, GamePhase::Idle).await;
+    }
+  }
+}
+
+/// Records the first client's new phase and emits `game_status_changed`.
+async fn set_game_phase<R: tauri::Runtime>(app_handle: &tauri::AppHandle<R>, phase_state: &Mutex<GamePhase>, phase: GamePhase) {
+  *phase_state.lock().await = phase;
+  let launch_id = app_handle.state::<GameState>().launch_id.lock().await.clone();
+  emit_game_status(app_handle, PRIMARY_SESSION_ID, launch_id.as_deref(), phase);
+}
+
+/// Records an extra client's new phase and emits `game_status_changed`.
+async fn set_session_phase(app_handle: &tauri::AppHandle, sessions: &Mutex<HashMap<u64, GameSession>>, session_id: u64, phase: GamePhase) {
+  let mut sessions = sessions.lock().await;
+  let launch_id = sessions.get_mut(&session_id).map(|session| {
+    session.phase = phase;
+    session.launch_id.clone()
+  });
+  drop(sessions);
+  emit_game_status(app_handle, session_id, launch_id.as_deref(), phase);
+}
+
+/// `game_status_changed` carries the session's boolean status, its phase,
+/// its id and the id of the launch it belongs to. It is also triggered for
+/// Rust listeners, which `emit_all` doesn't reach.
+fn emit_game_status<R: tauri::Runtime>(app_handle: &tauri::AppHandle<R>, session_id: u64, launch_id: Option<&str>, phase: GamePhase) {
+  let payload = json!({ "running": phase.is_active(), "phase": phase, "session_id": session_id, "launch_id": launch_id });
+  app_handle.trigger_global("game_status_changed", Some(payload.to_string()));
+  if let Err(e) = app_handle.emit_all("game_status_changed", payload) {
+    error!("Failed to emit game_status_changed event: {:?}", e);
+  }
+}
+
+/// Result of the last update check, kept on the Rust side so the file list
+/// doesn't have to cross the IPC boundary and a follow-up call doesn't scan
+/// the game directory again.
+struct CachedUpdate {
+  files: Vec<FileInfo>,
+  total_bytes: u64,
+  manifest_version: Option<String>,
+  checked_at: u64,
+}
+
+impl CachedUpdate {
+  fn new(files: Vec<FileInfo>) -> Self {
+    CachedUpdate {
+      total_bytes: files.iter().filter(|f| !f.protected).map(|f| f.transfer_size()).sum(),
+      manifest_version: CURRENT_MANIFEST_VERSION.read().unwrap().clone(),
+      checked_at: SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap_or_default().as_secs(),
+      files,
+    }
+  }
+
+  /// Whether the result is recent enough for `check_update_required` to
+  /// answer from it, per the `check_cache_ttl` setting (seconds, default 60).
+  fn is_fresh(&self) -> bool {
+    let ttl = get_launcher_setting("check_cache_ttl")
+      .and_then(|v| v.parse::<u64>().ok())
+      .unwrap_or(DEFAULT_CHECK_CACHE_TTL_SECS);
+    let now = SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap_or_default().as_secs();
+    now.saturating_sub(self.checked_at) < ttl
+  }
+}
+
+const DEFAULT_CHECK_CACHE_TTL_SECS: u64 = 60;
+
+#[derive(Default)]
+struct UpdateState {
+  cached: Arc<Mutex<Option<CachedUpdate>>>,
+}
+
+impl UpdateState {
+  /// Drops the stored check result once the game directory or the servers
+  /// it was computed against have changed.
+  async fn invalidate(&self) {
+    *self.cached.lock().await = None;
+  }
+}
+
+#[derive(Debug, Serialize)]
+struct UpdateSummary {
+  files_to_update: usize,
+  total_bytes: u64,
+  /// Out-of-date files in `protected_files`, not counted above.
+  protected_files: usize,
+  manifest_version: Option<String>,
+  /// Unix seconds when the check finished.
+  checked_at: u64,
 }
 
 
 //static INIT: Once = Once::new();
 
 
+// ─── HTTP clients ────────────────────────────────────────────────────────────
+
+/// Version reported in the User-Agent; set from the Tauri package info at startup.
+static LAUNCHER_VERSION: std::sync::OnceLock<String> = std::sync::OnceLock::new();
+
 lazy_static! {
-  static ref HASH_CACHE: Mutex<HashMap<String, CachedFileInfo>> = Mutex::new(HashMap::new());
+  // Headers from the [http.extra_headers] section of config.ini, sent with every request.
+  static ref EXTRA_HTTP_HEADERS: reqwest::header::HeaderMap = load_extra_http_headers();
+
+  static ref TLS_SETTINGS: TlsSettings = load_tls_settings();
+}
+
+/// Certificate handling for private file servers, from `TLS_CA_FILE` and
+/// `TLS_ACCEPT_INVALID_CERTS` in config.json.
+struct TlsSettings {
+  extra_roots: Vec<reqwest::Certificate>,
+  accept_invalid_certs: bool,
+}
+
+fn load_tls_settings() -> TlsSettings {
+  let mut extra_roots = Vec::new();
+  if let Some(ca_file) = get_optional_config_value("TLS_CA_FILE") {
+    // Relative paths are resolved next to the launcher exe.
+    let mut ca_path = PathBuf::from(&ca_file);
+    if ca_path.is_relative() {
+      if let Some(exe_dir) = env::current_exe().ok().and_then(|p| p.parent().map(|d| d.to_path_buf())) {
+        ca_path = exe_dir.join(ca_path);
+      }
+    }
+    match fs::read(&ca_path).map_err(|e| e.to_string())
+      .and_then(|pem| reqwest::Certificate::from_pem_bundle(&pem).map_err(|e| e.to_string()))
+    {
+      Ok(certs) => {
+        info!("Loaded {} CA certificate(s) from {}", certs.len(), ca_path.display());
+        extra_roots = certs;
+      }
+      Err(e) => {
+        let _ = log_error_to_file(&format!("Failed to load TLS_CA_FILE {}: {}", ca_path.display(), e));
+      }
+    }
+  }
+
+  let accept_invalid_certs = get_optional_config_value("TLS_ACCEPT_INVALID_CERTS")
+    .map(|v| v.eq_ignore_ascii_case("true") || v == "1")
+    .unwrap_or(false);
+  if accept_invalid_certs {
+    let warning = "WARNING: TLS_ACCEPT_INVALID_CERTS is enabled - server certificates are NOT verified and connections can be intercepted. Use TLS_CA_FILE instead.";
+    eprintln!("{}", warning);
+    error!("{}", warning);
+    let _ = log_error_to_file(warning);
+  }
+
+  TlsSettings { extra_roots, accept_invalid_certs }
+}
+
+/// True when a request failed because the server certificate was rejected.
+fn is_certificate_error(error: &reqwest::Error) -> bool {
+  let mut source: Option<&dyn std::error::Error> = Some(error);
+  while let Some(err) = source {
+    let text = err.to_string().to_ascii_lowercase();
+    if text.contains("certificate") || text.contains("unknownissuer") || text.contains("self signed") || text.contains("self-signed") {
+      return true;
+    }
+    source = err.source();
+  }
+  false
+}
+
+/// Header values that must never appear in logs.
+fn is_sensitive_header(name: &str) -> bool {
+  let name = name.to_ascii_lowercase();
+  ["auth", "token", "key", "secret", "cookie", "password", "session"]
+    .iter()
+    .any(|marker| name.contains(marker))
+}
+
+/// Parses `[http.extra_headers]` once. Invalid names or values are logged and
+/// skipped rather than failing every request later.
+fn load_extra_http_headers() -> reqwest::header::HeaderMap {
+  use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+
+  let mut headers = HeaderMap::new();
+  let section = find_config_file()
+    .and_then(|config_path| Ini::load_from_file(&config_path).ok())
+    .and_then(|conf| conf.section(Some("http.extra_headers")).cloned());
+  let Some(section) = section else {
+    return headers;
+  };
+
+  for (name, value) in section.iter() {
+    let sensitive = is_sensitive_header(name);
+    let logged_value = if sensitive { "<redacted>" } else { value };
+    let header_name = match HeaderName::from_bytes(name.trim().as_bytes()) {
+      Ok(header_name) => header_name,
+      Err(e) => {
+        let _ = log_error_to_file(&format!("Ignoring invalid extra HTTP header name '{}': {}", name, e));
+        continue;
+      }
+    };
+    let mut header_value = match HeaderValue::from_str(value.trim()) {
+      Ok(header_value) => header_value,
+      Err(e) => {
+        let _ = log_error_to_file(&format!("Ignoring invalid value for extra HTTP header '{}' ({}): {}", name, logged_value, e));
+        continue;
+      }
+    };
+    header_value.set_sensitive(sensitive);
+    info!("Using extra HTTP header {}: {}", header_name, logged_value);
+    headers.insert(header_name, header_value);
+  }
+
+  headers
+}
+
+fn http_user_agent() -> String {
+  let version = LAUNCHER_VERSION.get().map(|v| v.as_str()).unwrap_or(env!("CARGO_PKG_VERSION"));
+  format!("TeraLaunch/{} ({})", version, env::consts::OS)
+}
+
+/// Starting point for every launcher HTTP client: sets the launcher
+/// User-Agent and the configured extra headers.
+fn http_client_builder() -> reqwest::ClientBuilder {
+  let mut builder = Client::builder()
+    .user_agent(http_user_agent())
+    .default_headers(EXTRA_HTTP_HEADERS.clone());
+  for cert in &TLS_SETTINGS.extra_roots {
+    builder = builder.add_root_certificate(cert.clone());
+  }
+  if TLS_SETTINGS.accept_invalid_certs {
+    builder = builder.danger_accept_invalid_certs(true);
+  }
+  builder
+}
+
+fn http_client() -> Result<Client, String> {
+  http_client_builder().build().map_err(|e| format!("Failed to build HTTP client: {}", e))
 }
 
 // ─── Error Logging Module ────────────────────────────────────────────────────
@@ -257,7 +1102,9 @@ fn get_launcher_error_log_path() -> Result<PathBuf, String> {
 
 /// Formats a reqwest::Error into a detailed error description
 fn format_reqwest_error(url: &str, error: &reqwest::Error) -> String {
-  let error_type = if error.is_timeout() {
+  let error_type = if is_certificate_error(error) {
+    "CERTIFICATE_ERROR"
+  } else if error.is_timeout() {
     "TIMEOUT"
   } else if error.is_connect() {
     "CONNECTION_ERROR"
@@ -355,2626 +1202,10066 @@ fn log_error_to_file(error_msg: &str) -> Result<(), String> {
   CONFIG_JSON[key].as_str().expect(&format!("{} must be set in config.json", key)).to_string()
 } */
 
-fn is_ignored(path: &Path, game_path: &Path, ignored_paths: &HashSet<&str>) -> bool {
-  let relative_path = path.strip_prefix(game_path).unwrap().to_str().unwrap().replace("\\", "/");
+/// The game's own display and sound options. Ignored by updates; see
+/// `apply_display_options` for the keys the launcher edits.
+const S1_OPTION_INI: &str = "S1Game/Config/S1Option.ini";
+
+/// Paths left out of the hash file and skipped by the update check.
+const DEFAULT_IGNORED_PATHS: &[&str] = &[
+  "$Patch",
+  "Binaries/cookies.dat",
+  "Binaries/awesomium.log",
+  "S1Game/GuildFlagUpload",
+  "S1Game/GuildLogoUpload",
+  "S1Game/ImageCache",
+  "S1Game/Logs",
+  "S1Game/Screenshots",
+  "S1Game/Config/S1Engine.ini",
+  "S1Game/Config/S1Game.ini",
+  "S1Game/Config/S1Input.ini",
+  "S1Game/Config/S1Lightmass.ini",
+  S1_OPTION_INI,
+  "S1Game/Config/S1SystemSettings.ini",
+  "S1Game/Config/S1TBASettings.ini",
+  "S1Game/Config/S1UI.ini",
+  "Launcher.exe",
+  "local.db",
+  "version.ini",
+  "unins000.dat",
+  "unins000.exe",
+  "config.ini",
+  "file_cache.json",
+  "hash-file.json",
+  "hash-file.json.gz",
+  "version.json",
+  "diff/",
+  "priority-rules.txt",
+  "teralauncher.exe",
+  "ignore.json",
+  "launcher_ignore.txt",
+];
+
+/// Extra ignore rules server operators can ship in the game directory: a
+/// JSON array of paths in the same form as `DEFAULT_IGNORED_PATHS`.
+const IGNORE_FILE: &str = "ignore.json";
+/// The player's own ignore rules, one path per line. `#` starts a comment.
+const USER_IGNORE_FILE: &str = "launcher_ignore.txt";
+
+/// Splits a relative path into its components, accepting either separator.
+fn path_components(path: &str) -> Vec<&str> {
+  path.split(['/', '\\']).filter(|c| !c.is_empty() && *c != ".").collect()
+}
+
+fn normalize_ignore_rule(rule: &str) -> Option<String> {
+  let rule = rule.split('#').next().unwrap_or("").trim().replace("\\", "/");
+  (!rule.is_empty()).then_some(rule)
+}
 
-  // Ignore files at the root
-  if relative_path.chars().filter(|&c| c == '/').count() == 0 {
-    return true;
+/// Compiles ignore rules into one matcher. A rule is a glob (`*`, `?`, `**`)
+/// on the forward-slash relative path, and also covers everything underneath
+/// what it matches, so a bare directory name works as `dir/**`.
+fn build_ignore_set(rules: &[String]) -> Result<globset::GlobSet, String> {
+  let mut builder = globset::GlobSetBuilder::new();
+  for rule in rules {
+    let pattern = rule.trim_end_matches('/');
+    for glob in [pattern.to_string(), format!("{}/**", pattern)] {
+      let glob = globset::GlobBuilder::new(&glob)
+        .literal_separator(true)
+        .build()
+        .map_err(|e| format!("Invalid ignore rule {}: {}", rule, e))?;
+      builder.add(glob);
+    }
   }
+  builder.build().map_err(|e| format!("Invalid ignore rules: {}", e))
+}
 
-  // Check if the path is in the list of ignored paths
-  for ignored_path in ignored_paths {
-    if relative_path.starts_with(ignored_path) {
-      return true;
+/// Paths the launcher leaves alone: the built-in list, `ignore.json` and any
+/// extra rules from the caller, plus the player's `launcher_ignore.txt`.
+/// Ignored files are left out of the hash file, skipped by the update check
+/// and never overwritten by a download.
+#[derive(Debug)]
+struct IgnoreRules {
+  builtin: Vec<String>,
+  user: Vec<String>,
+  builtin_set: globset::GlobSet,
+  user_set: globset::GlobSet,
+}
+
+impl IgnoreRules {
+  /// Loads the rules for `game_path`. An unreadable rule file is logged and
+  /// left out; a rule that isn't a valid glob fails the load.
+  fn load(game_path: &Path, extra: &[String]) -> Result<Self, String> {
+    let mut builtin: Vec<String> = DEFAULT_IGNORED_PATHS.iter().map(|p| p.to_string()).collect();
+    match fs::read_to_string(game_path.join(IGNORE_FILE)) {
+      Ok(contents) => match serde_json::from_str::<Vec<String>>(&contents) {
+        Ok(paths) => builtin.extend(paths),
+        Err(e) => error!("Ignoring invalid {}: {}", IGNORE_FILE, e),
+      },
+      Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+      Err(e) => error!("Failed to read {}: {}", IGNORE_FILE, e),
     }
+    builtin.extend(extra.iter().cloned());
+    let mut builtin: Vec<String> = builtin.iter().filter_map(|rule| normalize_ignore_rule(rule)).collect();
+    builtin.sort();
+    builtin.dedup();
+    let user = load_user_ignore_rules(game_path);
+    Ok(IgnoreRules {
+      builtin_set: build_ignore_set(&builtin)?,
+      user_set: build_ignore_set(&user).map_err(|e| format!("{} in {}", e, USER_IGNORE_FILE))?,
+      builtin,
+      user,
+    })
   }
 
-  false
+  /// True when `relative_path` matches a rule or lies underneath a match,
+  /// compared on whole components so `S1Game/Logs` doesn't match
+  /// `S1Game/Logs_backup`.
+  fn is_ignored(&self, relative_path: &str) -> bool {
+    let path = path_components(relative_path).join("/");
+    self.builtin_set.is_match(&path) || self.user_set.is_match(&path)
+  }
+
+  fn is_user_ignored(&self, relative_path: &str) -> bool {
+    self.user_set.is_match(path_components(relative_path).join("/"))
+  }
 }
 
-async fn get_server_hash_file() -> Result<serde_json::Value, String> {
-  let url = get_hash_file_url();
-  let client = reqwest::Client::new();
-  let res = client
-    .get(&url)
-    .send().await
-    .map_err(|e| {
-      let detailed_error = format_reqwest_error(&url, &e);
-      let error_msg = format!("Failed to fetch hash file: {}", detailed_error);
-      let _ = log_error_to_file(&error_msg);
-      error_msg
-    })?;
-  
-  if !res.status().is_success() {
-    let error_msg = format!(
-      "Hash file request failed with HTTP status: {} (URL: {})",
-      res.status(), url
-    );
-    let _ = log_error_to_file(&error_msg);
-    return Err(error_msg);
-  }
-  
-  let json: serde_json::Value = res.json().await.map_err(|e| {
-    let error_msg = format!("Failed to parse hash file JSON: {} (URL: {})", e, url);
-    let _ = log_error_to_file(&error_msg);
-    error_msg
-  })?;
-  Ok(json)
+lazy_static! {
+  /// The rules of the current check and download session, with the game path
+  /// they were loaded for.
+  static ref SESSION_IGNORE_RULES: RwLock<Option<(PathBuf, Arc<IgnoreRules>)>> = RwLock::new(None);
 }
 
+/// Loads the rules from disk for a new session; a file check starts one.
+fn reload_ignore_rules(game_path: &Path) -> Result<Arc<IgnoreRules>, String> {
+  let rules = Arc::new(IgnoreRules::load(game_path, &[])?);
+  *SESSION_IGNORE_RULES.write().unwrap() = Some((game_path.to_path_buf(), Arc::clone(&rules)));
+  Ok(rules)
+}
 
-fn calculate_file_hash<P: AsRef<Path>>(path: P) -> Result<String, String> {
-  let mut file = File::open(path).map_err(|e| format!("Failed to open file: {}", e))?;
-  let mut hasher = Sha256::new();
-  let mut buffer = [0; 1024];
-
-  loop {
-    let bytes_read = file.read(&mut buffer).map_err(|e| format!("Failed to read file: {}", e))?;
-    if bytes_read == 0 {
-      break;
+/// The rules of the current session, loaded now when there is none for
+/// `game_path`.
+fn session_ignore_rules(game_path: &Path) -> Result<Arc<IgnoreRules>, String> {
+  if let Some((path, rules)) = SESSION_IGNORE_RULES.read().unwrap().as_ref() {
+    if path == game_path {
+      return Ok(Arc::clone(rules));
     }
-    hasher.update(&buffer[..bytes_read]);
   }
-
-  let result = hasher.finalize();
-  Ok(format!("{:x}", result))
+  reload_ignore_rules(game_path)
 }
 
-fn get_cache_file_path() -> Result<PathBuf, String> {
-  // Get the directory where config.ini is located
-  let config_path = find_config_file()
-    .ok_or("Config file not found - cannot determine cache directory")?;
-  
-  // Get the parent directory of config.ini
-  let cache_dir = config_path.parent()
-    .ok_or("Failed to get config directory")?;
-  
-  // Create cache file path in the same directory as config.ini
-  Ok(cache_dir.join("file_cache.json"))
-}
-
-fn save_cache_to_disk(cache: &HashMap<String, CachedFileInfo>) -> Result<(), String> {
-  let cache_path = get_cache_file_path()?;
-  let serialized = serde_json::to_string(cache).map_err(|e| e.to_string())?;
-  let mut file = File::create(cache_path).map_err(|e| e.to_string())?;
-  file.write_all(serialized.as_bytes()).map_err(|e| e.to_string())?;
+/// Refuses to overwrite a file the ignore rules cover. Every download and
+/// local copy goes through here from `prepare_game_file_path`.
+fn ensure_not_ignored(game_path: &Path, relative_path: &str) -> Result<(), String> {
+  if session_ignore_rules(game_path)?.is_ignored(relative_path) {
+    return Err(format!("{} is covered by the ignore rules and will not be overwritten", relative_path));
+  }
   Ok(())
 }
 
-fn load_cache_from_disk() -> Result<HashMap<String, CachedFileInfo>, String> {
-  let cache_path = get_cache_file_path()?;
-  let mut file = File::open(cache_path).map_err(|e| e.to_string())?;
-  let mut contents = String::new();
-  file.read_to_string(&mut contents).map_err(|e| e.to_string())?;
-  let cache: HashMap<String, CachedFileInfo> = serde_json::from_str(&contents).map_err(|e| e.to_string())?;
-  Ok(cache)
+fn load_user_ignore_rules(game_path: &Path) -> Vec<String> {
+  fs::read_to_string(game_path.join(USER_IGNORE_FILE))
+    .map(|contents| contents.lines().filter_map(normalize_ignore_rule).collect())
+    .unwrap_or_default()
 }
 
-
-fn get_hash_file_url() -> String {
-  get_config_value("HASH_FILE_URL")
+#[derive(Debug, Serialize)]
+struct IgnoreRulesInfo {
+  /// Rules from the launcher and the server; read-only in the settings UI.
+  builtin: Vec<String>,
+  /// Contents of `launcher_ignore.txt`.
+  user: Vec<String>,
 }
 
-fn find_config_file() -> Option<PathBuf> {
-  // Prefer config.ini next to the executable — stable regardless of cwd
-  if let Ok(exe_path) = env::current_exe() {
-    if let Some(exe_dir) = exe_path.parent() {
-      let config_in_exe_dir = exe_dir.join("config.ini");
-      if config_in_exe_dir.exists() {
-        return Some(config_in_exe_dir);
-      }
-    }
-  }
+#[tauri::command]
+fn get_ignore_rules() -> Result<IgnoreRulesInfo, String> {
+  let rules = IgnoreRules::load(&get_game_path()?, &[])?;
+  Ok(IgnoreRulesInfo { builtin: rules.builtin, user: rules.user })
+}
 
-  let current_dir = env::current_dir().ok()?;
-  let config_in_current = current_dir.join("config.ini");
-  if config_in_current.exists() {
-    return Some(config_in_current);
+/// Replaces the player's ignore rules. Rules that could escape the game
+/// directory or aren't valid globs are rejected.
+#[tauri::command]
+fn set_ignore_rules(rules: Vec<String>) -> Result<(), String> {
+  let rules: Vec<String> = rules.iter().filter_map(|rule| normalize_ignore_rule(rule)).collect();
+  if let Some(rule) = rules.iter().find(|rule| !is_safe_path(rule)) {
+    return Err(format!("Invalid ignore rule: {}", rule));
   }
-
-  let parent_dir = current_dir.parent()?;
-  let config_in_parent = parent_dir.join("config.ini");
-  if config_in_parent.exists() {
-    return Some(config_in_parent);
+  build_ignore_set(&rules)?;
+  let game_path = get_game_path()?;
+  let previous = load_user_ignore_rules(&game_path);
+  let mut contents = rules.join("\n");
+  contents.push('\n');
+  fs::write(game_path.join(USER_IGNORE_FILE), contents)
+    .map_err(|e| format!("Failed to save {}: {}", USER_IGNORE_FILE, e))?;
+  *SESSION_IGNORE_RULES.write().unwrap() = None;
+  // Files a dropped rule covered may be out of date.
+  if previous.iter().any(|rule| !rules.contains(rule)) {
+    reset_applied_manifest_version();
   }
-
-  None
+  Ok(())
 }
 
-/// Get the default configuration file path (in the launcher directory)
-fn get_default_config_path() -> Result<PathBuf, String> {
-  let exe_path = env::current_exe()
-    .map_err(|e| format!("Failed to get launcher directory: {}", e))?;
-  
-  let exe_dir = exe_path.parent()
-    .ok_or("Failed to get launcher parent directory")?;
-  
-  Ok(exe_dir.join("config.ini"))
+/// The last hash file body and the validators the server sent with it, so
+/// an unchanged manifest costs a 304 instead of a full download.
+#[derive(Debug, Serialize, Deserialize)]
+struct CachedManifest {
+  url: String,
+  etag: Option<String>,
+  last_modified: Option<String>,
+  body: String,
 }
 
-/// Create a default config file if it doesn't exist
-fn create_default_config(config_path: &PathBuf) -> Result<(), String> {
-  let exe_path = env::current_exe()
-    .map_err(|e| format!("Failed to get launcher directory: {}", e))?;
-  
-  let exe_dir = exe_path.parent()
-    .ok_or("Failed to get launcher parent directory")?;
-  
-  let mut conf = Ini::new();
-  conf.with_section(Some("game"))
-    .set("lang", "EUR")
-    .set("path", exe_dir.to_str().ok_or("Invalid launcher path")?);
-
-  let mut file = File::create(&config_path)
-    .map_err(|e| format!("Failed to create config file: {}", e))?;
-
-  conf.write_to(&mut file)
-    .map_err(|e| format!("Failed to write config: {}", e))?;
-  
-  info!("Created default config.ini at {:?}", config_path);
-  
-  Ok(())
+fn get_manifest_cache_path() -> Result<PathBuf, String> {
+  get_app_data_path("manifest_cache.json")
 }
 
-fn load_config() -> Result<(PathBuf, String), String> {
-  // Try to find existing config file
-  let config_path = if let Some(path) = find_config_file() {
-    path
-  } else {
-    // If not found, create default config at launcher directory
-    let default_path = get_default_config_path()?;
-    create_default_config(&default_path)?;
-    default_path
-  };
-
-  let conf = Ini::load_from_file(&config_path).map_err(|e|
-    format!("Failed to load config: {}", e)
-  )?;
-
-  let section = conf.section(Some("game")).ok_or("Game section not found in config")?;
-
-  let game_path = section.get("path").ok_or("Game path not found in config")?;
-
-  let game_path = PathBuf::from(game_path);
-
-  let game_lang = section.get("lang").ok_or("Game language not found in config")?.to_string();
-
-  Ok((game_path, game_lang))
+fn load_cached_manifest(url: &str) -> Option<CachedManifest> {
+  let contents = fs::read_to_string(get_manifest_cache_path().ok()?).ok()?;
+  let cached: CachedManifest = serde_json::from_str(&contents).ok()?;
+  (cached.url == url).then_some(cached)
 }
 
-/* fn save_config(game_path: &Path, game_lang: &str) -> Result<(), String> {
-  let config_path = find_config_file().ok_or("Config file not found")?;
-  let mut conf = Ini::new();
+fn save_cached_manifest(cached: &CachedManifest) -> Result<(), String> {
+  let cache_path = get_manifest_cache_path()?;
+  if let Some(parent) = cache_path.parent() {
+    fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+  }
+  let temp_path = cache_path.with_extension("json.tmp");
+  fs::write(&temp_path, serde_json::to_string(cached).map_err(|e| e.to_string())?)
+    .map_err(|e| e.to_string())?;
+  fs::rename(&temp_path, &cache_path).map_err(|e| e.to_string())
+}
 
-  conf.with_section(Some("game")).set("path", game_path.to_str().ok_or("Invalid game path")?);
-  conf.with_section(Some("game")).set("lang", game_lang);
+fn header_string(res: &reqwest::Response, name: reqwest::header::HeaderName) -> Option<String> {
+  res.headers().get(name).and_then(|v| v.to_str().ok()).map(str::to_string)
+}
 
-  let mut file = std::fs::File
-    ::create(&config_path)
-    .map_err(|e| format!("Failed to create config file: {}", e))?;
+/// Decompresses a manifest served as a `.gz` file rather than with
+/// `Content-Encoding: gzip` (which reqwest already undoes).
+fn decode_manifest_body(bytes: &[u8], content_type: Option<&str>) -> Result<String, String> {
+  let gzipped = bytes.starts_with(&[0x1f, 0x8b])
+    || matches!(content_type, Some(t) if t.contains("gzip"));
+  if !gzipped {
+    return String::from_utf8(bytes.to_vec()).map_err(|e| format!("Hash file is not valid UTF-8: {}", e));
+  }
+  let mut text = String::new();
+  flate2::read::GzDecoder::new(bytes)
+    .read_to_string(&mut text)
+    .map_err(|e| format!("Failed to decompress hash file: {}", e))?;
+  Ok(text)
+}
 
-  conf.write_to(&mut file).map_err(|e| format!("Failed to write config: {}", e))?;
+/// The text around a JSON parse error, for the log.
+fn json_error_snippet(text: &str, error: &serde_json::Error) -> String {
+  let line = text.lines().nth(error.line().saturating_sub(1)).unwrap_or("");
+  let column = error.column().saturating_sub(1);
+  let start = line.char_indices().map(|(i, _)| i).take_while(|&i| i + 60 <= column).last().unwrap_or(0);
+  line[start..].chars().take(120).collect()
+}
 
-  Ok(())
-} */
+/// Fetches hash-file.json, revalidating the cached copy with
+/// `If-None-Match`/`If-Modified-Since`. `force` skips the conditional
+/// headers and always downloads the full manifest. The client asks for gzip,
+/// and a `.gz` manifest is also accepted as-is.
+async fn fetch_base_hash_file(force: bool) -> Result<serde_json::Value, String> {
+  let url = get_hash_file_url();
+  let client = http_client()?;
+  let cached = if force { None } else { load_cached_manifest(&url) };
 
-async fn get_maintenance_status() -> Result<MaintenanceResponse, String> { 
-  let client = reqwest::Client::new();
-  let base_url = &*LAUNCHER_BASE_URL; 
-  let maintenance_url = format!("{}/launcher/GetMaintenanceStatusAction", base_url);
+  let mut request = client.get(&url);
+  if let Some(cached) = &cached {
+    if let Some(etag) = &cached.etag {
+      request = request.header(reqwest::header::IF_NONE_MATCH, etag);
+    }
+    if let Some(last_modified) = &cached.last_modified {
+      request = request.header(reqwest::header::IF_MODIFIED_SINCE, last_modified);
+    }
+  }
 
-  let res = client
-    .get(&maintenance_url)
+  let res = request
     .send().await
     .map_err(|e| {
-      let detailed_error = format_reqwest_error(&maintenance_url, &e);
-      let error_msg = format!("Failed to connect to maintenance server: {}", detailed_error);
+      let detailed_error = format_reqwest_error(&url, &e);
+      let error_msg = format!("Failed to fetch hash file: {}", detailed_error);
       let _ = log_error_to_file(&error_msg);
       error_msg
     })?;
 
-  if !res.status().is_success() {
-    let error_msg = format!(
-      "Maintenance check request failed with HTTP status: {} (URL: {})",
-      res.status(), maintenance_url
-    );
+  // Cached only once the signature checks out.
+  let mut to_cache = None;
+  let body = match (res.status(), cached) {
+    (reqwest::StatusCode::NOT_MODIFIED, Some(cached)) => {
+      println!("Hash file not modified, using cached copy");
+      cached.body
+    }
+    (status, _) if status.is_success() => {
+      let etag = header_string(&res, reqwest::header::ETAG);
+      let last_modified = header_string(&res, reqwest::header::LAST_MODIFIED);
+      let content_type = header_string(&res, reqwest::header::CONTENT_TYPE);
+      let bytes = res.bytes().await.map_err(|e| {
+        let error_msg = format!("Failed to read hash file: {} (URL: {})", e, url);
+        let _ = log_error_to_file(&error_msg);
+        error_msg
+      })?;
+      let body = decode_manifest_body(&bytes, content_type.as_deref()).map_err(|e| {
+        let error_msg = format!("{} (URL: {})", e, url);
+        let _ = log_error_to_file(&error_msg);
+        error_msg
+      })?;
+      println!("Hash file downloaded from network ({} bytes, {} decoded)", bytes.len(), body.len());
+
+      if etag.is_some() || last_modified.is_some() {
+        to_cache = Some(CachedManifest { url: url.clone(), etag, last_modified, body: String::new() });
+      }
+      body
+    }
+    (status, _) => {
+      let error_msg = format!(
+        "Hash file request failed with HTTP status: {} (URL: {})",
+        status, url
+      );
+      let _ = log_error_to_file(&error_msg);
+      return Err(error_msg);
+    }
+  };
+
+  let json: serde_json::Value = serde_json::from_str(&body).map_err(|e| {
+    let error_msg = format!("Failed to parse hash file JSON: {} (URL: {}) near: {}", e, url, json_error_snippet(&body, &e));
+    let _ = log_error_to_file(&error_msg);
+    error_msg
+  })?;
+
+  if let Err(e) = verify_manifest_signature(&json) {
+    let error_msg = format!("Hash file signature verification failed: {} (URL: {})", e, url);
     let _ = log_error_to_file(&error_msg);
     return Err(error_msg);
   }
+  if let Some(mut cached) = to_cache {
+    cached.body = body;
+    if let Err(e) = save_cached_manifest(&cached) {
+      eprintln!("Failed to cache hash file: {}", e);
+    }
+  }
 
-  let maintenance_body: MaintenanceResponse = res
-    .json()
-    .await
-    .map_err(|e| {
-      let error_msg = format!("Failed to parse maintenance response: {} (URL: {})", e, maintenance_url);
+  *CURRENT_MANIFEST_VERSION.write().unwrap() = version_string(&json["version"]);
+  *CURRENT_PATCH_PACKAGE.write().unwrap() = parse_patch_package(&json);
+  PENDING_REMOVALS.write().unwrap().clear();
+
+  Ok(json)
+}
+
+/// Overlay manifests served on top of hash-file.json, in order. Private
+/// servers use them to ship their own files next to a retail client on a CDN.
+fn extra_manifest_urls() -> Vec<String> {
+  get_config_list("EXTRA_MANIFEST_URLS")
+}
+
+/// The server's view of the game files: hash-file.json with every manifest
+/// in `EXTRA_MANIFEST_URLS` layered over it (see `merge_manifest_overlays`).
+/// Update checks, single-file updates and repairs all work from this.
+async fn get_server_hash_file(force: bool) -> Result<serde_json::Value, String> {
+  let mut manifest = fetch_base_hash_file(force).await?;
+  let overlay_urls = extra_manifest_urls();
+  if overlay_urls.is_empty() {
+    *PENDING_APPLIED_MANIFEST.write().unwrap() = Some(manifest.clone());
+    return Ok(manifest);
+  }
+
+  let mut overlays = Vec::with_capacity(overlay_urls.len());
+  for url in overlay_urls {
+    let parsed = Url::parse(&url).map_err(|e| format!("Invalid EXTRA_MANIFEST_URLS entry {}: {}", url, e))?;
+    let overlay = fetch_manifest_json(&parsed).await.map_err(|e| {
+      let error_msg = format!("Failed to fetch overlay manifest: {}", e);
       let _ = log_error_to_file(&error_msg);
       error_msg
     })?;
+    overlays.push((url, overlay));
+  }
+  let (touched, deleted) = merge_manifest_overlays(&mut manifest, &overlays)?;
+  println!("Merged {} overlay manifest(s): {} path(s) overlaid, {} deleted", overlays.len(), touched.len(), deleted.len());
 
-  if !maintenance_body.return_value {
-    return Err(format!("Maintenance check API error: {}", maintenance_body.msg));
+  // The package holds the base versions, which overlaid paths no longer use.
+  if let Some(package) = CURRENT_PATCH_PACKAGE.write().unwrap().as_mut() {
+    package.files.retain(|path| !touched.contains(path));
   }
+  *PENDING_REMOVALS.write().unwrap() = deleted;
+  *PENDING_APPLIED_MANIFEST.write().unwrap() = Some(manifest.clone());
+  Ok(manifest)
+}
 
-  Ok(maintenance_body)
+/// Layers `overlays` (source URL and manifest) over `base` by path: an
+/// overlay entry replaces any earlier one for the same path, and one with
+/// `"deleted": true` drops it, so the file is removed once the update is
+/// applied. Returns the paths the overlays touched and those left deleted.
+fn merge_manifest_overlays(
+  base: &mut serde_json::Value,
+  overlays: &[(String, serde_json::Value)],
+) -> Result<(HashSet<String>, Vec<String>), String> {
+  let base_algo = HashAlgorithm::for_manifest(base);
+  let files = base["files"].as_array().ok_or("Invalid server hash file format")?;
+  let mut merged: Vec<Option<serde_json::Value>> = files.iter().cloned().map(Some).collect();
+  // Position in `merged` and the manifest that provided the entry.
+  let mut index: HashMap<String, (usize, &str)> = files.iter()
+    .enumerate()
+    .filter_map(|(i, f)| Some((f["path"].as_str()?.to_string(), (i, "hash-file.json"))))
+    .collect();
+  let mut touched = HashSet::new();
+  let mut deleted = Vec::new();
+
+  for (url, overlay) in overlays {
+    let overlay_algo = HashAlgorithm::for_manifest(overlay);
+    let entries = overlay["files"].as_array().ok_or_else(|| format!("Invalid overlay manifest format: {}", url))?;
+    for entry in entries {
+      let Some(path) = entry["path"].as_str() else {
+        continue;
+      };
+      if !is_safe_path(path) {
+        eprintln!("Ignoring unsafe path {} in overlay manifest {}", path, url);
+        continue;
+      }
+      touched.insert(path.to_string());
+      let previous = index.remove(path);
+      if let Some((_, source)) = previous {
+        println!("{}: {} overrides {}", path, url, source);
+      }
+      if entry["deleted"].as_bool() == Some(true) {
+        if let Some((position, _)) = previous {
+          merged[position] = None;
+        }
+        deleted.push(path.to_string());
+        continue;
+      }
+      let mut entry = entry.clone();
+      // Entries keep the algorithm of the manifest they came from.
+      if entry.get("algo").is_none() && overlay_algo != base_algo {
+        entry["algo"] = json!(overlay_algo);
+      }
+      let position = match previous {
+        Some((position, _)) => {
+          merged[position] = Some(entry);
+          position
+        }
+        None => {
+          merged.push(Some(entry));
+          merged.len() - 1
+        }
+      };
+      index.insert(path.to_string(), (position, url.as_str()));
+    }
+  }
+
+  // A later overlay may bring back a path an earlier one deleted.
+  deleted.retain(|path| !index.contains_key(path));
+  deleted.sort();
+  deleted.dedup();
+  base["files"] = serde_json::Value::Array(merged.into_iter().flatten().collect());
+  Ok((touched, deleted))
 }
 
-#[tauri::command]
-async fn check_maintenance_and_notify(window: tauri::Window) -> Result<bool, String> {
-  match get_maintenance_status().await {
-    Ok(response) => {
-      let is_maintenance = response.start_time.is_some() || response.end_time.is_some();
 
-      if is_maintenance {
-        // Emit the event with full maintenance details for the modal
-        let payload = serde_json::to_value(&response)
-          .unwrap_or(json!({"msg": "Active maintenance"}));
+/// Files at least this large are hashed through a memory map.
+const MMAP_HASH_THRESHOLD: u64 = 64 * 1024 * 1024;
+const MMAP_HASH_SLICE: usize = 4 * 1024 * 1024;
 
-        if let Err(e) = window.emit("maintenance_active", payload) {
-          error!("Failed to emit maintenance_active event: {:?}", e);
-        }
-      }
+/// How many 64 KiB buffers are hashed between checks for `cancel_update`.
+const CANCEL_CHECK_BUFFERS: usize = 16;
 
-      // Return 'true' if maintenance is active, 'false' otherwise
-      Ok(is_maintenance)
+/// Feeds the contents of the file at `path` to `update` block by block.
+/// Stops with an error once `cancel_update` has been called.
+fn read_file_blocks<P: AsRef<Path>>(path: P, update: &mut dyn FnMut(&[u8])) -> Result<(), String> {
+  let file = File::open(path).map_err(|e| format!("Failed to open file: {}", e))?;
+
+  if file.metadata().map(|m| m.len() >= MMAP_HASH_THRESHOLD).unwrap_or(false) {
+    // Mapping can fail (e.g. on some network shares), in which case the
+    // buffered reader below takes over.
+    if let Some(result) = read_mapped_blocks(&file, update) {
+      return result;
     }
-    Err(e) => {
-      error!("Error checking maintenance status: {:?}", e);
-      // Return a specific error so the frontend can handle it as a network issue
-      Err(format!("ERROR_NETWORK_CHECK: {}", e))
+  }
+  read_buffered_blocks(file, update)
+}
+
+/// The memory-mapped path of `read_file_blocks`; `None` if the file can't be
+/// mapped.
+fn read_mapped_blocks(file: &File, update: &mut dyn FnMut(&[u8])) -> Option<Result<(), String>> {
+  let map = unsafe { memmap2::Mmap::map(file) }.ok()?;
+  for slice in map.chunks(MMAP_HASH_SLICE) {
+    if is_update_cancelled() {
+      return Some(Err(UPDATE_CANCELLED.to_string()));
     }
+    update(slice);
   }
+  Some(Ok(()))
 }
 
+/// The buffered path of `read_file_blocks`, for small files and files that
+/// can't be mapped.
+fn read_buffered_blocks(mut file: File, update: &mut dyn FnMut(&[u8])) -> Result<(), String> {
+  let mut buffer = vec![0; 64 * 1024];
+  for block in 0.. {
+    if block % CANCEL_CHECK_BUFFERS == 0 && is_update_cancelled() {
+      return Err(UPDATE_CANCELLED.to_string());
+    }
+    let bytes_read = file.read(&mut buffer).map_err(|e| format!("Failed to read file: {}", e))?;
+    if bytes_read == 0 {
+      break;
+    }
+    update(&buffer[..bytes_read]);
+  }
+  Ok(())
+}
 
-#[tauri::command]
-async fn generate_hash_file(window: tauri::Window) -> Result<String, String> {
-  let start_time = Instant::now();
+/// Incremental hasher for either manifest algorithm.
+enum StreamHasher {
+  Sha256(Sha256),
+  Blake3(Box<blake3::Hasher>),
+}
 
-  let game_path = get_game_path().map_err(|e| e.to_string())?;
-  info!("Game path: {:?}", game_path);
-  let output_path = game_path.join("hash-file.json");
-  info!("Output path: {:?}", output_path);
+impl StreamHasher {
+  fn new(algo: HashAlgorithm) -> Self {
+    match algo {
+      HashAlgorithm::Sha256 => StreamHasher::Sha256(Sha256::new()),
+      HashAlgorithm::Blake3 => StreamHasher::Blake3(Box::new(blake3::Hasher::new())),
+    }
+  }
 
-  // List of files and directories to ignore
-  let ignored_paths: HashSet<&str> = [
-    "$Patch",
-    "Binaries/cookies.dat",
-    "Binaries/awesomium.log",
-    "S1Game/GuildFlagUpload",
-    "S1Game/GuildLogoUpload",
-    "S1Game/ImageCache",
-    "S1Game/Logs",
-    "S1Game/Screenshots",
-    "S1Game/Config/S1Engine.ini",
-    "S1Game/Config/S1Game.ini",
-    "S1Game/Config/S1Input.ini",
-    "S1Game/Config/S1Lightmass.ini",
-    "S1Game/Config/S1Option.ini",
-    "S1Game/Config/S1SystemSettings.ini",
-    "S1Game/Config/S1TBASettings.ini",
-    "S1Game/Config/S1UI.ini",
-    "Launcher.exe",
-    "local.db",
-    "version.ini",
-    "unins000.dat",
-    "unins000.exe",
-    "config.ini",
-    "file_cache.json",
-    "hash-file.json",
-    "teralauncher.exe",
-  ].iter().cloned().collect();
-
-  let total_files = WalkDir::new(&game_path)
-    .into_iter()
-    .filter_map(|e| e.ok())
-    .filter(|e| e.file_type().is_file())
-    .filter(|e| !is_ignored(e.path(), &game_path, &ignored_paths))
-    .count();
-  info!("Total files to process: {}", total_files);
+  fn update(&mut self, data: &[u8]) {
+    match self {
+      StreamHasher::Sha256(hasher) => hasher.update(data),
+      StreamHasher::Blake3(hasher) => { hasher.update(data); }
+    }
+  }
 
-  let progress_bar = ProgressBar::new(total_files as u64);
-  let progress_style = ProgressStyle::default_bar()
-    .template("[{elapsed_precise}] {bar:40.cyan/blue} {pos}/{len} {msg}")
-    .map_err(|e| e.to_string())?
-    .progress_chars("##-");
-  progress_bar.set_style(progress_style);
-
-  let processed_files = AtomicU64::new(0);
-  let total_size = AtomicU64::new(0);
-  let files = Arc::new(Mutex::new(Vec::new()));
-
-  let result: Result<(), String> = WalkDir::new(&game_path)
-    .into_iter()
-    .par_bridge()
-    .try_for_each(|entry| -> Result<(), String> {
-      let entry = entry.map_err(|e| e.to_string())?;
-      let path = entry.path();
-      if path.is_file() && !is_ignored(path, &game_path, &ignored_paths) {
-        let relative_path = path.strip_prefix(&game_path).unwrap().to_str().unwrap().replace("\\", "/");
-        info!("Processing file: {}", relative_path);
-
-        let contents = std::fs::read(path).map_err(|e| e.to_string())?;
-        let mut hasher = Sha256::new();
-        hasher.update(&contents);
-        let hash = format!("{:x}", hasher.finalize());
-        let size = contents.len() as u64;
-        let file_server_url = get_config_value("FILE_SERVER_URL");
-        let url = format!("{}/files/{}", file_server_url, relative_path);
-
-        files.blocking_lock().push(FileInfo {
-          path: relative_path.clone(),
-          hash,
-          size,
-          url,
-        });
-
-        total_size.fetch_add(size, Ordering::Relaxed);
-        let current_processed = processed_files.fetch_add(1, Ordering::Relaxed) + 1;
-        progress_bar.set_position(current_processed);
+  fn finish(self) -> String {
+    match self {
+      StreamHasher::Sha256(hasher) => format!("{:x}", hasher.finalize()),
+      StreamHasher::Blake3(hasher) => hasher.finalize().to_hex().to_string(),
+    }
+  }
+}
 
-        let progress = (current_processed as f64 / total_files as f64) * 100.0;
-        window.emit("hash_file_progress", json!({
-          "current_file": relative_path,
-          "progress": progress,
-          "processed_files": current_processed,
-          "total_files": total_files,
-          "total_size": total_size.load(Ordering::Relaxed)
-        })).map_err(|e| e.to_string())?;
-      }
-      Ok(())
-    });
+fn calculate_file_hash_with<P: AsRef<Path>>(path: P, algo: HashAlgorithm) -> Result<String, String> {
+  let mut hasher = StreamHasher::new(algo);
+  read_file_blocks(path, &mut |data| hasher.update(data))?;
+  Ok(hasher.finish())
+}
 
-  if let Err(e) = result {
-    error!("Error during file processing: {:?}", e);
-    return Err(e);
+/// Hashes the whole file and each of `chunks` in a single pass. Returns the
+/// file hash and the indices of the chunks that match. Chunks past the end of
+/// the file never match, and bytes past the last chunk only count towards the
+/// file hash. `chunks` must be contiguous from offset 0, as written by
+/// `generate_hash_file`; anything else falls back to two passes.
+fn hash_file_with_chunks(path: &Path, algo: HashAlgorithm, chunks: &[FileChunk]) -> Result<(String, HashSet<usize>), String> {
+  let contiguous = chunks.iter().scan(0, |expected, chunk| {
+    let ok = chunk.offset == *expected && chunk.size > 0;
+    *expected += chunk.size;
+    Some(ok)
+  }).all(|ok| ok);
+  if !contiguous {
+    let hash = calculate_file_hash_with(path, algo)?;
+    return Ok((hash, find_valid_chunks(path, chunks, algo)));
   }
 
-  progress_bar.finish_with_message("File processing completed");
+  let mut whole = StreamHasher::new(algo);
+  let mut chunk_hasher = StreamHasher::new(algo);
+  let mut valid = HashSet::new();
+  let mut index = 0;
+  let mut remaining = chunks.first().map(|c| c.size).unwrap_or(0);
+  read_file_blocks(path, &mut |mut data| {
+    whole.update(data);
+    while !data.is_empty() && index < chunks.len() {
+      let take = (remaining as usize).min(data.len());
+      chunk_hasher.update(&data[..take]);
+      data = &data[take..];
+      remaining -= take as u64;
+      if remaining == 0 {
+        if std::mem::replace(&mut chunk_hasher, StreamHasher::new(algo)).finish() == chunks[index].hash {
+          valid.insert(index);
+        }
+        index += 1;
+        remaining = chunks.get(index).map(|c| c.size).unwrap_or(0);
+      }
+    }
+  })?;
+  Ok((whole.finish(), valid))
+}
 
-  info!("Generating JSON");
-  let json = serde_json::to_string(&json!({
-    "files": files.lock().await.clone()
-  })).map_err(|e| e.to_string())?;
+fn calculate_quick_hash<P: AsRef<Path>>(path: P) -> Result<String, String> {
+  let mut hasher = xxhash_rust::xxh3::Xxh3::new();
+  read_file_blocks(path, &mut |data| hasher.update(data))?;
+  Ok(format!("{:016x}", hasher.digest()))
+}
 
-  info!("Writing hash file");
-  let mut file = File::create(&output_path).map_err(|e| e.to_string())?;
-  file.write_all(json.as_bytes()).map_err(|e| e.to_string())?;
+fn hash_bytes(data: &[u8], algo: HashAlgorithm) -> String {
+  let mut hasher = StreamHasher::new(algo);
+  hasher.update(data);
+  hasher.finish()
+}
 
-  let duration = start_time.elapsed();
-  let total_processed = processed_files.load(Ordering::Relaxed);
-  let total_size = total_size.load(Ordering::Relaxed);
-  info!("Hash file generation completed in {:?}", duration);
-  info!("Total files processed: {}", total_processed);
-  info!("Total size: {} bytes", total_size);
+fn get_hash_thread_count() -> usize {
+  get_launcher_setting("hash_threads")
+    .and_then(|v| v.parse::<usize>().ok())
+    .unwrap_or(0)
+}
 
-  Ok(format!("Hash file generated successfully. Processed {} files with a total size of {} bytes in {:?}", total_processed, total_size, duration))
+/// Builds the pool used by file checks and hash file generation, sized by the
+/// `hash_threads` launcher setting (0 or unset = one thread per logical core).
+fn build_hash_thread_pool() -> Result<rayon::ThreadPool, String> {
+  let pool = rayon::ThreadPoolBuilder::new()
+    .num_threads(get_hash_thread_count())
+    .thread_name(|i| format!("hash-{}", i))
+    .build()
+    .map_err(|e| format!("Failed to create hash thread pool: {}", e))?;
+  println!("Hashing with {} thread(s)", pool.current_num_threads());
+  Ok(pool)
 }
 
+/// Returns the configured number of hashing threads (0 = automatic).
+#[tauri::command]
+fn get_hash_threads() -> Result<usize, String> {
+  Ok(get_hash_thread_count())
+}
 
+/// Sets the number of threads used to hash files during checks and hash file
+/// generation; 0 uses one per logical core. Applies from the next operation.
+///
+/// On an HDD the disk, not the CPU, is the limit, and several threads reading
+/// at once mostly adds seeking — 1 or 2 threads are usually as fast as more and
+/// keep the machine responsive. SSDs and NVMe drives benefit from more threads.
 #[tauri::command]
-async fn select_game_folder() -> Result<String, String> {
-  let (tx, mut rx) = mpsc::channel(1);
+fn set_hash_threads(threads: usize) -> Result<(), String> {
+  save_launcher_setting("hash_threads", &threads.to_string())
+}
 
-  FileDialogBuilder::new()
-    .set_title("Select Tera Game Folder")
-    .set_directory("/")
-    .pick_folder(move |folder_path| {
-      if let Some(path) = folder_path {
-        let _ = tx.try_send(path);
-      }
-    });
+/// The launcher's app data directory, resolved during setup.
+static APP_DATA_DIR: std::sync::OnceLock<PathBuf> = std::sync::OnceLock::new();
 
-  match rx.recv().await {
-    Some(path) => Ok(path.to_string_lossy().into_owned()),
-    None => Err("Folder selection cancelled or failed".into()),
-  }
+lazy_static! {
+  /// App data entries already checked for a copy left next to config.ini.
+  static ref MIGRATED_APP_DATA: std::sync::Mutex<HashSet<String>> = std::sync::Mutex::new(HashSet::new());
 }
 
-
-fn get_game_path() -> Result<PathBuf, String> {
-  let (game_path, _) = load_config()?;
-  Ok(game_path)
+/// Directory holding config.ini.
+fn get_config_dir() -> Result<PathBuf, String> {
+  let config_path = find_config_file()
+    .ok_or("Config file not found - cannot determine cache directory")?;
+  config_path.parent()
+    .map(|dir| dir.to_path_buf())
+    .ok_or_else(|| "Failed to get config directory".to_string())
 }
 
-/// Find Tera.exe in `binaries_dir` case-insensitively.
-/// On Windows the filesystem is case-insensitive so a direct join works.
-/// On Linux we scan the directory for a case-insensitive match.
-fn find_game_exe(binaries_dir: &PathBuf) -> Option<PathBuf> {
-  // Try the canonical name first (fast path, works on Windows and case-correct Linux installs)
-  for name in &["Tera.exe", "TERA.exe", "tera.exe"] {
-    let candidate = binaries_dir.join(name);
-    if candidate.exists() {
-      return Some(candidate);
+/// A file or directory in the app data directory, which is writable even
+/// when the launcher is installed under Program Files, or next to config.ini
+/// before setup has resolved it. A copy left next to config.ini by older
+/// versions is moved over on first use.
+fn get_app_data_path(name: &str) -> Result<PathBuf, String> {
+  let Some(data_dir) = APP_DATA_DIR.get() else {
+    return Ok(get_config_dir()?.join(name));
+  };
+  let path = data_dir.join(name);
+
+  if MIGRATED_APP_DATA.lock().unwrap_or_else(|e| e.into_inner()).insert(name.to_string()) {
+    let Ok(legacy_path) = get_config_dir().map(|dir| dir.join(name)) else { return Ok(path) };
+    if path.exists() || !legacy_path.exists() {
+      return Ok(path);
     }
-  }
-  // Case-insensitive scan (Linux only fallback)
-  #[cfg(not(windows))]
-  if let Ok(entries) = std::fs::read_dir(binaries_dir) {
-    for entry in entries.flatten() {
-      let fname = entry.file_name();
-      if fname.to_string_lossy().to_lowercase() == "tera.exe" {
-        return Some(entry.path());
-      }
+    let migrated = fs::create_dir_all(data_dir).map_err(|e| e.to_string())
+      .and_then(|_| if legacy_path.is_dir() { move_dir(&legacy_path, &path) } else { move_file(&legacy_path, &path) });
+    match migrated {
+      Ok(()) => println!("Moved {} to {}", name, path.display()),
+      Err(e) => eprintln!("Failed to move {} to {}: {}", name, path.display(), e),
     }
   }
-  None
+
+  Ok(path)
 }
 
+/// Version of the file_cache.json envelope. Bump it whenever `CachedFileInfo`
+/// changes shape and add a `migrate_cache_v<N>` step for the old layout.
+const CACHE_FORMAT_VERSION: u32 = 1;
 
-#[tauri::command]
-fn save_game_path_to_config(path: String) -> Result<(), String> {
-  let config_path = find_config_file().ok_or("Config file not found")?;
-  let mut conf = Ini::load_from_file(&config_path).map_err(|e|
-    format!("Failed to load config: {}", e)
-  )?;
+#[derive(Serialize)]
+struct CacheFileRef<'a> {
+  version: u32,
+  entries: &'a HashMap<String, CachedFileInfo>,
+}
 
-  conf.with_section(Some("game")).set("path", &path);
+/// Unversioned caches are a bare path → entry map from before the envelope;
+/// entries without `algo` were all sha256, which the serde default covers.
+fn migrate_cache_v0(map: serde_json::Value) -> Result<HashMap<String, CachedFileInfo>, String> {
+  serde_json::from_value(map).map_err(|e| e.to_string())
+}
 
-  conf.write_to_file(&config_path).map_err(|e| format!("Failed to write config: {}", e))?;
+/// Reads any known cache layout into the current entry type.
+fn parse_cache_file(contents: &str) -> Result<HashMap<String, CachedFileInfo>, String> {
+  let value: serde_json::Value = serde_json::from_str(contents).map_err(|e| e.to_string())?;
+  let version = match (value["version"].as_u64(), value["entries"].is_object()) {
+    (Some(version), true) => version,
+    _ => return migrate_cache_v0(value),
+  };
+
+  match version {
+    1 => serde_json::from_value(value["entries"].clone()).map_err(|e| e.to_string()),
+    newer => {
+      // Written by a newer launcher; rebuilding beats misreading it.
+      let warning = format!("File cache has unknown format version {}, ignoring it", newer);
+      eprintln!("{}", warning);
+      let _ = log_error_to_file(&warning);
+      Ok(HashMap::new())
+    }
+  }
+}
 
+/// Writes the cache through a temporary file and a rename, so a crash midway
+/// leaves the previous cache intact instead of a truncated one.
+fn save_json_cache(cache_path: &Path, cache: &HashMap<String, CachedFileInfo>) -> Result<(), String> {
+  if let Some(parent) = cache_path.parent() {
+    fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+  }
+  let envelope = CacheFileRef { version: CACHE_FORMAT_VERSION, entries: cache };
+  let serialized = serde_json::to_string(&envelope).map_err(|e| e.to_string())?;
+  let temp_path = cache_path.with_extension("json.tmp");
+  let mut file = File::create(&temp_path).map_err(|e| e.to_string())?;
+  file.write_all(serialized.as_bytes()).map_err(|e| e.to_string())?;
+  file.sync_all().map_err(|e| e.to_string())?;
+  drop(file);
+  fs::rename(&temp_path, cache_path).map_err(|e| e.to_string())?;
   Ok(())
 }
 
-#[tauri::command]
-fn get_game_path_from_config() -> Result<String, String> {
-  match get_game_path() {
-    Ok(game_path) => game_path
-      .to_str()
-      .ok_or_else(|| "Invalid UTF-8 in game path".to_string())
-      .map(|s| s.to_string()),
+/// A cache that fails to parse is renamed to `file_cache.json.bad` and
+/// replaced by an empty one, rather than failing every check.
+fn load_json_cache(cache_path: &Path) -> Result<HashMap<String, CachedFileInfo>, String> {
+  let mut file = File::open(cache_path).map_err(|e| e.to_string())?;
+  let mut contents = String::new();
+  file.read_to_string(&mut contents).map_err(|e| e.to_string())?;
+  drop(file);
+  match parse_cache_file(&contents) {
+    Ok(cache) => Ok(cache),
     Err(e) => {
-      if e.contains("Config file not found") {
-        Err("config.ini is missing".to_string())
-      } else {
-        Err(e)
+      let bad_path = cache_path.with_extension("json.bad");
+      let warning = format!("File cache {} is corrupt ({}); moved to {} and starting fresh",
+          cache_path.display(), e, bad_path.display());
+      eprintln!("{}", warning);
+      let _ = log_error_to_file(&warning);
+      if let Err(e) = move_file(cache_path, &bad_path) {
+        eprintln!("Failed to move corrupt cache aside: {}", e);
       }
+      Ok(HashMap::new())
     }
   }
 }
 
-#[tauri::command]
-fn clear_update_cache() -> Result<(), String> {
-  println!("Clearing update cache");
-  let cache_path = get_cache_file_path()?;
-  
-  if cache_path.exists() {
-    std::fs::remove_file(&cache_path)
-      .map_err(|e| format!("Failed to delete cache file: {}", e))?;
-    println!("Cache file deleted successfully");
-    Ok(())
-  } else {
-    println!("Cache file does not exist");
-    Ok(())
-  }
+/// Persistent map from game-relative path to the hash last computed for that
+/// file. Safe to read from every check thread at once; writes land as files
+/// are hashed and `flush` makes them durable.
+trait FileHashCache: Send + Sync {
+  fn get(&self, path: &str) -> Option<CachedFileInfo>;
+  fn insert(&self, path: &str, entry: CachedFileInfo);
+  fn remove(&self, path: &str);
+  fn entries(&self) -> Vec<(String, CachedFileInfo)>;
+  fn len(&self) -> usize;
+  fn clear(&self) -> Result<(), String>;
+  fn flush(&self) -> Result<(), String>;
+  /// Where the cache is stored and how many bytes it takes on disk.
+  fn location(&self) -> (PathBuf, u64);
 }
 
-#[tauri::command]
-async fn check_update_required(window: tauri::Window) -> Result<bool, String> {
-  match get_files_to_update(window).await {
-    Ok(files) => Ok(!files.is_empty()),
-    Err(e) => Err(e),
-  }
+/// file_cache.json, held in memory and written out whole on `flush`.
+struct JsonHashCache {
+  path: PathBuf,
+  entries: RwLock<HashMap<String, CachedFileInfo>>,
+  dirty: AtomicBool,
 }
 
-// Security: Validate file paths to prevent path traversal attacks
-fn is_safe_path(path: &str) -> bool {
-  use std::path::Component;
-  
-  // Reject paths with parent directory references
-  if path.contains("..") || path.starts_with("/") || path.starts_with("\\") {
-    return false;
+impl JsonHashCache {
+  fn open(path: PathBuf) -> Self {
+    let entries = load_json_cache(&path).unwrap_or_default();
+    JsonHashCache { path, entries: RwLock::new(entries), dirty: AtomicBool::new(false) }
   }
-  
-  // Reject paths that normalize to a parent directory
-  std::path::Path::new(path)
-    .components()
-    .all(|c| !matches!(c, Component::ParentDir | Component::RootDir))
 }
 
-#[tauri::command]
-async fn update_file(
-  _app_handle: tauri::AppHandle,
-  window: tauri::Window,
-  file_info: FileInfo,
-  total_files: usize,
-  current_file_index: usize,
-  total_size: u64,
-  downloaded_size: u64,
-) -> Result<u64, String> {
-  let game_path = get_game_path()?;
-  
-  // SECURITY: Validate file path to prevent path traversal attacks
-  if !is_safe_path(&file_info.path) {
-    return Err(format!("Invalid file path detected: {}. Path traversal attack blocked.", file_info.path));
-  }
-  
-  let file_path = game_path.join(&file_info.path);
-  
-  // SECURITY: Ensure the final file path is within the game directory
-  if !file_path.starts_with(&game_path) {
-    return Err(format!("Path traversal attack detected. File would be extracted outside game directory."));
+impl FileHashCache for JsonHashCache {
+  fn get(&self, path: &str) -> Option<CachedFileInfo> {
+    self.entries.read().unwrap().get(path).cloned()
   }
 
-  if let Some(parent) = file_path.parent() {
-    tokio::fs::create_dir_all(parent).await.map_err(|e| e.to_string())?;
+  fn insert(&self, path: &str, entry: CachedFileInfo) {
+    self.entries.write().unwrap().insert(path.to_string(), entry);
+    self.dirty.store(true, Ordering::SeqCst);
   }
 
-  let client = reqwest::Client::builder()
-    .no_proxy()
-    .build()
-    .map_err(|e| e.to_string())?;
-
-  let res = client.get(&file_info.url)
-    .send()
-    .await
-    .map_err(|e| e.to_string())?;
-
-  let file_size = res.content_length().unwrap_or(file_info.size);
-  let mut file = tokio::fs::File::create(&file_path).await.map_err(|e| e.to_string())?;
-  let mut downloaded: u64 = 0;
-  let mut stream = res.bytes_stream();
-  let start_time = Instant::now();
-  let mut last_update = Instant::now();
-
-  println!("Downloading file: {}", file_info.path);
-
-  while let Some(chunk_result) = stream.next().await {
-    let chunk = chunk_result.map_err(|e| e.to_string())?;
-    file.write_all(&chunk).await.map_err(|e| e.to_string())?;
-    downloaded += chunk.len() as u64;
-
-    let now = Instant::now();
-    if now.duration_since(last_update) >= Duration::from_millis(100) || downloaded == file_size {
-      let elapsed = now.duration_since(start_time);
-      let speed = if elapsed.as_secs() > 0 { downloaded / elapsed.as_secs() } else { downloaded };
-
-      let total_downloaded = downloaded_size + downloaded;
-      let progress_payload = ProgressPayload {
-        file_name: file_info.path.clone(),
-        progress: (downloaded as f64 / file_size as f64) * 100.0,
-        speed: speed as f64,
-        downloaded_bytes: total_downloaded,
-        total_bytes: total_size,
-        total_files,
-        elapsed_time: elapsed.as_secs_f64(),
-        current_file_index,
-      };
-
-      println!("Current file: {}, Download speed: {}/s, Progress: {:.2}%",
-          file_info.path, format_bytes(speed), progress_payload.progress);
-
-      if let Err(e) = window.emit("download_progress", &progress_payload) {
-        println!("Failed to emit download_progress event: {}", e);
-      }
-      last_update = now;
+  fn remove(&self, path: &str) {
+    if self.entries.write().unwrap().remove(path).is_some() {
+      self.dirty.store(true, Ordering::SeqCst);
     }
+  }
 
-    tokio::time::sleep(Duration::from_millis(1)).await;
+  fn entries(&self) -> Vec<(String, CachedFileInfo)> {
+    self.entries.read().unwrap().iter().map(|(path, entry)| (path.clone(), entry.clone())).collect()
   }
 
-  file.flush().await.map_err(|e| e.to_string())?;
+  fn len(&self) -> usize {
+    self.entries.read().unwrap().len()
+  }
 
-  let downloaded_hash = tokio::task::spawn_blocking(move || calculate_file_hash(&file_path)).await.map_err(|e| e.to_string())??;
-  if downloaded_hash != file_info.hash {
-    return Err(format!("Hash mismatch for file: {}", file_info.path));
+  fn clear(&self) -> Result<(), String> {
+    self.entries.write().unwrap().clear();
+    self.dirty.store(false, Ordering::SeqCst);
+    if self.path.exists() {
+      fs::remove_file(&self.path).map_err(|e| format!("Failed to delete cache file: {}", e))?;
+    }
+    Ok(())
   }
 
-  // Emit a final event for this file
-  let final_progress_payload = ProgressPayload {
-    file_name: file_info.path.clone(),
-    progress: 100.0,
-    speed: 0.0,
-    downloaded_bytes: downloaded_size + downloaded,
-    total_bytes: total_size,
-    total_files,
-    elapsed_time: start_time.elapsed().as_secs_f64(),
-    current_file_index,
-  };
-  if let Err(e) = window.emit("download_progress", &final_progress_payload) {
-    println!("Failed to emit final download_progress event: {}", e);
+  fn flush(&self) -> Result<(), String> {
+    if !self.dirty.swap(false, Ordering::SeqCst) {
+      return Ok(());
+    }
+    let result = save_json_cache(&self.path, &self.entries.read().unwrap());
+    if result.is_err() {
+      self.dirty.store(true, Ordering::SeqCst);
+    }
+    result
   }
 
-  println!("File download completed: {}", file_info.path);
+  fn location(&self) -> (PathBuf, u64) {
+    (self.path.clone(), fs::metadata(&self.path).map(|m| m.len()).unwrap_or(0))
+  }
+}
 
-  Ok(downloaded)
+/// `file_cache.db`, an embedded sled store written entry by entry. Entries
+/// are stored as JSON so `CachedFileInfo`'s serde defaults keep working.
+#[cfg(feature = "sled-cache")]
+struct SledHashCache {
+  path: PathBuf,
+  db: sled::Db,
 }
 
-fn format_bytes(bytes: u64) -> String {
-  const UNITS: [&str; 4] = ["B", "KB", "MB", "GB"];
-  let mut size = bytes as f64;
-  let mut unit_index = 0;
+#[cfg(feature = "sled-cache")]
+impl SledHashCache {
+  /// Opens the store, clearing it if it was written in an unknown format and
+  /// importing `legacy_json` on first use.
+  fn open(path: PathBuf, legacy_json: &Path) -> Result<Self, String> {
+    let db = sled::open(&path).map_err(|e| e.to_string())?;
+    let meta = db.open_tree("meta").map_err(|e| e.to_string())?;
+    let version = meta.get("version").ok().flatten()
+      .and_then(|v| std::str::from_utf8(&v).ok().and_then(|v| v.parse::<u32>().ok()));
+    let cache = SledHashCache { path, db };
+
+    if matches!(version, Some(v) if v != CACHE_FORMAT_VERSION) {
+      let warning = format!("File cache store has unknown format version {:?}, clearing it", version);
+      eprintln!("{}", warning);
+      let _ = log_error_to_file(&warning);
+      cache.db.clear().map_err(|e| e.to_string())?;
+    }
+    meta.insert("version", CACHE_FORMAT_VERSION.to_string().as_bytes()).map_err(|e| e.to_string())?;
+
+    if cache.db.is_empty() && legacy_json.exists() {
+      match load_json_cache(legacy_json) {
+        Ok(entries) => {
+          let count = entries.len();
+          for (path, entry) in entries {
+            cache.insert(&path, entry);
+          }
+          cache.flush()?;
+          let _ = move_file(legacy_json, &legacy_json.with_extension("json.migrated"));
+          println!("Imported {} entries from {}", count, legacy_json.display());
+        }
+        Err(e) => eprintln!("Failed to import {}: {}", legacy_json.display(), e),
+      }
+    }
 
-  while size >= 1024.0 && unit_index < UNITS.len() - 1 {
-    size /= 1024.0;
-    unit_index += 1;
+    Ok(cache)
   }
-
-  format!("{:.2} {}", size, UNITS[unit_index])
 }
 
-#[tauri::command]
-async fn download_all_files(
-  app_handle: tauri::AppHandle,
-  window: tauri::Window,
-  files_to_update: Vec<FileInfo>
-) -> Result<Vec<u64>, String> {
-  let total_files = files_to_update.len();
-  let total_size: u64 = files_to_update.iter().map(|f| f.size).sum();
+#[cfg(feature = "sled-cache")]
+impl FileHashCache for SledHashCache {
+  fn get(&self, path: &str) -> Option<CachedFileInfo> {
+    let value = self.db.get(path).ok()??;
+    serde_json::from_slice(&value).ok()
+  }
 
-  if total_files == 0 {
-    println!("No files to download");
-    if let Err(e) = window.emit("download_complete", ()) {
-      eprintln!("Failed to emit download_complete event: {}", e);
+  fn insert(&self, path: &str, entry: CachedFileInfo) {
+    let result = serde_json::to_vec(&entry).map_err(|e| e.to_string())
+      .and_then(|value| self.db.insert(path, value).map_err(|e| e.to_string()));
+    if let Err(e) = result {
+      eprintln!("Failed to write cache entry for {}: {}", path, e);
     }
-    return Ok(vec![]);
   }
 
-  let mut downloaded_sizes = Vec::with_capacity(total_files);
-  let mut downloaded_size: u64 = 0;
+  fn remove(&self, path: &str) {
+    if let Err(e) = self.db.remove(path) {
+      eprintln!("Failed to remove cache entry for {}: {}", path, e);
+    }
+  }
 
-  for (index, file_info) in files_to_update.into_iter().enumerate() {
-    let file_size = update_file(
-      app_handle.clone(),
-      window.clone(),
-      file_info,
-      total_files,
-      index + 1,
-      total_size,
-      downloaded_size
-    ).await?;
+  fn entries(&self) -> Vec<(String, CachedFileInfo)> {
+    self.db.iter()
+      .filter_map(|item| item.ok())
+      .filter_map(|(key, value)| {
+        let path = String::from_utf8(key.to_vec()).ok()?;
+        Some((path, serde_json::from_slice(&value).ok()?))
+      })
+      .collect()
+  }
 
-    downloaded_size += file_size;
-    downloaded_sizes.push(file_size);
+  fn len(&self) -> usize {
+    self.db.len()
   }
 
-  println!("Download complete for {} file(s)", total_files);
-  if let Err(e) = window.emit("download_complete", ()) {
-    eprintln!("Failed to emit download_complete event: {}", e);
+  fn clear(&self) -> Result<(), String> {
+    self.db.clear().map_err(|e| e.to_string())?;
+    self.flush()
+  }
+
+  fn flush(&self) -> Result<(), String> {
+    self.db.flush().map(|_| ()).map_err(|e| e.to_string())
   }
 
-  Ok(downloaded_sizes)
+  fn location(&self) -> (PathBuf, u64) {
+    (self.path.clone(), self.db.size_on_disk().unwrap_or(0))
+  }
 }
 
+static HASH_CACHE_STORE: std::sync::OnceLock<Box<dyn FileHashCache>> = std::sync::OnceLock::new();
 
-#[tauri::command]
-async fn get_files_to_update(window: tauri::Window) -> Result<Vec<FileInfo>, String> {
-  println!("Starting get_files_to_update (normal - using cache)");
+/// The shared hash cache, opened on first use. Builds with the `sled-cache`
+/// feature use the embedded store and fall back to file_cache.json when it
+/// can't be opened (for example while another launcher instance holds it).
+fn hash_cache() -> &'static dyn FileHashCache {
+  HASH_CACHE_STORE.get_or_init(open_hash_cache).as_ref()
+}
 
-  let start_time = Instant::now();
-  let server_hash_file = get_server_hash_file().await?;
+fn open_hash_cache() -> Box<dyn FileHashCache> {
+  let json_path = get_app_data_path("file_cache.json").unwrap_or_else(|_| PathBuf::from("file_cache.json"));
 
-  // Get the path to the game folder, which is the folder that contains the Tera game
-  // files. This is the folder that we will be comparing with the server hash file
-  // to determine which files need to be updated.
-  let local_game_path = get_game_path()?;
-  println!("Local game path: {:?}", local_game_path);
-
-  println!("Attempting to read server hash file");
-  let files = server_hash_file["files"].as_array().ok_or("Invalid server hash file format")?;
-  println!("Server hash file parsed, {} files found", files.len());
-
-  println!("Starting file comparison");
-  let _cache = load_cache_from_disk().unwrap_or_else(|_| HashMap::new());
-  let cache = Arc::new(RwLock::new(_cache));
-
-  let progress_bar = ProgressBar::new(files.len() as u64);
-  progress_bar.set_style(ProgressStyle::default_bar()
-    .template("[{elapsed_precise}] {bar:40.cyan/blue} {pos}/{len} {msg}")
-    .unwrap()
-    .progress_chars("##-"));
-
-  let processed_count = Arc::new(AtomicUsize::new(0));
-  let files_to_update_count = Arc::new(AtomicUsize::new(0));
-  let total_size = Arc::new(AtomicU64::new(0));
-
-  let files_to_update: Vec<FileInfo> = files.par_iter().enumerate()
-    .filter_map(|(_index, file_info)| {
-      let path = file_info["path"].as_str().unwrap_or("");
-      let server_hash = file_info["hash"].as_str().unwrap_or("");
-      let size = file_info["size"].as_u64().unwrap_or(0);
-      let url = file_info["url"].as_str().unwrap_or("").to_string();
-
-      let local_file_path = local_game_path.join(path);
-
-      let current_count = processed_count.fetch_add(1, Ordering::SeqCst) + 1;
-      if current_count % 100 == 0 || current_count == files.len() {
-        let progress_payload = FileCheckProgress {
-          current_file: path.to_string(),
-          progress: (current_count as f64 / files.len() as f64) * 100.0,
-          current_count,
-          total_files: files.len(),
-          elapsed_time: start_time.elapsed().as_secs_f64(),
-          files_to_update: files_to_update_count.load(Ordering::SeqCst),
-        };
-
-        let _ = window.emit("file_check_progress", progress_payload)
-          .map_err(|e| {
-            println!("Error emitting file_check_progress event: {}", e);
-            e.to_string()
-          });
+  #[cfg(feature = "sled-cache")]
+  {
+    let store_path = json_path.with_file_name("file_cache.db");
+    match SledHashCache::open(store_path.clone(), &json_path) {
+      Ok(store) => return Box::new(store),
+      Err(e) => {
+        let warning = format!("Failed to open cache store {}, using {}: {}", store_path.display(), json_path.display(), e);
+        eprintln!("{}", warning);
+        let _ = log_error_to_file(&warning);
       }
+    }
+  }
 
-      progress_bar.inc(1);
-
-      if !local_file_path.exists() {
-        files_to_update_count.fetch_add(1, Ordering::SeqCst);
-        total_size.fetch_add(size, Ordering::SeqCst);
-        return Some(FileInfo {
-          path: path.to_string(),
-          hash: server_hash.to_string(),
-          size,
-          url,
-        });
-      }
+  Box::new(JsonHashCache::open(json_path))
+}
 
-      let metadata = match fs::metadata(&local_file_path) {
-        Ok(m) => m,
-        Err(_) => {
-          files_to_update_count.fetch_add(1, Ordering::SeqCst);
-          total_size.fetch_add(size, Ordering::SeqCst);
-          return Some(FileInfo {
-            path: path.to_string(),
-            hash: server_hash.to_string(),
-            size,
-            url,
-          });
-        }
-      };
+/// Upper bound on cache entries, in case pruning by path isn't enough.
+const MAX_CACHE_ENTRIES: usize = 200_000;
+
+/// Drops entries for files that are neither in the server manifest nor on
+/// disk, then, if the cache is still over `MAX_CACHE_ENTRIES`, evicts entries
+/// outside the manifest and then the least recently modified files. Returns
+/// the number of entries removed.
+fn prune_cache(cache: &dyn FileHashCache, manifest_paths: &HashSet<&str>, game_path: &Path) -> usize {
+  let mut kept = Vec::new();
+  let mut pruned = 0;
+  for (path, entry) in cache.entries() {
+    if manifest_paths.contains(path.as_str()) || game_path.join(&path).exists() {
+      kept.push((manifest_paths.contains(path.as_str()), entry.last_modified, path));
+    } else {
+      cache.remove(&path);
+      pruned += 1;
+    }
+  }
 
-      let last_modified = metadata.modified().ok();
+  if kept.len() > MAX_CACHE_ENTRIES {
+    kept.sort();
+    let excess = kept.len() - MAX_CACHE_ENTRIES;
+    for (_, _, path) in kept.into_iter().take(excess) {
+      cache.remove(&path);
+      pruned += 1;
+    }
+  }
 
-      let cache_read = cache.read().unwrap();
-      if let Some(cached_info) = cache_read.get(path) {
-        if let Some(lm) = last_modified {
-          if cached_info.last_modified == lm && cached_info.hash == server_hash {
-            return None;
-          }
-        }
-      }
-      drop(cache_read);
+  pruned
+}
 
-      if metadata.len() != size {
-        files_to_update_count.fetch_add(1, Ordering::SeqCst);
-        total_size.fetch_add(size, Ordering::SeqCst);
-        return Some(FileInfo {
-          path: path.to_string(),
-          hash: server_hash.to_string(),
-          size,
-          url,
-        });
-      }
+#[derive(Debug, Serialize)]
+struct CacheInfo {
+  path: String,
+  exists: bool,
+  entries: usize,
+  size_bytes: u64,
+}
 
-      let local_hash = match calculate_file_hash(&local_file_path) {
-        Ok(hash) => hash,
-        Err(_) => {
-          files_to_update_count.fetch_add(1, Ordering::SeqCst);
-          total_size.fetch_add(size, Ordering::SeqCst);
-          return Some(FileInfo {
-            path: path.to_string(),
-            hash: server_hash.to_string(),
-            size,
-            url,
-          });
-        }
-      };
+/// Where the hash cache is stored and how big it is, for diagnostics.
+#[tauri::command]
+fn get_cache_info() -> Result<CacheInfo, String> {
+  let cache = hash_cache();
+  let (path, size_bytes) = cache.location();
+  Ok(CacheInfo {
+    path: path.display().to_string(),
+    exists: path.exists(),
+    entries: cache.len(),
+    size_bytes,
+  })
+}
 
-      let mut cache_write = cache.write().unwrap();
-      cache_write.insert(path.to_string(), CachedFileInfo {
-        hash: local_hash.clone(),
-        last_modified: last_modified.unwrap_or_else(SystemTime::now),
-      });
-      drop(cache_write);
-
-      if local_hash != server_hash {
-        files_to_update_count.fetch_add(1, Ordering::SeqCst);
-        total_size.fetch_add(size, Ordering::SeqCst);
-        Some(FileInfo {
-          path: path.to_string(),
-          hash: server_hash.to_string(),
-          size,
-          url,
-        })
-      } else {
-        None
-      }
-    })
-    .collect();
+/// Applies the manifest mtime and attributes (if any) to a freshly verified
+/// download and records the file in the hash cache so the next check is a
+/// pure cache hit.
+fn record_verified_download(file_path: &Path, file_info: &FileInfo) {
+  if let Some(mtime) = file_info.mtime {
+    let file_time = filetime::FileTime::from_unix_time(mtime as i64, 0);
+    if let Err(e) = filetime::set_file_mtime(file_path, file_time) {
+      println!("Failed to set modification time for {}: {}", file_info.path, e);
+    }
+  }
+  // After the mtime, which can't be set on a read-only file on Windows.
+  if let Some(attributes) = &file_info.attributes {
+    if let Err(e) = attributes.apply(file_path) {
+      println!("Failed to set attributes for {}: {}", file_info.path, e);
+    }
+  }
 
-  progress_bar.finish_with_message("File comparison completed");
+  // Read the time back rather than trusting the manifest value, since the
+  // filesystem may store it at a different precision.
+  match fs::metadata(file_path).and_then(|m| m.modified()) {
+    Ok(last_modified) => {
+      hash_cache().insert(&file_info.path, CachedFileInfo::verified(file_info.hash.clone(), last_modified, file_info.algo));
+    }
+    Err(e) => println!("Failed to read modification time for {}: {}", file_info.path, e),
+  }
+}
 
-  // Save the updated cache to disk
-  let final_cache = cache.read().unwrap();
-  if let Err(e) = save_cache_to_disk(&*final_cache) {
+/// Makes the entries recorded by `record_verified_download` durable.
+fn flush_hash_cache() {
+  if let Err(e) = hash_cache().flush() {
     eprintln!("Failed to save cache to disk: {}", e);
   }
+}
 
-  let total_time = start_time.elapsed();
-  println!("File comparison completed. Files to update: {}", files_to_update.len());
-
-  // Emit a final event with complete statistics
-  let _ = window.emit("file_check_completed", json!({
-    "total_files": files.len(),
-    "files_to_update": files_to_update.len(),
-    "total_size": total_size.load(Ordering::SeqCst),
-    "total_time_seconds": total_time.as_secs(),
-    "average_time_per_file_ms": (total_time.as_millis() as f64) / (files.len() as f64)
-  }));
-
-  Ok(files_to_update)
+/// On-disk record of an in-progress `download_all_files` run, so an update
+/// interrupted by a reboot or crash can resume without another full check.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+struct DownloadSession {
+  files: Vec<FileInfo>,
+  completed: HashSet<String>,
+  downloaded_bytes: u64,
+  total_bytes: u64,
 }
 
-#[tauri::command]
-async fn get_files_to_update_force(window: tauri::Window) -> Result<Vec<FileInfo>, String> {
-  println!("Starting get_files_to_update_force (FORCE MODE - ignoring cache)");
+/// What the frontend needs to offer "Resume previous update".
+#[derive(Debug, Serialize)]
+struct PendingDownloadSession {
+  remaining_files: Vec<FileInfo>,
+  completed_files: usize,
+  total_files: usize,
+  downloaded_bytes: u64,
+  total_bytes: u64,
+}
 
-  let start_time = Instant::now();
-  let server_hash_file = get_server_hash_file().await?;
+fn get_download_session_path() -> Result<PathBuf, String> {
+  get_app_data_path("download_session.json")
+}
 
-  // Get the path to the game folder, which is the folder that contains the Tera game
-  // files. This is the folder that we will be comparing with the server hash file
-  // to determine which files need to be updated.
-  let local_game_path = get_game_path()?;
-  println!("Local game path: {:?}", local_game_path);
+fn save_download_session(session: &DownloadSession) -> Result<(), String> {
+  let session_path = get_download_session_path()?;
+  let serialized = serde_json::to_string(session).map_err(|e| e.to_string())?;
+  let mut file = File::create(session_path).map_err(|e| e.to_string())?;
+  file.write_all(serialized.as_bytes()).map_err(|e| e.to_string())?;
+  Ok(())
+}
 
-  println!("Attempting to read server hash file");
-  let files = server_hash_file["files"].as_array().ok_or("Invalid server hash file format")?;
-  println!("Server hash file parsed, {} files found", files.len());
+fn load_download_session() -> Result<DownloadSession, String> {
+  let session_path = get_download_session_path()?;
+  let contents = fs::read_to_string(session_path).map_err(|e| e.to_string())?;
+  serde_json::from_str(&contents).map_err(|e| e.to_string())
+}
 
-  println!("Starting file comparison (FORCE MODE - empty cache)");
-  // In force mode, we use an empty cache so all files are rechecked
-  let _cache: HashMap<String, CachedFileInfo> = HashMap::new();
-  let cache = Arc::new(RwLock::new(_cache));
+fn clear_download_session() {
+  if let Ok(session_path) = get_download_session_path() {
+    if session_path.exists() {
+      if let Err(e) = fs::remove_file(&session_path) {
+        eprintln!("Failed to remove download session file: {}", e);
+      }
+    }
+  }
+}
 
-  let progress_bar = ProgressBar::new(files.len() as u64);
-  progress_bar.set_style(ProgressStyle::default_bar()
-    .template("[{elapsed_precise}] {bar:40.cyan/blue} {pos}/{len} {msg}")
-    .unwrap()
-    .progress_chars("##-"));
+/// The saved session `files` resume, if they are all among its remaining files.
+fn resumed_download_session(files: &[FileInfo]) -> Option<DownloadSession> {
+  let session = load_download_session().ok()?;
+  let remaining: HashSet<&str> = session.files
+    .iter()
+    .filter(|f| !session.completed.contains(&f.path))
+    .map(|f| f.path.as_str())
+    .collect();
+  let resumes = files.iter().all(|f| remaining.contains(f.path.as_str()));
+  resumes.then_some(session)
+}
 
-  let processed_count = Arc::new(AtomicUsize::new(0));
-  let files_to_update_count = Arc::new(AtomicUsize::new(0));
-  let total_size = Arc::new(AtomicU64::new(0));
 
-  let files_to_update: Vec<FileInfo> = files.par_iter().enumerate()
-    .filter_map(|(_index, file_info)| {
-      let path = file_info["path"].as_str().unwrap_or("");
-      let server_hash = file_info["hash"].as_str().unwrap_or("");
-      let size = file_info["size"].as_u64().unwrap_or(0);
-      let url = file_info["url"].as_str().unwrap_or("").to_string();
+fn get_hash_file_url() -> String {
+  active_region()
+    .and_then(|region| region.hash_file_url)
+    .unwrap_or_else(|| get_config_value("HASH_FILE_URL"))
+}
 
-      let local_file_path = local_game_path.join(path);
+/// Base of relative file URLs, if any is configured.
+fn get_files_server_url() -> Option<String> {
+  active_region()
+    .and_then(|region| region.file_server_url)
+    .or_else(|| get_optional_config_value("FILE_SERVER_URL"))
+}
 
-      let current_count = processed_count.fetch_add(1, Ordering::SeqCst) + 1;
-      if current_count % 100 == 0 || current_count == files.len() {
-        let progress_payload = FileCheckProgress {
-          current_file: path.to_string(),
-          progress: (current_count as f64 / files.len() as f64) * 100.0,
-          current_count,
-          total_files: files.len(),
-          elapsed_time: start_time.elapsed().as_secs_f64(),
-          files_to_update: files_to_update_count.load(Ordering::SeqCst),
-        };
+/// Base of the launcher's login and account endpoints.
+fn get_launcher_base_url() -> String {
+  active_region()
+    .and_then(|region| region.login_url)
+    .unwrap_or_else(|| get_config_value("LAUNCHER_ACTION_URL"))
+}
 
-        let _ = window.emit("file_check_progress", progress_payload)
-          .map_err(|e| {
-            println!("Error emitting file_check_progress event: {}", e);
-            e.to_string()
-          });
-      }
+// ─── Regions ─────────────────────────────────────────────────────────────────
 
-      progress_bar.inc(1);
+/// A patch and login infrastructure the player can pick, e.g. EU or NA.
+/// URLs left out fall back to the flat config keys.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+struct Region {
+  id: String,
+  name: String,
+  #[serde(default)]
+  hash_file_url: Option<String>,
+  #[serde(default)]
+  file_server_url: Option<String>,
+  #[serde(default)]
+  login_url: Option<String>,
+}
 
-      if !local_file_path.exists() {
-        files_to_update_count.fetch_add(1, Ordering::SeqCst);
-        total_size.fetch_add(size, Ordering::SeqCst);
-        return Some(FileInfo {
-          path: path.to_string(),
-          hash: server_hash.to_string(),
-          size,
-          url,
-        });
+impl Region {
+  /// Every URL the region sets must be an absolute http(s) URL with a host,
+  /// since the login and download code builds on them without checking.
+  fn validate(&self) -> Result<(), String> {
+    if self.id.is_empty() {
+      return Err(format!("Region {:?} has no id", self.name));
+    }
+    for url in [&self.hash_file_url, &self.file_server_url, &self.login_url].into_iter().flatten() {
+      let parsed = Url::parse(url).map_err(|e| format!("Region {} has an invalid URL {}: {}", self.id, url, e))?;
+      if !matches!(parsed.scheme(), "http" | "https") || parsed.host_str().is_none() {
+        return Err(format!("Region {} has an invalid URL {}: not an http(s) URL", self.id, url));
       }
+    }
+    Ok(())
+  }
+}
 
-      let metadata = match fs::metadata(&local_file_path) {
-        Ok(m) => m,
-        Err(_) => {
-          files_to_update_count.fetch_add(1, Ordering::SeqCst);
-          total_size.fetch_add(size, Ordering::SeqCst);
-          return Some(FileInfo {
-            path: path.to_string(),
-            hash: server_hash.to_string(),
-            size,
-            url,
-          });
-        }
-      };
-
-      let local_hash = match calculate_file_hash(&local_file_path) {
-        Ok(hash) => hash,
-        Err(_) => {
-          files_to_update_count.fetch_add(1, Ordering::SeqCst);
-          total_size.fetch_add(size, Ordering::SeqCst);
-          return Some(FileInfo {
-            path: path.to_string(),
-            hash: server_hash.to_string(),
-            size,
-            url,
-          });
-        }
-      };
-
-      // In force mode, even if hashes match, we mark the file as needing update
-      // to force a complete rebuild of the cache
-      if server_hash == local_hash {
-        // Update cache with current file info (this rebuilds the cache)
-        cache.write().unwrap().insert(path.to_string(), CachedFileInfo {
-          hash: server_hash.to_string(),
-          last_modified: metadata.modified().unwrap_or(SystemTime::now()),
-        });
-        None
-      } else {
-        files_to_update_count.fetch_add(1, Ordering::SeqCst);
-        total_size.fetch_add(size, Ordering::SeqCst);
-        Some(FileInfo {
-          path: path.to_string(),
-          hash: server_hash.to_string(),
-          size,
-          url,
-        })
+/// Drops the regions that fail `Region::validate`, logging why.
+fn valid_regions(regions: Vec<Region>) -> Vec<Region> {
+  regions.into_iter()
+    .filter(|region| match region.validate() {
+      Ok(()) => true,
+      Err(e) => {
+        eprintln!("Ignoring region: {}", e);
+        false
       }
     })
-    .collect();
+    .collect()
+}
 
-  progress_bar.finish_with_message("File comparison completed");
+lazy_static! {
+  /// Parsed and validated regions, loaded on first use and replaced when
+  /// `get_regions` fetches a new list.
+  static ref REGIONS: RwLock<Option<Vec<Region>>> = RwLock::new(None);
+}
 
-  // Save the rebuilt cache
-  let cache_data = cache.read().unwrap().clone();
-  let _ = save_cache_to_disk(&cache_data);
+#[derive(Debug, Serialize)]
+struct RegionsInfo {
+  regions: Vec<Region>,
+  active_region: Option<String>,
+}
 
-  println!("File comparison completed. Files to update: {}", files_to_update.len());
+fn get_regions_cache_path() -> Result<PathBuf, String> {
+  get_app_data_path("regions.json")
+}
 
-  let total_time = start_time.elapsed();
-  window.emit("file_check_completed", json!({
-    "success": true,
-    "total_files": files.len(),
-    "files_to_update": files_to_update.len(),
-    "total_size": total_size.load(Ordering::SeqCst),
-    "elapsed_time": total_time.as_secs_f64(),
-    "average_time_per_file_ms": (total_time.as_millis() as f64) / (files.len() as f64)
-  })).ok();
+/// Regions from the `REGIONS` config key, or the list last fetched from
+/// `REGIONS_URL`. Empty when neither is set.
+fn load_regions() -> Vec<Region> {
+  if let Some(regions) = REGIONS.read().unwrap().as_ref() {
+    return regions.clone();
+  }
+  let regions = valid_regions(read_regions());
+  *REGIONS.write().unwrap() = Some(regions.clone());
+  regions
+}
 
-  Ok(files_to_update)
+fn read_regions() -> Vec<Region> {
+  if let Some(regions) = get_config_json("REGIONS") {
+    return serde_json::from_value(regions).unwrap_or_else(|e| {
+      eprintln!("Invalid REGIONS in config: {}", e);
+      Vec::new()
+    });
+  }
+  get_regions_cache_path().ok()
+    .and_then(|path| fs::read_to_string(path).ok())
+    .and_then(|contents| serde_json::from_str(&contents).ok())
+    .unwrap_or_default()
 }
 
+/// The region selected with `set_active_region`, if it still exists.
+fn active_region() -> Option<Region> {
+  let id = get_launcher_setting("active_region").filter(|id| !id.is_empty())?;
+  load_regions().into_iter().find(|region| region.id == id)
+}
 
+/// Lists the available regions, refreshing the server-provided list from
+/// `REGIONS_URL` when one is configured.
 #[tauri::command]
-async fn get_game_status(state: tauri::State<'_, GameState>) -> Result<bool, String> {
-  let status = state.status_receiver.lock().await.borrow().clone();
-  let is_launching = *state.is_launching.lock().await;
-  Ok(status || is_launching)
+async fn get_regions() -> Result<RegionsInfo, String> {
+  if let (None, Some(url)) = (get_config_json("REGIONS"), get_optional_config_value("REGIONS_URL")) {
+    let fetched: Result<Vec<Region>, String> = async {
+      let res = http_client()?.get(&url).send().await.map_err(|e| format_reqwest_error(&url, &e))?;
+      if !res.status().is_success() {
+        return Err(format!("{} returned HTTP status {}", url, res.status()));
+      }
+      res.json().await.map_err(|e| format!("Failed to parse {}: {}", url, e))
+    }.await;
+    // A stale list is better than none when the server is unreachable.
+    match fetched {
+      Ok(regions) => {
+        let regions = valid_regions(regions);
+        let path = get_regions_cache_path()?;
+        fs::write(&path, serde_json::to_string(&regions).map_err(|e| e.to_string())?)
+          .map_err(|e| format!("Failed to save {}: {}", path.display(), e))?;
+        *REGIONS.write().unwrap() = Some(regions);
+      }
+      Err(e) => eprintln!("Failed to fetch regions: {}", e),
+    }
+  }
+  Ok(RegionsInfo {
+    regions: load_regions(),
+    active_region: active_region().map(|region| region.id),
+  })
 }
 
+/// Switches to another region; an empty `id` goes back to the flat config
+/// URLs. The cached manifest, the pending update and the applied manifest
+/// version belong to the old servers, so they are dropped and the next
+/// check starts from the new region's full hash file.
 #[tauri::command]
-async fn handle_launch_game(
-  app_handle: tauri::AppHandle,
-  state: tauri::State<'_, GameState>
-) -> Result<String, String> {
-  println!("handle_launch_game: Starting");
-  
-  // Step 1: Check if game is already launching or running
-  let mut is_launching = state.is_launching.lock().await;
-  if *is_launching {
-    println!("handle_launch_game: Already launching");
-    return Err("Game is already launching".to_string());
+async fn set_active_region(id: String, update_state: tauri::State<'_, UpdateState>) -> Result<(), String> {
+  if !id.is_empty() && !load_regions().iter().any(|region| region.id == id) {
+    return Err(format!("Unknown region: {}", id));
   }
-  *is_launching = true;
-
-  let is_running = *state.status_receiver.lock().await.borrow();
-  if is_running {
-    println!("handle_launch_game: Game already running");
-    *is_launching = false;
-    return Err("Game is already running".to_string());
+  if get_launcher_setting("active_region").unwrap_or_default() == id {
+    return Ok(());
   }
+  save_launcher_setting("active_region", &id)?;
+  reset_applied_manifest_version();
 
-  // Step 2: Validate and retrieve authentication info
-  println!("handle_launch_game: Validating authentication info");
-  let (account_name, characters_count, ticket) = {
-    let auth_info = GLOBAL_AUTH_INFO.read()
-      .map_err(|e| {
-        *is_launching = false;
-        format!("Failed to read auth info: {}", e)
-      })?;
-    
-    // Validate all required fields are present and non-empty
-    if auth_info.auth_key.is_empty() {
-      *is_launching = false;
-      return Err("Auth key is missing. Please login again.".to_string());
-    }
-    
-    if auth_info.user_no <= 0 {
-      *is_launching = false;
-      return Err("Invalid user number. Please login again.".to_string());
-    }
-    
-    if auth_info.character_count.is_empty() {
-      *is_launching = false;
-      return Err("Character count is missing. Please login again.".to_string());
+  if let Ok(path) = get_manifest_cache_path() {
+    if path.exists() {
+      fs::remove_file(&path).map_err(|e| format!("Failed to clear the manifest cache: {}", e))?;
     }
-    
-    println!("handle_launch_game: Auth validation successful for user_no: {}", auth_info.user_no);
-    (
-      auth_info.user_no.to_string(),
-      auth_info.character_count.clone(),
-      auth_info.auth_key.clone()
-    )
-  };
+  }
+  update_state.invalidate().await;
+  *CURRENT_MANIFEST_VERSION.write().unwrap() = None;
+  *CURRENT_PATCH_PACKAGE.write().unwrap() = None;
+  *PENDING_APPLIED_MANIFEST.write().unwrap() = None;
+  PENDING_REMOVALS.write().unwrap().clear();
+  println!("Switched to region {}", if id.is_empty() { "(default)" } else { id.as_str() });
+  Ok(())
+}
 
-  // Step 3: Load and validate game configuration
-  let (game_path, game_lang) = match load_config() {
-    Ok(config) => config,
-    Err(e) => {
-      *is_launching = false;
-      return Err(format!("Failed to load game config: {}", e));
+fn find_config_file() -> Option<PathBuf> {
+  // Prefer config.ini next to the executable — stable regardless of cwd
+  if let Ok(exe_path) = env::current_exe() {
+    if let Some(exe_dir) = exe_path.parent() {
+      let config_in_exe_dir = exe_dir.join("config.ini");
+      if config_in_exe_dir.exists() {
+        return Some(config_in_exe_dir);
+      }
     }
-  };
-  
-  println!("handle_launch_game: Game path: {:?}, lang: {}", game_path, game_lang);
-  
-  if !game_path.exists() {
-    *is_launching = false;
-    return Err(format!("Game path does not exist: {:?}", game_path));
   }
 
-  // Step 4: Validate game executable exists (case-insensitive on Linux)
-  let full_game_path = find_game_exe(&game_path.join("Binaries"))
-    .ok_or_else(|| {
-      *is_launching = false;
-      format!("Game executable not found in {:?}/Binaries. Please verify your game installation.", game_path)
-    });
-  let full_game_path = match full_game_path {
-    Ok(p) => p,
-    Err(e) => { *is_launching = false; return Err(e); }
-  };
+  let current_dir = env::current_dir().ok()?;
+  let config_in_current = current_dir.join("config.ini");
+  if config_in_current.exists() {
+    return Some(config_in_current);
+  }
 
-  let full_game_path_str = match full_game_path.to_str() {
-    Some(path_str) => path_str.to_string(),
-    None => {
-      *is_launching = false;
-      return Err("Invalid characters in game executable path".to_string());
-    }
-  };
+  let parent_dir = current_dir.parent()?;
+  let config_in_parent = parent_dir.join("config.ini");
+  if config_in_parent.exists() {
+    return Some(config_in_parent);
+  }
 
-  // Step 5: Retrieve and validate ACTS_MAP and PAGES_MAP
-  let (acts_map_clone, pages_map_clone) = {
-    let acts_map_guard = GLOBAL_ACTS_MAP.read()
-      .map_err(|e| {
-        *is_launching = false;
-        format!("Failed to read ACTS_MAP: {}", e)
-      })?;
-    
-    let pages_map_guard = GLOBAL_PAGES_MAP.read()
-      .map_err(|e| {
-        *is_launching = false;
-        format!("Failed to read PAGES_MAP: {}", e)
-      })?;
-    
-    if acts_map_guard.is_empty() {
-      println!("Warning: ACTS_MAP is empty");
-    }
-    
-    if pages_map_guard.is_empty() {
-      println!("Warning: PAGES_MAP is empty");
-    }
-    
-    println!("handle_launch_game: ACTS_MAP entries: {}, PAGES_MAP entries: {}", 
-      acts_map_guard.len(), 
-      pages_map_guard.len()
-    );
-    
-    (acts_map_guard.clone(), pages_map_guard.clone())
-  };
+  None
+}
 
-  // Step 6: Spawn the game launch in background
-  let app_handle_clone = app_handle.clone();
-  let is_launching_clone = Arc::clone(&state.is_launching);
+/// Get the default configuration file path (in the launcher directory)
+fn get_default_config_path() -> Result<PathBuf, String> {
+  let exe_path = env::current_exe()
+    .map_err(|e| format!("Failed to get launcher directory: {}", e))?;
+  
+  let exe_dir = exe_path.parent()
+    .ok_or("Failed to get launcher parent directory")?;
+  
+  Ok(exe_dir.join("config.ini"))
+}
 
-  tokio::task::spawn(async move {
-    // Emit the game_status_changed event at the start of the launch
-    if let Err(e) = app_handle_clone.emit_all("game_status_changed", true) {
-      error!("Failed to emit game_status_changed event: {:?}", e);
-    }
+/// Create a default config file if it doesn't exist
+fn create_default_config(config_path: &PathBuf) -> Result<(), String> {
+  let exe_path = env::current_exe()
+    .map_err(|e| format!("Failed to get launcher directory: {}", e))?;
+  
+  let exe_dir = exe_path.parent()
+    .ok_or("Failed to get launcher parent directory")?;
+  
+  let mut conf = Ini::new();
+  conf.with_section(Some("game"))
+    .set("lang", "EUR")
+    .set("path", exe_dir.to_str().ok_or("Invalid launcher path")?);
 
-    info!("Launching game with executable: {}", full_game_path_str);
-    let launch_error: Option<String>;
-    match
-      run_game(
-        &account_name,
-        &characters_count,
-        &ticket,
-        &game_lang,
-        &full_game_path_str,
-        acts_map_clone,
-        pages_map_clone,
-      ).await
-    {
-      Ok(exit_status) => {
-        let result = format!("Game exited with status: {:?}", exit_status);
-        app_handle_clone.emit_all("game_status", &result).unwrap();
-        info!("{}", result);
-        launch_error = None;
-      }
-      Err(e) => {
-        let error = format!("Error launching game: {:?}", e);
-        app_handle_clone.emit_all("game_status", &error).unwrap();
-        error!("{}", error);
-        launch_error = Some(e.to_string());
-      }
-    }
+  let mut file = File::create(&config_path)
+    .map_err(|e| format!("Failed to create config file: {}", e))?;
 
-    // Emit structured exit info (code + reason) so the frontend can show a message.
-    {
-      let exit_info = get_last_exit_info();
-      let crash_details = get_last_crash_details();
-      let stderr = get_last_game_stderr();
-      let payload = serde_json::json!({
-        "code":   exit_info.code,
-        "reason": exit_info.reason,
-        "crash":  !crash_details.is_empty(),
-        "details": crash_details,
-        "stderr": stderr,
-        // non-null when run_game() itself errored (not a TERA exit code)
-        "launch_error": launch_error,
-      });
-      if let Err(e) = app_handle_clone.emit_all("game_exit_info", payload) {
-        error!("Failed to emit game_exit_info event: {:?}", e);
-      }
-    }
+  conf.write_to(&mut file)
+    .map_err(|e| format!("Failed to write config: {}", e))?;
+  
+  info!("Created default config.ini at {:?}", config_path);
+  
+  Ok(())
+}
 
-    info!("Emitting game_ended event");
-    if let Err(e) = app_handle_clone.emit_all("game_ended", ()) {
-      error!("Failed to emit game_ended event: {:?}", e);
-    }
+fn load_config() -> Result<(PathBuf, String), String> {
+  // Try to find existing config file
+  let config_path = if let Some(path) = find_config_file() {
+    path
+  } else {
+    // If not found, create default config at launcher directory
+    let default_path = get_default_config_path()?;
+    create_default_config(&default_path)?;
+    default_path
+  };
 
-    let mut is_launching = is_launching_clone.lock().await;
-    *is_launching = false;
-    if let Err(e) = app_handle_clone.emit_all("game_status_changed", false) {
-      error!("Failed to emit game_status_changed event: {:?}", e);
-    }
+  let conf = Ini::load_from_file(&config_path).map_err(|e|
+    format!("Failed to load config: {}", e)
+  )?;
 
-    reset_global_state();
+  let section = conf.section(Some("game")).ok_or("Game section not found in config")?;
 
-    info!("Game launch state reset");
-  });
+  let game_path = section.get("path").ok_or("Game path not found in config")?;
 
-  Ok("Game launch initiated".to_string())
+  let game_path = PathBuf::from(game_path);
+
+  let game_lang = section.get("lang").ok_or("Game language not found in config")?.to_string();
+
+  Ok((game_path, game_lang))
 }
 
+/// Language codes the client ships with, unless the embedded config lists
+/// its own under `SUPPORTED_LANGUAGES`.
+const DEFAULT_SUPPORTED_LANGUAGES: [&str; 4] = ["EUR", "FRA", "RUS", "GER"];
 
-#[tauri::command]
-fn get_language_from_config() -> Result<String, String> {
-  info!("Attempting to read language from config file");
-  let (_, game_lang) = load_config()?;
-  info!("Language read from config: {}", game_lang);
-  Ok(game_lang)
+fn supported_languages() -> Vec<String> {
+  let configured: Vec<String> = get_config_list("SUPPORTED_LANGUAGES").iter()
+    .map(|lang| lang.to_ascii_uppercase())
+    .collect();
+  if configured.is_empty() {
+    DEFAULT_SUPPORTED_LANGUAGES.iter().map(|lang| lang.to_string()).collect()
+  } else {
+    configured
+  }
+}
+
+/// Trims and uppercases a language code and checks it's one the client
+/// supports.
+fn normalize_language(lang: &str) -> Result<String, String> {
+  let code = lang.trim().to_ascii_uppercase();
+  let supported = supported_languages();
+  if supported.contains(&code) {
+    Ok(code)
+  } else {
+    Err(format!("Unsupported language \"{}\"; expected one of {}", lang.trim(), supported.join(", ")))
+  }
 }
 
+/// Language codes for the settings dropdown.
 #[tauri::command]
-fn save_language_to_config(language: String) -> Result<(), String> {
-  info!("Attempting to save language {} to config file", language);
+fn get_supported_languages() -> Vec<String> {
+  supported_languages()
+}
+
+/// Reads an optional key from the `[launcher]` section of config.ini.
+fn get_launcher_setting(key: &str) -> Option<String> {
+  load_launcher_settings().get(key).map(|v| v.to_string())
+}
+
+/// Reads the whole `[launcher]` section, for callers that need several keys.
+/// Missing config or section yields an empty set.
+fn load_launcher_settings() -> ini::Properties {
+  find_config_file()
+    .and_then(|config_path| Ini::load_from_file(&config_path).ok())
+    .and_then(|conf| conf.section(Some("launcher")).cloned())
+    .unwrap_or_default()
+}
+
+/// Writes a key to the `[launcher]` section of config.ini.
+fn save_launcher_setting(key: &str, value: &str) -> Result<(), String> {
   let config_path = find_config_file().ok_or("Config file not found")?;
   let mut conf = Ini::load_from_file(&config_path).map_err(|e|
     format!("Failed to load config: {}", e)
   )?;
 
-  conf.with_section(Some("game")).set("lang", &language);
+  conf.with_section(Some("launcher")).set(key, value);
 
   conf.write_to_file(&config_path).map_err(|e| format!("Failed to write config: {}", e))?;
 
-  info!("Language successfully saved to config");
   Ok(())
 }
 
-#[tauri::command]
-async fn reset_launch_state(state: tauri::State<'_, GameState>) -> Result<(), String> {
-  let mut is_launching = state.is_launching.lock().await;
-  *is_launching = false;
+/* fn save_config(game_path: &Path, game_lang: &str) -> Result<(), String> {
+  let config_path = find_config_file().ok_or("Config file not found")?;
+  let mut conf = Ini::new();
+
+  conf.with_section(Some("game")).set("path", game_path.to_str().ok_or("Invalid game path")?);
+  conf.with_section(Some("game")).set("lang", game_lang);
+
+  let mut file = std::fs::File
+    ::create(&config_path)
+    .map_err(|e| format!("Failed to create config file: {}", e))?;
+
+  conf.write_to(&mut file).map_err(|e| format!("Failed to write config: {}", e))?;
+
   Ok(())
-}
+} */
 
-#[tauri::command]
-async fn set_auth_info( 
-  auth_key: String, 
-  user_name: String, 
-  user_no: i32, 
-  character_count: String,
-  session_cookie: Option<String>, 
-) { 
-  {
-    let mut auth_info = GLOBAL_AUTH_INFO.write().unwrap();
-    auth_info.auth_key = auth_key;
-    auth_info.user_name = user_name;
-    auth_info.user_no = user_no;
-    auth_info.character_count = character_count;
+async fn get_maintenance_status() -> Result<MaintenanceResponse, String> { 
+  let client = http_client()?;
+  let base_url = &get_launcher_base_url(); 
+  let maintenance_url = format!("{}/launcher/GetMaintenanceStatusAction", base_url);
 
-    info!("Auth info set from frontend:");
-    info!("User Name: {}", auth_info.user_name);
-    info!("User No: {}", auth_info.user_no);
-    info!("Character Count: {}", auth_info.character_count);
-    info!("Auth Key: {}", auth_info.auth_key);
+  let res = client
+    .get(&maintenance_url)
+    .send().await
+    .map_err(|e| {
+      let detailed_error = format_reqwest_error(&maintenance_url, &e);
+      let error_msg = format!("Failed to connect to maintenance server: {}", detailed_error);
+      let _ = log_error_to_file(&error_msg);
+      error_msg
+    })?;
+
+  if !res.status().is_success() {
+    let error_msg = format!(
+      "Maintenance check request failed with HTTP status: {} (URL: {})",
+      res.status(), maintenance_url
+    );
+    let _ = log_error_to_file(&error_msg);
+    return Err(error_msg);
   }
 
-  if let Some(cookie_value) = session_cookie {
-    if !cookie_value.is_empty() {
-      info!("Rebuilding authenticated client from stored cookie...");
-      let base_url = &*LAUNCHER_BASE_URL;
-      let url = Url::parse(base_url).expect("Failed to parse LAUNCHER_BASE_URL");
-      let host = url.host_str().expect("LAUNCHER_BASE_URL has no host");
+  let maintenance_body: MaintenanceResponse = res
+    .json()
+    .await
+    .map_err(|e| {
+      let error_msg = format!("Failed to parse maintenance response: {} (URL: {})", e, maintenance_url);
+      let _ = log_error_to_file(&error_msg);
+      error_msg
+    })?;
 
-      // Build cookie
-      let cookie_str = format!("launcher.sid={}; Domain={}; Path=/", cookie_value, host);
-      
-      let jar = Arc::new(Jar::default());
-      jar.add_cookie_str(&cookie_str, &url);
+  if !maintenance_body.return_value {
+    return Err(format!("Maintenance check API error: {}", maintenance_body.msg));
+  }
 
-      // Build new client using the cookie jar
-      let client = Client::builder()
-        .cookie_store(true)
-        .cookie_provider(jar)
-        .build()
-        .expect("Failed to rebuild client");
+  Ok(maintenance_body)
+}
 
-      // Store client globally
-      let mut client_guard = AUTHENTICATED_CLIENT.lock().await; // <-- 6. 'await' is now valid
-      *client_guard = Some(client);
-      info!("Authenticated client rebuilt successfully.");
-    } else {
-      info!("No session cookie found to rebuild client.");
+#[tauri::command]
+async fn check_maintenance_and_notify(window: tauri::Window) -> Result<bool, String> {
+  match get_maintenance_status().await {
+    Ok(response) => {
+      let is_maintenance = response.start_time.is_some() || response.end_time.is_some();
+
+      if is_maintenance {
+        // Emit the event with full maintenance details for the modal
+        let payload = serde_json::to_value(&response)
+          .unwrap_or(json!({"msg": "Active maintenance"}));
+
+        if let Err(e) = window.emit("maintenance_active", payload) {
+          error!("Failed to emit maintenance_active event: {:?}", e);
+        }
+      }
+
+      // Return 'true' if maintenance is active, 'false' otherwise
+      Ok(is_maintenance)
+    }
+    Err(e) => {
+      error!("Error checking maintenance status: {:?}", e);
+      // Return a specific error so the frontend can handle it as a network issue
+      Err(format!("ERROR_NETWORK_CHECK: {}", e))
     }
   }
 }
 
 
-/// Handles the complete login process for the TERA launcher.
-///
-/// ### Overview
-/// This function:
-/// 1. Authenticates the user using their credentials.
-/// 2. Retrieves the session cookie and essential account details (account info, auth key, character count).
-/// 3. Fetches and parses the main launcher HTML page to extract `ACTS_MAP` and `PAGES_MAP`.
-/// 4. Stores these maps globally for future use.
-/// 5. Returns a structured JSON response with all relevant login and session data.
-///
-/// The function communicates with the launcher’s backend endpoints, maintains cookies
-/// across requests, and reconstructs necessary URLs dynamically using `LAUNCHER_BASE_URL`.
-///
-/// ### Arguments
-/// * `username` - The user's login name.
-/// * `password` - The user's password.
-///
-/// ### Returns
-/// * `Ok(String)` - JSON containing authentication results and user data.
-/// * `Err(String)` - A descriptive error message in case of failure.
-#[tauri::command]
-async fn login(username: String, password: String) -> Result<String, String> {
-    // 1. Create an HTTP client with a persistent cookie jar
-    let cookie_jar = Arc::new(Jar::default());
-    let client = Client::builder()
-        .cookie_store(true)
-        .cookie_provider(Arc::clone(&cookie_jar))
-        .timeout(Duration::from_secs(30))
-        .build()
-        .map_err(|e| {
-          let error_msg = format!("Failed to build HTTP client: {}", e);
-          let _ = log_error_to_file(&error_msg);
-          error_msg
-        })?;
+/// Priority assigned by `generate_hash_file` when the game directory has no
+/// `priority-rules.txt`: executables first, then the cooked packages.
+const DEFAULT_PRIORITY_RULES: &str = "0 Binaries/\n1 S1Game/CookedPC/\n";
+/// Order used for files without a priority.
+const DEFAULT_FILE_PRIORITY: u32 = 100;
+
+/// Parses `priority-rules.txt` from the game directory. Each line is
+/// `<priority> <pattern>`, where a pattern is a path prefix (`S1Game/CookedPC/`),
+/// an extension (`*.exe`) or an exact path. `#` starts a comment.
+fn load_priority_rules(game_path: &Path) -> Vec<(u32, String)> {
+  let contents = fs::read_to_string(game_path.join("priority-rules.txt"))
+    .unwrap_or_else(|_| DEFAULT_PRIORITY_RULES.to_string());
+  contents.lines()
+    .map(|line| line.split('#').next().unwrap_or("").trim())
+    .filter_map(|line| {
+      let (priority, pattern) = line.split_once(char::is_whitespace)?;
+      Some((priority.parse().ok()?, pattern.trim().replace("\\", "/")))
+    })
+    .collect()
+}
 
-    // --- Step 1: Define Base URL ---
-    // The base launcher URL is obtained once from the global constant.
-    let base_url = &*LAUNCHER_BASE_URL;
-    let login_url = format!("{}/launcher/LoginAction", base_url);
+/// Returns the priority of the first rule matching `relative_path`.
+fn file_priority(rules: &[(u32, String)], relative_path: &str) -> Option<u32> {
+  rules.iter()
+    .find(|(_, pattern)| matches_rule_pattern(pattern, relative_path))
+    .map(|(priority, _)| *priority)
+}
 
-    // --- Step 2: POST to /launcher/LoginAction (Authentication) ---
-    // Use .form() to properly encode parameters and prevent URL injection attacks
-    let login_res = client
-        .post(&login_url)
-        .form(&[("login", username.as_str()), ("password", password.as_str())])
-        .send()
-        .await
-        .map_err(|e| {
-          let detailed_error = format_reqwest_error(&login_url, &e);
-          let error_msg = format!("Login connection failed: {}", detailed_error);
-          let _ = log_error_to_file(&error_msg);
-          error_msg
-        })?;
+/// Pattern syntax shared by `priority-rules.txt` and `content-groups.txt`.
+fn matches_rule_pattern(pattern: &str, relative_path: &str) -> bool {
+  match pattern.strip_prefix('*') {
+    Some(extension) => relative_path.ends_with(extension),
+    None => relative_path.starts_with(pattern),
+  }
+}
 
-    // --- Parse the login response and extract session cookie ---
-    if !login_res.status().is_success() {
-        let error_msg = format!("Login request failed with HTTP status: {} (URL: {})", login_res.status(), login_url);
-        let _ = log_error_to_file(&error_msg);
-        return Err(error_msg);
-    }
+/// Parses `content-groups.txt` from the game directory. Each line is
+/// `<group> <pattern>` with the patterns of `priority-rules.txt`, e.g.
+/// `voice_jp S1Game/CookedPC/Sound/JPN/`. Files matching no line belong to
+/// the base game and are always installed.
+fn load_content_group_rules(game_path: &Path) -> Vec<(String, String)> {
+  fs::read_to_string(game_path.join("content-groups.txt"))
+    .unwrap_or_default()
+    .lines()
+    .map(|line| line.split('#').next().unwrap_or("").trim())
+    .filter_map(|line| {
+      let (group, pattern) = line.split_once(char::is_whitespace)?;
+      Some((group.to_string(), pattern.trim().replace("\\", "/")))
+    })
+    .collect()
+}
 
-    let login_body: InitialLoginResponse = login_res
-        .json()
-        .await
-        .map_err(|e| {
-          let error_msg = format!("Failed to parse login response: {} (URL: {})", e, login_url);
-          let _ = log_error_to_file(&error_msg);
-          error_msg
-        })?;
+/// Returns the group of the first rule matching `relative_path`.
+fn content_group(rules: &[(String, String)], relative_path: &str) -> Option<String> {
+  rules.iter()
+    .find(|(_, pattern)| matches_rule_pattern(pattern, relative_path))
+    .map(|(group, _)| group.clone())
+}
 
-    if !login_body.return_value {
-        return Err(login_body.msg);
-    }
+/// Unix time recorded in generated manifests. `SOURCE_DATE_EPOCH` pins it, so
+/// runs over identical content give byte-identical output.
+fn manifest_timestamp() -> u64 {
+  env::var("SOURCE_DATE_EPOCH").ok()
+    .and_then(|v| v.trim().parse().ok())
+    .unwrap_or_else(|| SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap_or_default().as_secs())
+}
 
-    // Parse the cookies to retrieve the session identifier (launcher.sid)
-    let login_url_parsed = Url::parse(&login_url)
-        .map_err(|e| {
-          let error_msg = format!("Failed to parse login URL: {} (URL: {})", e, login_url);
-          let _ = log_error_to_file(&error_msg);
-          error_msg
-        })?;
-    let cookie_header_value = cookie_jar.cookies(&login_url_parsed);
+/// The hash file's header and entries. `files` come from
+/// `hash_game_directory` already sorted by path, so the same content and
+/// timestamp always give the same manifest.
+fn build_hash_file_manifest(version: &str, generated_at: u64, algo: HashAlgorithm, files: &[FileInfo], total_size: u64) -> serde_json::Value {
+  json!({
+    "version": version,
+    "generated_at": generated_at,
+    "generator_version": env!("CARGO_PKG_VERSION"),
+    "algo": algo,
+    "total_files": files.len(),
+    "total_size": total_size,
+    "files": files
+  })
+}
 
-    let session_cookie: Option<String> = cookie_header_value
-        .and_then(|header_val| header_val.to_str().ok().map(String::from))
-        .and_then(|cookie_str| {
-            cookie_str.split(';').find_map(|cookie_pair| {
-                let cookie_pair = cookie_pair.trim();
-                if cookie_pair.starts_with("launcher.sid=") {
-                    Some(cookie_pair.trim_start_matches("launcher.sid=").to_string())
-                } else {
-                    None
-                }
-            })
-        });
+/// Output settings for `generate_hash_file`. Every field is optional; the
+/// defaults write `<game_path>/hash-file.json` with `FILE_SERVER_URL` URLs.
+#[derive(Debug, Deserialize, Default)]
+struct HashFileOptions {
+  /// Where to write the manifest. Relative paths are resolved against the
+  /// game directory; `version.json` and `diff/` are written next to it.
+  output_path: Option<String>,
+  /// Base of the file URLs, for manifests served from another CDN.
+  base_url: Option<String>,
+  /// Write `files/<path>` URLs, resolved against the client's
+  /// `FILE_SERVER_URL` at download time, so the manifest works on any host.
+  relative_urls: Option<bool>,
+  /// Write without indentation (default true).
+  compact: Option<bool>,
+  /// Record file modification times (default true).
+  include_mtime: Option<bool>,
+  /// Manifest whose hashes are reused for files with the same size and
+  /// mtime. Defaults to the existing file at `output_path`.
+  previous_manifest: Option<String>,
+  /// Hash every file even when the previous manifest has it.
+  force: Option<bool>,
+  /// Paths ignored on top of the defaults and the game directory's `ignore.json`.
+  #[serde(default)]
+  extra_ignores: Vec<String>,
+  /// Descend into symlinked directories and junctions. Defaults to the
+  /// `follow_symlinks` launcher setting.
+  follow_symlinks: Option<bool>,
+}
 
-    let success_msg = login_body.msg.clone();
+#[derive(Debug, Serialize)]
+struct HashFileResult {
+  output_path: String,
+  file_count: u64,
+  total_size: u64,
+  /// Files whose hashes were carried over from the previous manifest.
+  reused_hashes: u64,
+  /// Files left out of the manifest because they could not be read.
+  skipped: Vec<SkippedFile>,
+  /// How many of `skipped` have a name that isn't valid UTF-8.
+  skipped_encoding: u64,
+  duration_ms: u64,
+}
 
-    // --- Step 3: Retrieve account data using the authenticated client ---
-    // These endpoints depend on the valid session cookie.
-    let account_info_url = format!("{}/launcher/GetAccountInfoAction", base_url);
-    let account_info: AccountInfoResponse = client
-        .get(&account_info_url)
-        .send()
-        .await
-        .map_err(|e| {
-          let detailed_error = format_reqwest_error(&account_info_url, &e);
-          let error_msg = format!("Failed to get account info: {}", detailed_error);
-          let _ = log_error_to_file(&error_msg);
-          error_msg
-        })?
-        .json()
-        .await
-        .map_err(|e| {
-          let error_msg = format!("Failed to parse account info: {} (URL: {})", e, account_info_url);
-          let _ = log_error_to_file(&error_msg);
-          error_msg
-        })?;
+#[derive(Debug, Serialize, Clone)]
+struct SkippedFile {
+  path: String,
+  error: String,
+}
 
-    let auth_key_url = format!("{}/launcher/GetAuthKeyAction", base_url);
-    let auth_key: AuthKeyResponse = client
-        .get(&auth_key_url)
-        .send()
-        .await
-        .map_err(|e| {
-          let detailed_error = format_reqwest_error(&auth_key_url, &e);
-          let error_msg = format!("Failed to get auth key: {}", detailed_error);
-          let _ = log_error_to_file(&error_msg);
-          error_msg
-        })?
-        .json()
-        .await
-        .map_err(|e| {
-          let error_msg = format!("Failed to parse auth key: {} (URL: {})", e, auth_key_url);
-          let _ = log_error_to_file(&error_msg);
-          error_msg
-        })?;
+/// Entries of a manifest by path, with the algorithm resolved per entry.
+fn manifest_entries_by_path(manifest: &serde_json::Value) -> HashMap<String, FileInfo> {
+  let manifest_algo = HashAlgorithm::for_manifest(manifest);
+  manifest["files"].as_array()
+    .map(|files| files.iter()
+      .filter_map(|entry| {
+        let mut info: FileInfo = serde_json::from_value(entry.clone()).ok()?;
+        info.algo = HashAlgorithm::for_entry(manifest_algo, entry);
+        Some((info.path.clone(), info))
+      })
+      .collect())
+    .unwrap_or_default()
+}
 
-    let char_count_url = format!("{}/launcher/GetCharacterCountAction", base_url);
-    let char_count: CharCountResponse = client
-        .get(&char_count_url)
-        .send()
-        .await
-        .map_err(|e| {
-          let detailed_error = format_reqwest_error(&char_count_url, &e);
-          let error_msg = format!("Failed to get character count: {}", detailed_error);
-          let _ = log_error_to_file(&error_msg);
-          error_msg
-        })?
-        .json()
-        .await
-        .map_err(|e| {
-          let error_msg = format!("Failed to parse character count: {} (URL: {})", e, char_count_url);
-          let _ = log_error_to_file(&error_msg);
-          error_msg
-        })?;
+/// Whether `previous` can stand in for hashing a file of `size` and `mtime`
+/// with the current settings.
+fn can_reuse_hash(previous: &FileInfo, size: u64, mtime: u64, algo: HashAlgorithm, chunk_size: u64, with_quick_hash: bool) -> bool {
+  let wants_chunks = chunk_size > 0 && size > CHUNKED_FILE_THRESHOLD.max(chunk_size);
+  let chunks_match = match &previous.chunks {
+    Some(chunks) => wants_chunks && chunks.first().map(|c| c.size) == Some(chunk_size.min(size)),
+    None => !wants_chunks,
+  };
+  previous.size == size
+    && previous.mtime == Some(mtime)
+    && previous.algo == algo
+    && chunks_match
+    && (!with_quick_hash || previous.quick_hash.is_some())
+}
 
-    // --- Step 4: GET /launcher/Main to extract ActsMap/PagesMap ---
-    // Important: Add locale if the server requires it to serve localized pages.
-    let main_url = format!("{}/launcher/Main?locale=en", base_url);
+/// How `hash_game_directory` walks the game directory and builds entries.
+struct DirectoryHashOptions<'a> {
+  algo: HashAlgorithm,
+  with_quick_hash: bool,
+  /// 0 turns chunk hashes off.
+  chunk_size: u64,
+  include_mtime: bool,
+  follow_symlinks: bool,
+  /// Prepended to `files/<path>` to form each entry's URL; entries get no
+  /// URL without one.
+  url_prefix: Option<&'a str>,
+  ignore_rules: &'a IgnoreRules,
+  priority_rules: &'a [(u32, String)],
+  group_rules: &'a [(String, String)],
+  /// Hashes reused for files with the same size and mtime.
+  previous_entries: &'a HashMap<String, FileInfo>,
+  /// Also reuse hash cache entries whose mtime matches. The cache is only
+  /// read, so it keeps vouching for verified files alone.
+  use_hash_cache: bool,
+}
 
-    let main_res = client
-        .get(&main_url)
-        .send()
-        .await
-        .map_err(|e| {
-          let detailed_error = format_reqwest_error(&main_url, &e);
-          let error_msg = format!("Failed to get launcher main page: {}", detailed_error);
-          let _ = log_error_to_file(&error_msg);
-          error_msg
-        })?;
+struct DirectoryHashes {
+  files: Vec<FileInfo>,
+  total_size: u64,
+  reused_hashes: u64,
+  skipped: Vec<SkippedFile>,
+  skipped_encoding: u64,
+}
 
-    if !main_res.status().is_success() {
-        let error_msg = format!(
-            "Launcher main page request failed with HTTP status: {} (URL: {})",
-            main_res.status(), main_url
-        );
-        let _ = log_error_to_file(&error_msg);
-        return Err(error_msg);
-    }
+/// Payload of `hash_file_progress`.
+fn hash_progress_payload(current_file: &str, processed: u64, total_files: u64, total_size: u64, reused: u64) -> serde_json::Value {
+  json!({
+    "current_file": current_file,
+    "progress": percent(processed, total_files),
+    "processed_files": processed,
+    "total_files": total_files,
+    "total_size": total_size,
+    "reused_hashes": reused,
+    "computed_hashes": processed.saturating_sub(reused),
+  })
+}
 
-    let main_html = main_res
-        .text()
-        .await
-        .map_err(|e| {
-          let error_msg = format!("Failed to read main page body: {} (URL: {})", e, main_url);
-          let _ = log_error_to_file(&error_msg);
-          error_msg
-        })?;
+/// Hashes every file under `game_path` that isn't ignored, emitting
+/// `hash_file_progress` as it goes. Returns `UPDATE_CANCELLED` after
+/// `cancel_hash_generation`.
+fn hash_game_directory<R: tauri::Runtime>(window: &tauri::Window<R>, game_path: &Path, options: &DirectoryHashOptions) -> Result<DirectoryHashes, String> {
+  let DirectoryHashOptions { algo, with_quick_hash, chunk_size, include_mtime, follow_symlinks, .. } = *options;
+  // Files at the root belong to the launcher or installer and are never
+  // part of the hash file.
+  let is_ignored = |path: &Path| {
+    let relative = path.strip_prefix(game_path).unwrap_or(path).to_string_lossy();
+    path_components(&relative).len() <= 1 || options.ignore_rules.is_ignored(&relative)
+  };
+  let cache = options.use_hash_cache.then(hash_cache);
+  // Cache entries carry only the full hash.
+  let cache = cache.filter(|_| chunk_size == 0 && !with_quick_hash);
 
-    // KEY STEP: Pass `base_url` so that `extract_maps_from_html` can rebuild the full URLs in ACTS_MAP.
-    let (acts_map, pages_map) = extract_maps_from_html(&main_html, base_url)?;
+  // Symlinked files count as files either way and are hashed by their target.
+  let total_files = WalkDir::new(game_path)
+    .follow_links(follow_symlinks)
+    .into_iter()
+    .filter_map(|e| e.ok())
+    .filter(|e| e.path().is_file())
+    .filter(|e| !is_ignored(e.path()))
+    .count();
+  info!("Total files to process: {}", total_files);
 
-    // --- Step 5: Save parsed maps into global state ---
-    if let Some(map_object) = acts_map.as_object() {
-        let mut acts_map_guard = GLOBAL_ACTS_MAP.write().unwrap();
-        acts_map_guard.clear();
-        for (key, value) in map_object {
-            if let Some(url_str) = value.as_str() {
-                acts_map_guard.insert(key.clone(), url_str.to_string());
-            }
-        }
-        info!("Saved GLOBAL_ACTS_MAP with {} entries", acts_map_guard.len());
-    }
+  let progress_bar = ProgressBar::new(total_files as u64);
+  let progress_style = ProgressStyle::default_bar()
+    .template("[{elapsed_precise}] {bar:40.cyan/blue} {pos}/{len} {msg}")
+    .map_err(|e| e.to_string())?
+    .progress_chars("##-");
+  progress_bar.set_style(progress_style);
 
-    if let Some(map_object) = pages_map.as_object() {
-        let mut pages_map_guard = GLOBAL_PAGES_MAP.write().unwrap();
-        pages_map_guard.clear(); // Clear previous map before inserting new values
-        for (key, value) in map_object {
-            if let Some(url_str) = value.as_str() {
-                pages_map_guard.insert(key.clone(), url_str.to_string());
-            }
+  let processed_files = AtomicU64::new(0);
+  let reused_hashes = AtomicU64::new(0);
+  let skipped_encoding = AtomicU64::new(0);
+  let total_size = AtomicU64::new(0);
+  let files = Mutex::new(Vec::new());
+  let skipped = Mutex::new(Vec::new());
+
+  let pool = build_hash_thread_pool()?;
+  let result: Result<(), String> = tokio::task::block_in_place(|| pool.install(|| WalkDir::new(game_path)
+    .follow_links(follow_symlinks)
+    .into_iter()
+    .par_bridge()
+    .try_for_each(|entry| -> Result<(), String> {
+      if is_hash_generation_cancelled() {
+        return Err(UPDATE_CANCELLED.to_string());
+      }
+      let relative = |path: &Path| path.strip_prefix(game_path).unwrap_or(path).to_string_lossy().replace("\\", "/");
+      // Unreadable files are reported instead of failing the whole run.
+      let skip = |path: String, error: String| {
+        error!("Skipping {}: {}", path, error);
+        skipped.blocking_lock().push(SkippedFile { path, error });
+        let current_processed = processed_files.fetch_add(1, Ordering::Relaxed) + 1;
+        progress_bar.set_position(current_processed);
+      };
+      let entry = match entry {
+        Ok(entry) => entry,
+        Err(e) => {
+          skip(e.path().map(relative).unwrap_or_default(), e.to_string());
+          return Ok(());
         }
-        info!("Saved GLOBAL_PAGES_MAP with {} entries", pages_map_guard.len());
-    }
+      };
+      let path = entry.path();
+      if path.is_file() && !is_ignored(path) {
+        let relative_path = relative(path);
+        // Manifest paths are JSON strings, so a name that isn't UTF-8 can't be
+        // listed; it's reported under its lossy name instead.
+        if path.strip_prefix(game_path).ok().and_then(Path::to_str).is_none() {
+          skipped_encoding.fetch_add(1, Ordering::Relaxed);
+          skip(relative_path, "File name is not valid UTF-8".to_string());
+          return Ok(());
+        }
+        info!("Processing file: {}", relative_path);
 
-    // --- Step 6: Consolidate and return the final JSON response ---
-    let combined_response = CombinedLoginResponse {
-        return_value: true,
-        return_code: login_body.return_code,
-        msg: success_msg,
-        character_count: char_count.character_count,
-        permission: account_info.permission,
-        privilege: account_info.privilege,
-        user_no: account_info.user_no,
-        user_name: account_info.user_name,
-        auth_key: auth_key.auth_key,
+        let metadata = match fs::metadata(path) {
+          Ok(metadata) => metadata,
+          Err(e) => {
+            skip(relative_path, e.to_string());
+            return Ok(());
+          }
+        };
+        let modified = metadata.modified().ok()
+          .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+          .map(|d| d.as_secs());
+        let reusable = options.previous_entries.get(&relative_path).filter(|previous| {
+          modified.is_some_and(|mtime| can_reuse_hash(previous, metadata.len(), mtime, algo, chunk_size, with_quick_hash))
+        });
+        let cached = cache
+          .and_then(|cache| cache.get(&relative_path))
+          .filter(|cached| cached.algo == algo && metadata.modified().ok() == Some(cached.last_modified));
+
+        let (hash, quick_hash, size, chunks) = match (reusable, cached) {
+          (Some(previous), _) => {
+            reused_hashes.fetch_add(1, Ordering::Relaxed);
+            (previous.hash.clone(), previous.quick_hash.clone(), previous.size, previous.chunks.clone())
+          }
+          (None, Some(cached)) => {
+            reused_hashes.fetch_add(1, Ordering::Relaxed);
+            (cached.hash, None, metadata.len(), None)
+          }
+          (None, None) => {
+            let contents = match std::fs::read(path) {
+              Ok(contents) => contents,
+              Err(e) => {
+                skip(relative_path, e.to_string());
+                return Ok(());
+              }
+            };
+            let size = contents.len() as u64;
+            let chunks = (chunk_size > 0 && size > CHUNKED_FILE_THRESHOLD.max(chunk_size)).then(|| {
+              contents.chunks(chunk_size as usize)
+                .enumerate()
+                .map(|(i, chunk)| FileChunk {
+                  offset: i as u64 * chunk_size,
+                  size: chunk.len() as u64,
+                  hash: hash_bytes(chunk, algo),
+                })
+                .collect()
+            });
+            let quick_hash = with_quick_hash.then(|| format!("{:016x}", xxhash_rust::xxh3::xxh3_64(&contents)));
+            (hash_bytes(&contents, algo), quick_hash, size, chunks)
+          }
+        };
+        let url = options.url_prefix
+          .map(|prefix| format!("{}files/{}", prefix, relative_path))
+          .unwrap_or_default();
+
+        files.blocking_lock().push(FileInfo {
+          priority: file_priority(options.priority_rules, &relative_path),
+          path: relative_path.clone(),
+          hash,
+          size,
+          url,
+          mtime: modified.filter(|_| include_mtime),
+          chunks,
+          locally_modified: false,
+          algo,
+          quick_hash,
+          changed_bytes: None,
+          reason: None,
+          local_hash: None,
+          group: content_group(options.group_rules, &relative_path),
+          protected: false,
+          attributes: FileAttributes::notable(&metadata),
+        });
+
+        total_size.fetch_add(size, Ordering::Relaxed);
+        let current_processed = processed_files.fetch_add(1, Ordering::Relaxed) + 1;
+        progress_bar.set_position(current_processed);
+
+        let payload = hash_progress_payload(&relative_path, current_processed, total_files as u64,
+          total_size.load(Ordering::Relaxed), reused_hashes.load(Ordering::Relaxed));
+        window.emit("hash_file_progress", payload).map_err(|e| e.to_string())?;
+      }
+      Ok(())
+    })));
+
+  // Callers write nothing after a cancel, so earlier output stays intact.
+  if is_hash_generation_cancelled() {
+    let processed = processed_files.load(Ordering::Relaxed);
+    info!("Hash generation cancelled after {} of {} files", processed, total_files);
+    let _ = window.emit("hash_file_cancelled", json!({
+      "processed_files": processed,
+      "total_files": total_files,
+      "total_size": total_size.load(Ordering::Relaxed),
+    }));
+    return Err(UPDATE_CANCELLED.to_string());
+  }
+  if let Err(e) = result {
+    error!("Error during file processing: {:?}", e);
+    return Err(e);
+  }
+
+  progress_bar.finish_with_message("File processing completed");
+  if total_files == 0 {
+    info!("No files to hash");
+    let _ = window.emit("hash_file_progress", hash_progress_payload("", 0, 0, 0, 0));
+  }
+
+  let skipped = skipped.into_inner();
+  let skipped_encoding = skipped_encoding.into_inner();
+  if !skipped.is_empty() {
+    let _ = window.emit("hash_file_warning", json!({
+      "skipped": skipped,
+      "skipped_encoding": skipped_encoding,
+    }));
+  }
+
+  // Workers finish in any order; sorting keeps the output stable between runs.
+  let mut files = files.into_inner();
+  files.sort_by(|a, b| a.path.cmp(&b.path));
+
+  Ok(DirectoryHashes {
+    files,
+    total_size: total_size.into_inner(),
+    reused_hashes: reused_hashes.into_inner(),
+    skipped,
+    skipped_encoding,
+  })
+}
+
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+async fn generate_hash_file(
+  window: tauri::Window,
+  algorithm: Option<String>,
+  quick_hash: Option<bool>,
+  chunk_size_mb: Option<u64>,
+  signing_key: Option<String>,
+  version: Option<String>,
+  gzip: Option<bool>,
+  options: Option<HashFileOptions>,
+) -> Result<HashFileResult, String> {
+  let options = options.unwrap_or_default();
+  HASH_GENERATION_CANCEL_REQUESTED.store(false, Ordering::SeqCst);
+  let _sleep_guard = SleepGuard::new();
+  let start_time = Instant::now();
+  let algo = algorithm.as_deref().map(HashAlgorithm::parse).transpose()?.unwrap_or_default();
+  let with_quick_hash = quick_hash.unwrap_or(false);
+  info!("Hash algorithm: {:?}, quick hashes: {}", algo, with_quick_hash);
+  // Load the key up front so a bad path fails before hours of hashing.
+  let signing_key = signing_key.as_deref().map(|path| load_signing_key(Path::new(path))).transpose()?;
+  // 0 turns chunk hashes off.
+  let chunk_size = chunk_size_mb.map(|mb| mb * 1024 * 1024).unwrap_or(FILE_CHUNK_SIZE);
+
+  let game_path = get_game_files_path()?;
+  info!("Game path: {:?}", game_path);
+  let output_path = game_path.join(options.output_path.as_deref().unwrap_or("hash-file.json"));
+  info!("Output path: {:?}", output_path);
+  let output_dir = output_path.parent().map(Path::to_path_buf).unwrap_or_else(|| game_path.clone());
+  fs::create_dir_all(&output_dir)
+    .map_err(|e| format!("Cannot create output directory {}: {}", output_dir.display(), e))?;
+  let url_prefix = if options.relative_urls.unwrap_or(false) {
+    String::new()
+  } else {
+    let base_url = match options.base_url {
+      Some(base_url) => {
+        Url::parse(&base_url).map_err(|e| format!("Invalid base URL {}: {}", base_url, e))?;
+        base_url
+      }
+      None => get_files_server_url()
+        .ok_or("FILE_SERVER_URL is not configured; pass a base URL or use relative URLs")?,
+    };
+    let base_url = base_url.trim_end_matches('/').to_string();
+    info!("Base URL: {}", base_url);
+    format!("{}/", base_url)
+  };
+  let include_mtime = options.include_mtime.unwrap_or(true);
+
+  // The previous manifest sets the default version and is the base of the diff.
+  let previous_manifest: Option<serde_json::Value> = match &options.previous_manifest {
+    Some(path) => {
+      let bytes = fs::read(path).map_err(|e| format!("Failed to read previous manifest {}: {}", path, e))?;
+      let text = decode_manifest_body(&bytes, None)?;
+      Some(serde_json::from_str(&text).map_err(|e| format!("Invalid previous manifest {}: {}", path, e))?)
+    }
+    None => fs::read_to_string(&output_path).ok()
+      .and_then(|contents| serde_json::from_str(&contents).ok()),
+  };
+  let previous_entries = match (&previous_manifest, options.force.unwrap_or(false)) {
+    (Some(previous), false) => manifest_entries_by_path(previous),
+    _ => HashMap::new(),
+  };
+  info!("Previous manifest entries available for reuse: {}", previous_entries.len());
+  let previous_version = previous_manifest.as_ref().and_then(|m| version_string(&m["version"]));
+  let version = match version {
+    Some(version) => version,
+    None => previous_version.as_deref()
+      .and_then(|v| v.parse::<u64>().ok())
+      .map(|v| (v + 1).to_string())
+      .unwrap_or_else(|| "1".to_string()),
+  };
+  if !is_valid_manifest_version(&version) {
+    return Err(format!("Invalid manifest version: {}", version));
+  }
+  info!("Manifest version: {}", version);
+  let compact = options.compact.unwrap_or(true);
+
+  let ignore_rules = IgnoreRules::load(&game_path, &options.extra_ignores)?;
+  let priority_rules = load_priority_rules(&game_path);
+  let group_rules = load_content_group_rules(&game_path);
+
+  let follow_symlinks = options.follow_symlinks.unwrap_or_else(follow_symlinks_enabled);
+  info!("Following symlinked directories: {}", follow_symlinks);
+
+  let DirectoryHashes { files, total_size, reused_hashes, skipped, skipped_encoding } = hash_game_directory(&window, &game_path, &DirectoryHashOptions {
+    algo,
+    with_quick_hash,
+    chunk_size,
+    include_mtime,
+    follow_symlinks,
+    url_prefix: Some(&url_prefix),
+    ignore_rules: &ignore_rules,
+    priority_rules: &priority_rules,
+    group_rules: &group_rules,
+    previous_entries: &previous_entries,
+    use_hash_cache: false,
+  })?;
+
+  info!("Generating JSON");
+  let generated_at = manifest_timestamp();
+  let manifest = build_hash_file_manifest(&version, generated_at, algo, &files, total_size);
+  let file_count = manifest["files"].as_array().map_or(0, Vec::len);
+
+  info!("Writing hash file");
+  let json = write_manifest_file(&output_path, manifest.clone(), signing_key.as_ref(), compact)?;
+  if gzip.unwrap_or(false) {
+    let gz_path = output_path.with_extension("json.gz");
+    info!("Writing {:?}", gz_path);
+    let mut encoder = flate2::write::GzEncoder::new(File::create(&gz_path).map_err(|e| e.to_string())?, flate2::Compression::best());
+    encoder.write_all(json.as_bytes()).map_err(|e| e.to_string())?;
+    encoder.finish().map_err(|e| e.to_string())?;
+  }
+
+  // Clients at the previous version fetch only this diff; anyone further
+  // behind falls back to the full hash file.
+  if let (Some(previous), Some(previous_version)) = (&previous_manifest, &previous_version) {
+    if previous_version != &version && is_valid_manifest_version(previous_version) {
+      let diff_path = output_dir.join("diff").join(format!("{}-{}.json", previous_version, version));
+      info!("Writing diff {:?}", diff_path);
+      fs::create_dir_all(output_dir.join("diff")).map_err(|e| e.to_string())?;
+      write_manifest_file(&diff_path, build_manifest_diff(previous, &manifest), signing_key.as_ref(), compact)?;
+    }
+  }
+  write_manifest_file(
+    &output_dir.join("version.json"),
+    json!({ "version": version, "generated_at": generated_at }),
+    signing_key.as_ref(),
+    compact,
+  )?;
+
+  let duration = start_time.elapsed();
+  let total_processed = file_count as u64;
+  info!("Hash file generation completed in {:?}", duration);
+  info!("Total files processed: {} ({} hashes reused)", total_processed, reused_hashes);
+  info!("Total size: {} bytes", total_size);
+  if !skipped.is_empty() {
+    info!("Skipped {} unreadable file(s)", skipped.len());
+  }
+
+  Ok(HashFileResult {
+    output_path: output_path.display().to_string(),
+    file_count: total_processed,
+    total_size,
+    reused_hashes,
+    skipped,
+    skipped_encoding,
+    duration_ms: duration.as_millis() as u64,
+  })
+}
+
+
+#[derive(Debug, Serialize)]
+struct LocalManifest {
+  /// Left out when the snapshot was written to `output_path` instead.
+  #[serde(skip_serializing_if = "Option::is_none")]
+  files: Option<Vec<FileInfo>>,
+  output_path: Option<String>,
+  file_count: u64,
+  total_size: u64,
+  reused_hashes: u64,
+  skipped: Vec<SkippedFile>,
+}
+
+/// Hashes the game directory like `generate_hash_file` without needing a file
+/// server, for comparing a local install offline. Entries have no URL. With
+/// `write_to`, the snapshot is written there and its hashes are reused on the
+/// next run alongside the hash cache.
+#[tauri::command]
+async fn get_local_manifest(window: tauri::Window, write_to: Option<String>) -> Result<LocalManifest, String> {
+  HASH_GENERATION_CANCEL_REQUESTED.store(false, Ordering::SeqCst);
+  let _sleep_guard = SleepGuard::new();
+  let game_path = get_game_files_path()?;
+  let output_path = write_to.map(PathBuf::from);
+  let previous_entries = output_path.as_ref()
+    .and_then(|path| fs::read_to_string(path).ok())
+    .and_then(|contents| serde_json::from_str(&contents).ok())
+    .map(|previous: serde_json::Value| manifest_entries_by_path(&previous))
+    .unwrap_or_default();
+  let ignore_rules = IgnoreRules::load(&game_path, &[])?;
+  let priority_rules = load_priority_rules(&game_path);
+  let group_rules = load_content_group_rules(&game_path);
+
+  let hashes = hash_game_directory(&window, &game_path, &DirectoryHashOptions {
+    algo: HashAlgorithm::default(),
+    with_quick_hash: false,
+    chunk_size: 0,
+    include_mtime: true,
+    follow_symlinks: follow_symlinks_enabled(),
+    url_prefix: None,
+    ignore_rules: &ignore_rules,
+    priority_rules: &priority_rules,
+    group_rules: &group_rules,
+    previous_entries: &previous_entries,
+    use_hash_cache: true,
+  })?;
+  info!("Local manifest: {} files, {} hashes reused", hashes.files.len(), hashes.reused_hashes);
+
+  let file_count = hashes.files.len() as u64;
+  let files = match &output_path {
+    Some(path) => {
+      if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        fs::create_dir_all(parent).map_err(|e| format!("Cannot create output directory {}: {}", parent.display(), e))?;
+      }
+      write_manifest_file(path, json!({ "algo": HashAlgorithm::default(), "files": hashes.files }), None, false)?;
+      None
+    }
+    None => Some(hashes.files),
+  };
+  Ok(LocalManifest {
+    files,
+    output_path: output_path.map(|path| path.display().to_string()),
+    file_count,
+    total_size: hashes.total_size,
+    reused_hashes: hashes.reused_hashes,
+    skipped: hashes.skipped,
+  })
+}
+
+
+#[tauri::command]
+async fn select_game_folder() -> Result<String, String> {
+  let (tx, mut rx) = mpsc::channel(1);
+
+  FileDialogBuilder::new()
+    .set_title("Select Tera Game Folder")
+    .set_directory("/")
+    .pick_folder(move |folder_path| {
+      if let Some(path) = folder_path {
+        let _ = tx.try_send(path);
+      }
+    });
+
+  match rx.recv().await {
+    Some(path) => Ok(path.to_string_lossy().into_owned()),
+    None => Err("Folder selection cancelled or failed".into()),
+  }
+}
+
+
+fn get_game_path() -> Result<PathBuf, String> {
+  let (game_path, _) = load_config()?;
+  Ok(game_path)
+}
+
+/// The game path for file operations, in the long-path form on Windows.
+/// Paths joined onto it inherit the prefix, while paths made relative to it
+/// with `strip_prefix` stay clean for the manifest and the cache.
+fn get_game_files_path() -> Result<PathBuf, String> {
+  Ok(extended_length_path(&get_game_path()?))
+}
+
+/// Rewrites an absolute Windows path in the `\\?\` form, which file APIs
+/// accept past MAX_PATH (260 characters). Paths that are relative or already
+/// prefixed are returned unchanged.
+#[cfg(windows)]
+fn extended_length_path(path: &Path) -> PathBuf {
+  use std::path::{Component, Prefix};
+  let mut components = path.components();
+  let Some(Component::Prefix(prefix)) = components.next() else {
+    return path.to_path_buf();
+  };
+  // Verbatim paths skip normalization, so `.`, `..` and `/` are resolved here.
+  let mut extended = match prefix.kind() {
+    Prefix::Disk(letter) if path.has_root() => PathBuf::from(format!("\\\\?\\{}:\\", letter as char)),
+    Prefix::UNC(server, share) => {
+      let mut root = std::ffi::OsString::from("\\\\?\\UNC\\");
+      root.push(server);
+      root.push("\\");
+      root.push(share);
+      root.push("\\");
+      PathBuf::from(root)
+    }
+    _ => return path.to_path_buf(),
+  };
+  for component in components {
+    match component {
+      Component::Normal(part) => extended.push(part),
+      Component::ParentDir => { extended.pop(); }
+      _ => {}
+    }
+  }
+  extended
+}
+
+#[cfg(not(windows))]
+fn extended_length_path(path: &Path) -> PathBuf {
+  path.to_path_buf()
+}
+
+/// Whether manifest paths are matched to local files ignoring case, for
+/// manifests generated with different casing than the files on disk. Off by
+/// default: Windows already matches paths ignoring case, and the lookup lists
+/// directories for every missing file.
+fn case_insensitive_paths_enabled() -> bool {
+  get_launcher_setting("case_insensitive_paths")
+    .map(|v| v.eq_ignore_ascii_case("true") || v == "1")
+    .unwrap_or(false)
+}
+
+/// Local path of a manifest file. With `case_insensitive`, a path that doesn't
+/// exist as written is looked up component by component ignoring case, and
+/// the casing on disk is kept; components that don't exist at all keep the
+/// manifest's casing. Writes go to the same path, so no parallel directories
+/// are created.
+fn game_file_path(game_path: &Path, relative_path: &str, case_insensitive: bool) -> PathBuf {
+  let exact = game_path.join(relative_path);
+  if !case_insensitive || fs::symlink_metadata(&exact).is_ok() {
+    return exact;
+  }
+  let mut resolved = game_path.to_path_buf();
+  let mut searching = true;
+  for component in path_components(relative_path) {
+    let candidate = resolved.join(component);
+    if !searching || fs::symlink_metadata(&candidate).is_ok() {
+      resolved = candidate;
+      continue;
+    }
+    let wanted = component.to_lowercase();
+    let on_disk = fs::read_dir(&resolved).ok().and_then(|entries| entries
+      .filter_map(|e| e.ok())
+      .find(|e| e.file_name().to_string_lossy().to_lowercase() == wanted)
+      .map(|e| e.path()));
+    match on_disk {
+      Some(path) => resolved = path,
+      None => {
+        searching = false;
+        resolved = candidate;
+      }
+    }
+  }
+  resolved
+}
+
+/// The game executable used unless `[game] executable` says otherwise.
+const DEFAULT_GAME_EXECUTABLE: &str = "Binaries/Tera.exe";
+
+/// Directories `detect_game_executables` looks in.
+const GAME_EXECUTABLE_DIRS: [&str; 3] = ["Binaries", "Binaries64", "Binaries32"];
+
+/// The `executable` key of the `[game]` section: the game executable
+/// relative to the game directory, e.g. `Binaries64/Tera.exe`.
+fn load_game_executable() -> String {
+  find_config_file()
+    .and_then(|path| Ini::load_from_file(path).ok())
+    .and_then(|conf| conf.get_from(Some("game"), "executable").map(|v| v.trim().to_string()))
+    .filter(|v| !v.is_empty())
+    .unwrap_or_else(|| DEFAULT_GAME_EXECUTABLE.to_string())
+}
+
+/// Resolves `executable` under `game_path`, case-insensitively, and checks
+/// it's an existing file that doesn't leave the game directory.
+fn resolve_game_executable(game_path: &Path, executable: &str) -> Result<PathBuf, String> {
+  let executable = executable.trim();
+  if executable.is_empty() || !is_safe_path(executable) || Path::new(executable).has_root()
+    || Path::new(executable).components().any(|c| matches!(c, std::path::Component::Prefix(_)))
+  {
+    return Err(format!("Invalid game executable path: {}", executable));
+  }
+  let full_path = game_file_path(game_path, executable, true);
+  if !full_path.is_file() {
+    return Err(format!("Game executable {} not found in {:?}. Please verify your game installation.", executable, game_path));
+  }
+  // A symlink could still point outside the game directory. Paths that can't
+  // be resolved (some network drives) are taken as configured, having
+  // passed the checks above.
+  let inside = match (full_path.canonicalize(), game_path.canonicalize()) {
+    (Ok(exe), Ok(dir)) => exe.starts_with(dir),
+    (Err(e), _) | (_, Err(e)) => {
+      warn!("Failed to resolve the game executable {:?}, using it as configured: {}", full_path, e);
+      true
+    }
+  };
+  if !inside {
+    return Err(format!("Game executable {} is outside the game directory", executable));
+  }
+  Ok(full_path)
+}
+
+#[tauri::command]
+fn get_game_executable() -> String {
+  load_game_executable()
+}
+
+/// Sets the game executable, relative to the game directory, after checking
+/// it exists there. An empty value goes back to `Binaries/Tera.exe`.
+#[tauri::command]
+fn set_game_executable(executable: String) -> Result<(), String> {
+  let executable = executable.trim().replace('\\', "/");
+  if !executable.is_empty() {
+    resolve_game_executable(&get_game_path()?, &executable)?;
+  }
+  let config_path = find_config_file().ok_or("Config file not found")?;
+  let mut conf = Ini::load_from_file(&config_path).map_err(|e|
+    format!("Failed to load config: {}", e)
+  )?;
+
+  if executable.is_empty() {
+    conf.delete_from(Some("game"), "executable");
+  } else {
+    conf.with_section(Some("game")).set("executable", executable);
+  }
+
+  conf.write_to_file(&config_path).map_err(|e| format!("Failed to write config: {}", e))
+}
+
+/// Game executables found in the usual binary directories, relative to the
+/// game directory, for the settings page to offer.
+#[tauri::command]
+fn detect_game_executables() -> Result<Vec<String>, String> {
+  let game_path = get_game_path()?;
+  let mut candidates = Vec::new();
+  for dir in GAME_EXECUTABLE_DIRS {
+    let Ok(entries) = fs::read_dir(game_file_path(&game_path, dir, true)) else { continue };
+    let mut found: Vec<String> = entries.flatten()
+      .filter(|e| e.path().is_file())
+      .filter_map(|e| e.file_name().to_str().map(str::to_string))
+      .filter(|name| {
+        let name = name.to_lowercase();
+        name.starts_with("tera") && name.ends_with(".exe")
+      })
+      .map(|name| format!("{}/{}", dir, name))
+      .collect();
+    found.sort();
+    candidates.extend(found);
+  }
+  Ok(candidates)
+}
+
+#[tauri::command]
+async fn save_game_path_to_config(path: String, update_state: tauri::State<'_, UpdateState>) -> Result<(), String> {
+  let config_path = find_config_file().ok_or("Config file not found")?;
+  let mut conf = Ini::load_from_file(&config_path).map_err(|e|
+    format!("Failed to load config: {}", e)
+  )?;
+
+  conf.with_section(Some("game")).set("path", &path);
+
+  conf.write_to_file(&config_path).map_err(|e| format!("Failed to write config: {}", e))?;
+  update_state.invalidate().await;
+
+  Ok(())
+}
+
+#[tauri::command]
+fn get_game_path_from_config() -> Result<String, String> {
+  match get_game_path() {
+    Ok(game_path) => game_path
+      .to_str()
+      .ok_or_else(|| "Invalid UTF-8 in game path".to_string())
+      .map(|s| s.to_string()),
+    Err(e) => {
+      if e.contains("Config file not found") {
+        Err("config.ini is missing".to_string())
+      } else {
+        Err(e)
+      }
+    }
+  }
+}
+
+#[tauri::command]
+fn clear_update_cache() -> Result<(), String> {
+  println!("Clearing update cache");
+  hash_cache().clear()?;
+  if let Ok(manifest_cache) = get_manifest_cache_path() {
+    let _ = fs::remove_file(manifest_cache);
+  }
+  println!("Cache cleared successfully");
+  Ok(())
+}
+
+/// Answers from the last check while it is younger than `check_cache_ttl`,
+/// so a check followed by `get_files_to_update(use_cached)` scans only once.
+/// Otherwise, unless `deep` is set, a server `version.json` matching the
+/// applied manifest version is taken as up to date without hashing anything.
+#[tauri::command]
+async fn check_update_required(
+  window: tauri::Window,
+  update_state: tauri::State<'_, UpdateState>,
+  deep: Option<bool>,
+) -> Result<bool, String> {
+  if let Some(cached) = update_state.cached.lock().await.as_ref().filter(|cached| cached.is_fresh()) {
+    return Ok(!cached.files.is_empty());
+  }
+  if !deep.unwrap_or(false) {
+    match applied_version_is_current().await {
+      Ok(true) => return Ok(false),
+      Ok(false) => {}
+      Err(e) => println!("Version check failed, running a full check: {}", e),
+    }
+  }
+  let _operation = begin_operation(&window.app_handle(), LauncherOperation::Verifying)?;
+  let files = find_files_to_update(Some(&window), false, None, false).await?;
+  let required = !files.is_empty();
+  *update_state.cached.lock().await = Some(CachedUpdate::new(files));
+  Ok(required)
+}
+
+// Security: Validate file paths to prevent path traversal attacks
+fn is_safe_path(path: &str) -> bool {
+  use std::path::Component;
+  
+  // Reject paths with parent directory references
+  if path.contains("..") || path.starts_with("/") || path.starts_with("\\") {
+    return false;
+  }
+  
+  // Reject paths that normalize to a parent directory
+  std::path::Path::new(path)
+    .components()
+    .all(|c| !matches!(c, Component::ParentDir | Component::RootDir))
+}
+
+/// Counters shared by every transfer of a download session.
+struct SessionProgress {
+  /// Bytes downloaded or copied so far across all files.
+  downloaded: AtomicU64,
+  /// Number of transfers currently allowed to run in parallel.
+  concurrency: AtomicUsize,
+}
+
+impl SessionProgress {
+  fn new(downloaded: u64, concurrency: usize) -> Arc<Self> {
+    Arc::new(SessionProgress {
+      downloaded: AtomicU64::new(downloaded),
+      concurrency: AtomicUsize::new(concurrency),
+    })
+  }
+}
+
+/// Position of a single file within the current download session, used to
+/// fill in the session-wide fields of `download_progress` events.
+#[derive(Clone)]
+struct DownloadProgressContext {
+  total_files: usize,
+  current_file_index: usize,
+  total_size: u64,
+  progress: Arc<SessionProgress>,
+}
+
+/// Returns the `quarantine/` directory in the app data directory.
+fn get_quarantine_dir() -> Result<PathBuf, String> {
+  get_app_data_path("quarantine")
+}
+
+/// Formats unix seconds as a UTC `YYYY-MM-DD` date.
+fn format_date(unix_secs: u64) -> String {
+  // Civil-from-days conversion (proleptic Gregorian calendar).
+  let days = (unix_secs / 86_400) as i64 + 719_468;
+  let era = days.div_euclid(146_097);
+  let day_of_era = days.rem_euclid(146_097);
+  let year_of_era = (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+  let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+  let mp = (5 * day_of_year + 2) / 153;
+  let day = day_of_year - (153 * mp + 2) / 5 + 1;
+  let month = if mp < 10 { mp + 3 } else { mp - 9 };
+  let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+  format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+/// Whether directory scans and update checks follow symlinks and junctions
+/// out of the game directory. Off by default, since a link to a large
+/// external folder would be hashed along with the game, or loop. Symlinked
+/// files are read through their target either way.
+fn follow_symlinks_enabled() -> bool {
+  get_launcher_setting("follow_symlinks")
+    .map(|v| v.eq_ignore_ascii_case("true") || v == "1")
+    .unwrap_or(false)
+}
+
+/// Whether update checks compare manifest `attributes` with up-to-date files
+/// and queue attribute-only fixes. Off by default.
+fn attribute_checks_enabled() -> bool {
+  get_launcher_setting("check_attributes")
+    .map(|v| v.eq_ignore_ascii_case("true") || v == "1")
+    .unwrap_or(false)
+}
+
+fn quarantine_enabled() -> bool {
+  get_launcher_setting("quarantine_modified_files")
+    .map(|v| !(v.eq_ignore_ascii_case("false") || v == "0"))
+    .unwrap_or(true)
+}
+
+/// Moves a file into `quarantine/<date>/`, preserving its relative game path
+/// so it can be inspected or restored later. Used both for downloads that
+/// failed verification and for locally modified files about to be replaced.
+/// Returns the path the file was moved to.
+fn quarantine_file(file_path: &Path, relative_path: &str) -> Result<PathBuf, String> {
+  let target = quarantine_target(relative_path)?;
+  move_file(file_path, &target)
+    .map_err(|e| format!("Failed to move file to quarantine: {}", e))?;
+
+  Ok(target)
+}
+
+/// Like `quarantine_file`, but leaves `file_path` in place.
+fn quarantine_copy(file_path: &Path, relative_path: &str) -> Result<PathBuf, String> {
+  let target = quarantine_target(relative_path)?;
+  fs::copy(file_path, &target)
+    .map_err(|e| format!("Failed to copy file to quarantine: {}", e))?;
+
+  Ok(target)
+}
+
+/// Today's quarantine path for `relative_path`, with its parent created.
+fn quarantine_target(relative_path: &str) -> Result<PathBuf, String> {
+  let now = SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap_or_default().as_secs();
+  let target = get_quarantine_dir()?.join(format_date(now)).join(relative_path);
+  if let Some(parent) = target.parent() {
+    fs::create_dir_all(parent)
+      .map_err(|e| format!("Failed to create quarantine directory: {}", e))?;
+  }
+  Ok(target)
+}
+
+#[derive(Debug, Serialize)]
+struct QuarantinedFile {
+  /// Day the file was quarantined (`YYYY-MM-DD`).
+  date: String,
+  /// Original path relative to the game directory.
+  path: String,
+  quarantined_path: String,
+  size: u64,
+}
+
+#[tauri::command]
+fn list_quarantined_files() -> Result<Vec<QuarantinedFile>, String> {
+  let quarantine_dir = get_quarantine_dir()?;
+  if !quarantine_dir.exists() {
+    return Ok(Vec::new());
+  }
+
+  let mut files: Vec<QuarantinedFile> = WalkDir::new(&quarantine_dir)
+    .min_depth(2)
+    .into_iter()
+    .filter_map(|e| e.ok())
+    .filter(|e| e.file_type().is_file())
+    .filter_map(|e| {
+      let relative = e.path().strip_prefix(&quarantine_dir).ok()?;
+      let mut components = relative.components();
+      let date = components.next()?.as_os_str().to_string_lossy().to_string();
+      let path = components.as_path().to_string_lossy().replace("\\", "/");
+      Some(QuarantinedFile {
+        date,
+        path,
+        quarantined_path: e.path().to_string_lossy().to_string(),
+        size: e.metadata().map(|m| m.len()).unwrap_or(0),
+      })
+    })
+    .collect();
+  files.sort_by(|a, b| b.date.cmp(&a.date).then_with(|| a.path.cmp(&b.path)));
+  Ok(files)
+}
+
+/// Moves `from` to `to`, replacing any existing file at `to`.
+fn move_file(from: &Path, to: &Path) -> Result<(), String> {
+  if to.exists() {
+    let _ = fs::remove_file(to);
+  }
+
+  // rename fails across volumes, so fall back to copy + delete.
+  if fs::rename(from, to).is_err() {
+    fs::copy(from, to).map_err(|e| format!("Failed to copy {}: {}", from.display(), e))?;
+    fs::remove_file(from).map_err(|e| format!("Failed to remove {}: {}", from.display(), e))?;
+  }
+
+  Ok(())
+}
+
+/// Moves a directory tree, copying it when a rename isn't possible (e.g.
+/// across volumes).
+fn move_dir(from: &Path, to: &Path) -> Result<(), String> {
+  if fs::rename(from, to).is_ok() {
+    return Ok(());
+  }
+  for entry in WalkDir::new(from) {
+    let entry = entry.map_err(|e| e.to_string())?;
+    let target = to.join(entry.path().strip_prefix(from).map_err(|e| e.to_string())?);
+    if entry.file_type().is_dir() {
+      fs::create_dir_all(&target).map_err(|e| format!("Failed to create {}: {}", target.display(), e))?;
+    } else {
+      fs::copy(entry.path(), &target).map_err(|e| format!("Failed to copy {}: {}", entry.path().display(), e))?;
+    }
+  }
+  fs::remove_dir_all(from).map_err(|e| format!("Failed to remove {}: {}", from.display(), e))
+}
+
+// ─── Update backups ──────────────────────────────────────────────────────────
+
+const BACKUP_MANIFEST_FILE: &str = "backup.json";
+const DEFAULT_BACKUP_MAX_SIZE_MB: u64 = 2048;
+
+/// Contents of `backup/<version>/backup.json`.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+struct BackupManifest {
+  version: String,
+  /// Unix seconds when the backup was started.
+  created: u64,
+  /// Files whose previous copy was saved into the backup directory.
+  replaced: Vec<String>,
+  /// Files that did not exist before the update; rollback deletes them.
+  added: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct BackupInfo {
+  version: String,
+  created: u64,
+  replaced_files: usize,
+  added_files: usize,
+  size_bytes: u64,
+}
+
+#[derive(Debug, Serialize)]
+struct RollbackResult {
+  version: String,
+  restored_files: usize,
+  removed_files: usize,
+}
+
+lazy_static! {
+  // Version from the most recently fetched hash file, used to name backups.
+  static ref CURRENT_MANIFEST_VERSION: RwLock<Option<String>> = RwLock::new(None);
+
+  // Everything the most recently loaded manifest (or applied manifest plus
+  // diff) lists, stored by mark_manifest_applied.
+  static ref PENDING_APPLIED_MANIFEST: RwLock<Option<serde_json::Value>> = RwLock::new(None);
+
+  // Backup being filled by the current update, written out by flush_backup_manifest.
+  static ref ACTIVE_BACKUP: Mutex<Option<BackupManifest>> = Mutex::new(None);
+}
+
+fn backups_enabled() -> bool {
+  get_launcher_setting("backup_enabled")
+    .map(|v| v.eq_ignore_ascii_case("true") || v == "1")
+    .unwrap_or(false)
+}
+
+fn get_backup_max_size() -> u64 {
+  get_launcher_setting("backup_max_size_mb")
+    .and_then(|v| v.parse::<u64>().ok())
+    .unwrap_or(DEFAULT_BACKUP_MAX_SIZE_MB)
+    * 1024 * 1024
+}
+
+/// Returns the `backup/` directory in the app data directory.
+fn get_backup_root() -> Result<PathBuf, String> {
+  get_app_data_path("backup")
+}
+
+fn get_backup_dir(version: &str) -> Result<PathBuf, String> {
+  // The version ends up as a directory name, so it must be a single plain component.
+  if version.is_empty() || version.contains('/') || version.contains('\\') || !is_safe_path(version) {
+    return Err(format!("Invalid backup version: {}", version));
+  }
+  Ok(get_backup_root()?.join(version))
+}
+
+fn load_backup_manifest(backup_dir: &Path) -> Result<BackupManifest, String> {
+  let contents = fs::read_to_string(backup_dir.join(BACKUP_MANIFEST_FILE)).map_err(|e| e.to_string())?;
+  serde_json::from_str(&contents).map_err(|e| e.to_string())
+}
+
+fn save_backup_manifest(backup_dir: &Path, manifest: &BackupManifest) -> Result<(), String> {
+  fs::create_dir_all(backup_dir).map_err(|e| format!("Failed to create backup directory: {}", e))?;
+  let serialized = serde_json::to_string(manifest).map_err(|e| e.to_string())?;
+  fs::write(backup_dir.join(BACKUP_MANIFEST_FILE), serialized).map_err(|e| e.to_string())
+}
+
+fn dir_size(path: &Path) -> u64 {
+  WalkDir::new(path)
+    .into_iter()
+    .filter_map(|e| e.ok())
+    .filter(|e| e.file_type().is_file())
+    .filter_map(|e| e.metadata().ok())
+    .map(|m| m.len())
+    .sum()
+}
+
+/// Loads every backup manifest, oldest first.
+fn load_all_backups() -> Vec<(PathBuf, BackupManifest)> {
+  let root = match get_backup_root() {
+    Ok(root) => root,
+    Err(_) => return Vec::new(),
+  };
+  let entries = match fs::read_dir(&root) {
+    Ok(entries) => entries,
+    Err(_) => return Vec::new(),
+  };
+
+  let mut backups: Vec<(PathBuf, BackupManifest)> = entries
+    .flatten()
+    .filter(|e| e.path().is_dir())
+    .filter_map(|e| load_backup_manifest(&e.path()).ok().map(|m| (e.path(), m)))
+    .collect();
+  backups.sort_by_key(|(_, m)| m.created);
+  backups
+}
+
+/// Saves the current copy of `relative_path` into the active backup before it
+/// gets overwritten, returning where it was copied. The file stays in place
+/// so a chunked download can reuse its unchanged chunks. Does nothing unless
+/// backups are enabled in config.ini.
+async fn backup_before_overwrite(file_path: &Path, relative_path: &str) -> Result<Option<PathBuf>, String> {
+  if !backups_enabled() {
+    return Ok(None);
+  }
+
+  let mut active = ACTIVE_BACKUP.lock().await;
+  if active.is_none() {
+    let now = SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap_or_default().as_secs();
+    let version = CURRENT_MANIFEST_VERSION.read().unwrap().clone()
+      .unwrap_or_else(|| now.to_string());
+    let backup_dir = get_backup_dir(&version)?;
+    // Resuming an update to the same version keeps adding to the existing backup.
+    let manifest = load_backup_manifest(&backup_dir).unwrap_or(BackupManifest {
+      version,
+      created: now,
+      ..Default::default()
+    });
+    *active = Some(manifest);
+  }
+  let manifest = active.as_mut().unwrap();
+
+  // Only the first copy seen is the pre-update original.
+  if manifest.replaced.iter().chain(manifest.added.iter()).any(|p| p == relative_path) {
+    return Ok(None);
+  }
+
+  if file_path.exists() {
+    let target = get_backup_dir(&manifest.version)?.join(relative_path);
+    if let Some(parent) = target.parent() {
+      fs::create_dir_all(parent)
+        .map_err(|e| format!("Failed to create backup directory: {}", e))?;
+    }
+    fs::copy(file_path, &target)
+      .map_err(|e| format!("Failed to back up {}: {}", relative_path, e))?;
+    manifest.replaced.push(relative_path.to_string());
+    Ok(Some(target))
+  } else {
+    manifest.added.push(relative_path.to_string());
+    Ok(None)
+  }
+}
+
+/// Writes out the active backup and evicts the oldest backups until the total
+/// size fits under `backup_max_size_mb`. The backup just written is never evicted.
+async fn flush_backup_manifest() {
+  let manifest = match ACTIVE_BACKUP.lock().await.take() {
+    Some(manifest) => manifest,
+    None => return,
+  };
+
+  let backup_dir = match get_backup_dir(&manifest.version) {
+    Ok(dir) => dir,
+    Err(e) => {
+      eprintln!("Failed to save backup manifest: {}", e);
+      return;
+    }
+  };
+  if let Err(e) = save_backup_manifest(&backup_dir, &manifest) {
+    eprintln!("Failed to save backup manifest: {}", e);
+    return;
+  }
+
+  let max_size = get_backup_max_size();
+  let mut backups: Vec<(PathBuf, u64)> = load_all_backups()
+    .into_iter()
+    .map(|(path, _)| {
+      let size = dir_size(&path);
+      (path, size)
+    })
+    .collect();
+  let mut total_size: u64 = backups.iter().map(|(_, size)| size).sum();
+
+  while total_size > max_size && !backups.is_empty() {
+    let (path, size) = backups.remove(0);
+    if path == backup_dir {
+      continue;
+    }
+    println!("Evicting backup {} to stay under the size cap", path.display());
+    if let Err(e) = fs::remove_dir_all(&path) {
+      eprintln!("Failed to remove backup {}: {}", path.display(), e);
+    }
+    total_size -= size;
+  }
+}
+
+/// Removes `paths` from the hash cache so the next check re-hashes them.
+fn invalidate_hash_cache_entries(paths: &[String]) {
+  let cache = hash_cache();
+  for path in paths {
+    cache.remove(path);
+  }
+  flush_hash_cache();
+}
+
+#[tauri::command]
+fn list_backups() -> Result<Vec<BackupInfo>, String> {
+  Ok(load_all_backups()
+    .into_iter()
+    .rev()
+    .map(|(path, manifest)| BackupInfo {
+      size_bytes: dir_size(&path),
+      replaced_files: manifest.replaced.len(),
+      added_files: manifest.added.len(),
+      version: manifest.version,
+      created: manifest.created,
+    })
+    .collect())
+}
+
+/// Restores the files saved in `backup/<version>/`, deletes the files that
+/// update added, and removes the backup.
+#[tauri::command]
+async fn rollback_to_backup(version: String) -> Result<RollbackResult, String> {
+  let backup_dir = get_backup_dir(&version)?;
+  let manifest = load_backup_manifest(&backup_dir)
+    .map_err(|e| format!("Backup {} not found: {}", version, e))?;
+  let game_path = get_game_files_path()?;
+
+  let mut restored_files = 0;
+  for relative_path in &manifest.replaced {
+    if !is_safe_path(relative_path) {
+      continue;
+    }
+    let source = backup_dir.join(relative_path);
+    let target = game_path.join(relative_path);
+    if let Some(parent) = target.parent() {
+      fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    move_file(&source, &target).map_err(|e| {
+      let error_msg = format!("Failed to restore {} from backup {}: {}", relative_path, version, e);
+      let _ = log_error_to_file(&error_msg);
+      error_msg
+    })?;
+    restored_files += 1;
+  }
+
+  let mut removed_files = 0;
+  for relative_path in &manifest.added {
+    if !is_safe_path(relative_path) {
+      continue;
+    }
+    let target = game_path.join(relative_path);
+    if target.exists() {
+      fs::remove_file(&target)
+        .map_err(|e| format!("Failed to remove {}: {}", relative_path, e))?;
+      removed_files += 1;
+    }
+  }
+
+  let touched: Vec<String> = manifest.replaced.iter().chain(manifest.added.iter()).cloned().collect();
+  invalidate_hash_cache_entries(&touched);
+
+  if let Err(e) = fs::remove_dir_all(&backup_dir) {
+    eprintln!("Failed to remove backup {}: {}", backup_dir.display(), e);
+  }
+
+  println!("Rolled back to backup {}: {} restored, {} removed", version, restored_files, removed_files);
+  Ok(RollbackResult {
+    version,
+    restored_files,
+    removed_files,
+  })
+}
+
+/// Result of streaming a single response body to disk.
+struct DownloadAttempt {
+  bytes: u64,
+  peak_speed: f64,
+}
+
+/// Result of downloading and verifying one file, including any retries.
+struct FileDownloadOutcome {
+  bytes: u64,
+  retries: u32,
+  peak_speed: f64,
+}
+
+/// Statistics for one `download_all_files` run, emitted with `download_complete`
+/// and returned from the command.
+#[derive(Debug, Clone, Serialize, Default)]
+struct DownloadSummary {
+  files_attempted: usize,
+  files_succeeded: usize,
+  files_failed: usize,
+  total_bytes: u64,
+  elapsed_seconds: f64,
+  /// Bytes per second over the whole session.
+  average_speed: f64,
+  /// Highest per-file bytes per second observed.
+  peak_speed: f64,
+  retries: u32,
+  /// Files filled by a local copy (a duplicate downloaded earlier in the run,
+  /// or a matching file from an existing install) instead of a download.
+  copied_files: usize,
+  /// Bytes copied locally instead of downloaded; not included in `total_bytes`.
+  bytes_saved: u64,
+  /// Pending files left for later by `download_files_subset`.
+  deferred_files: usize,
+  /// Protected files left alone because `override_protected` wasn't set.
+  protected_files: usize,
+  /// Locally modified files left alone because `overwrite_modified` wasn't set.
+  modified_files: usize,
+  /// Only populated when `continue_on_error` is set.
+  failed_files: Vec<String>,
+}
+
+impl DownloadSummary {
+  /// Whether every file the check asked for is now in place: nothing
+  /// failed, was deferred or was skipped as protected or modified.
+  fn is_complete(&self) -> bool {
+    self.files_failed == 0 && self.deferred_files == 0 && self.protected_files == 0 && self.modified_files == 0
+  }
+}
+
+const FILE_IN_USE_RETRIES: u32 = 5;
+const FILE_IN_USE_RETRY_DELAY: Duration = Duration::from_millis(500);
+
+/// True for errors caused by another process holding the file open
+/// (ERROR_SHARING_VIOLATION / ERROR_LOCK_VIOLATION on Windows). Access denied
+/// is a permission or read-only problem that waiting won't fix.
+fn is_file_in_use_error(error: &std::io::Error) -> bool {
+  cfg!(windows) && matches!(error.raw_os_error(), Some(32) | Some(33))
+}
+
+/// Error returned when a game file stays locked after all retries. Starts with
+/// "File is in use" so the frontend can tell it apart from network failures.
+fn file_in_use_error(relative_path: &str) -> String {
+  format!(
+    "File is in use — close the game and try again. {} is held open by another process (TERA, its crash reporter or an antivirus scanner).",
+    relative_path
+  )
+}
+
+/// Opens a game file for writing, retrying for a few seconds if another
+/// process still has it open. `truncate` is off for chunked downloads, which
+/// keep the ranges that are already correct.
+async fn create_game_file(file_path: &Path, relative_path: &str, truncate: bool) -> Result<tokio::fs::File, String> {
+  let mut attempt = 0;
+  loop {
+    let opened = tokio::fs::OpenOptions::new()
+      .write(true)
+      .create(true)
+      .truncate(truncate)
+      .open(file_path)
+      .await;
+    match opened {
+      Ok(file) => return Ok(file),
+      Err(e) if is_file_in_use_error(&e) => {
+        attempt += 1;
+        if attempt > FILE_IN_USE_RETRIES {
+          let error_msg = file_in_use_error(relative_path);
+          let _ = log_error_to_file(&format!("{} ({})", error_msg, e));
+          return Err(error_msg);
+        }
+        println!("{} is locked ({}), retrying ({}/{})", relative_path, e, attempt, FILE_IN_USE_RETRIES);
+        tokio::time::sleep(FILE_IN_USE_RETRY_DELAY).await;
+      }
+      Err(e) => return Err(e.to_string()),
+    }
+  }
+}
+
+const DEFAULT_WRITE_BUFFER_KB: usize = 1024;
+
+/// Write buffer for downloads, from `write_buffer_kb` in the `[launcher]` section.
+fn get_write_buffer_size() -> usize {
+  get_launcher_setting("write_buffer_kb")
+    .and_then(|v| v.parse::<usize>().ok())
+    .filter(|v| *v > 0)
+    .unwrap_or(DEFAULT_WRITE_BUFFER_KB)
+    * 1024
+}
+
+/// Wraps a download target so the 8-16KB response chunks are batched into
+/// fewer, larger writes.
+fn download_writer<W: tokio::io::AsyncWrite>(file: W) -> tokio::io::BufWriter<W> {
+  tokio::io::BufWriter::with_capacity(get_write_buffer_size(), file)
+}
+
+/// Failure of a single transfer attempt.
+struct TransferError {
+  message: String,
+  /// Network errors, 5xx and 429 responses are worth another attempt.
+  retryable: bool,
+  /// Delay requested by the server with `Retry-After`.
+  retry_after: Option<Duration>,
+}
+
+impl From<String> for TransferError {
+  fn from(message: String) -> Self {
+    TransferError { message, retryable: false, retry_after: None }
+  }
+}
+
+/// Backoff between download attempts, from the `retry_*` keys in the
+/// `[launcher]` section of config.ini.
+struct RetryPolicy {
+  max_retries: u32,
+  base_delay: Duration,
+  multiplier: f64,
+  max_delay: Duration,
+  jitter_percent: f64,
+}
+
+impl RetryPolicy {
+  fn from_config() -> Self {
+    let settings = load_launcher_settings();
+    let get = |key: &str| settings.get(key).and_then(|v| v.trim().parse::<f64>().ok());
+    RetryPolicy {
+      max_retries: get("retry_max_attempts").map(|v| v as u32).unwrap_or(3),
+      base_delay: Duration::from_millis(get("retry_base_delay_ms").unwrap_or(1000.0) as u64),
+      multiplier: get("retry_multiplier").unwrap_or(2.0).max(1.0),
+      max_delay: Duration::from_millis(get("retry_max_delay_ms").unwrap_or(30_000.0) as u64),
+      jitter_percent: get("retry_jitter_percent").unwrap_or(20.0).clamp(0.0, 100.0),
+    }
+  }
+
+  /// Delay before retry number `retry` (1-based). A server `Retry-After` wins
+  /// over the computed backoff but is still capped by `max_delay`; jitter
+  /// spreads clients out so they don't all come back at the same instant.
+  fn delay_for(&self, retry: u32, retry_after: Option<Duration>) -> Duration {
+    use rand::Rng;
+
+    let max = self.max_delay.as_secs_f64();
+    if let Some(retry_after) = retry_after {
+      return Duration::from_secs_f64(retry_after.as_secs_f64().min(max));
+    }
+    let backoff = (self.base_delay.as_secs_f64() * self.multiplier.powi(retry as i32 - 1)).min(max);
+    let jitter = backoff * self.jitter_percent / 100.0;
+    let delay = backoff + rand::thread_rng().gen_range(-jitter..=jitter);
+    Duration::from_secs_f64(delay.clamp(0.0, max))
+  }
+}
+
+/// `download_file_to` with retries on transient failures, waiting between
+/// attempts according to `RetryPolicy`.
+async fn download_file_with_retries(
+  window: &tauri::Window,
+  client: &Client,
+  file_info: &FileInfo,
+  file_path: &Path,
+  bypass_cache: bool,
+  ctx: &DownloadProgressContext,
+) -> Result<DownloadAttempt, String> {
+  let policy = RetryPolicy::from_config();
+  let mut retry = 0;
+  loop {
+    match download_file_to(window, client, file_info, file_path, bypass_cache, ctx).await {
+      Ok(attempt) => return Ok(attempt),
+      Err(e) if e.retryable && retry < policy.max_retries => {
+        retry += 1;
+        let delay = policy.delay_for(retry, e.retry_after);
+        let _ = log_error_to_file(&format!(
+          "Retrying download of {} in {}ms (retry {}/{}): {}",
+          file_info.path, delay.as_millis(), retry, policy.max_retries, e.message
+        ));
+        tokio::time::sleep(delay).await;
+      }
+      Err(e) => return Err(e.message),
+    }
+  }
+}
+
+/// Streams `file_info.url` into `file_path`, emitting `download_progress` events.
+/// When `bypass_cache` is set the request asks intermediate proxies/CDNs for a
+/// fresh copy, which is used when retrying after a hash mismatch.
+async fn download_file_to(
+  window: &tauri::Window,
+  client: &Client,
+  file_info: &FileInfo,
+  file_path: &Path,
+  bypass_cache: bool,
+  ctx: &DownloadProgressContext,
+) -> Result<DownloadAttempt, TransferError> {
+  let mut request = client.get(&file_info.url);
+  if bypass_cache {
+    request = request
+      .header(reqwest::header::CACHE_CONTROL, "no-cache")
+      .header(reqwest::header::PRAGMA, "no-cache");
+  }
+
+  let res = request
+    .send()
+    .await
+    .map_err(|e| TransferError {
+      message: format_reqwest_error(&file_info.url, &e),
+      retryable: true,
+      retry_after: None,
+    })?;
+
+  let status = res.status();
+  if !status.is_success() {
+    let retry_after = res.headers()
+      .get(reqwest::header::RETRY_AFTER)
+      .and_then(|v| v.to_str().ok())
+      .and_then(|v| v.trim().parse::<u64>().ok())
+      .map(Duration::from_secs);
+    return Err(TransferError {
+      message: format!("Download of {} failed with HTTP status: {}", file_info.path, status),
+      retryable: status.is_server_error() || status == reqwest::StatusCode::TOO_MANY_REQUESTS,
+      retry_after,
+    });
+  }
+
+  let file_size = res.content_length().unwrap_or(file_info.size);
+  let mut file = download_writer(create_game_file(file_path, &file_info.path, true).await?);
+  let mut downloaded: u64 = 0;
+  let mut peak_speed: f64 = 0.0;
+  let mut stream = res.bytes_stream();
+  let start_time = Instant::now();
+  let mut last_update = Instant::now();
+
+  println!("Downloading file: {}", file_info.path);
+
+  while let Some(chunk_result) = stream.next().await {
+    let chunk = match chunk_result {
+      Ok(chunk) => chunk,
+      Err(e) => {
+        // The partial body is discarded, so take it back out of the session total.
+        ctx.progress.downloaded.fetch_sub(downloaded, Ordering::SeqCst);
+        return Err(TransferError { message: e.to_string(), retryable: true, retry_after: None });
+      }
+    };
+    if is_update_cancelled() {
+      ctx.progress.downloaded.fetch_sub(downloaded, Ordering::SeqCst);
+      return Err(UPDATE_CANCELLED.to_string().into());
+    }
+    file.write_all(&chunk).await.map_err(|e| e.to_string())?;
+    downloaded += chunk.len() as u64;
+    ctx.progress.downloaded.fetch_add(chunk.len() as u64, Ordering::SeqCst);
+
+    let now = Instant::now();
+    if now.duration_since(last_update) >= Duration::from_millis(100) || downloaded == file_size {
+      let elapsed = now.duration_since(start_time);
+      let speed = if elapsed.as_secs() > 0 { downloaded / elapsed.as_secs() } else { downloaded };
+      // The sub-second figure is just the bytes received so far, not a rate.
+      if elapsed.as_secs() > 0 {
+        peak_speed = peak_speed.max(speed as f64);
+      }
+
+      let total_downloaded = ctx.progress.downloaded.load(Ordering::SeqCst);
+      let progress_payload = ProgressPayload {
+        file_name: file_info.path.clone(),
+        progress: (downloaded as f64 / file_size as f64) * 100.0,
+        speed: speed as f64,
+        downloaded_bytes: total_downloaded,
+        total_bytes: ctx.total_size,
+        total_files: ctx.total_files,
+        elapsed_time: elapsed.as_secs_f64(),
+        current_file_index: ctx.current_file_index,
+        copied_locally: false,
+        concurrency: ctx.progress.concurrency.load(Ordering::SeqCst),
+      };
+
+      println!("Current file: {}, Download speed: {}/s, Progress: {:.2}%",
+          file_info.path, format_bytes(speed), progress_payload.progress);
+
+      if let Err(e) = window.emit("download_progress", &progress_payload) {
+        println!("Failed to emit download_progress event: {}", e);
+      }
+      set_taskbar_progress(window, TaskbarProgress::Normal {
+        completed: progress_payload.downloaded_bytes,
+        total: progress_payload.total_bytes,
+      });
+      last_update = now;
+    }
+
+    tokio::time::sleep(Duration::from_millis(1)).await;
+  }
+
+  file.flush().await.map_err(|e| e.to_string())?;
+
+  Ok(DownloadAttempt { bytes: downloaded, peak_speed })
+}
+
+// Files above the threshold get per-chunk hashes in the manifest.
+const CHUNKED_FILE_THRESHOLD: u64 = 64 * 1024 * 1024;
+const FILE_CHUNK_SIZE: u64 = 16 * 1024 * 1024;
+// Chunks are buffered in memory, so the manifest can't ask for more than this.
+const MAX_FILE_CHUNK_SIZE: u64 = 256 * 1024 * 1024;
+const PARALLEL_CHUNKS: usize = 4;
+const CHUNK_RETRIES: u32 = 2;
+
+/// Returns the indices of `chunks` whose bytes already on disk match their hash.
+fn find_valid_chunks(file_path: &Path, chunks: &[FileChunk], algo: HashAlgorithm) -> HashSet<usize> {
+  use std::io::{Seek, SeekFrom};
+
+  let mut valid = HashSet::new();
+  let mut file = match File::open(file_path) {
+    Ok(file) => file,
+    Err(_) => return valid,
+  };
+  for (index, chunk) in chunks.iter().enumerate() {
+    if chunk.size == 0 || chunk.size > MAX_FILE_CHUNK_SIZE {
+      continue;
+    }
+    let mut buffer = vec![0; chunk.size as usize];
+    if file.seek(SeekFrom::Start(chunk.offset)).is_err() || file.read_exact(&mut buffer).is_err() {
+      continue;
+    }
+    if hash_bytes(&buffer, algo) == chunk.hash {
+      valid.insert(index);
+    }
+  }
+  valid
+}
+
+/// Rejects chunk lists a download can't use: empty or oversized chunks and
+/// chunks reaching past the end of the file.
+fn validate_chunks(chunks: &[FileChunk], file_size: u64) -> Result<(), String> {
+  for chunk in chunks {
+    let end = chunk.offset.checked_add(chunk.size);
+    if chunk.size == 0 || chunk.size > MAX_FILE_CHUNK_SIZE || !matches!(end, Some(end) if end <= file_size) {
+      return Err(format!("Invalid chunk of {} bytes at offset {} for a {} byte file", chunk.size, chunk.offset, file_size));
+    }
+  }
+  Ok(())
+}
+
+/// Fetches a single range of `file_info` and writes it at its offset once its
+/// hash checks out. Every byte added to `progress` is also added to
+/// `counted`, and taken back out of both if the chunk is rejected.
+async fn download_chunk(
+  client: &Client,
+  file_info: &FileInfo,
+  file_path: &Path,
+  chunk: &FileChunk,
+  bypass_cache: bool,
+  progress: &SessionProgress,
+  counted: &AtomicU64,
+) -> Result<u64, String> {
+  use tokio::io::AsyncSeekExt;
+
+  validate_chunks(std::slice::from_ref(chunk), file_info.size)?;
+  let range = format!("bytes={}-{}", chunk.offset, chunk.offset + chunk.size - 1);
+  let mut request = client.get(&file_info.url).header(reqwest::header::RANGE, range);
+  if bypass_cache {
+    request = request
+      .header(reqwest::header::CACHE_CONTROL, "no-cache")
+      .header(reqwest::header::PRAGMA, "no-cache");
+  }
+  let res = request.send().await.map_err(|e| e.to_string())?;
+  if res.status() != reqwest::StatusCode::PARTIAL_CONTENT {
+    return Err(format!("Server did not honour range request for {} (HTTP {})", file_info.path, res.status()));
+  }
+
+  let uncount = |bytes: usize| {
+    progress.downloaded.fetch_sub(bytes as u64, Ordering::SeqCst);
+    counted.fetch_sub(bytes as u64, Ordering::SeqCst);
+  };
+  let mut buffer = Vec::with_capacity(chunk.size as usize);
+  let mut stream = res.bytes_stream();
+  while let Some(chunk_result) = stream.next().await {
+    let bytes = match chunk_result {
+      Ok(bytes) => bytes,
+      Err(e) => {
+        uncount(buffer.len());
+        return Err(e.to_string());
+      }
+    };
+    if is_update_cancelled() {
+      uncount(buffer.len());
+      return Err(UPDATE_CANCELLED.to_string());
+    }
+    if (buffer.len() + bytes.len()) as u64 > chunk.size {
+      uncount(buffer.len());
+      return Err(format!("Server sent more than the {} byte chunk at offset {} of {}", chunk.size, chunk.offset, file_info.path));
+    }
+    progress.downloaded.fetch_add(bytes.len() as u64, Ordering::SeqCst);
+    counted.fetch_add(bytes.len() as u64, Ordering::SeqCst);
+    buffer.extend_from_slice(&bytes);
+  }
+
+  let received = buffer.len() as u64;
+  let hash = hash_bytes(&buffer, file_info.algo);
+  if received != chunk.size || hash != chunk.hash {
+    uncount(buffer.len());
+    return Err(format!("Chunk at offset {} of {} failed verification", chunk.offset, file_info.path));
+  }
+
+  let mut file = create_game_file(file_path, &file_info.path, false).await?;
+  file.seek(std::io::SeekFrom::Start(chunk.offset)).await.map_err(|e| e.to_string())?;
+  file.write_all(&buffer).await.map_err(|e| e.to_string())?;
+  file.flush().await.map_err(|e| e.to_string())?;
+  Ok(received)
+}
+
+/// Downloads a file described by per-chunk hashes. Ranges already correct on
+/// disk (e.g. from an interrupted run) are kept, the rest are fetched a few at
+/// a time, and a failing chunk is retried on its own without cache. On
+/// failure everything this added to the session progress is taken back out,
+/// so the whole-file download that follows isn't counted twice.
+async fn download_chunked_file(
+  window: &tauri::Window,
+  client: &Client,
+  file_info: &FileInfo,
+  file_path: &Path,
+  chunks: &[FileChunk],
+  ctx: &DownloadProgressContext,
+) -> Result<DownloadAttempt, String> {
+  validate_chunks(chunks, file_info.size)?;
+  let counted = AtomicU64::new(0);
+  let result = fetch_chunks(window, client, file_info, file_path, chunks, ctx, &counted).await;
+  if result.is_err() {
+    ctx.progress.downloaded.fetch_sub(counted.load(Ordering::SeqCst), Ordering::SeqCst);
+  }
+  result
+}
+
+async fn fetch_chunks(
+  window: &tauri::Window,
+  client: &Client,
+  file_info: &FileInfo,
+  file_path: &Path,
+  chunks: &[FileChunk],
+  ctx: &DownloadProgressContext,
+  counted: &AtomicU64,
+) -> Result<DownloadAttempt, String> {
+  let check_path = file_path.to_path_buf();
+  let check_chunks = chunks.to_vec();
+  let algo = file_info.algo;
+  let valid = tokio::task::spawn_blocking(move || find_valid_chunks(&check_path, &check_chunks, algo))
+    .await.map_err(|e| e.to_string())?;
+
+  let file = create_game_file(file_path, &file_info.path, false).await?;
+  file.set_len(file_info.size).await.map_err(|e| e.to_string())?;
+  drop(file);
+
+  let reused: u64 = valid.iter().map(|&i| chunks[i].size).sum();
+  ctx.progress.downloaded.fetch_add(reused, Ordering::SeqCst);
+  counted.fetch_add(reused, Ordering::SeqCst);
+  if !valid.is_empty() {
+    println!("Reusing {} of {} chunk(s) already on disk for {}", valid.len(), chunks.len(), file_info.path);
+  }
+
+  let start_time = Instant::now();
+  let mut done = reused;
+  let mut downloaded = 0;
+  let mut pending = chunks.iter().enumerate().filter(|(i, _)| !valid.contains(i)).map(|(_, c)| c);
+  let mut in_flight = FuturesUnordered::new();
+
+  loop {
+    while in_flight.len() < PARALLEL_CHUNKS {
+      let Some(chunk) = pending.next() else { break };
+      in_flight.push(async move {
+        let mut attempt = 0;
+        loop {
+          match download_chunk(client, file_info, file_path, chunk, attempt > 0, &ctx.progress, counted).await {
+            Ok(received) => return Ok(received),
+            Err(e) if attempt < CHUNK_RETRIES => {
+              println!("{}, retrying", e);
+              attempt += 1;
+            }
+            Err(e) => return Err(e),
+          }
+        }
+      });
+    }
+    let Some(result) = in_flight.next().await else { break };
+    let received = result?;
+    downloaded += received;
+    done += received;
+
+    let elapsed = start_time.elapsed();
+    let progress_payload = ProgressPayload {
+      file_name: file_info.path.clone(),
+      progress: (done as f64 / file_info.size as f64) * 100.0,
+      speed: downloaded as f64 / elapsed.as_secs_f64().max(1.0),
+      downloaded_bytes: ctx.progress.downloaded.load(Ordering::SeqCst),
+      total_bytes: ctx.total_size,
+      total_files: ctx.total_files,
+      elapsed_time: elapsed.as_secs_f64(),
+      current_file_index: ctx.current_file_index,
+      copied_locally: false,
+      concurrency: ctx.progress.concurrency.load(Ordering::SeqCst),
+    };
+    if let Err(e) = window.emit("download_progress", &progress_payload) {
+      println!("Failed to emit download_progress event: {}", e);
+    }
+    set_taskbar_progress(window, TaskbarProgress::Normal {
+      completed: progress_payload.downloaded_bytes,
+      total: progress_payload.total_bytes,
+    });
+  }
+
+  let elapsed = start_time.elapsed().as_secs_f64();
+  Ok(DownloadAttempt {
+    bytes: downloaded,
+    peak_speed: if elapsed >= 1.0 { downloaded as f64 / elapsed } else { 0.0 },
+  })
+}
+
+/// Resolves `relative_path` inside the game directory, rejecting anything that
+/// would escape it, and creates the parent directories.
+async fn prepare_game_file_path(relative_path: &str) -> Result<PathBuf, String> {
+  let game_path = get_game_files_path()?;
+  
+  // SECURITY: Validate file path to prevent path traversal attacks
+  if !is_safe_path(relative_path) {
+    return Err(format!("Invalid file path detected: {}. Path traversal attack blocked.", relative_path));
+  }
+  
+  let file_path = game_file_path(&game_path, relative_path, case_insensitive_paths_enabled());
+  
+  // SECURITY: Ensure the final file path is within the game directory
+  if !file_path.starts_with(&game_path) {
+    return Err(format!("Path traversal attack detected. File would be extracted outside game directory."));
+  }
+  ensure_not_ignored(&game_path, relative_path)?;
+
+  if let Some(parent) = file_path.parent() {
+    tokio::fs::create_dir_all(parent).await.map_err(|e| e.to_string())?;
+  }
+
+  // Read-only files can't be replaced; the manifest attributes are applied
+  // again once the new copy is verified.
+  if let Ok(metadata) = fs::metadata(&file_path) {
+    let mut permissions = metadata.permissions();
+    if permissions.readonly() {
+      #[allow(clippy::permissions_set_readonly_false)]
+      permissions.set_readonly(false);
+      fs::set_permissions(&file_path, permissions)
+        .map_err(|e| format!("Failed to make {} writable: {}", relative_path, e))?;
+    }
+  }
+
+  Ok(file_path)
+}
+
+/// Fills `file_info.path` by copying an already verified file with the same
+/// hash instead of downloading it again. Returns the number of bytes copied.
+async fn copy_duplicate_file(
+  window: &tauri::Window,
+  source_path: &Path,
+  file_info: &FileInfo,
+  ctx: &DownloadProgressContext,
+) -> Result<u64, String> {
+  let file_path = prepare_game_file_path(&file_info.path).await?;
+  backup_before_overwrite(&file_path, &file_info.path).await?;
+
+  let copied = tokio::fs::copy(source_path, &file_path).await
+    .map_err(|e| format!("Failed to copy {} to {}: {}", source_path.display(), file_info.path, e))?;
+
+  let hash_path = file_path.clone();
+  let algo = file_info.algo;
+  let copied_hash = tokio::task::spawn_blocking(move || calculate_file_hash_with(&hash_path, algo)).await.map_err(|e| e.to_string())??;
+  if copied_hash != file_info.hash {
+    return Err(format!("Hash mismatch for copied file: {} (expected {}, got {})", file_info.path, file_info.hash, copied_hash));
+  }
+
+  record_verified_download(&file_path, file_info);
+
+  let progress_payload = ProgressPayload {
+    file_name: file_info.path.clone(),
+    progress: 100.0,
+    speed: 0.0,
+    downloaded_bytes: ctx.progress.downloaded.fetch_add(copied, Ordering::SeqCst) + copied,
+    total_bytes: ctx.total_size,
+    total_files: ctx.total_files,
+    elapsed_time: 0.0,
+    current_file_index: ctx.current_file_index,
+    copied_locally: true,
+    concurrency: ctx.progress.concurrency.load(Ordering::SeqCst),
+  };
+  if let Err(e) = window.emit("download_progress", &progress_payload) {
+    println!("Failed to emit download_progress event: {}", e);
+  }
+  set_taskbar_progress(window, TaskbarProgress::Normal {
+    completed: progress_payload.downloaded_bytes,
+    total: progress_payload.total_bytes,
+  });
+
+  println!("Copied duplicate file: {} from {}", file_info.path, source_path.display());
+  Ok(copied)
+}
+
+/// The absolute URL to download `file_info` from. Relative URLs are joined
+/// onto `FILE_SERVER_URL`, and an entry without one gets `files/<path>`.
+fn resolve_download_url(file_info: &FileInfo) -> Result<String, String> {
+  if Url::parse(&file_info.url).is_ok() {
+    return Ok(file_info.url.clone());
+  }
+  let base_url = get_files_server_url()
+    .ok_or_else(|| format!("{} has no URL and no FILE_SERVER_URL configured", file_info.path))?;
+  // Without the trailing slash, join would replace the last path segment.
+  let base = Url::parse(&format!("{}/", base_url.trim_end_matches('/')))
+    .map_err(|e| format!("Invalid FILE_SERVER_URL {}: {}", base_url, e))?;
+  let relative = match file_info.url.as_str() {
+    "" => format!("files/{}", file_info.path),
+    url => url.to_string(),
+  };
+  base.join(&relative)
+    .map(String::from)
+    .map_err(|e| format!("Invalid URL {} for {}: {}", relative, file_info.path, e))
+}
+
+/// Downloads a single file into the game directory and verifies its hash,
+/// retrying once without cache on a mismatch.
+async fn download_and_verify_file(
+  window: &tauri::Window,
+  file_info: &FileInfo,
+  ctx: &DownloadProgressContext,
+) -> Result<FileDownloadOutcome, String> {
+  if let (Some(UpdateReason::AttributeMismatch), Some(attributes)) = (file_info.reason, &file_info.attributes) {
+    let file_path = game_file_path(&get_game_files_path()?, &file_info.path, case_insensitive_paths_enabled());
+    attributes.apply(&file_path).map_err(|e| format!("Failed to set attributes for {}: {}", file_info.path, e))?;
+    println!("Updated attributes of {}", file_info.path);
+    return Ok(FileDownloadOutcome { bytes: 0, retries: 0, peak_speed: 0.0 });
+  }
+  let resolved;
+  let file_info = match resolve_download_url(file_info)? {
+    url if url == file_info.url => file_info,
+    url => {
+      resolved = FileInfo { url, ..file_info.clone() };
+      &resolved
+    }
+  };
+  let file_path = prepare_game_file_path(&file_info.path).await?;
+
+  let client = http_client_builder()
+    .no_proxy()
+    .build()
+    .map_err(|e| e.to_string())?;
+
+  // The backup comes first so a rollback can restore the player's copy; the
+  // quarantine then gets a copy of the backup, or the file itself when
+  // backups are off.
+  let backup_path = backup_before_overwrite(&file_path, &file_info.path).await?;
+  if file_info.locally_modified && quarantine_enabled() {
+    let quarantined = match backup_path {
+      Some(backup_path) => Some(quarantine_copy(&backup_path, &file_info.path)?),
+      None if file_path.exists() => Some(quarantine_file(&file_path, &file_info.path)?),
+      None => None,
+    };
+    if let Some(quarantine_path) = quarantined {
+      println!("Quarantined locally modified {} to {}", file_info.path, quarantine_path.display());
+    }
+  }
+
+  let start_time = Instant::now();
+  let mut retries = 0;
+
+  let chunked = match file_info.chunks.as_deref() {
+    Some(chunks) if !chunks.is_empty() => {
+      download_chunked_file(window, &client, file_info, &file_path, chunks, ctx).await
+        .map_err(|e| println!("Chunked download of {} failed, downloading the whole file: {}", file_info.path, e))
+        .ok()
+    }
+    _ => None,
+  };
+  let mut attempt = match chunked {
+    Some(attempt) => attempt,
+    None => download_file_with_retries(window, &client, file_info, &file_path, false, ctx).await?,
+  };
+  let mut peak_speed = attempt.peak_speed;
+
+  let hash_path = file_path.clone();
+  let algo = file_info.algo;
+  let mut downloaded_hash = tokio::task::spawn_blocking(move || calculate_file_hash_with(&hash_path, algo)).await.map_err(|e| e.to_string())??;
+  if downloaded_hash != file_info.hash {
+    // Mismatches are usually transient proxy/CDN corruption, so try once more
+    // asking for an uncached copy before giving up on the file.
+    println!("Hash mismatch for file: {} (expected {}, got {}), retrying without cache",
+        file_info.path, file_info.hash, downloaded_hash);
+    retries += 1;
+    // The first copy is being thrown away, so take it back out of the session total.
+    ctx.progress.downloaded.fetch_sub(attempt.bytes, Ordering::SeqCst);
+    attempt = download_file_with_retries(window, &client, file_info, &file_path, true, ctx).await?;
+    peak_speed = peak_speed.max(attempt.peak_speed);
+
+    let hash_path = file_path.clone();
+    downloaded_hash = tokio::task::spawn_blocking(move || calculate_file_hash_with(&hash_path, algo)).await.map_err(|e| e.to_string())??;
+    if downloaded_hash != file_info.hash {
+      let error_msg = match quarantine_file(&file_path, &file_info.path) {
+        Ok(quarantine_path) => format!(
+          "Hash mismatch for file: {} after retry (expected {}, got {}). Bad file moved to {}",
+          file_info.path, file_info.hash, downloaded_hash, quarantine_path.display()
+        ),
+        Err(e) => format!(
+          "Hash mismatch for file: {} after retry (expected {}, got {}). Failed to quarantine it: {}",
+          file_info.path, file_info.hash, downloaded_hash, e
+        ),
+      };
+      let _ = log_error_to_file(&error_msg);
+      return Err(error_msg);
+    }
+  }
+
+  record_verified_download(&file_path, file_info);
+
+  // Emit a final event for this file
+  let final_progress_payload = ProgressPayload {
+    file_name: file_info.path.clone(),
+    progress: 100.0,
+    speed: 0.0,
+    downloaded_bytes: ctx.progress.downloaded.load(Ordering::SeqCst),
+    total_bytes: ctx.total_size,
+    total_files: ctx.total_files,
+    elapsed_time: start_time.elapsed().as_secs_f64(),
+    current_file_index: ctx.current_file_index,
+    copied_locally: false,
+    concurrency: ctx.progress.concurrency.load(Ordering::SeqCst),
+  };
+  if let Err(e) = window.emit("download_progress", &final_progress_payload) {
+    println!("Failed to emit final download_progress event: {}", e);
+  }
+  set_taskbar_progress(window, TaskbarProgress::Normal {
+    completed: final_progress_payload.downloaded_bytes,
+    total: final_progress_payload.total_bytes,
+  });
+
+  println!("File download completed: {}", file_info.path);
+
+  Ok(FileDownloadOutcome {
+    bytes: attempt.bytes,
+    retries,
+    peak_speed,
+  })
+}
+
+#[tauri::command]
+async fn update_file(
+  _app_handle: tauri::AppHandle,
+  window: tauri::Window,
+  file_info: FileInfo,
+  total_files: usize,
+  current_file_index: usize,
+  total_size: u64,
+  downloaded_size: u64,
+) -> Result<u64, String> {
+  let ctx = DownloadProgressContext {
+    total_files,
+    current_file_index,
+    total_size,
+    progress: SessionProgress::new(downloaded_size, 1),
+  };
+  let result = download_and_verify_file(&window, &file_info, &ctx).await;
+  flush_hash_cache();
+  flush_backup_manifest().await;
+  window.state::<UpdateState>().invalidate().await;
+  Ok(result?.bytes)
+}
+
+/// `done` as a percentage of `total`. Nothing to do counts as done, so an
+/// empty job reports 100 rather than NaN.
+fn percent(done: u64, total: u64) -> f64 {
+  if total == 0 {
+    100.0
+  } else {
+    done as f64 / total as f64 * 100.0
+  }
+}
+
+/// Average time per file for the check summaries. An empty manifest divides
+/// by one rather than zero.
+fn average_ms_per_file(total_time: Duration, files: usize) -> f64 {
+  total_time.as_millis() as f64 / files.max(1) as f64
+}
+
+fn format_bytes(bytes: u64) -> String {
+  const UNITS: [&str; 4] = ["B", "KB", "MB", "GB"];
+  let mut size = bytes as f64;
+  let mut unit_index = 0;
+
+  while size >= 1024.0 && unit_index < UNITS.len() - 1 {
+    size /= 1024.0;
+    unit_index += 1;
+  }
+
+  format!("{:.2} {}", size, UNITS[unit_index])
+}
+
+// ─── Patch packages ──────────────────────────────────────────────────────────
+
+const DEFAULT_PACKAGE_THRESHOLD: usize = 1000;
+
+/// Archive advertised by the hash file (`package_url`, `package_hash`,
+/// `package_files`) as a faster alternative to fetching many small files.
+#[derive(Debug, Clone)]
+struct PatchPackage {
+  url: String,
+  hash: String,
+  files: HashSet<String>,
+}
+
+struct PackageInstallOutcome {
+  installed: HashSet<String>,
+}
+
+lazy_static! {
+  // Package from the most recently fetched hash file, if it advertised one.
+  static ref CURRENT_PATCH_PACKAGE: RwLock<Option<PatchPackage>> = RwLock::new(None);
+}
+
+fn parse_patch_package(hash_file: &serde_json::Value) -> Option<PatchPackage> {
+  let url = hash_file["package_url"].as_str()?;
+  let hash = hash_file["package_hash"].as_str()?;
+  let files = hash_file["package_files"].as_array()?;
+  Some(PatchPackage {
+    url: url.to_string(),
+    hash: hash.to_string(),
+    files: files.iter().filter_map(|f| f.as_str()).map(|f| f.to_string()).collect(),
+  })
+}
+
+/// Returns the current patch package if enough of `files_to_update` is covered
+/// by it to be worth downloading the archive (`package_threshold` in config.ini).
+fn select_patch_package(files_to_update: &[FileInfo]) -> Option<PatchPackage> {
+  let package = CURRENT_PATCH_PACKAGE.read().unwrap().clone()?;
+  let threshold = get_launcher_setting("package_threshold")
+    .and_then(|v| v.parse::<usize>().ok())
+    .unwrap_or(DEFAULT_PACKAGE_THRESHOLD);
+  let covered = files_to_update.iter().filter(|f| package.files.contains(&f.path)).count();
+  if covered >= threshold {
+    Some(package)
+  } else {
+    None
+  }
+}
+
+/// Extracts the entries of `archive_path` listed in `wanted` into `game_path`,
+/// emitting `package_extract_progress` per entry. Returns the extracted paths.
+fn extract_patch_package(
+  window: &tauri::Window,
+  archive_path: &Path,
+  game_path: &Path,
+  wanted: &HashSet<String>,
+) -> Result<Vec<String>, String> {
+  let archive_file = File::open(archive_path).map_err(|e| format!("Failed to open patch package: {}", e))?;
+  let mut archive = zip::ZipArchive::new(archive_file).map_err(|e| format!("Invalid patch package: {}", e))?;
+  let start_time = Instant::now();
+  let mut extracted = Vec::new();
+
+  for i in 0..archive.len() {
+    let mut entry = archive.by_index(i).map_err(|e| format!("Failed to read patch package entry: {}", e))?;
+    if entry.is_dir() {
+      continue;
+    }
+    // enclosed_name rejects absolute paths and `..` components.
+    let relative_path = match entry.enclosed_name() {
+      Some(name) => name.to_string_lossy().replace("\\", "/"),
+      None => continue,
+    };
+    if !wanted.contains(&relative_path) {
+      continue;
+    }
+
+    let target = game_path.join(&relative_path);
+    if let Some(parent) = target.parent() {
+      fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    let mut out = File::create(&target)
+      .map_err(|e| format!("Failed to create {}: {}", relative_path, e))?;
+    std::io::copy(&mut entry, &mut out)
+      .map_err(|e| format!("Failed to extract {}: {}", relative_path, e))?;
+    extracted.push(relative_path.clone());
+
+    let _ = window.emit("package_extract_progress", json!({
+      "current_file": relative_path,
+      "extracted_files": extracted.len(),
+      "total_files": wanted.len(),
+      "progress": (extracted.len() as f64 / wanted.len() as f64) * 100.0,
+      "elapsed_time": start_time.elapsed().as_secs_f64()
+    }));
+  }
+
+  Ok(extracted)
+}
+
+/// Downloads and extracts the patch package, then verifies every extracted
+/// file against its manifest hash. Files that are missing from the archive or
+/// fail verification are left for the per-file download.
+async fn install_patch_package(
+  window: &tauri::Window,
+  package: &PatchPackage,
+  files_to_update: &[FileInfo],
+  ctx: &DownloadProgressContext,
+) -> Result<PackageInstallOutcome, String> {
+  let game_path = get_game_files_path()?;
+  let ignore_rules = session_ignore_rules(&game_path)?;
+  let covered: Vec<FileInfo> = files_to_update.iter()
+    .filter(|f| package.files.contains(&f.path) && is_safe_path(&f.path) && !ignore_rules.is_ignored(&f.path))
+    .cloned()
+    .collect();
+  let wanted: HashSet<String> = covered.iter().map(|f| f.path.clone()).collect();
+
+  let archive_path = get_app_data_path("patch_package.zip")?;
+
+  let client = http_client_builder()
+    .no_proxy()
+    .build()
+    .map_err(|e| e.to_string())?;
+  let archive_info = FileInfo {
+    path: "patch package".to_string(),
+    hash: package.hash.clone(),
+    size: 0,
+    url: package.url.clone(),
+    mtime: None,
+    priority: None,
+    chunks: None,
+    locally_modified: false,
+    algo: HashAlgorithm::Sha256,
+    quick_hash: None,
+    changed_bytes: None,
+    reason: None,
+    local_hash: None,
+    group: None,
+    protected: false,
+    attributes: None,
+  };
+  println!("Downloading patch package covering {} file(s)", covered.len());
+  download_file_with_retries(window, &client, &archive_info, &archive_path, false, ctx).await?;
+
+  let hash_path = archive_path.clone();
+  let archive_hash = tokio::task::spawn_blocking(move || calculate_file_hash_with(&hash_path, HashAlgorithm::Sha256)).await.map_err(|e| e.to_string())??;
+  if archive_hash != package.hash {
+    let _ = fs::remove_file(&archive_path);
+    return Err(format!("Patch package hash mismatch (expected {}, got {})", package.hash, archive_hash));
+  }
+
+  for file_info in &covered {
+    backup_before_overwrite(&game_path.join(&file_info.path), &file_info.path).await?;
+  }
+
+  let extract_window = window.clone();
+  let extract_archive = archive_path.clone();
+  let extract_game_path = game_path.clone();
+  let extracted = tokio::task::spawn_blocking(move || {
+    extract_patch_package(&extract_window, &extract_archive, &extract_game_path, &wanted)
+  }).await.map_err(|e| e.to_string())?;
+  let _ = fs::remove_file(&archive_path);
+  let extracted: HashSet<String> = extracted?.into_iter().collect();
+
+  let to_verify: Vec<FileInfo> = covered.into_iter().filter(|f| extracted.contains(&f.path)).collect();
+  let verify_game_path = game_path.clone();
+  let verified: Vec<FileInfo> = tokio::task::spawn_blocking(move || {
+    to_verify.into_par_iter()
+      .filter(|f| calculate_file_hash_with(verify_game_path.join(&f.path), f.algo).map(|h| h == f.hash).unwrap_or(false))
+      .collect()
+  }).await.map_err(|e| e.to_string())?;
+
+  for file_info in &verified {
+    record_verified_download(&game_path.join(&file_info.path), file_info);
+  }
+  println!("Patch package installed {} of {} extracted file(s)", verified.len(), extracted.len());
+
+  Ok(PackageInstallOutcome {
+    installed: verified.into_iter().map(|f| f.path).collect(),
+  })
+}
+
+/// Downloads every file in `files_to_update`, emitting `download_progress` per file
+/// and a `download_complete` event carrying a `DownloadSummary`.
+///
+/// By default the first failure aborts the run. With `continue_on_error` the
+/// remaining files are still attempted and the failures are listed in the summary.
+#[tauri::command]
+async fn download_all_files(
+  _app_handle: tauri::AppHandle,
+  window: tauri::Window,
+  state: tauri::State<'_, GameState>,
+  files_to_update: Vec<FileInfo>,
+  continue_on_error: Option<bool>,
+  override_protected: Option<bool>,
+  overwrite_modified: Option<bool>,
+) -> Result<DownloadSummary, String> {
+  ensure_game_not_running(&state).await?;
+  let _operation = begin_operation(&window.app_handle(), LauncherOperation::Updating)?;
+  let options = DownloadSessionOptions {
+    continue_on_error: continue_on_error.unwrap_or(false),
+    override_protected: override_protected.unwrap_or(false),
+    overwrite_modified: overwrite_modified.unwrap_or(false),
+    ..Default::default()
+  };
+  run_download_session(&window, files_to_update, options).await
+}
+
+/// Downloads only the pending files listed in `paths`, so the frontend can get
+/// the required files in place first and fetch optional content later.
+#[tauri::command]
+async fn download_files_subset(
+  window: tauri::Window,
+  state: tauri::State<'_, GameState>,
+  files_to_update: Vec<FileInfo>,
+  paths: Vec<String>,
+  continue_on_error: Option<bool>,
+  override_protected: Option<bool>,
+  overwrite_modified: Option<bool>,
+) -> Result<DownloadSummary, String> {
+  ensure_game_not_running(&state).await?;
+  let _operation = begin_operation(&window.app_handle(), LauncherOperation::Updating)?;
+  let wanted: HashSet<String> = paths.into_iter().collect();
+  let (subset, deferred): (Vec<FileInfo>, Vec<FileInfo>) = files_to_update
+    .into_iter()
+    .partition(|f| wanted.contains(&f.path));
+  let options = DownloadSessionOptions {
+    continue_on_error: continue_on_error.unwrap_or(false),
+    deferred_files: deferred.len(),
+    override_protected: override_protected.unwrap_or(false),
+    overwrite_modified: overwrite_modified.unwrap_or(false),
+    ..Default::default()
+  };
+  run_download_session(&window, subset, options).await
+}
+
+#[derive(Debug, Serialize, Clone, Default)]
+struct RepairSummary {
+  files_repaired: usize,
+  bytes_downloaded: u64,
+  /// Files still broken after the repair.
+  failed_files: Vec<String>,
+}
+
+/// Checks the game files and downloads whatever is wrong in one call, so the
+/// file list can't be lost between the two steps. Emits the usual check and
+/// download events followed by `repair_complete`.
+#[tauri::command]
+async fn repair_game_files(
+  window: tauri::Window,
+  state: tauri::State<'_, GameState>,
+  full: Option<bool>,
+) -> Result<RepairSummary, String> {
+  ensure_game_not_running(&state).await?;
+  let operation = begin_operation(&window.app_handle(), LauncherOperation::Verifying)?;
+
+  let files_to_update = check_game_files(&window, full.unwrap_or(false)).await?;
+  println!("Repair found {} file(s) to fix", files_to_update.len());
+  operation.switch_to(LauncherOperation::Updating);
+  // A repair restores the server's files; modified ones are quarantined first.
+  let options = DownloadSessionOptions {
+    continue_on_error: true,
+    overwrite_modified: true,
+    ..Default::default()
+  };
+  let download = run_download_session(&window, files_to_update, options).await?;
+
+  let summary = RepairSummary {
+    files_repaired: download.files_succeeded,
+    bytes_downloaded: download.total_bytes,
+    failed_files: download.failed_files,
+  };
+  if let Err(e) = window.emit("repair_complete", &summary) {
+    eprintln!("Failed to emit repair_complete event: {}", e);
+  }
+  Ok(summary)
+}
+
+/// Refuses to touch game files while the client is running, since every file
+/// it has open would fail to write.
+async fn ensure_game_not_running(state: &tauri::State<'_, GameState>) -> Result<(), String> {
+  if state.is_game_running() {
+    return Err("The game is currently running — close it before updating.".to_string());
+  }
+  Ok(())
+}
+
+const TUNER_INITIAL_CONCURRENCY: usize = 2;
+const TUNER_MAX_CONCURRENCY: usize = 16;
+const TUNER_WINDOW: Duration = Duration::from_secs(10);
+
+/// Picks the number of parallel transfers from the throughput measured over
+/// 10-second windows: it adds a connection while throughput keeps improving by
+/// more than 10%, and drops one when per-connection speed halves or the error
+/// rate rises. Setting `download_concurrency` in config.ini pins the value.
+struct ConcurrencyTuner {
+  concurrency: usize,
+  fixed: bool,
+  window_start: Instant,
+  window_start_bytes: u64,
+  window_successes: usize,
+  window_errors: usize,
+  last_throughput: f64,
+  last_per_connection: f64,
+  last_error_rate: f64,
+}
+
+impl ConcurrencyTuner {
+  fn new() -> Self {
+    let fixed = get_launcher_setting("download_concurrency")
+      .and_then(|v| v.parse::<usize>().ok())
+      .filter(|v| *v > 0);
+    ConcurrencyTuner {
+      concurrency: fixed.unwrap_or(TUNER_INITIAL_CONCURRENCY),
+      fixed: fixed.is_some(),
+      window_start: Instant::now(),
+      window_start_bytes: 0,
+      window_successes: 0,
+      window_errors: 0,
+      last_throughput: 0.0,
+      last_per_connection: 0.0,
+      last_error_rate: 0.0,
+    }
+  }
+
+  fn record_completion(&mut self, success: bool) {
+    if success {
+      self.window_successes += 1;
+    } else {
+      self.window_errors += 1;
+    }
+  }
+
+  /// Closes the current window once it is 10 seconds old. `network_bytes` is
+  /// the session total excluding local copies.
+  fn update(&mut self, network_bytes: u64) {
+    let elapsed = self.window_start.elapsed();
+    if self.fixed || elapsed < TUNER_WINDOW {
+      return;
+    }
+
+    let throughput = network_bytes.saturating_sub(self.window_start_bytes) as f64 / elapsed.as_secs_f64();
+    let per_connection = throughput / self.concurrency as f64;
+    let attempts = self.window_successes + self.window_errors;
+    let error_rate = if attempts > 0 { self.window_errors as f64 / attempts as f64 } else { 0.0 };
+
+    let collapsed = self.last_per_connection > 0.0 && per_connection < self.last_per_connection * 0.5;
+    let more_errors = error_rate > 0.0 && error_rate > self.last_error_rate;
+    let previous = self.concurrency;
+    if (collapsed || more_errors) && self.concurrency > 1 {
+      self.concurrency -= 1;
+    } else if throughput > self.last_throughput * 1.1 && self.concurrency < TUNER_MAX_CONCURRENCY {
+      self.concurrency += 1;
+    }
+    if self.concurrency != previous {
+      println!("Download concurrency {} -> {} ({}/s, {:.0}% errors)",
+          previous, self.concurrency, format_bytes(throughput as u64), error_rate * 100.0);
+    }
+
+    self.window_start = Instant::now();
+    self.window_start_bytes = network_bytes;
+    self.window_successes = 0;
+    self.window_errors = 0;
+    self.last_throughput = throughput;
+    self.last_per_connection = per_connection;
+    self.last_error_rate = error_rate;
+  }
+}
+
+enum FileTransfer {
+  Copied(u64),
+  Downloaded(FileDownloadOutcome),
+}
+
+/// Copies `file_info` from `source` when given, falling back to a download if
+/// the copy fails. Returns the file back so the session can account for it.
+async fn transfer_file(
+  window: &tauri::Window,
+  file_info: FileInfo,
+  source: Option<PathBuf>,
+  ctx: DownloadProgressContext,
+) -> (FileInfo, Result<FileTransfer, String>) {
+  if let Some(source_path) = source {
+    match copy_duplicate_file(window, &source_path, &file_info, &ctx).await {
+      Ok(copied) => return (file_info, Ok(FileTransfer::Copied(copied))),
+      Err(e) => println!("Failed to copy duplicate {}, downloading it instead: {}", file_info.path, e),
+    }
+  }
+
+  let result = download_and_verify_file(window, &file_info, &ctx).await.map(FileTransfer::Downloaded);
+  (file_info, result)
+}
+
+#[derive(Default)]
+struct DownloadSessionOptions {
+  continue_on_error: bool,
+  /// Hashes mapped to files on disk that can be copied instead of downloaded;
+  /// files verified during the run are added to it as well.
+  local_sources: HashMap<String, PathBuf>,
+  /// Pending files the caller left out of this session, reported in the summary.
+  deferred_files: usize,
+  /// Download files in `protected_files` too.
+  override_protected: bool,
+  /// Replace locally modified files (moving them to quarantine first).
+  overwrite_modified: bool,
+}
+
+/// Shared body of `download_all_files`, `download_files_subset` and
+/// `import_from_existing_install`. Files are fetched in priority order, smallest
+/// first within the same priority.
+async fn run_download_session(
+  window: &tauri::Window,
+  files_to_update: Vec<FileInfo>,
+  options: DownloadSessionOptions,
+) -> Result<DownloadSummary, String> {
+  let DownloadSessionOptions { continue_on_error, local_sources, deferred_files, override_protected, overwrite_modified } = options;
+  let _sleep_guard = SleepGuard::new();
+  // Checked against the setting rather than the `protected` flag, which the
+  // frontend may not pass back.
+  let protected: HashSet<String> = if override_protected { HashSet::new() } else { load_protected_files().into_iter().collect() };
+  let (protected_files, files_to_update): (Vec<FileInfo>, Vec<FileInfo>) = files_to_update
+    .into_iter()
+    .partition(|f| protected.contains(&f.path));
+  if !protected_files.is_empty() {
+    println!("Leaving {} protected file(s) untouched", protected_files.len());
+  }
+  let (modified_files, files_to_update): (Vec<FileInfo>, Vec<FileInfo>) = files_to_update
+    .into_iter()
+    .partition(|f| f.locally_modified && !overwrite_modified);
+  if !modified_files.is_empty() {
+    println!("Skipping {} locally modified file(s) without overwrite_modified", modified_files.len());
+  }
+  let total_files = files_to_update.len();
+  let mut total_size: u64 = files_to_update.iter().map(|f| f.size).sum();
+  let start_time = Instant::now();
+  let mut summary = DownloadSummary {
+    deferred_files,
+    protected_files: protected_files.len(),
+    modified_files: modified_files.len(),
+    ..Default::default()
+  };
+
+  if total_files == 0 {
+    println!("No files to download");
+    set_taskbar_progress(window, TaskbarProgress::Clear);
+    if let Err(e) = window.emit("download_complete", &summary) {
+      eprintln!("Failed to emit download_complete event: {}", e);
+    }
+    return Ok(summary);
+  }
+
+  // Record the session up front so it can be resumed if the launcher dies mid-update.
+  // A resumed session keeps counting from the bytes it already downloaded.
+  let mut session = match resumed_download_session(&files_to_update) {
+    Some(saved) => {
+      println!("Resuming download session at {} of {}", format_bytes(saved.downloaded_bytes), format_bytes(saved.total_bytes));
+      total_size = saved.total_bytes;
+      saved
+    }
+    None => DownloadSession {
+      files: files_to_update.clone(),
+      completed: HashSet::new(),
+      downloaded_bytes: 0,
+      total_bytes: total_size,
+    },
+  };
+  let resumed_bytes = session.downloaded_bytes;
+  let mut tuner = ConcurrencyTuner::new();
+  let progress = SessionProgress::new(resumed_bytes, tuner.concurrency);
+  if let Err(e) = save_download_session(&session) {
+    eprintln!("Failed to save download session: {}", e);
+  }
+  let mut last_session_save = Instant::now();
+
+  // Large updates go through the patch package when the hash file offers one;
+  // whatever it doesn't cover (or fails to verify) is downloaded per file below.
+  let mut files_to_update = files_to_update;
+  files_to_update.sort_by_key(|f| (f.priority.unwrap_or(DEFAULT_FILE_PRIORITY), f.size));
+  let mut completed_count = 0;
+  // Files that can be copied locally are never worth pulling from the archive.
+  let package_candidates: Vec<FileInfo> = files_to_update.iter()
+    .filter(|f| !local_sources.contains_key(&f.hash))
+    .cloned()
+    .collect();
+  if let Some(package) = select_patch_package(&package_candidates) {
+    let ctx = DownloadProgressContext {
+      total_files,
+      current_file_index: 1,
+      total_size,
+      progress: progress.clone(),
+    };
+    match install_patch_package(window, &package, &package_candidates, &ctx).await {
+      Ok(outcome) => {
+        completed_count = outcome.installed.len();
+        summary.files_attempted += completed_count;
+        summary.files_succeeded += completed_count;
+        files_to_update.retain(|f| !outcome.installed.contains(&f.path));
+        session.completed.extend(outcome.installed);
+        session.downloaded_bytes = progress.downloaded.load(Ordering::SeqCst);
+        if let Err(e) = save_download_session(&session) {
+          eprintln!("Failed to save download session: {}", e);
+        }
+      }
+      Err(e) => {
+        let error_msg = format!("Patch package failed, falling back to per-file downloads: {}", e);
+        let _ = log_error_to_file(&error_msg);
+        eprintln!("{}", error_msg);
+      }
+    }
+  }
+
+  // Only the first file of each hash is transferred; byte-identical copies wait
+  // for it to be verified and are then copied from it locally.
+  let mut verified_by_hash = local_sources;
+  let mut queue: VecDeque<FileInfo> = VecDeque::new();
+  let mut waiting_on_hash: HashMap<String, Vec<FileInfo>> = HashMap::new();
+  let mut scheduled_hashes: HashSet<String> = HashSet::new();
+  for file_info in files_to_update {
+    if verified_by_hash.contains_key(&file_info.hash) || scheduled_hashes.insert(file_info.hash.clone()) {
+      queue.push_back(file_info);
+    } else {
+      waiting_on_hash.entry(file_info.hash.clone()).or_default().push(file_info);
+    }
+  }
+
+  let mut in_flight = FuturesUnordered::new();
+  let mut next_index = completed_count;
+  let mut tuner_tick = tokio::time::interval(Duration::from_secs(1));
+
+  loop {
+    if is_update_cancelled() {
+      // Let the transfers in flight notice and stop; nothing new is started.
+      queue.clear();
+      waiting_on_hash.clear();
+    }
+    while in_flight.len() < tuner.concurrency {
+      let Some(file_info) = queue.pop_front() else { break };
+      next_index += 1;
+      summary.files_attempted += 1;
+      let ctx = DownloadProgressContext {
+        total_files,
+        current_file_index: next_index,
+        total_size,
+        progress: progress.clone(),
+      };
+      let source = verified_by_hash.get(&file_info.hash).cloned();
+      in_flight.push(transfer_file(window, file_info, source, ctx));
+    }
+    if in_flight.is_empty() {
+      break;
+    }
+
+    let (file_info, result) = tokio::select! {
+      Some(finished) = in_flight.next() => finished,
+      _ = tuner_tick.tick() => {
+        let network_bytes = progress.downloaded.load(Ordering::SeqCst)
+          .saturating_sub(resumed_bytes + summary.bytes_saved);
+        tuner.update(network_bytes);
+        progress.concurrency.store(tuner.concurrency, Ordering::SeqCst);
+        continue;
+      }
+    };
+
+    match result {
+      Ok(transfer) => {
+        summary.files_succeeded += 1;
+        match transfer {
+          FileTransfer::Copied(copied) => {
+            summary.copied_files += 1;
+            summary.bytes_saved += copied;
+          }
+          FileTransfer::Downloaded(outcome) => {
+            tuner.record_completion(true);
+            summary.retries += outcome.retries;
+            summary.peak_speed = summary.peak_speed.max(outcome.peak_speed);
+          }
+        }
+
+        if let Ok(game_path) = get_game_files_path() {
+          verified_by_hash.entry(file_info.hash.clone()).or_insert_with(|| game_path.join(&file_info.path));
+        }
+        if let Some(duplicates) = waiting_on_hash.remove(&file_info.hash) {
+          for duplicate in duplicates.into_iter().rev() {
+            queue.push_front(duplicate);
+          }
+        }
+
+        session.completed.insert(file_info.path);
+        session.downloaded_bytes = progress.downloaded.load(Ordering::SeqCst);
+        // Rewriting the whole list after every small file is wasteful; losing up
+        // to a second of progress on a crash is fine.
+        if last_session_save.elapsed() >= Duration::from_secs(1) {
+          if let Err(e) = save_download_session(&session) {
+            eprintln!("Failed to save download session: {}", e);
+          }
+          last_session_save = Instant::now();
+        }
+      }
+      Err(e) => {
+        summary.files_failed += 1;
+        tuner.record_completion(false);
+        set_taskbar_progress(window, TaskbarProgress::Error);
+
+        // Nothing verified to copy from, so the next duplicate becomes the source.
+        if let Some(mut duplicates) = waiting_on_hash.remove(&file_info.hash) {
+          queue.push_back(duplicates.remove(0));
+          if !duplicates.is_empty() {
+            waiting_on_hash.insert(file_info.hash.clone(), duplicates);
+          }
+        }
+
+        if continue_on_error && !is_update_cancelled() {
+          eprintln!("Failed to download {}: {}", file_info.path, e);
+          summary.failed_files.push(file_info.path.clone());
+          continue;
+        }
+        if let Err(save_err) = save_download_session(&session) {
+          eprintln!("Failed to save download session: {}", save_err);
+        }
+        flush_hash_cache();
+        flush_backup_manifest().await;
+        window.state::<UpdateState>().invalidate().await;
+        return Err(e);
+      }
+    }
+  }
+  if is_update_cancelled() {
+    if let Err(e) = save_download_session(&session) {
+      eprintln!("Failed to save download session: {}", e);
+    }
+    flush_hash_cache();
+    flush_backup_manifest().await;
+    window.state::<UpdateState>().invalidate().await;
+    set_taskbar_progress(window, TaskbarProgress::Clear);
+    return Err(UPDATE_CANCELLED.to_string());
+  }
+  let downloaded_size = progress.downloaded.load(Ordering::SeqCst);
+
+  // Keep the session around when files failed so they can be resumed.
+  if summary.files_failed == 0 {
+    clear_download_session();
+  } else if let Err(e) = save_download_session(&session) {
+    eprintln!("Failed to save download session: {}", e);
+  }
+  flush_hash_cache();
+  flush_backup_manifest().await;
+  window.state::<UpdateState>().invalidate().await;
+  if summary.is_complete() {
+    mark_manifest_applied().await;
+  } else {
+    reset_applied_manifest_version();
+  }
+
+  let elapsed = start_time.elapsed().as_secs_f64();
+  summary.total_bytes = downloaded_size - summary.bytes_saved;
+  summary.elapsed_seconds = elapsed;
+  // Bytes from the earlier run weren't transferred in `elapsed`.
+  let transferred = summary.total_bytes.saturating_sub(resumed_bytes);
+  summary.average_speed = if elapsed > 0.0 { transferred as f64 / elapsed } else { 0.0 };
+
+  println!("Download complete for {} file(s): {} succeeded, {} failed, {} in {:.1}s",
+      total_files, summary.files_succeeded, summary.files_failed, format_bytes(downloaded_size), elapsed);
+  set_taskbar_progress(window, TaskbarProgress::Clear);
+  if let Err(e) = window.emit("download_complete", &summary) {
+    eprintln!("Failed to emit download_complete event: {}", e);
+  }
+
+  Ok(summary)
+}
+
+/// Runs the update check and returns only its totals; the file list stays in
+/// `UpdateState` for `start_cached_download`.
+#[tauri::command]
+async fn get_update_summary(
+  window: tauri::Window,
+  update_state: tauri::State<'_, UpdateState>,
+) -> Result<UpdateSummary, String> {
+  let _operation = begin_operation(&window.app_handle(), LauncherOperation::Verifying)?;
+  let files = find_files_to_update(Some(&window), false, None, false).await?;
+  let cached = CachedUpdate::new(files);
+  let summary = UpdateSummary {
+    files_to_update: cached.files.iter().filter(|f| !f.protected).count(),
+    total_bytes: cached.total_bytes,
+    protected_files: cached.files.iter().filter(|f| f.protected).count(),
+    manifest_version: cached.manifest_version.clone(),
+    checked_at: cached.checked_at,
+  };
+  *update_state.cached.lock().await = Some(cached);
+  Ok(summary)
+}
+
+#[derive(Debug, Serialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+enum InstallState {
+  Installed,
+  Partial,
+  NotInstalled,
+}
+
+#[derive(Debug, Serialize)]
+struct ContentGroup {
+  name: String,
+  file_count: usize,
+  total_size: u64,
+  /// Files present with the manifest size; they may still be outdated.
+  installed_files: usize,
+  installed_size: u64,
+  state: InstallState,
+  selected: bool,
+}
+
+/// Lists the optional content groups in the server manifest with their size
+/// and how much of each is installed.
+#[tauri::command]
+async fn get_content_groups() -> Result<Vec<ContentGroup>, String> {
+  let manifest = get_server_hash_file(false).await?;
+  let game_path = get_game_files_path()?;
+  let case_insensitive = case_insensitive_paths_enabled();
+  let selected = selected_content_groups();
+  let mut groups: BTreeMap<String, ContentGroup> = BTreeMap::new();
+  for file in manifest["files"].as_array().ok_or("Invalid server hash file format")? {
+    let Some(name) = file["group"].as_str() else {
+      continue;
+    };
+    let size = file["size"].as_u64().unwrap_or(0);
+    let group = groups.entry(name.to_string()).or_insert_with(|| ContentGroup {
+      name: name.to_string(),
+      file_count: 0,
+      total_size: 0,
+      installed_files: 0,
+      installed_size: 0,
+      state: InstallState::NotInstalled,
+      selected: selected.as_ref().is_none_or(|selected| selected.contains(name)),
+    });
+    group.file_count += 1;
+    group.total_size += size;
+    let local_path = game_file_path(&game_path, file["path"].as_str().unwrap_or(""), case_insensitive);
+    if fs::metadata(local_path).is_ok_and(|m| m.len() == size) {
+      group.installed_files += 1;
+      group.installed_size += size;
+    }
+  }
+  Ok(groups.into_values()
+    .map(|mut group| {
+      group.state = match group.installed_files {
+        0 => InstallState::NotInstalled,
+        n if n == group.file_count => InstallState::Installed,
+        _ => InstallState::Partial,
+      };
+      group
+    })
+    .collect())
+}
+
+/// Saves the content groups to install and runs the update check again, so
+/// newly selected groups are queued for `start_cached_download` and the
+/// installed files of deselected ones are reported by `removable_group_files`.
+#[tauri::command]
+async fn set_selected_groups(
+  window: tauri::Window,
+  groups: Vec<String>,
+  update_state: tauri::State<'_, UpdateState>,
+) -> Result<UpdateSummary, String> {
+  let groups: Vec<&str> = groups.iter().map(|group| group.trim()).filter(|group| !group.is_empty()).collect();
+  if let Some(group) = groups.iter().find(|group| group.contains(',')) {
+    return Err(format!("Invalid content group: {}", group));
+  }
+  let selection = groups.join(",");
+  if get_launcher_setting("selected_groups").unwrap_or_default() != selection {
+    // Newly selected groups aren't in a diff from the applied version.
+    reset_applied_manifest_version();
+  }
+  save_launcher_setting("selected_groups", &selection)?;
+  get_update_summary(window, update_state).await
+}
+
+#[derive(Debug, Serialize, Default)]
+struct RemovedGroupFiles {
+  files_removed: usize,
+  bytes_freed: u64,
+}
+
+/// Deletes the installed files of content groups that aren't selected.
+/// Ignored paths are left alone.
+#[tauri::command]
+async fn remove_deselected_group_files(state: tauri::State<'_, GameState>) -> Result<RemovedGroupFiles, String> {
+  ensure_game_not_running(&state).await?;
+  let Some(selected) = selected_content_groups() else {
+    return Ok(RemovedGroupFiles::default());
+  };
+  let manifest = parse_server_manifest(&get_server_hash_file(false).await?)?;
+  let game_path = get_game_files_path()?;
+  let mut files: Vec<&ServerFileEntry> = manifest.files.iter().collect();
+  let removable = drop_unselected_group_entries(&mut files, &selected, &game_path);
+  let rules = session_ignore_rules(&game_path)?;
+  let case_insensitive = case_insensitive_paths_enabled();
+  let cache = hash_cache();
+  let mut removed = RemovedGroupFiles::default();
+  for path in removable.iter().filter(|path| is_safe_path(path) && !rules.is_ignored(path)) {
+    let local_path = game_file_path(&game_path, path, case_insensitive);
+    let size = fs::metadata(&local_path).map(|m| m.len()).unwrap_or(0);
+    match fs::remove_file(&local_path) {
+      Ok(()) => {
+        cache.remove(path);
+        removed.files_removed += 1;
+        removed.bytes_freed += size;
+      }
+      Err(e) => println!("Failed to remove {}: {}", path, e),
+    }
+  }
+  flush_hash_cache();
+  println!("Removed {} file(s) of deselected content groups ({})", removed.files_removed, format_bytes(removed.bytes_freed));
+  Ok(removed)
+}
+
+#[derive(Debug, Serialize)]
+struct UpdateReportEntry {
+  path: String,
+  reason: Option<UpdateReason>,
+  local_hash: Option<String>,
+  server_hash: String,
+  local_size: Option<u64>,
+  server_size: u64,
+}
+
+#[derive(Debug, Serialize)]
+struct UpdateReportResult {
+  path: String,
+  files: usize,
+  total_bytes: u64,
+}
+
+/// Quotes a CSV field when it contains a separator, quote or line break.
+fn csv_field(value: &str) -> String {
+  if value.contains([',', '"', '\n', '\r']) {
+    format!("\"{}\"", value.replace('"', "\"\""))
+  } else {
+    value.to_string()
+  }
+}
+
+fn update_report_csv(entries: &[UpdateReportEntry], total_bytes: u64) -> String {
+  let mut csv = String::from("path,reason,local_hash,server_hash,local_size,server_size\n");
+  for entry in entries {
+    let reason = entry.reason.and_then(|r| serde_json::to_value(r).ok())
+      .and_then(|r| r.as_str().map(str::to_string))
+      .unwrap_or_default();
+    let fields = [
+      csv_field(&entry.path),
+      reason,
+      entry.local_hash.clone().unwrap_or_default(),
+      entry.server_hash.clone(),
+      entry.local_size.map(|s| s.to_string()).unwrap_or_default(),
+      entry.server_size.to_string(),
+    ];
+    csv.push_str(&fields.join(","));
+    csv.push('\n');
+  }
+  csv.push_str(&format!("TOTAL,{} files,,,,{}\n", entries.len(), total_bytes));
+  csv
+}
+
+/// Writes the files found by the last `get_update_summary` call, or by a new
+/// check when there is none, as a JSON or CSV report players can send to the
+/// server admins. Without `output_path` the report is written next to the
+/// launcher as `update-report-<date>-<time>.<format>`.
+#[tauri::command]
+async fn export_update_report(
+  app_handle: tauri::AppHandle,
+  format: String,
+  output_path: Option<String>,
+  update_state: tauri::State<'_, UpdateState>,
+) -> Result<UpdateReportResult, String> {
+  let format = format.to_ascii_lowercase();
+  if format != "json" && format != "csv" {
+    return Err(format!("Unsupported report format: {}", format));
+  }
+  let last_check = update_state.cached.lock().await.as_ref()
+    .map(|cached| (cached.files.clone(), cached.manifest_version.clone(), cached.checked_at));
+  let (files, manifest_version, checked_at) = match last_check {
+    Some(last_check) => last_check,
+    None => {
+      let _operation = begin_operation(&app_handle, LauncherOperation::Verifying)?;
+      let files = find_files_to_update(None, false, None, false).await?;
+      let checked_at = SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap_or_default().as_secs();
+      (files, CURRENT_MANIFEST_VERSION.read().unwrap().clone(), checked_at)
+    }
+  };
+
+  let game_path = get_game_files_path()?;
+  let case_insensitive = case_insensitive_paths_enabled();
+  let entries: Vec<UpdateReportEntry> = files.iter()
+    .map(|file| UpdateReportEntry {
+      path: file.path.clone(),
+      reason: file.reason,
+      local_hash: file.local_hash.clone(),
+      server_hash: file.hash.clone(),
+      local_size: fs::metadata(game_file_path(&game_path, &file.path, case_insensitive)).ok().map(|m| m.len()),
+      server_size: file.size,
+    })
+    .collect();
+  let total_bytes: u64 = files.iter().map(|f| f.transfer_size()).sum();
+
+  let output_path = match output_path {
+    Some(path) => PathBuf::from(path),
+    None => {
+      let exe_dir = env::current_exe().ok()
+        .and_then(|p| p.parent().map(Path::to_path_buf))
+        .ok_or("Failed to locate the launcher directory")?;
+      let time = checked_at % 86_400;
+      exe_dir.join(format!(
+        "update-report-{}-{:02}{:02}{:02}.{}",
+        format_date(checked_at), time / 3600, time / 60 % 60, time % 60, format
+      ))
+    }
+  };
+  let contents = if format == "csv" {
+    update_report_csv(&entries, total_bytes)
+  } else {
+    serde_json::to_string_pretty(&json!({
+      "manifest_version": manifest_version,
+      "checked_at": checked_at,
+      "summary": { "files": entries.len(), "total_bytes": total_bytes },
+      "files": entries,
+    })).map_err(|e| e.to_string())?
+  };
+  fs::write(&output_path, contents)
+    .map_err(|e| format!("Failed to write {}: {}", output_path.display(), e))?;
+  println!("Update report written to {}", output_path.display());
+
+  Ok(UpdateReportResult {
+    path: output_path.display().to_string(),
+    files: entries.len(),
+    total_bytes,
+  })
+}
+
+/// Downloads the files found by the last `get_update_summary` call.
+#[tauri::command]
+async fn start_cached_download(
+  window: tauri::Window,
+  state: tauri::State<'_, GameState>,
+  update_state: tauri::State<'_, UpdateState>,
+  continue_on_error: Option<bool>,
+  override_protected: Option<bool>,
+  overwrite_modified: Option<bool>,
+) -> Result<DownloadSummary, String> {
+  ensure_game_not_running(&state).await?;
+  let _operation = begin_operation(&window.app_handle(), LauncherOperation::Updating)?;
+  let cached = update_state.cached.lock().await.take()
+    .ok_or("No cached update check; call get_update_summary first")?;
+  let options = DownloadSessionOptions {
+    continue_on_error: continue_on_error.unwrap_or(false),
+    override_protected: override_protected.unwrap_or(false),
+    overwrite_modified: overwrite_modified.unwrap_or(false),
+    ..Default::default()
+  };
+  run_download_session(&window, cached.files, options).await
+}
+
+/// Updates the game from another local install: every pending file whose
+/// SHA-256 matches the file at the same path under `source_path` is copied,
+/// and only the rest is downloaded.
+#[tauri::command]
+async fn import_from_existing_install(
+  window: tauri::Window,
+  state: tauri::State<'_, GameState>,
+  source_path: String,
+  files_to_update: Vec<FileInfo>,
+  continue_on_error: Option<bool>,
+) -> Result<DownloadSummary, String> {
+  ensure_game_not_running(&state).await?;
+  let _operation = begin_operation(&window.app_handle(), LauncherOperation::Updating)?;
+
+  let source_path = PathBuf::from(source_path);
+  if !source_path.is_dir() {
+    return Err(format!("Import source {} is not a directory", source_path.display()));
+  }
+  let game_path = get_game_files_path()?;
+  if fs::canonicalize(&source_path).ok() == fs::canonicalize(&game_path).ok() {
+    return Err("Import source is the configured game directory".to_string());
+  }
+
+  println!("Scanning {} for files to import", source_path.display());
+  let scan_files = files_to_update.clone();
+  let scan_window = window.clone();
+  let scan_source = source_path.clone();
+  let local_sources: HashMap<String, PathBuf> = tokio::task::spawn_blocking(move || {
+    let start_time = Instant::now();
+    let processed_count = AtomicUsize::new(0);
+    let matched_count = AtomicUsize::new(0);
+    scan_files.par_iter()
+      .filter_map(|file_info| {
+        let current_count = processed_count.fetch_add(1, Ordering::SeqCst) + 1;
+        if current_count.is_multiple_of(100) || current_count == scan_files.len() {
+          let _ = scan_window.emit("import_scan_progress", json!({
+            "current_file": file_info.path,
+            "progress": (current_count as f64 / scan_files.len() as f64) * 100.0,
+            "current_count": current_count,
+            "total_files": scan_files.len(),
+            "matched_files": matched_count.load(Ordering::SeqCst),
+            "elapsed_time": start_time.elapsed().as_secs_f64()
+          }));
+        }
+
+        if !is_safe_path(&file_info.path) {
+          return None;
+        }
+        let candidate = scan_source.join(&file_info.path);
+        let size_matches = fs::metadata(&candidate).map(|m| m.len() == file_info.size).unwrap_or(false);
+        if !size_matches {
+          return None;
+        }
+        match calculate_file_hash_with(&candidate, file_info.algo) {
+          Ok(hash) if hash == file_info.hash => {
+            matched_count.fetch_add(1, Ordering::SeqCst);
+            Some((hash, candidate))
+          }
+          _ => None,
+        }
+      })
+      .collect()
+  }).await.map_err(|e| e.to_string())?;
+  println!("{} of {} pending file(s) can be imported", local_sources.len(), files_to_update.len());
+
+  let options = DownloadSessionOptions {
+    continue_on_error: continue_on_error.unwrap_or(false),
+    local_sources,
+    ..Default::default()
+  };
+  run_download_session(&window, files_to_update, options).await
+}
+
+/// Returns the files left over from an interrupted `download_all_files` run,
+/// or `None` when there is nothing to resume. The remaining files can be passed
+/// straight back into `download_all_files` without another file check.
+#[tauri::command]
+fn get_pending_download_session() -> Result<Option<PendingDownloadSession>, String> {
+  let session = match load_download_session() {
+    Ok(session) => session,
+    Err(_) => return Ok(None),
+  };
+
+  let total_files = session.files.len();
+  let remaining_files: Vec<FileInfo> = session.files
+    .into_iter()
+    .filter(|f| !session.completed.contains(&f.path))
+    .collect();
+
+  if remaining_files.is_empty() {
+    clear_download_session();
+    return Ok(None);
+  }
+
+  Ok(Some(PendingDownloadSession {
+    completed_files: total_files - remaining_files.len(),
+    remaining_files,
+    total_files,
+    downloaded_bytes: session.downloaded_bytes,
+    total_bytes: session.total_bytes,
+  }))
+}
+
+/// Emits `modified_files_detected` with the pending files the player changed,
+/// so the frontend can ask before downloading with `overwrite_modified`.
+fn emit_modified_files(window: &tauri::Window, files: &[FileInfo]) {
+  let modified: Vec<&str> = files.iter()
+    .filter(|f| f.locally_modified && !f.protected)
+    .map(|f| f.path.as_str())
+    .collect();
+  if !modified.is_empty() {
+    println!("{} file(s) were modified locally", modified.len());
+    let _ = window.emit("modified_files_detected", json!({ "files": modified }));
+  }
+}
+
+/// A file counts as locally modified when it matches neither the server nor
+/// the version recorded at the last verified update. A cache entry from a
+/// different algorithm can't be compared, so it says nothing either way.
+fn is_locally_modified(cached: Option<&CachedFileInfo>, local_hash: &str, server_hash: &str, algo: HashAlgorithm) -> bool {
+  match cached {
+    Some(cached_info) if cached_info.algo == algo => local_hash != server_hash && local_hash != cached_info.last_verified_hash(),
+    _ => false,
+  }
+}
+
+/// A hash file entry as the update check reads it. Only the fields the check
+/// can't work without are required, but every field must have the right type.
+#[derive(Debug, Deserialize)]
+struct ServerFileEntry {
+  path: String,
+  hash: String,
+  size: u64,
+  url: String,
+  #[serde(default)]
+  mtime: Option<u64>,
+  #[serde(default)]
+  priority: Option<u32>,
+  #[serde(default)]
+  chunks: Option<Vec<FileChunk>>,
+  /// Overrides the manifest's algorithm; unknown names fall back to it.
+  #[serde(default)]
+  algo: Option<String>,
+  #[serde(default)]
+  quick_hash: Option<String>,
+  #[serde(default)]
+  group: Option<String>,
+  #[serde(default)]
+  attributes: Option<FileAttributes>,
+  /// Checked before every launch, see `check_critical_files`.
+  #[serde(default)]
+  critical: bool,
+}
+
+impl ServerFileEntry {
+  fn algo(&self, manifest_default: HashAlgorithm) -> HashAlgorithm {
+    self.algo.as_deref()
+      .and_then(|name| HashAlgorithm::parse(name).ok())
+      .unwrap_or(manifest_default)
+  }
+
+  /// The entry as a file to update, before the check fills in why.
+  fn to_pending(&self, algo: HashAlgorithm) -> FileInfo {
+    FileInfo {
+      path: self.path.clone(),
+      hash: self.hash.clone(),
+      size: self.size,
+      url: self.url.clone(),
+      mtime: self.mtime,
+      priority: self.priority,
+      chunks: self.chunks.clone(),
+      locally_modified: false,
+      algo,
+      quick_hash: self.quick_hash.clone(),
+      changed_bytes: None,
+      reason: None,
+      local_hash: None,
+      group: self.group.clone(),
+      protected: false,
+      attributes: self.attributes.clone(),
+    }
+  }
+}
+
+struct ServerManifest {
+  files: Vec<ServerFileEntry>,
+}
+
+/// Reads the `files` of a hash file. A malformed entry fails the whole
+/// check, naming its index and content, rather than being skipped and
+/// leaving the file silently out of date.
+fn parse_server_manifest(manifest: &serde_json::Value) -> Result<ServerManifest, String> {
+  let entries = manifest["files"].as_array().ok_or("Invalid server hash file format: no files array")?;
+  let files = entries.iter()
+    .enumerate()
+    .map(|(index, entry)| ServerFileEntry::deserialize(entry).map_err(|e| {
+      let snippet: String = entry.to_string().chars().take(200).collect();
+      let error_msg = format!("Invalid server hash file entry {}: {} in {}", index, e, snippet);
+      let _ = log_error_to_file(&error_msg);
+      error_msg
+    }))
+    .collect::<Result<_, _>>()?;
+  Ok(ServerManifest { files })
+}
+
+/// Drops the manifest entries covered by `rules`. Entries excluded only by
+/// the player's rules are also returned by path when the local copy is
+/// missing or a different size, i.e. updates the check is leaving out.
+fn filter_ignored_entries<'a>(
+  files: &'a [ServerFileEntry],
+  rules: &IgnoreRules,
+  game_path: &Path,
+) -> (Vec<&'a ServerFileEntry>, Vec<String>) {
+  let mut kept = Vec::with_capacity(files.len());
+  let mut user_skipped = Vec::new();
+  for file in files {
+    let path = file.path.as_str();
+    if !rules.is_ignored(path) {
+      kept.push(file);
+    } else if rules.is_user_ignored(path) {
+      let outdated = fs::metadata(game_path.join(path))
+        .map(|m| m.len() != file.size)
+        .unwrap_or(true);
+      if outdated {
+        user_skipped.push(path.to_string());
+      }
+    }
+  }
+  if !user_skipped.is_empty() {
+    println!("{} update(s) skipped by launcher_ignore.txt", user_skipped.len());
+  }
+  (kept, user_skipped)
+}
+
+/// True when a directory between `game_path` and `relative_path` is a symlink
+/// or junction. `links` remembers the directories already looked at.
+fn is_behind_symlink(game_path: &Path, relative_path: &str, links: &mut HashMap<PathBuf, bool>) -> bool {
+  let components = path_components(relative_path);
+  let mut dir = game_path.to_path_buf();
+  for component in &components[..components.len().saturating_sub(1)] {
+    dir.push(component);
+    let is_link = *links.entry(dir.clone()).or_insert_with(|| {
+      fs::symlink_metadata(&dir).map(|m| m.file_type().is_symlink()).unwrap_or(false)
+    });
+    if is_link {
+      return true;
+    }
+  }
+  false
+}
+
+/// Leaves files reached through a symlinked directory out of the update check
+/// when symlinks aren't followed, so nothing behind the link is hashed or
+/// replaced.
+fn drop_symlinked_entries(files: &mut Vec<&ServerFileEntry>, game_path: &Path) {
+  let mut links = HashMap::new();
+  let before = files.len();
+  files.retain(|f| !is_behind_symlink(game_path, &f.path, &mut links));
+  if files.len() < before {
+    println!("Skipped {} file(s) behind symlinked directories", before - files.len());
+  }
+}
+
+/// Content groups from the `selected_groups` launcher setting (comma
+/// separated), or `None` when it was never set and every group is installed.
+fn selected_content_groups() -> Option<HashSet<String>> {
+  get_launcher_setting("selected_groups").map(|groups| groups.split(',')
+    .map(str::trim)
+    .filter(|group| !group.is_empty())
+    .map(str::to_string)
+    .collect())
+}
+
+/// Leaves files of content groups the player hasn't selected out of the
+/// update check. Returns the paths of those that are installed, which can be
+/// deleted to free space.
+fn drop_unselected_group_entries(files: &mut Vec<&ServerFileEntry>, selected: &HashSet<String>, game_path: &Path) -> Vec<String> {
+  let case_insensitive = case_insensitive_paths_enabled();
+  let before = files.len();
+  let mut removable = Vec::new();
+  files.retain(|f| match &f.group {
+    Some(group) if !selected.contains(group) => {
+      if game_file_path(game_path, &f.path, case_insensitive).is_file() {
+        removable.push(f.path.clone());
+      }
+      false
+    }
+    _ => true,
+  });
+  if files.len() < before {
+    println!("Skipped {} file(s) in unselected content groups", before - files.len());
+  }
+  removable
+}
+
+/// Separator of the `protected_files` setting; `|` can't appear in a Windows
+/// file name.
+const PROTECTED_FILES_SEPARATOR: char = '|';
+
+/// Files the player keeps even when they differ from the server (custom UI
+/// xml, translated strings), from the `protected_files` launcher setting.
+fn load_protected_files() -> Vec<String> {
+  get_launcher_setting("protected_files")
+    .map(|files| files.split(PROTECTED_FILES_SEPARATOR)
+      .filter(|path| !path.trim().is_empty())
+      .map(|path| path_components(path).join("/"))
+      .collect())
+    .unwrap_or_default()
+}
+
+fn save_protected_files(files: &[String]) -> Result<(), String> {
+  save_launcher_setting("protected_files", &files.join(&PROTECTED_FILES_SEPARATOR.to_string()))
+}
+
+/// Flags the pending files that are protected. Returns how many there are
+/// and their size.
+fn mark_protected_files(files: &mut [FileInfo]) -> (usize, u64) {
+  let protected: HashSet<String> = load_protected_files().into_iter().collect();
+  let mut count = 0;
+  let mut size = 0;
+  for file in files.iter_mut().filter(|f| protected.contains(&f.path)) {
+    file.protected = true;
+    count += 1;
+    size += file.size;
+  }
+  (count, size)
+}
+
+fn normalize_protected_path(path: &str) -> Result<String, String> {
+  let normalized = path_components(path).join("/");
+  if normalized.is_empty() || normalized.contains(PROTECTED_FILES_SEPARATOR) || !is_safe_path(&normalized) {
+    return Err(format!("Invalid protected file: {}", path));
+  }
+  Ok(normalized)
+}
+
+#[tauri::command]
+fn list_protected_files() -> Vec<String> {
+  load_protected_files()
+}
+
+/// Protects a game file, given relative to the game directory, from updates.
+#[tauri::command]
+fn add_protected_file(path: String) -> Result<Vec<String>, String> {
+  let path = normalize_protected_path(&path)?;
+  let mut files = load_protected_files();
+  if !files.contains(&path) {
+    files.push(path);
+    save_protected_files(&files)?;
+  }
+  Ok(files)
+}
+
+#[tauri::command]
+fn remove_protected_file(path: String) -> Result<Vec<String>, String> {
+  let path = normalize_protected_path(&path)?;
+  let mut files = load_protected_files();
+  let count = files.len();
+  files.retain(|f| f != &path);
+  save_protected_files(&files)?;
+  // The file may have been left out of date while it was protected.
+  if files.len() != count {
+    reset_applied_manifest_version();
+  }
+  Ok(files)
+}
+
+/// With `use_cached`, returns the list from the last check without scanning,
+/// whatever its age; a fresh check runs only when nothing is stored.
+#[tauri::command]
+async fn get_files_to_update(
+  window: tauri::Window,
+  update_state: tauri::State<'_, UpdateState>,
+  follow_symlinks: Option<bool>,
+  use_cached: Option<bool>,
+) -> Result<Vec<FileInfo>, String> {
+  if use_cached.unwrap_or(false) {
+    if let Some(cached) = update_state.cached.lock().await.as_ref() {
+      return Ok(cached.files.clone());
+    }
+  }
+  let _operation = begin_operation(&window.app_handle(), LauncherOperation::Verifying)?;
+  let files = find_files_to_update(Some(&window), false, follow_symlinks, false).await?;
+  *update_state.cached.lock().await = Some(CachedUpdate::new(files.clone()));
+  Ok(files)
+}
+
+/// Decides whether one manifest entry needs updating, hashing the local copy
+/// only when the cache can't vouch for it, and records the result in the
+/// cache and `counters`. With `ignore_cache` the file is hashed whatever the
+/// cache says; its entry then only tells a local modification apart.
+/// Returns the entry to queue, if any.
+fn check_local_file(
+  file_info: &ServerFileEntry,
+  local_file_path: &Path,
+  cache: &dyn FileHashCache,
+  manifest_algo: HashAlgorithm,
+  check_attributes: bool,
+  ignore_cache: bool,
+  counters: &FileCheckCounters,
+) -> Option<FileInfo> {
+  let path = file_info.path.as_str();
+  let server_hash = file_info.hash.as_str();
+  let size = file_info.size;
+  let algo = file_info.algo(manifest_algo);
+  let mut pending = file_info.to_pending(algo);
+  // Called for files whose contents are up to date.
+  let attribute_update = |pending: FileInfo, metadata: &fs::Metadata| -> Option<FileInfo> {
+    let differs = pending.attributes.as_ref().is_some_and(|attributes| !attributes.matches(metadata));
+    if !(check_attributes && differs) {
+      return None;
+    }
+    counters.pending.fetch_add(1, Ordering::SeqCst);
+    Some(pending.attributes_only())
+  };
+
+  if !local_file_path.exists() {
+    counters.pending.fetch_add(1, Ordering::SeqCst);
+    counters.pending_size.fetch_add(size, Ordering::SeqCst);
+    return Some(pending.pending_because(UpdateReason::Missing, None));
+  }
+
+  let metadata = match fs::metadata(local_file_path) {
+    Ok(m) => m,
+    Err(_) => {
+      counters.pending.fetch_add(1, Ordering::SeqCst);
+      counters.pending_size.fetch_add(size, Ordering::SeqCst);
+      return Some(pending.pending_because(UpdateReason::Unreadable, None));
+    }
+  };
+
+  let last_modified = metadata.modified().ok();
+
+  let cached = cache.get(path);
+  if let (Some(cached_info), Some(lm), false) = (&cached, last_modified, ignore_cache) {
+    if cached_info.last_modified == lm && cached_info.algo == algo {
+      if cached_info.hash == server_hash {
+        counters.cached.fetch_add(1, Ordering::SeqCst);
+        return attribute_update(pending, &metadata);
+      }
+      // Unchanged since it was last hashed, and that hash isn't the server's.
+      pending.locally_modified = is_locally_modified(Some(cached_info), &cached_info.hash, server_hash, algo);
+      counters.pending.fetch_add(1, Ordering::SeqCst);
+      counters.pending_size.fetch_add(size, Ordering::SeqCst);
+      return Some(pending.pending_because(UpdateReason::HashMismatch, Some(cached_info.hash.clone())));
+    }
+  }
+
+  if metadata.len() != size {
+    // Clearly not the server's copy. Whether it is still the last verified
+    // version takes a hash, and only a cache entry can say what that was.
+    pending.locally_modified = match &cached {
+      Some(cached_info) if cached_info.algo == algo => match calculate_file_hash_with(local_file_path, algo) {
+        Ok(local_hash) => {
+          counters.bytes_checked.fetch_add(metadata.len(), Ordering::SeqCst);
+          is_locally_modified(Some(cached_info), &local_hash, server_hash, algo)
+        }
+        Err(_) => false,
+      },
+      _ => false,
+    };
+    counters.pending.fetch_add(1, Ordering::SeqCst);
+    counters.pending_size.fetch_add(size, Ordering::SeqCst);
+    return Some(pending.pending_because(UpdateReason::SizeMismatch, None));
+  }
+
+  // A matching xxh3 is taken as up to date; anything else falls through
+  // to the full hash before the file is queued.
+  if let Some(quick_hash) = &pending.quick_hash {
+    let local_quick_hash = calculate_quick_hash(local_file_path);
+    counters.bytes_checked.fetch_add(metadata.len(), Ordering::SeqCst);
+    if local_quick_hash.map(|h| &h == quick_hash).unwrap_or(false) {
+      counters.quick_resolved.fetch_add(1, Ordering::SeqCst);
+      counters.ok.fetch_add(1, Ordering::SeqCst);
+      match last_modified {
+        Some(last_modified) => {
+          cache.insert(path, CachedFileInfo::verified(server_hash.to_string(), last_modified, algo));
+        }
+        None => { counters.no_mtime.fetch_add(1, Ordering::SeqCst); }
+      }
+      return attribute_update(pending, &metadata);
+    }
+  }
+
+  // Chunked files are hashed per chunk in the same pass, so an edit to a
+  // few ranges of a large file only reports those ranges as pending.
+  let hashed = match pending.chunks.as_deref() {
+    Some(chunks) if !chunks.is_empty() => hash_file_with_chunks(local_file_path, algo, chunks)
+      .map(|(hash, valid)| {
+        let changed = chunks.iter().enumerate().filter(|(i, _)| !valid.contains(i)).map(|(_, c)| c.size).sum();
+        (hash, Some(changed))
+      }),
+    _ => calculate_file_hash_with(local_file_path, algo).map(|hash| (hash, None)),
+  };
+  let (local_hash, changed_bytes) = match hashed {
+    Ok(hashed) => {
+      counters.bytes_checked.fetch_add(metadata.len(), Ordering::SeqCst);
+      hashed
+    }
+    Err(_) => {
+      counters.pending.fetch_add(1, Ordering::SeqCst);
+      counters.pending_size.fetch_add(size, Ordering::SeqCst);
+      return Some(pending.pending_because(UpdateReason::Unreadable, None));
+    }
+  };
+
+  // Without a modification time an entry could never be matched again,
+  // so the file is simply hashed on every check.
+  let Some(last_modified) = last_modified else {
+    counters.no_mtime.fetch_add(1, Ordering::SeqCst);
+    if local_hash == server_hash {
+      counters.ok.fetch_add(1, Ordering::SeqCst);
+      return attribute_update(pending, &metadata);
+    }
+    pending.changed_bytes = changed_bytes;
+    counters.pending.fetch_add(1, Ordering::SeqCst);
+    counters.pending_size.fetch_add(pending.transfer_size(), Ordering::SeqCst);
+    return Some(pending.pending_because(UpdateReason::HashMismatch, Some(local_hash)));
+  };
+
+  if local_hash != server_hash {
+    pending.locally_modified = is_locally_modified(cached.as_ref(), &local_hash, server_hash, algo);
+    pending.changed_bytes = changed_bytes;
+    // The local hash is recorded so an untouched file isn't hashed again,
+    // and the last verified version carried over so it is still
+    // recognised as modified on the next check.
+    cache.insert(path, CachedFileInfo {
+      hash: local_hash.clone(),
+      last_modified,
+      algo,
+      verified_hash: cached.as_ref()
+        .filter(|cached_info| cached_info.algo == algo)
+        .map(|cached_info| cached_info.last_verified_hash().to_string()),
+    });
+    counters.pending.fetch_add(1, Ordering::SeqCst);
+    counters.pending_size.fetch_add(pending.transfer_size(), Ordering::SeqCst);
+    Some(pending.pending_because(UpdateReason::HashMismatch, Some(local_hash)))
+  } else {
+    cache.insert(path, CachedFileInfo::verified(local_hash, last_modified, algo));
+    counters.ok.fetch_add(1, Ordering::SeqCst);
+    attribute_update(pending, &metadata)
+  }
+}
+
+/// Compares the server hash file with the game directory, using the hash cache
+/// unless `ignore_cache` asks for every file to be hashed. Progress events
+/// are only emitted when a window is given, so background checks don't drive
+/// the frontend's check UI. `follow_symlinks` defaults to the launcher
+/// setting.
+async fn find_files_to_update(
+  window: Option<&tauri::Window>,
+  force_manifest: bool,
+  follow_symlinks: Option<bool>,
+  ignore_cache: bool,
+) -> Result<Vec<FileInfo>, String> {
+  let _sleep_guard = SleepGuard::new();
+  println!("Starting get_files_to_update ({})", if ignore_cache { "hashing every file" } else { "using cache" });
+
+  let start_time = Instant::now();
+  // A diff against the applied version only lists what changed; repairs
+  // always compare against the full hash file.
+  let diff_manifest = if force_manifest { None } else { fetch_manifest_diff().await };
+  let from_diff = diff_manifest.is_some();
+  let server_hash_file = match diff_manifest {
+    Some(manifest) => manifest,
+    None => get_server_hash_file(force_manifest).await?,
+  };
+
+  // Get the path to the game folder, which is the folder that contains the Tera game
+  // files. This is the folder that we will be comparing with the server hash file
+  // to determine which files need to be updated.
+  let local_game_path = get_game_files_path()?;
+  println!("Local game path: {:?}", local_game_path);
+
+  println!("Attempting to read server hash file");
+  let manifest_files = parse_server_manifest(&server_hash_file)?.files;
+  let (mut files, user_skipped) = filter_ignored_entries(&manifest_files, &*reload_ignore_rules(&local_game_path)?, &local_game_path);
+  if !follow_symlinks.unwrap_or_else(follow_symlinks_enabled) {
+    drop_symlinked_entries(&mut files, &local_game_path);
+  }
+  let removable = selected_content_groups()
+    .map(|selected| drop_unselected_group_entries(&mut files, &selected, &local_game_path))
+    .unwrap_or_default();
+  let files_ignored = manifest_files.len() - files.len();
+  let manifest_algo = HashAlgorithm::for_manifest(&server_hash_file);
+  println!("Server hash file parsed, {} files found", files.len());
+  if let (Some(window), false) = (window, user_skipped.is_empty()) {
+    let _ = window.emit("updates_skipped_by_ignore_rules", json!({ "files": user_skipped }));
+  }
+  if let (Some(window), false) = (window, removable.is_empty()) {
+    let _ = window.emit("removable_group_files", json!({ "files": removable }));
+  }
+
+  println!("Starting file comparison");
+  let cache = hash_cache();
+  let case_insensitive = case_insensitive_paths_enabled();
+
+  let progress_bar = ProgressBar::new(files.len() as u64);
+  progress_bar.set_style(ProgressStyle::default_bar()
+    .template("[{elapsed_precise}] {bar:40.cyan/blue} {pos}/{len} {msg}")
+    .unwrap()
+    .progress_chars("##-"));
+
+  let processed_count = Arc::new(AtomicUsize::new(0));
+  let counters = FileCheckCounters::default();
+  let check_attributes = attribute_checks_enabled();
+
+  // Hashing runs on the hash pool; block_in_place hands this worker's other
+  // tasks to the rest of the runtime meanwhile.
+  let pool = build_hash_thread_pool()?;
+  let mut files_to_update: Vec<FileInfo> = tokio::task::block_in_place(|| pool.install(|| files.par_iter().enumerate()
+    .filter_map(|(_index, file_info)| {
+      // Once cancelled, the remaining files are skipped without being counted.
+      if is_update_cancelled() {
+        return None;
+      }
+      let path = file_info.path.as_str();
+      let local_file_path = game_file_path(&local_game_path, path, case_insensitive);
+
+      let current_count = processed_count.fetch_add(1, Ordering::SeqCst) + 1;
+      if current_count % 100 == 0 || current_count == files.len() {
+        let progress_payload = FileCheckProgress {
+          current_file: path.to_string(),
+          progress: percent(current_count as u64, files.len() as u64),
+          current_count,
+          total_files: files.len(),
+          elapsed_time: start_time.elapsed().as_secs_f64(),
+          files_to_update: counters.pending.load(Ordering::SeqCst),
+          files_ok: counters.ok.load(Ordering::SeqCst),
+          files_cached: counters.cached.load(Ordering::SeqCst),
+          files_ignored,
+          bytes_checked: counters.bytes_checked.load(Ordering::SeqCst),
+        };
+
+        if let Some(window) = window {
+          set_taskbar_progress(window, TaskbarProgress::Normal {
+            completed: current_count as u64,
+            total: progress_payload.total_files as u64,
+          });
+          let _ = window.emit("file_check_progress", progress_payload)
+            .map_err(|e| {
+              println!("Error emitting file_check_progress event: {}", e);
+              e.to_string()
+            });
+        }
+      }
+
+      progress_bar.inc(1);
+
+      check_local_file(file_info, &local_file_path, cache, manifest_algo, check_attributes, ignore_cache, &counters)
+    })
+    .collect()));
+
+  progress_bar.finish_with_message("File comparison completed");
+
+  // A diff doesn't list unchanged files, so it can't tell which entries are stale.
+  let pruned_entries = if from_diff {
+    0
+  } else {
+    let manifest_paths: HashSet<&str> = files.iter().map(|f| f.path.as_str()).collect();
+    prune_cache(cache, &manifest_paths, &local_game_path)
+  };
+  if pruned_entries > 0 {
+    println!("Pruned {} stale cache entries", pruned_entries);
+  }
+
+  flush_hash_cache();
+
+  if is_update_cancelled() {
+    println!("File check cancelled after {} of {} files", processed_count.load(Ordering::SeqCst), files.len());
+    if let Some(window) = window {
+      set_taskbar_progress(window, TaskbarProgress::Clear);
+      let _ = window.emit("file_check_cancelled", json!({
+        "processed_files": processed_count.load(Ordering::SeqCst),
+        "total_files": files.len()
+      }));
+    }
+    return Err(UPDATE_CANCELLED.to_string());
+  }
+
+  let (protected_count, protected_size) = mark_protected_files(&mut files_to_update);
+  let total_time = start_time.elapsed();
+  println!("File comparison completed. Files to update: {}", files_to_update.len() - protected_count);
+  if protected_count > 0 {
+    println!("{} protected file(s) are out of date", protected_count);
+  }
+  if let Some(window) = window {
+    emit_modified_files(window, &files_to_update);
+  }
+  println!("{} of {} file(s) resolved by quick hash", counters.quick_resolved.load(Ordering::SeqCst), files.len());
+  if counters.no_mtime.load(Ordering::SeqCst) > 0 {
+    println!("{} file(s) have no modification time and can't be cached", counters.no_mtime.load(Ordering::SeqCst));
+  }
+
+  // Emit a final event with complete statistics
+  if let Some(window) = window {
+    set_taskbar_progress(window, TaskbarProgress::Clear);
+    let _ = window.emit("file_check_completed", json!({
+      "total_files": files.len(),
+      "files_to_update": files_to_update.len() - protected_count,
+      "files_ok": counters.ok.load(Ordering::SeqCst),
+      "files_cached": counters.cached.load(Ordering::SeqCst),
+      "files_ignored": files_ignored,
+      "protected_out_of_date": protected_count,
+      "bytes_checked": counters.bytes_checked.load(Ordering::SeqCst),
+      "pruned_cache_entries": pruned_entries,
+      "total_size": counters.pending_size.load(Ordering::SeqCst) - protected_size,
+      "total_time_seconds": total_time.as_secs(),
+      "average_time_per_file_ms": average_ms_per_file(total_time, files.len())
+    }));
+  }
+
+  // Anything left out of date, protected files included, has to show up in
+  // the next check, which a diff from this version wouldn't list.
+  if files_to_update.is_empty() {
+    mark_manifest_applied().await;
+  } else {
+    reset_applied_manifest_version();
+  }
+
+  Ok(files_to_update)
+}
+
+#[derive(Debug, Serialize)]
+struct SingleFileVerification {
+  exists: bool,
+  local_hash: Option<String>,
+  server_hash: String,
+  local_size: Option<u64>,
+  server_size: u64,
+  matches: bool,
+}
+
+/// Checks one file against the server manifest without a full check, and
+/// refreshes its cache entry with the result.
+#[tauri::command]
+async fn verify_single_file(relative_path: String) -> Result<SingleFileVerification, String> {
+  let relative_path = relative_path.replace('\\', "/");
+  if !is_safe_path(&relative_path) {
+    return Err(format!("Invalid file path: {}", relative_path));
+  }
+
+  let server_hash_file = get_server_hash_file(false).await?;
+  let manifest_algo = HashAlgorithm::for_manifest(&server_hash_file);
+  let files = parse_server_manifest(&server_hash_file)?.files;
+  let entry = files.iter()
+    .find(|f| f.path == relative_path)
+    .ok_or_else(|| format!("File is not in the server manifest: {}", relative_path))?;
+  let server_hash = entry.hash.clone();
+  let server_size = entry.size;
+  let algo = entry.algo(manifest_algo);
+
+  let local_path = game_file_path(&get_game_files_path()?, &relative_path, case_insensitive_paths_enabled());
+  let metadata = match fs::metadata(&local_path) {
+    Ok(metadata) if metadata.is_file() => metadata,
+    _ => {
+      return Ok(SingleFileVerification {
+        exists: false,
+        local_hash: None,
+        server_hash,
+        local_size: None,
+        server_size,
+        matches: false,
+      });
+    }
+  };
+
+  let hash_path = local_path.clone();
+  let local_hash = tokio::task::spawn_blocking(move || calculate_file_hash_with(&hash_path, algo))
+    .await.map_err(|e| e.to_string())??;
+  let matches = local_hash == server_hash;
+
+  let cache = hash_cache();
+  if matches {
+    if let Ok(last_modified) = metadata.modified() {
+      cache.insert(&relative_path, CachedFileInfo::verified(local_hash.clone(), last_modified, algo));
+    }
+  } else if cache.get(&relative_path).map(|c| c.hash == server_hash).unwrap_or(false) {
+    // The entry vouches for a file that no longer matches (e.g. bit rot with
+    // an unchanged mtime); drop it so the next check hashes the file.
+    cache.remove(&relative_path);
+  }
+  flush_hash_cache();
+
+  println!("Verified {}: {}", relative_path, if matches { "OK" } else { "mismatch" });
+  Ok(SingleFileVerification {
+    exists: true,
+    local_hash: Some(local_hash),
+    server_hash,
+    local_size: Some(metadata.len()),
+    server_size,
+    matches,
+  })
+}
+
+/// Checks the game files against the server manifest for the "Repair" button.
+/// With `full`, every file is hashed regardless of the cache and the cache is
+/// rebuilt from the result, which catches corruption that left the
+/// modification time alone. The manifest is always downloaded in full.
+#[tauri::command]
+async fn verify_game_files(window: tauri::Window, full: bool) -> Result<Vec<FileInfo>, String> {
+  let _operation = begin_operation(&window.app_handle(), LauncherOperation::Verifying)?;
+  check_game_files(&window, full).await
+}
+
+async fn check_game_files(window: &tauri::Window, full: bool) -> Result<Vec<FileInfo>, String> {
+  find_files_to_update(Some(window), true, None, full).await
+}
+
+#[tauri::command]
+async fn get_files_to_update_force(window: tauri::Window) -> Result<Vec<FileInfo>, String> {
+  let _operation = begin_operation(&window.app_handle(), LauncherOperation::Verifying)?;
+  find_files_to_update(Some(&window), true, None, true).await
+}
+
+/// Whether the game is launching or running, with the id of the launch it
+/// belongs to. The per-launch id is named `launch_id` in every command and
+/// event, since `session_id` already identifies the client a
+/// `game_status_changed` event is about.
+#[derive(Debug, Serialize)]
+struct GameStatusInfo {
+  running: bool,
+  launch_id: Option<String>,
+}
+
+#[tauri::command]
+async fn get_game_status(state: tauri::State<'_, GameState>) -> Result<GameStatusInfo, String> {
+  let status = state.is_game_running();
+  let is_launching = *state.is_launching.lock().await;
+  let external = matches!(*state.phase.lock().await, GamePhase::RunningExternal { .. });
+  Ok(GameStatusInfo {
+    running: status || is_launching || external,
+    launch_id: state.launch_id.lock().await.clone(),
+  })
+}
+
+/// PID and uptime of the running game, or `None` when it isn't running.
+#[tauri::command]
+async fn get_game_process_info(state: tauri::State<'_, GameState>) -> Result<Option<GameProcessInfo>, String> {
+  let mut current_process = state.current_process.lock().await;
+  let Some(process) = current_process.as_mut() else { return Ok(None) };
+  if process.pid.is_none() {
+    process.pid = get_game_pid();
+  }
+  Ok(Some(GameProcessInfo {
+    pid: process.pid,
+    started_at: process.started_at.duration_since(std::time::UNIX_EPOCH).unwrap_or_default().as_secs(),
+    elapsed_seconds: process.started_at.elapsed().unwrap_or_default().as_secs(),
+  }))
+}
+
+/// Every client started by the launcher, the first one included while it
+/// runs, ordered by session id.
+#[tauri::command]
+async fn get_game_sessions(state: tauri::State<'_, GameState>) -> Result<Vec<GameSessionInfo>, String> {
+  fn unix_secs(time: SystemTime) -> u64 {
+    time.duration_since(std::time::UNIX_EPOCH).unwrap_or_default().as_secs()
+  }
+
+  let mut sessions = Vec::new();
+  let phase = *state.phase.lock().await;
+  if phase.is_active() {
+    let process = state.current_process.lock().await;
+    let started_at = process.as_ref().map_or_else(SystemTime::now, |p| p.started_at);
+    sessions.push(GameSessionInfo {
+      session_id: PRIMARY_SESSION_ID,
+      launch_id: state.launch_id.lock().await.clone(),
+      account: state.primary_account.lock().await.clone(),
+      pid: process.as_ref().and_then(|p| p.pid),
+      phase,
+      started_at: unix_secs(started_at),
+      elapsed_seconds: started_at.elapsed().unwrap_or_default().as_secs(),
+    });
+  }
+  let mut extra: Vec<_> = state.extra_sessions.lock().await.iter()
+    .map(|(&session_id, session)| GameSessionInfo {
+      session_id,
+      launch_id: Some(session.launch_id.clone()),
+      account: session.account.clone(),
+      pid: get_session_pid(session_id),
+      phase: session.phase,
+      started_at: unix_secs(session.started_at),
+      elapsed_seconds: session.started_at.elapsed().unwrap_or_default().as_secs(),
+    })
+    .collect();
+  extra.sort_by_key(|s| s.session_id);
+  sessions.extend(extra);
+  Ok(sessions)
+}
+
+/// The first client's phase, with the id of the launch it belongs to;
+/// `launch_id` is `None` before the first launch and for a game started
+/// outside the launcher.
+#[derive(Debug, Serialize)]
+struct GamePhaseInfo {
+  #[serde(flatten)]
+  phase: GamePhase,
+  launch_id: Option<String>,
+}
+
+#[tauri::command]
+async fn get_game_phase(state: tauri::State<'_, GameState>) -> Result<GamePhaseInfo, String> {
+  Ok(GamePhaseInfo {
+    phase: *state.phase.lock().await,
+    launch_id: state.launch_id.lock().await.clone(),
+  })
+}
+
+/// Restores the launch state if the launch task ends without doing so
+/// itself, e.g. because something in it panicked; `is_launching` would
+/// otherwise stay set and Play disabled until the launcher restarts. Also
+/// stops the helper tasks tied to the launch.
+struct LaunchStateGuard<R: tauri::Runtime = tauri::Wry> {
+  app_handle: tauri::AppHandle<R>,
+  helper_tasks: Vec<tokio::task::AbortHandle>,
+  armed: bool,
+}
+
+impl<R: tauri::Runtime> LaunchStateGuard<R> {
+  fn new(app_handle: tauri::AppHandle<R>) -> Self {
+    LaunchStateGuard { app_handle, helper_tasks: Vec::new(), armed: true }
+  }
+
+  fn track<T>(&mut self, task: &tokio::task::JoinHandle<T>) {
+    self.helper_tasks.push(task.abort_handle());
+  }
+
+  /// Called once the launch task has reset the state itself.
+  fn disarm(mut self) {
+    self.armed = false;
+  }
+}
+
+impl<R: tauri::Runtime> Drop for LaunchStateGuard<R> {
+  fn drop(&mut self) {
+    for task in &self.helper_tasks {
+      task.abort();
+    }
+    if !self.armed {
+      return;
+    }
+
+    error!("Launch task ended without resetting the launch state, resetting it");
+    let app_handle = self.app_handle.clone();
+    // The state locks can't be awaited here.
+    tauri::async_runtime::spawn(async move {
+      let game_state = app_handle.state::<GameState>();
+      *game_state.is_launching.lock().await = false;
+      *game_state.launch_started_at.lock().await = None;
+      reset_global_state();
+      set_game_phase(&app_handle, &game_state.phase, GamePhase::Idle).await;
+      let launch_id = game_state.launch_id.lock().await.clone();
+      if let Err(e) = app_handle.emit_all("game_ended", json!({ "launch_id": launch_id })) {
+        error!("Failed to emit game_ended event: {:?}", e);
+      }
+      restore_launcher_window(&app_handle);
+    });
+  }
+}
+
+/// Error prefix when a launch is refused because the login info is missing
+/// or invalid; the reason follows. The frontend asks the player to log in
+/// again.
+const NOT_AUTHENTICATED: &str = "NotAuthenticated";
+
+/// Checks the login info a launch hands to the game.
+fn validate_auth_info(auth_info: &GlobalAuthInfo) -> Result<(), &'static str> {
+  if auth_info.auth_key.trim().is_empty() {
+    return Err("the auth key is missing");
+  }
+  if auth_info.user_no <= 0 {
+    return Err("the user number is invalid");
+  }
+  Ok(())
+}
+
+/// What a launch has gathered and checked before the game is spawned.
+struct PreparedLaunch {
+  operation: Arc<OperationGuard>,
+  account_name: String,
+  characters_count: String,
+  ticket: String,
+  game_path: PathBuf,
+  game_lang: String,
+  executable: String,
+  acts_map: HashMap<String, String>,
+  pages_map: HashMap<String, String>,
+  launch_args: Vec<String>,
+  launch_env: Vec<(String, String)>,
+}
+
+/// Validates the login, configuration and game files for a launch already
+/// reserved with `GameState::reserve_launch`; the caller clears the flag if
+/// this fails.
+async fn prepare_launch(
+  app_handle: &tauri::AppHandle,
+  state: &GameState,
+  additional: bool
+) -> Result<PreparedLaunch, String> {
+  // Held by the launch tasks until the last client exits, so no update
+  // starts underneath them.
+  let operation = state.game_operation(app_handle).await?;
+
+  // Step 2: Validate and retrieve authentication info
+  println!("handle_launch_game: Validating authentication info");
+  emit_launch_stage(app_handle, LaunchStage::ValidatingAuth);
+  let (account_name, characters_count, ticket) = {
+    let auth_info = GLOBAL_AUTH_INFO.read()
+      .map_err(|e| format!("Failed to read auth info: {}", e))?;
+
+    // Without these the game starts only to bounce back to its own login
+    // error.
+    if let Err(reason) = validate_auth_info(&auth_info) {
+      info!("Launch refused, not authenticated: {}", reason);
+      if let Err(e) = app_handle.emit_all("auth_required", json!({ "reason": reason })) {
+        error!("Failed to emit auth_required event: {:?}", e);
+      }
+      return Err(format!("{}: {}", NOT_AUTHENTICATED, reason));
+    }
+    info!("Auth info valid for user_no {}", auth_info.user_no);
+    (
+      auth_info.user_no.to_string(),
+      auth_info.character_count.clone(),
+      auth_info.auth_key.clone()
+    )
+  };
+
+  // Step 3: Load and validate game configuration
+  let (game_path, game_lang) = load_config()
+    .map_err(|e| format!("Failed to load game config: {}", e))?;
+  let game_lang = normalize_language(&game_lang)?;
+
+  println!("handle_launch_game: Game path: {:?}, lang: {}", game_path, game_lang);
+  
+  if !game_path.exists() {
+    return Err(format!("Game path does not exist: {:?}", game_path));
+  }
+
+  // Step 4: Validate game executable exists (case-insensitive on Linux)
+  let full_game_path = resolve_game_executable(&game_path, &load_game_executable())?;
+  let full_game_path_str = full_game_path.to_str()
+    .ok_or_else(|| "Invalid characters in game executable path".to_string())?
+    .to_string();
+
+  // Step 5: Retrieve and validate ACTS_MAP and PAGES_MAP
+  let (acts_map, pages_map) = {
+    let acts_map_guard = GLOBAL_ACTS_MAP.read()
+      .map_err(|e| format!("Failed to read ACTS_MAP: {}", e))?;
+    
+    let pages_map_guard = GLOBAL_PAGES_MAP.read()
+      .map_err(|e| format!("Failed to read PAGES_MAP: {}", e))?;
+    
+    if acts_map_guard.is_empty() {
+      println!("Warning: ACTS_MAP is empty");
+    }
+    
+    if pages_map_guard.is_empty() {
+      println!("Warning: PAGES_MAP is empty");
+    }
+    
+    println!("handle_launch_game: ACTS_MAP entries: {}, PAGES_MAP entries: {}", 
+      acts_map_guard.len(), 
+      pages_map_guard.len()
+    );
+    
+    (acts_map_guard.clone(), pages_map_guard.clone())
+  };
+
+  let mut launch_args = parse_launch_args(&load_launch_args().unwrap_or_default())
+    .map_err(|e| format!("Invalid launch arguments: {}", e))?;
+  let launch_options = load_launch_options();
+  if launch_options.skip_splash && !launch_args.iter().any(|arg| arg.eq_ignore_ascii_case(NO_SPLASH_ARG)) {
+    launch_args.push(NO_SPLASH_ARG.to_string());
+  }
+  // A stale display setting isn't worth keeping the player out of the game.
+  if let Err(e) = apply_display_options(&game_path, &launch_options) {
+    error!("Failed to apply display options: {}", e);
+  }
+  let launch_env = load_launch_env();
+  set_capture_output(capture_game_output_enabled());
+  // The server list the game asks for goes out with the launcher's headers.
+  set_http_client(http_client()?);
+
+  match check_executable_integrity(&full_game_path, &load_game_executable()).await {
+    Ok(IntegrityStatus::Unverified) => {
+      let payload = json!({ "path": full_game_path_str, "status": IntegrityStatus::Unverified });
+      if let Err(e) = app_handle.emit_all("executable_integrity_unverified", payload) {
+        error!("Failed to emit executable_integrity_unverified event: {:?}", e);
+      }
+    }
+    Ok(_) => {}
+    Err(reason) => {
+      error!("Refusing to launch: {}", reason);
+      let payload = json!({ "path": full_game_path_str, "reason": reason, "suggested_action": "repair_game_files" });
+      if let Err(e) = app_handle.emit_all("executable_integrity_failed", payload) {
+        error!("Failed to emit executable_integrity_failed event: {:?}", e);
+      }
+      return Err(format!("{}: {}", EXECUTABLE_INTEGRITY_FAILED, reason));
+    }
+  }
+
+  if prelaunch_check_enabled() {
+    emit_launch_stage(app_handle, LaunchStage::CheckingFiles);
+    let check_start = Instant::now();
+    match check_critical_files().await {
+      Ok(outdated) if !outdated.is_empty() => {
+        return Err(format!("{}: {}", CRITICAL_FILES_OUTDATED, outdated.join(", ")));
+      }
+      Ok(_) => println!("handle_launch_game: Critical files OK ({} ms)", check_start.elapsed().as_millis()),
+      // A check that can't run shouldn't keep the player out of the game.
+      Err(e) => eprintln!("handle_launch_game: Pre-launch check failed: {}", e),
+    }
+  }
+
+  // Helpers such as a proxy have to be up before the game connects.
+  if !additional {
+    if let Some(hook) = load_launch_hook(PRE_LAUNCH_HOOK)? {
+      if let Err(e) = run_launch_hook(&hook).await {
+        if hook.required {
+          return Err(e);
+        }
+        error!("{}; launching anyway", e);
+      }
+    }
+  }
+
+  Ok(PreparedLaunch {
+    operation,
+    account_name,
+    characters_count,
+    ticket,
+    game_path,
+    game_lang,
+    executable: full_game_path_str,
+    acts_map,
+    pages_map,
+    launch_args,
+    launch_env,
+  })
+}
+
+#[tauri::command]
+async fn handle_launch_game(
+  app_handle: tauri::AppHandle,
+  state: tauri::State<'_, GameState>
+) -> Result<String, String> {
+  println!("handle_launch_game: Starting");
+  
+  // Step 1: Check if game is already launching or running
+  let additional = state.reserve_launch().await?;
+  let PreparedLaunch {
+    operation,
+    account_name,
+    characters_count,
+    ticket,
+    game_path,
+    game_lang,
+    executable: full_game_path_str,
+    acts_map: acts_map_clone,
+    pages_map: pages_map_clone,
+    launch_args,
+    launch_env,
+  } = match prepare_launch(&app_handle, &state, additional).await {
+    Ok(prepared) => prepared,
+    Err(e) => {
+      // An extra client leaves the first client's flag alone.
+      if !additional {
+        *state.is_launching.lock().await = false;
+      }
+      return Err(e);
+    }
+  };
+
+  // Step 6: Spawn the game launch in background
+  let app_handle_clone = app_handle.clone();
+  let launch_id = uuid::Uuid::new_v4().to_string();
+  info!("Launch id: {}", launch_id);
+
+  if additional {
+    let session_id = NEXT_SESSION_ID.fetch_add(1, Ordering::SeqCst);
+    let sessions = Arc::clone(&state.extra_sessions);
+    let recent_sessions = Arc::clone(&state.recent_sessions);
+    let is_launching_clone = Arc::clone(&state.is_launching);
+    sessions.lock().await.insert(session_id, GameSession {
+      account: account_name.clone(),
+      launch_id: launch_id.clone(),
+      phase: GamePhase::Launching,
+      started_at: SystemTime::now(),
+    });
+
+    tokio::task::spawn(async move {
+      let session_start = Instant::now();
+      set_session_phase(&app_handle_clone, &sessions, session_id, GamePhase::Launching).await;
+      let playtime_heartbeat = start_playtime_tracking(session_id, &account_name);
+
+      // Extra clients have no status channel; they count as running once
+      // the bridge has reported the game's pid.
+      let running_watch = {
+        let app_handle = app_handle_clone.clone();
+        let sessions = Arc::clone(&sessions);
+        tokio::task::spawn(async move {
+          while get_session_pid(session_id).is_none() {
+            tokio::time::sleep(Duration::from_millis(500)).await;
+          }
+          set_session_phase(&app_handle, &sessions, session_id, GamePhase::Running).await;
+        })
+      };
+
+      info!("Launching additional client {} with executable: {}", session_id, full_game_path_str);
+      let result = run_additional_client(
+        session_id,
+        &account_name,
+        &characters_count,
+        &ticket,
+        &game_lang,
+        &full_game_path_str,
+        acts_map_clone,
+        pages_map_clone,
+        launch_args,
+        launch_env,
+      ).await.map_err(|e| e.to_string());
+      running_watch.abort();
+
+      let terminated = sessions.lock().await.get(&session_id)
+        .is_some_and(|session| session.phase == GamePhase::Exiting);
+      set_session_phase(&app_handle_clone, &sessions, session_id, GamePhase::Exiting).await;
+      finish_playtime_tracking(&app_handle_clone, session_id, playtime_heartbeat);
+
+      let (exit_code, exited_cleanly, details) = match result {
+        Ok(exit_status) => {
+          info!("Client {} exited with status: {:?}", session_id, exit_status);
+          (exit_status.code(), exit_status.success(), String::new())
+        }
+        Err(e) => {
+          error!("Error launching client {}: {}", session_id, e);
+          (None, false, e)
+        }
+      };
+      let exited_cleanly = terminated || exited_cleanly;
+      if !exited_cleanly {
+        report_crash(&app_handle_clone, Some(&launch_id), exit_code, session_start.elapsed().as_secs(), details.clone(), Vec::new());
+      }
+
+      let launch_error = (!details.is_empty()).then_some(details);
+      record_game_session(&recent_sessions, GameSessionRecord::new(
+        session_id,
+        Some(launch_id.clone()),
+        &account_name,
+        session_start,
+        exit_code,
+        !exited_cleanly,
+        launch_error,
+      )).await;
+
+      let final_phase = if exited_cleanly { GamePhase::Idle } else { GamePhase::Crashed { exit_code } };
+      emit_game_status(&app_handle_clone, session_id, Some(&launch_id), final_phase);
+      // Same lock order as the first client's task, so exactly one of them
+      // sees the last client go.
+      let last_client = {
+        let is_launching = is_launching_clone.lock().await;
+        let mut sessions = sessions.lock().await;
+        sessions.remove(&session_id);
+        sessions.is_empty() && !*is_launching
+      };
+      if let Err(e) = app_handle_clone.emit_all("game_session_ended", json!({ "session_id": session_id, "launch_id": launch_id, "exit_code": exit_code })) {
+        error!("Failed to emit game_session_ended event: {:?}", e);
+      }
+      // Run under the operation lock so an update can't start underneath the
+      // hook.
+      if last_client {
+        run_post_exit_hook().await;
+      }
+      drop(operation);
+      if last_client {
+        run_post_game_action(&app_handle_clone, !exited_cleanly, false).await;
+      }
+    });
+
+    return Ok(format!("Additional client launch initiated (session {})", session_id));
+  }
+
+  *state.primary_account.lock().await = account_name.clone();
+  *state.launch_id.lock().await = Some(launch_id.clone());
+  if cfg!(windows) && !is_elevated() && !game_dir_writable(&game_path) {
+    warn!("The game directory can't be written to; enable run_as_admin if the game fails to save its settings");
+  }
+  *state.launch_started_at.lock().await = Some(Instant::now());
+  let is_launching_clone = Arc::clone(&state.is_launching);
+  let phase_clone = Arc::clone(&state.phase);
+  let extra_sessions = Arc::clone(&state.extra_sessions);
+  let recent_sessions = Arc::clone(&state.recent_sessions);
+  let launch_started_at = Arc::clone(&state.launch_started_at);
+
+  tokio::task::spawn(async move {
+    let mut launch_guard = LaunchStateGuard::new(app_handle_clone.clone());
+    let session_start = Instant::now();
+    // `watch_game_status` moves this on to Running once the process is up.
+    set_game_phase(&app_handle_clone, &phase_clone, GamePhase::Launching).await;
+    let playtime_heartbeat = start_playtime_tracking(PRIMARY_SESSION_ID, &account_name);
+    let process_tuning = tokio::task::spawn(apply_process_tuning());
+    let resource_monitor = tokio::task::spawn(monitor_game_resources(app_handle_clone.clone()));
+    emit_launch_stage(&app_handle_clone, LaunchStage::SpawningProcess);
+    let stage_tracking = tokio::task::spawn(track_launch_stages(app_handle_clone.clone()));
+    // Whether the game ever came up; a launch that failed before that has
+    // nothing to run the post-game action for.
+    let reached_running = Arc::new(AtomicBool::new(false));
+    let running_watch = {
+      let mut status = app_handle_clone.state::<GameState>().subscribe();
+      let reached_running = Arc::clone(&reached_running);
+      tokio::task::spawn(async move {
+        while status.changed().await.is_ok() {
+          if *status.borrow_and_update() {
+            reached_running.store(true, Ordering::SeqCst);
+            break;
+          }
+        }
+      })
+    };
+    launch_guard.track(&process_tuning);
+    launch_guard.track(&resource_monitor);
+    launch_guard.track(&stage_tracking);
+    launch_guard.track(&running_watch);
+
+    info!("Launching game with executable: {}", full_game_path_str);
+    let launch = run_game_with_env(
+      &account_name,
+      &characters_count,
+      &ticket,
+      &game_lang,
+      &full_game_path_str,
+      acts_map_clone,
+      pages_map_clone,
+      launch_args,
+      launch_env,
+    );
+    // A launch that hangs before the game process exists would otherwise
+    // leave Play disabled until the launcher restarts. Dropping the launch
+    // future kills whatever it spawned and closes the IPC window.
+    let result = tokio::select! {
+      result = launch => result.map_err(|e| e.to_string()),
+      timeout = launch_watchdog(&launch_started_at) => {
+        error!("Game launch timed out after {:?}", timeout);
+        if let Err(e) = app_handle_clone.emit_all("launch_timed_out", json!({ "timeout_seconds": timeout.as_secs() })) {
+          error!("Failed to emit launch_timed_out event: {:?}", e);
+        }
+        Err(format!("Launch timed out after {} seconds", timeout.as_secs()))
+      }
+    };
+    let (launch_error, exit_code, mut exited_cleanly) = match result {
+      Ok(exit_status) => {
+        let result = format!("Game exited with status: {:?}", exit_status);
+        if let Err(e) = app_handle_clone.emit_all("game_status", json!({ "status": result, "launch_id": launch_id })) {
+          error!("Failed to emit game_status event: {:?}", e);
+        }
+        info!("{}", result);
+        (None, exit_status.code(), exit_status.success())
+      }
+      Err(e) => {
+        let error = format!("Error launching game: {}", e);
+        if let Err(e) = app_handle_clone.emit_all("game_status", json!({ "status": error, "launch_id": launch_id })) {
+          error!("Failed to emit game_status event: {:?}", e);
+        }
+        error!("{}", error);
+        (Some(e), None, false)
+      }
+    };
+    *launch_started_at.lock().await = None;
+    // terminate_game moves the phase to Exiting; a game closed that way
+    // didn't crash, whatever its exit code.
+    let terminated = *phase_clone.lock().await == GamePhase::Exiting;
+    set_game_phase(&app_handle_clone, &phase_clone, GamePhase::Exiting).await;
+    process_tuning.abort();
+    resource_monitor.abort();
+    stage_tracking.abort();
+    running_watch.abort();
+    let game_ran = launch_error.is_none() && reached_running.load(Ordering::SeqCst);
+    finish_playtime_tracking(&app_handle_clone, PRIMARY_SESSION_ID, playtime_heartbeat);
+
+    // Emit structured exit info (code + reason) so the frontend can show a message.
+    {
+      let exit_info = get_last_exit_info();
+      let crash_details = get_last_crash_details();
+      let stderr = get_last_game_stderr();
+      exited_cleanly = terminated || (exited_cleanly && crash_details.is_empty());
+      if !exited_cleanly {
+        let details = launch_error.clone().unwrap_or_else(|| crash_details.clone());
+        report_crash(&app_handle_clone, Some(&launch_id), exit_code, session_start.elapsed().as_secs(), details, get_last_game_output());
+      }
+      let payload = serde_json::json!({
+        "code":   exit_info.code,
+        "reason": exit_info.reason,
+        "crash":  !crash_details.is_empty(),
+        "details": crash_details,
+        "stderr": stderr,
+        // non-null when run_game() itself errored (not a TERA exit code)
+        "launch_error": launch_error,
+      });
+      if let Err(e) = app_handle_clone.emit_all("game_exit_info", payload) {
+        error!("Failed to emit game_exit_info event: {:?}", e);
+      }
+    }
+
+    // Saved before game_ended so a UI that misses the event can still ask
+    // `get_last_game_session`.
+    record_game_session(&recent_sessions, GameSessionRecord::new(
+      PRIMARY_SESSION_ID,
+      Some(launch_id.clone()),
+      &account_name,
+      session_start,
+      exit_code,
+      !exited_cleanly,
+      launch_error,
+    )).await;
+
+    info!("Emitting game_ended event");
+    if let Err(e) = app_handle_clone.emit_all("game_ended", json!({ "launch_id": launch_id })) {
+      error!("Failed to emit game_ended event: {:?}", e);
+    }
+    restore_launcher_window(&app_handle_clone);
+
+    let mut is_launching = is_launching_clone.lock().await;
+    *is_launching = false;
+    let final_phase = if exited_cleanly { GamePhase::Idle } else { GamePhase::Crashed { exit_code } };
+    set_game_phase(&app_handle_clone, &phase_clone, final_phase).await;
+
+    reset_global_state();
+    // Checked before releasing `is_launching`: an extra client that exits
+    // later runs the exit handling itself.
+    let game_still_running = !extra_sessions.lock().await.is_empty();
+    drop(is_launching);
+    launch_guard.disarm();
+
+    info!("Game launch state reset");
+
+    if !game_still_running {
+      run_post_exit_hook().await;
+    }
+    drop(operation);
+    if game_ran {
+      run_post_game_action(&app_handle_clone, !exited_cleanly, game_still_running).await;
+    } else {
+      info!("Skipping the post-game action, the game never started");
+    }
+  });
+
+  Ok("Game launch initiated".to_string())
+}
+
+/// What the launcher does once the game exits, from the `post_game_action`
+/// setting.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum PostGameAction {
+  None,
+  ShowLauncher,
+  CloseLauncher,
+  ShutdownPc,
+  SleepPc,
+}
+
+impl PostGameAction {
+  fn parse(value: &str) -> Option<Self> {
+    serde_json::from_value(json!(value.trim().to_ascii_lowercase())).ok()
+  }
+
+  /// Actions that take the whole PC down get a cancellable countdown.
+  fn needs_countdown(self) -> bool {
+    matches!(self, PostGameAction::ShutdownPc | PostGameAction::SleepPc)
+  }
+}
+
+const POST_GAME_COUNTDOWN_SECS: u64 = 30;
+
+/// Set by `cancel_post_game_action` to stop a running countdown.
+static POST_GAME_CANCELLED: AtomicBool = AtomicBool::new(false);
+
+fn load_post_game_action() -> PostGameAction {
+  get_launcher_setting("post_game_action")
+    .and_then(|v| PostGameAction::parse(&v))
+    .unwrap_or(PostGameAction::None)
+}
+
+/// Whether the post-game action also runs after a crash. Off by default so
+/// a crash doesn't shut the PC down mid-troubleshooting.
+fn post_game_action_after_crash() -> bool {
+  get_launcher_setting("post_game_action_after_crash")
+    .map(|v| v.eq_ignore_ascii_case("true") || v == "1")
+    .unwrap_or(false)
+}
+
+/// Runs the configured post-game action once the first client has exited.
+/// Nothing happens while other clients are still running.
+async fn run_post_game_action(app_handle: &tauri::AppHandle, crashed: bool, game_still_running: bool) {
+  let action = load_post_game_action();
+  if action == PostGameAction::None || game_still_running {
+    return;
+  }
+  if crashed && !post_game_action_after_crash() {
+    info!("Skipping post-game action {:?} after a crash", action);
+    return;
+  }
+
+  if action.needs_countdown() {
+    POST_GAME_CANCELLED.store(false, Ordering::SeqCst);
+    for seconds_left in (0..=POST_GAME_COUNTDOWN_SECS).rev() {
+      if POST_GAME_CANCELLED.load(Ordering::SeqCst) {
+        info!("Post-game action {:?} cancelled", action);
+        if let Err(e) = app_handle.emit_all("post_game_countdown", json!({ "action": action, "seconds_left": null, "cancelled": true })) {
+          error!("Failed to emit post_game_countdown event: {:?}", e);
+        }
+        return;
+      }
+      if let Err(e) = app_handle.emit_all("post_game_countdown", json!({ "action": action, "seconds_left": seconds_left, "cancelled": false })) {
+        error!("Failed to emit post_game_countdown event: {:?}", e);
+      }
+      if seconds_left > 0 {
+        tokio::time::sleep(Duration::from_secs(1)).await;
+      }
+    }
+  }
+
+  info!("Running post-game action {:?}", action);
+  let result = match action {
+    PostGameAction::None => Ok(()),
+    PostGameAction::ShowLauncher => match app_handle.get_window("main") {
+      Some(window) => window.unminimize()
+        .and_then(|_| window.show())
+        .and_then(|_| window.set_focus())
+        .map_err(|e| e.to_string()),
+      None => Err("Main window not found".to_string()),
+    },
+    PostGameAction::CloseLauncher => {
+      app_handle.exit(0);
+      Ok(())
+    }
+    PostGameAction::ShutdownPc => power_command(&["shutdown", "/s", "/t", "0"], &["systemctl", "poweroff"]),
+    PostGameAction::SleepPc => power_command(
+      &["rundll32.exe", "powrprof.dll,SetSuspendState", "0,1,0"],
+      &["systemctl", "suspend"],
+    ),
+  };
+  if let Err(e) = result {
+    error!("Post-game action {:?} failed: {}", action, e);
+  }
+}
+
+const PRE_LAUNCH_HOOK: &str = "pre_launch";
+const POST_EXIT_HOOK: &str = "post_exit";
+const DEFAULT_HOOK_TIMEOUT_SECS: u64 = 60;
+const HOOK_STDERR_TAIL_LINES: usize = 10;
+
+lazy_static! {
+  /// A pre-launch hook left running next to the game, stopped once the last
+  /// client exits.
+  static ref BACKGROUND_HOOK: Mutex<Option<tokio::process::Child>> = Mutex::new(None);
+}
+
+/// A helper program run before the game starts or after it exits, e.g. a
+/// proxy or DPS meter, from the `[hooks]` section of config.ini:
+/// `<stage>_command`, `<stage>_args` and `<stage>_working_dir`, plus
+/// `pre_launch_wait_for_exit` (default true) and `pre_launch_required`
+/// (default false) for the pre-launch hook. `timeout_secs` bounds every hook
+/// that is waited for.
+#[derive(Debug)]
+struct LaunchHook {
+  stage: &'static str,
+  command: String,
+  args: Vec<String>,
+  working_dir: Option<PathBuf>,
+  wait_for_exit: bool,
+  required: bool,
+  timeout: Duration,
+}
+
+/// Reads a hook; `None` when its command isn't set, which is the default.
+fn load_launch_hook(stage: &'static str) -> Result<Option<LaunchHook>, String> {
+  let Some(conf) = find_config_file().and_then(|path| Ini::load_from_file(path).ok()) else {
+    return Ok(None);
+  };
+  let Some(section) = conf.section(Some("hooks")) else {
+    return Ok(None);
+  };
+  let get = |key: &str| section.get(format!("{}_{}", stage, key)).map(str::trim).filter(|v| !v.is_empty());
+  let flag = |key: &str, default: bool| get(key).map(|v| v.eq_ignore_ascii_case("true") || v == "1").unwrap_or(default);
+  let Some(command) = get("command") else {
+    return Ok(None);
+  };
+  let args = parse_launch_args(get("args").unwrap_or_default())
+    .map_err(|e| format!("Invalid {}_args: {}", stage, e))?;
+  let timeout_secs = section.get("timeout_secs")
+    .and_then(|v| v.trim().parse::<u64>().ok())
+    .unwrap_or(DEFAULT_HOOK_TIMEOUT_SECS);
+  Ok(Some(LaunchHook {
+    stage,
+    command: command.to_string(),
+    args,
+    working_dir: get("working_dir").map(PathBuf::from),
+    // Only the pre-launch hook may keep running next to the game.
+    wait_for_exit: stage != PRE_LAUNCH_HOOK || flag("wait_for_exit", true),
+    required: stage == PRE_LAUNCH_HOOK && flag("required", false),
+    timeout: Duration::from_secs(timeout_secs),
+  }))
+}
+
+/// Runs a hook and logs its output with a `[hook]` prefix. A hook that isn't
+/// waited for keeps running until `stop_background_hook`. A failure carries
+/// the last lines of the hook's stderr.
+async fn run_launch_hook(hook: &LaunchHook) -> Result<(), String> {
+  use tokio::io::{AsyncBufReadExt, BufReader};
+
+  // Hooks run arbitrary programs, which should never go unnoticed.
+  warn!("[hook] Running the {} hook: {} {:?}", hook.stage, hook.command, hook.args);
+  let mut command = tokio::process::Command::new(&hook.command);
+  command.args(&hook.args)
+    .stdin(std::process::Stdio::null())
+    .stdout(std::process::Stdio::piped())
+    .stderr(std::process::Stdio::piped())
+    .kill_on_drop(true);
+  if let Some(working_dir) = &hook.working_dir {
+    command.current_dir(working_dir);
+  }
+  let mut child = command.spawn()
+    .map_err(|e| format!("Failed to start the {} hook {}: {}", hook.stage, hook.command, e))?;
+
+  let stage = hook.stage;
+  let stderr_tail = Arc::new(std::sync::Mutex::new(VecDeque::new()));
+  let stdout_task = child.stdout.take().map(|stdout| tokio::spawn(async move {
+    let mut lines = BufReader::new(stdout).lines();
+    while let Ok(Some(line)) = lines.next_line().await {
+      info!("[hook] {}: {}", stage, line);
+    }
+  }));
+  let stderr_task = child.stderr.take().map(|stderr| {
+    let stderr_tail = Arc::clone(&stderr_tail);
+    tokio::spawn(async move {
+      let mut lines = BufReader::new(stderr).lines();
+      while let Ok(Some(line)) = lines.next_line().await {
+        info!("[hook] {} (stderr): {}", stage, line);
+        let mut tail = stderr_tail.lock().unwrap();
+        if tail.len() == HOOK_STDERR_TAIL_LINES {
+          tail.pop_front();
+        }
+        tail.push_back(line);
+      }
+    })
+  });
+
+  if !hook.wait_for_exit {
+    info!("[hook] The {} hook keeps running (PID {:?})", stage, child.id());
+    // A hook left from a launch that never got to exit handling is replaced.
+    if let Some(previous) = BACKGROUND_HOOK.lock().await.replace(child) {
+      stop_hook_process(previous).await;
+    }
+    return Ok(());
+  }
+
+  let status = match tokio::time::timeout(hook.timeout, child.wait()).await {
+    Ok(status) => status.map_err(|e| format!("Failed to wait for the {} hook: {}", stage, e))?,
+    Err(_) => {
+      let _ = child.kill().await;
+      return Err(format!("The {} hook didn't finish within {} seconds", stage, hook.timeout.as_secs()));
+    }
+  };
+  // Something the hook started may hold its output open.
+  for task in [stdout_task, stderr_task].into_iter().flatten() {
+    let _ = tokio::time::timeout(Duration::from_secs(1), task).await;
+  }
+  if !status.success() {
+    let tail: Vec<String> = stderr_tail.lock().unwrap().iter().cloned().collect();
+    let mut error = format!("The {} hook exited with {}", stage, status);
+    if !tail.is_empty() {
+      error.push_str(&format!(": {}", tail.join("\n")));
+    }
+    return Err(error);
+  }
+  info!("[hook] The {} hook finished", stage);
+  Ok(())
+}
+
+async fn stop_hook_process(mut child: tokio::process::Child) {
+  if let Ok(Some(_)) = child.try_wait() {
+    return;
+  }
+  info!("[hook] Stopping the background hook (PID {:?})", child.id());
+  if let Err(e) = child.kill().await {
+    error!("[hook] Failed to stop the background hook: {}", e);
+  }
+}
+
+/// Stops the pre-launch hook left running next to the game, if any.
+async fn stop_background_hook() {
+  if let Some(child) = BACKGROUND_HOOK.lock().await.take() {
+    stop_hook_process(child).await;
+  }
+}
+
+/// Once the last client has exited, stops a pre-launch hook still running
+/// and runs the post-exit hook, if any.
+async fn run_post_exit_hook() {
+  stop_background_hook().await;
+  match load_launch_hook(POST_EXIT_HOOK) {
+    Ok(Some(hook)) => {
+      if let Err(e) = run_launch_hook(&hook).await {
+        error!("{}", e);
+      }
+    }
+    Ok(None) => {}
+    Err(e) => error!("Post-exit hook not run: {}", e),
+  }
+}
+
+/// Runs the Windows or the Linux form of a power command.
+fn power_command(windows: &[&str], other: &[&str]) -> Result<(), String> {
+  let command = if cfg!(windows) { windows } else { other };
+  let status = std::process::Command::new(command[0])
+    .args(&command[1..])
+    .status()
+    .map_err(|e| format!("Failed to run {}: {}", command[0], e))?;
+  if !status.success() {
+    return Err(format!("{} exited with {}", command[0], status));
+  }
+  Ok(())
+}
+
+#[tauri::command]
+fn get_post_game_action() -> PostGameAction {
+  load_post_game_action()
+}
+
+#[tauri::command]
+fn set_post_game_action(action: PostGameAction) -> Result<(), String> {
+  let value = serde_json::to_value(action).map_err(|e| e.to_string())?;
+  save_launcher_setting("post_game_action", value.as_str().unwrap_or("none"))
+}
+
+/// Stops a shutdown or sleep countdown started after the game exited.
+#[tauri::command]
+fn cancel_post_game_action() {
+  POST_GAME_CANCELLED.store(true, Ordering::SeqCst);
+}
+
+
+/// Error prefix when the pre-launch check finds outdated critical files; the
+/// offending paths follow, comma-separated.
+const CRITICAL_FILES_OUTDATED: &str = "CriticalFilesOutdated";
+
+/// Compares the manifest's `critical` entries with the game directory before
+/// a launch, so a half-finished update fails here instead of crashing the
+/// game. Uses the applied manifest (see `load_applied_manifest`) and trusts
+/// the hash cache for files whose mtime hasn't changed, so it normally reads
+/// no file contents. Returns the paths that are missing or don't match.
+async fn check_critical_files() -> Result<Vec<String>, String> {
+  let Some(manifest) = load_applied_manifest() else {
+    println!("No applied manifest, skipping the pre-launch check");
+    return Ok(Vec::new());
+  };
+  let manifest_algo = HashAlgorithm::for_manifest(&manifest);
+  let critical: Vec<ServerFileEntry> = parse_server_manifest(&manifest)?.files.into_iter()
+    .filter(|entry| entry.critical)
+    .collect();
+  if critical.is_empty() {
+    return Ok(Vec::new());
+  }
+
+  let game_path = get_game_files_path()?;
+  let case_insensitive = case_insensitive_paths_enabled();
+  tokio::task::spawn_blocking(move || {
+    let cache = hash_cache();
+    let outdated = critical.iter()
+      .filter(|entry| {
+        let local_path = game_file_path(&game_path, &entry.path, case_insensitive);
+        let Ok(metadata) = fs::metadata(&local_path) else { return true };
+        if !metadata.is_file() || metadata.len() != entry.size {
+          return true;
+        }
+        let algo = entry.algo(manifest_algo);
+        let cached_hash = cache.get(&entry.path)
+          .filter(|c| c.algo == algo && metadata.modified().ok() == Some(c.last_modified))
+          .map(|c| c.hash);
+        let hash = match cached_hash {
+          Some(hash) => hash,
+          None => match calculate_file_hash_with(&local_path, algo) {
+            Ok(hash) => hash,
+            Err(_) => return true,
+          },
+        };
+        hash != entry.hash
+      })
+      .map(|entry| entry.path.clone())
+      .collect();
+    Ok(outdated)
+  }).await.map_err(|e| e.to_string())?
+}
+
+/// Error prefix when `check_executable_integrity` rejects the game
+/// executable; the reason follows.
+const EXECUTABLE_INTEGRITY_FAILED: &str = "ExecutableIntegrityFailed";
+
+/// Servers that ship a modified game executable on purpose set
+/// `ALLOW_MODIFIED_EXECUTABLE` in config.json to skip the hash check.
+fn executable_hash_check_enabled() -> bool {
+  !get_optional_config_value("ALLOW_MODIFIED_EXECUTABLE")
+    .map(|v| v.eq_ignore_ascii_case("true") || v == "1")
+    .unwrap_or(false)
+}
+
+/// Whether the game executable must carry a valid Authenticode signature,
+/// from the `require_signed_exe` setting. Off by default.
+fn require_signed_exe() -> bool {
+  get_launcher_setting("require_signed_exe")
+    .map(|v| v.eq_ignore_ascii_case("true") || v == "1")
+    .unwrap_or(false)
+}
+
+/// Outcome of an executable check that didn't reject the file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+enum IntegrityStatus {
+  Verified,
+  /// No applied manifest lists the executable, so its hash wasn't compared.
+  Unverified,
+  /// `ALLOW_MODIFIED_EXECUTABLE` turned the hash check off.
+  Skipped,
+}
+
+/// Checks the game executable before it's started, so a replaced one isn't
+/// run: its hash against the applied manifest (see `load_applied_manifest`)
+/// and, with `require_signed_exe`, its signature. The hash is always read
+/// from disk rather than the hash cache, which trusts mtimes. Without an
+/// applied manifest listing the executable the result is `Unverified`. The
+/// file is never removed; a repair restores it.
+async fn check_executable_integrity(executable: &Path, relative_path: &str) -> Result<IntegrityStatus, String> {
+  let relative_path = relative_path.trim().replace('\\', "/");
+  let status = if !executable_hash_check_enabled() {
+    IntegrityStatus::Skipped
+  } else {
+    let expected = load_applied_manifest().and_then(|manifest| {
+      let manifest_algo = HashAlgorithm::for_manifest(&manifest);
+      let entry = parse_server_manifest(&manifest).ok()?.files.into_iter()
+        .find(|entry| entry.path.replace('\\', "/").eq_ignore_ascii_case(&relative_path))?;
+      Some((entry.algo(manifest_algo), entry.hash))
+    });
+    match expected {
+      Some((algo, expected_hash)) => {
+        let path = executable.to_path_buf();
+        let hash = tokio::task::spawn_blocking(move || calculate_file_hash_with(&path, algo))
+          .await
+          .map_err(|e| e.to_string())??;
+        if hash != expected_hash {
+          return Err(format!("{} doesn't match the applied manifest", relative_path));
+        }
+        info!("{} matches the applied manifest", relative_path);
+        IntegrityStatus::Verified
+      }
+      None => {
+        warn!("{} isn't in an applied manifest, its hash is unverified", relative_path);
+        IntegrityStatus::Unverified
+      }
+    }
+  };
+
+  if require_signed_exe() {
+    let path = executable.to_path_buf();
+    tokio::task::spawn_blocking(move || verify_signature(&path))
+      .await
+      .map_err(|e| e.to_string())??;
+    info!("{} has a valid signature", relative_path);
+  }
+  Ok(status)
+}
+
+/// Verifies the file's Authenticode signature with WinVerifyTrust. Skips
+/// certificate revocation, which would need the network.
+#[cfg(windows)]
+fn verify_signature(path: &Path) -> Result<(), String> {
+  use std::os::windows::ffi::OsStrExt;
+  use winapi::um::softpub::WINTRUST_ACTION_GENERIC_VERIFY_V2;
+  use winapi::um::wintrust::{
+    WinVerifyTrust, WINTRUST_DATA, WINTRUST_FILE_INFO, WTD_CHOICE_FILE, WTD_REVOKE_NONE, WTD_STATEACTION_CLOSE,
+    WTD_STATEACTION_VERIFY, WTD_UI_NONE,
+  };
+
+  let wide_path: Vec<u16> = path.as_os_str().encode_wide().chain(Some(0)).collect();
+  unsafe {
+    let mut file_info: WINTRUST_FILE_INFO = std::mem::zeroed();
+    file_info.cbStruct = std::mem::size_of::<WINTRUST_FILE_INFO>() as u32;
+    file_info.pcwszFilePath = wide_path.as_ptr();
+
+    let mut data: WINTRUST_DATA = std::mem::zeroed();
+    data.cbStruct = std::mem::size_of::<WINTRUST_DATA>() as u32;
+    data.dwUIChoice = WTD_UI_NONE;
+    data.fdwRevocationChecks = WTD_REVOKE_NONE;
+    data.dwUnionChoice = WTD_CHOICE_FILE;
+    *data.u.pFile_mut() = &mut file_info;
+    data.dwStateAction = WTD_STATEACTION_VERIFY;
+
+    let mut action = WINTRUST_ACTION_GENERIC_VERIFY_V2;
+    let status = WinVerifyTrust(std::ptr::null_mut(), &mut action, &mut data as *mut WINTRUST_DATA as *mut _);
+    // Releases what the verification allocated.
+    data.dwStateAction = WTD_STATEACTION_CLOSE;
+    WinVerifyTrust(std::ptr::null_mut(), &mut action, &mut data as *mut WINTRUST_DATA as *mut _);
+
+    if status != 0 {
+      return Err(format!(
+        "{} has no valid signature (WinVerifyTrust: {:#010x})",
+        path.display(),
+        status as u32
+      ));
+    }
+  }
+  Ok(())
+}
+
+/// Wine has no Authenticode; the setting only applies on Windows.
+#[cfg(not(windows))]
+fn verify_signature(path: &Path) -> Result<(), String> {
+  info!("Signature of {} not checked: only supported on Windows", path.display());
+  Ok(())
+}
+
+/// The pre-launch check can be turned off with `skip_prelaunch_check`, e.g.
+/// for a client patched by hand.
+fn prelaunch_check_enabled() -> bool {
+  !get_launcher_setting("skip_prelaunch_check")
+    .map(|v| v.eq_ignore_ascii_case("true") || v == "1")
+    .unwrap_or(false)
+}
+
+/// Whether the launcher should restart itself elevated at startup, for
+/// installs under Program Files. Opt-in through `run_as_admin`: the game has
+/// to run at the launcher's integrity level or its messages to the IPC
+/// window are dropped. Windows only; Wine has no UAC.
+fn run_as_admin_enabled() -> bool {
+  cfg!(windows) && get_launcher_setting("run_as_admin")
+    .map(|v| v.eq_ignore_ascii_case("true") || v == "1")
+    .unwrap_or(false)
+}
+
+/// Probes the game directory by creating and removing a scratch file.
+fn game_dir_writable(game_path: &Path) -> bool {
+  let probe = game_path.join(".launcher_write_probe");
+  match fs::OpenOptions::new().write(true).create_new(true).open(&probe) {
+    Ok(_) => {
+      let _ = fs::remove_file(&probe);
+      true
+    }
+    Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => {
+      let _ = fs::remove_file(&probe);
+      true
+    }
+    Err(e) => {
+      info!("Game directory {:?} is not writable: {}", game_path, e);
+      false
+    }
+  }
+}
+
+/// How long to wait for the game's pid before giving up on priority and
+/// affinity; covers a slow start on a busy machine.
+const PROCESS_TUNING_TIMEOUT_SECS: u64 = 120;
+
+#[derive(Debug, Serialize)]
+struct CpuInfo {
+  logical_cores: usize,
+  /// Every usable core, one bit each, bit 0 = core 0.
+  all_cores_mask: u64,
+  /// The `cpu_affinity_mask` setting, if valid.
+  affinity_mask: Option<u64>,
+  /// The `process_priority` setting, if valid.
+  priority: Option<String>,
+}
+
+/// Logical cores an affinity mask can address, capped at the 64 bits of
+/// a mask.
+fn logical_core_count() -> usize {
+  std::thread::available_parallelism().map(|n| n.get()).unwrap_or(1).min(64)
+}
+
+fn all_cores_mask() -> u64 {
+  match logical_core_count() {
+    64 => u64::MAX,
+    cores => (1u64 << cores) - 1,
+  }
+}
+
+/// Parses an affinity mask, decimal or `0x` hex, and checks it selects at
+/// least one core and no core this machine lacks.
+fn parse_affinity_mask(value: &str) -> Result<u64, String> {
+  let value = value.trim();
+  let mask = match value.strip_prefix("0x").or_else(|| value.strip_prefix("0X")) {
+    Some(hex) => u64::from_str_radix(hex, 16),
+    None => value.parse::<u64>(),
+  }.map_err(|_| format!("Invalid CPU affinity mask: {}", value))?;
+  if mask == 0 {
+    return Err("The CPU affinity mask selects no core".to_string());
+  }
+  if mask & !all_cores_mask() != 0 {
+    return Err(format!("The CPU affinity mask {:#x} selects cores beyond the {} available", mask, logical_core_count()));
+  }
+  Ok(mask)
+}
+
+fn load_process_priority() -> Option<ProcessPriority> {
+  let value = get_launcher_setting("process_priority").filter(|v| !v.trim().is_empty())?;
+  let priority = ProcessPriority::parse(&value);
+  if priority.is_none() {
+    error!("Ignoring invalid process_priority setting: {}", value);
+  }
+  priority
+}
+
+fn load_cpu_affinity_mask() -> Option<u64> {
+  let value = get_launcher_setting("cpu_affinity_mask").filter(|v| !v.trim().is_empty())?;
+  parse_affinity_mask(&value)
+    .map_err(|e| error!("Ignoring cpu_affinity_mask setting: {}", e))
+    .ok()
+}
+
+/// Applies `process_priority` and `cpu_affinity_mask` to the game once it
+/// has a pid. Nothing needs reverting: both go away with the process.
+async fn apply_process_tuning() {
+  let priority = load_process_priority();
+  let affinity_mask = load_cpu_affinity_mask();
+  if !cfg!(windows) || (priority.is_none() && affinity_mask.is_none()) {
+    return;
+  }
+
+  let deadline = Instant::now() + Duration::from_secs(PROCESS_TUNING_TIMEOUT_SECS);
+  let pid = loop {
+    if let Some(pid) = get_game_pid() {
+      break pid;
+    }
+    if Instant::now() >= deadline {
+      error!("Game pid not available, priority and affinity not applied");
+      return;
+    }
+    tokio::time::sleep(Duration::from_millis(250)).await;
+  };
+
+  if let Some(priority) = priority {
+    match set_process_priority(pid, priority) {
+      Ok(()) => info!("Set game process {} priority to {:?}", pid, priority),
+      Err(e) => error!("Failed to set game process priority: {}", e),
+    }
+  }
+  if let Some(mask) = affinity_mask {
+    match set_process_affinity(pid, mask) {
+      Ok(()) => info!("Set game process {} CPU affinity to {:#x}", pid, mask),
+      Err(e) => error!("Failed to set game process CPU affinity: {}", e),
+    }
+  }
+}
+
+const DEFAULT_RESOURCE_SAMPLE_SECS: u64 = 5;
+/// The 32-bit client tends to crash at around 3.5 GB; warn ahead of that.
+const DEFAULT_MEMORY_WARNING_MB: u64 = 3000;
+
+/// How often the game's usage is sampled, from the `resource_sample_secs`
+/// setting; 0 turns sampling off.
+fn resource_sample_interval() -> Option<Duration> {
+  let secs = get_launcher_setting("resource_sample_secs")
+    .and_then(|v| v.parse::<u64>().ok())
+    .unwrap_or(DEFAULT_RESOURCE_SAMPLE_SECS);
+  (secs > 0).then(|| Duration::from_secs(secs))
+}
+
+/// Memory use that triggers `game_memory_warning`, from the
+/// `memory_warning_mb` setting; 0 turns the warning off.
+fn memory_warning_bytes() -> Option<u64> {
+  let mb = get_launcher_setting("memory_warning_mb")
+    .and_then(|v| v.parse::<u64>().ok())
+    .unwrap_or(DEFAULT_MEMORY_WARNING_MB);
+  (mb > 0).then(|| mb * 1024 * 1024)
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct GameResourceUsage {
+  pid: u32,
+  rss_bytes: u64,
+  /// Share of the whole machine, 0-100.
+  cpu_percent: f32,
+}
+
+/// Emits `game_resource_usage` while the first client is Running, and
+/// `game_memory_warning` once when its memory use crosses
+/// `memory_warning_bytes`. Runs until aborted by the launch task.
+async fn monitor_game_resources(app_handle: tauri::AppHandle) {
+  use sysinfo::{Pid, ProcessRefreshKind, System};
+
+  let Some(interval) = resource_sample_interval() else {
+    return;
+  };
+  let warning_bytes = memory_warning_bytes();
+  let game_state = app_handle.state::<GameState>();
+  let cores = logical_core_count() as f32;
+  let mut system = System::new();
+  let mut warned = false;
+  let mut tick = tokio::time::interval(interval);
+  tick.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+  loop {
+    tick.tick().await;
+    if *game_state.phase.lock().await != GamePhase::Running {
+      continue;
+    }
+    let Some(pid) = get_game_pid() else { continue };
+    let sys_pid = Pid::from_u32(pid);
+    // CPU use is measured between two refreshes, so the first sample
+    // reads 0.
+    if !system.refresh_process_specifics(sys_pid, ProcessRefreshKind::new().with_cpu().with_memory()) {
+      continue;
+    }
+    let Some(process) = system.process(sys_pid) else { continue };
+    let usage = GameResourceUsage {
+      pid,
+      rss_bytes: process.memory(),
+      cpu_percent: process.cpu_usage() / cores,
+    };
+    if let Err(e) = app_handle.emit_all("game_resource_usage", &usage) {
+      error!("Failed to emit game_resource_usage event: {:?}", e);
+    }
+
+    if let Some(threshold) = warning_bytes.filter(|&threshold| !warned && usage.rss_bytes >= threshold) {
+      warned = true;
+      info!("Game process {} uses {} MB of memory", pid, usage.rss_bytes / (1024 * 1024));
+      let payload = json!({ "pid": pid, "rss_bytes": usage.rss_bytes, "threshold_bytes": threshold });
+      if let Err(e) = app_handle.emit_all("game_memory_warning", payload) {
+        error!("Failed to emit game_memory_warning event: {:?}", e);
+      }
+    }
+  }
+}
+
+/// Core count and the current priority and affinity settings, for the
+/// settings page.
+#[tauri::command]
+fn get_cpu_info() -> CpuInfo {
+  CpuInfo {
+    logical_cores: logical_core_count(),
+    all_cores_mask: all_cores_mask(),
+    affinity_mask: load_cpu_affinity_mask(),
+    priority: load_process_priority()
+      .and(get_launcher_setting("process_priority"))
+      .map(|v| v.trim().to_ascii_lowercase()),
+  }
+}
+
+/// Sets the game's priority class for the next launches: `idle`,
+/// `below_normal`, `normal`, `above_normal` or `high`; empty leaves it to
+/// Windows.
+#[tauri::command]
+fn set_game_priority(priority: String) -> Result<(), String> {
+  if !priority.trim().is_empty() && ProcessPriority::parse(&priority).is_none() {
+    return Err(format!("Unknown process priority: {}", priority));
+  }
+  save_launcher_setting("process_priority", priority.trim())
+}
+
+/// Sets the cores the game may run on for the next launches; `None` lets it
+/// use them all.
+#[tauri::command]
+fn set_cpu_affinity(mask: Option<u64>) -> Result<(), String> {
+  match mask {
+    Some(mask) => {
+      parse_affinity_mask(&mask.to_string())?;
+      save_launcher_setting("cpu_affinity_mask", &format!("{:#x}", mask))
+    }
+    None => save_launcher_setting("cpu_affinity_mask", ""),
+  }
+}
+
+/// Arguments the launcher sets itself or that could hand the game's command
+/// line to something else.
+const RESERVED_LAUNCH_ARGS: [&str; 1] = ["-LANGUAGEEXT"];
+const FORBIDDEN_LAUNCH_ARG_CHARS: [char; 8] = ['&', '|', ';', '<', '>', '`', '$', '%'];
+
+/// Splits `launch_args` like a command line: whitespace separates arguments
+/// and double quotes keep spaces inside one.
+fn parse_launch_args(launch_args: &str) -> Result<Vec<String>, String> {
+  let mut args = Vec::new();
+  let mut current = String::new();
+  let mut in_quotes = false;
+  let mut has_arg = false;
+  for c in launch_args.chars() {
+    match c {
+      '"' => {
+        in_quotes = !in_quotes;
+        has_arg = true;
+      }
+      c if c.is_whitespace() && !in_quotes => {
+        if has_arg {
+          args.push(std::mem::take(&mut current));
+          has_arg = false;
+        }
+      }
+      c => {
+        current.push(c);
+        has_arg = true;
+      }
+    }
+  }
+  if in_quotes {
+    return Err("Unterminated quote in launch arguments".to_string());
+  }
+  if has_arg {
+    args.push(current);
+  }
+  for arg in &args {
+    if let Some(c) = arg.chars().find(|c| FORBIDDEN_LAUNCH_ARG_CHARS.contains(c) || c.is_control()) {
+      return Err(format!("Launch argument {:?} contains the forbidden character {:?}", arg, c));
+    }
+    let name = arg.split('=').next().unwrap_or_default();
+    if RESERVED_LAUNCH_ARGS.iter().any(|reserved| name.eq_ignore_ascii_case(reserved)) {
+      return Err(format!("{} is set by the launcher and can't be overridden", name));
+    }
+  }
+  Ok(args)
+}
+
+/// The raw `launch_args` value from the `[game]` section of config.ini.
+fn load_launch_args() -> Option<String> {
+  let config_path = find_config_file()?;
+  let conf = Ini::load_from_file(config_path).ok()?;
+  conf.get_from(Some("game"), "launch_args").map(str::to_string)
+}
+
+#[tauri::command]
+fn get_launch_args() -> String {
+  load_launch_args().unwrap_or_default()
+}
+
+/// Saves extra game arguments after checking they parse and pass validation.
+#[tauri::command]
+fn set_launch_args(launch_args: String) -> Result<Vec<String>, String> {
+  let args = parse_launch_args(&launch_args)?;
+  let config_path = find_config_file().ok_or("Config file not found")?;
+  let mut conf = Ini::load_from_file(&config_path).map_err(|e|
+    format!("Failed to load config: {}", e)
+  )?;
+
+  conf.with_section(Some("game")).set("launch_args", launch_args.trim());
+
+  conf.write_to_file(&config_path).map_err(|e| format!("Failed to write config: {}", e))?;
+
+  Ok(args)
+}
+
+const NO_SPLASH_ARG: &str = "-nosplash";
+const S1_OPTION_DISPLAY_SECTION: &str = "SystemSettings";
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum WindowMode {
+  Fullscreen,
+  Windowed,
+  Borderless,
+}
+
+impl WindowMode {
+  fn parse(value: &str) -> Option<Self> {
+    match value.trim().to_ascii_lowercase().as_str() {
+      "fullscreen" => Some(WindowMode::Fullscreen),
+      "windowed" => Some(WindowMode::Windowed),
+      "borderless" => Some(WindowMode::Borderless),
+      _ => None,
+    }
+  }
+
+  fn as_str(self) -> &'static str {
+    match self {
+      WindowMode::Fullscreen => "fullscreen",
+      WindowMode::Windowed => "windowed",
+      WindowMode::Borderless => "borderless",
+    }
+  }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+struct Resolution {
+  width: u32,
+  height: u32,
+}
+
+impl Resolution {
+  /// Parses `1920x1080`.
+  fn parse(value: &str) -> Option<Self> {
+    let (width, height) = value.trim().to_ascii_lowercase().split_once('x')
+      .and_then(|(w, h)| Some((w.trim().parse().ok()?, h.trim().parse().ok()?)))?;
+    Some(Resolution { width, height })
+  }
+
+  fn validate(self) -> Result<Self, String> {
+    if !(640..=7680).contains(&self.width) || !(480..=4320).contains(&self.height) {
+      return Err(format!("Unsupported resolution: {}x{}", self.width, self.height));
+    }
+    Ok(self)
+  }
+}
+
+/// Display options applied at every launch, from the `[game]` section of
+/// config.ini. `None` leaves the game's own setting alone.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct LaunchOptions {
+  skip_splash: bool,
+  window_mode: Option<WindowMode>,
+  resolution: Option<Resolution>,
+}
+
+/// Reads the launch options; invalid values are logged and left out.
+fn load_launch_options() -> LaunchOptions {
+  let Some(conf) = find_config_file().and_then(|path| Ini::load_from_file(path).ok()) else {
+    return LaunchOptions::default();
+  };
+  let get = |key: &str| conf.get_from(Some("game"), key).map(str::trim).filter(|v| !v.is_empty());
+  let window_mode = get("window_mode").and_then(|v| {
+    let mode = WindowMode::parse(v);
+    if mode.is_none() {
+      error!("Ignoring unknown window_mode: {}", v);
+    }
+    mode
+  });
+  let resolution = get("resolution").and_then(|v| {
+    match Resolution::parse(v).ok_or_else(|| format!("Invalid resolution: {}", v)).and_then(Resolution::validate) {
+      Ok(resolution) => Some(resolution),
+      Err(e) => {
+        error!("Ignoring resolution setting: {}", e);
+        None
+      }
+    }
+  });
+  LaunchOptions {
+    skip_splash: get("skip_splash").map(|v| v.eq_ignore_ascii_case("true") || v == "1").unwrap_or(false),
+    window_mode,
+    resolution,
+  }
+}
+
+#[tauri::command]
+fn get_launch_options() -> LaunchOptions {
+  load_launch_options()
+}
+
+#[tauri::command]
+fn set_launch_options(options: LaunchOptions) -> Result<LaunchOptions, String> {
+  if let Some(resolution) = options.resolution {
+    resolution.validate()?;
+  }
+  let config_path = find_config_file().ok_or("Config file not found")?;
+  let mut conf = Ini::load_from_file(&config_path).map_err(|e|
+    format!("Failed to load config: {}", e)
+  )?;
+
+  conf.with_section(Some("game")).set("skip_splash", options.skip_splash.to_string());
+  match options.window_mode {
+    Some(mode) => {
+      conf.with_section(Some("game")).set("window_mode", mode.as_str());
+    }
+    None => {
+      conf.delete_from(Some("game"), "window_mode");
+    }
+  }
+  match options.resolution {
+    Some(resolution) => {
+      conf.with_section(Some("game")).set("resolution", format!("{}x{}", resolution.width, resolution.height));
+    }
+    None => {
+      conf.delete_from(Some("game"), "resolution");
+    }
+  }
+
+  conf.write_to_file(&config_path).map_err(|e| format!("Failed to write config: {}", e))?;
+  Ok(options)
+}
+
+/// Writes the window mode and resolution into S1Option.ini, where the game
+/// reads them at startup. Only those keys change; the rest of the file,
+/// which holds the player's other options, is left byte for byte. Nothing
+/// is done before the game has created the file.
+fn apply_display_options(game_path: &Path, options: &LaunchOptions) -> Result<(), String> {
+  // Booleans are spelled the way the game writes them.
+  let ini_bool = |value: bool| if value { "True" } else { "False" }.to_string();
+  let mut keys = Vec::new();
+  if let Some(mode) = options.window_mode {
+    keys.push(("Fullscreen", ini_bool(mode == WindowMode::Fullscreen)));
+    keys.push(("Borderless", ini_bool(mode == WindowMode::Borderless)));
+  }
+  if let Some(resolution) = options.resolution {
+    keys.push(("ResX", resolution.width.to_string()));
+    keys.push(("ResY", resolution.height.to_string()));
+  }
+  if keys.is_empty() {
+    return Ok(());
+  }
+
+  let path = game_path.join(S1_OPTION_INI);
+  let bytes = match fs::read(&path) {
+    Ok(bytes) => bytes,
+    Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+      info!("{} doesn't exist yet, display options not applied", S1_OPTION_INI);
+      return Ok(());
+    }
+    Err(e) => return Err(format!("Failed to read {}: {}", path.display(), e)),
+  };
+  // The game may save the file as UTF-16.
+  let utf16 = bytes.starts_with(&[0xFF, 0xFE]);
+  let contents = if utf16 {
+    let units: Vec<u16> = bytes[2..].chunks_exact(2).map(|c| u16::from_le_bytes([c[0], c[1]])).collect();
+    String::from_utf16(&units).map_err(|e| format!("Failed to decode {}: {}", path.display(), e))?
+  } else {
+    String::from_utf8(bytes).map_err(|e| format!("Failed to decode {}: {}", path.display(), e))?
+  };
+
+  let updated = set_ini_keys(&contents, S1_OPTION_DISPLAY_SECTION, &keys);
+  if updated == contents {
+    return Ok(());
+  }
+  let bytes = if utf16 {
+    [0xFF, 0xFE].into_iter().chain(updated.encode_utf16().flat_map(u16::to_le_bytes)).collect()
+  } else {
+    updated.into_bytes()
+  };
+  fs::write(&path, bytes).map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+  info!("Applied display options to {}: {:?}", S1_OPTION_INI, keys);
+  Ok(())
+}
+
+/// Sets `keys` in `section` of an ini file's text and leaves every other
+/// line as it was. Keys missing from the section are added after its last
+/// entry, a missing section at the end.
+fn set_ini_keys(contents: &str, section: &str, keys: &[(&str, String)]) -> String {
+  let newline = if contents.contains("\r\n") { "\r\n" } else { "\n" };
+  let mut lines: Vec<String> = contents.lines().map(str::to_string).collect();
+  let mut found = vec![false; keys.len()];
+  let mut in_section = false;
+  let mut section_end = None;
+  for (index, line) in lines.iter_mut().enumerate() {
+    let trimmed = line.trim_start_matches('\u{feff}').trim();
+    if trimmed.starts_with('[') && trimmed.ends_with(']') {
+      in_section = trimmed[1..trimmed.len() - 1].trim().eq_ignore_ascii_case(section);
+      if in_section {
+        section_end = Some(index + 1);
+      }
+      continue;
+    }
+    if !in_section {
+      continue;
+    }
+    if !trimmed.is_empty() {
+      section_end = Some(index + 1);
+    }
+    let Some((name, _)) = trimmed.split_once('=') else { continue };
+    if let Some(i) = keys.iter().position(|(key, _)| name.trim().eq_ignore_ascii_case(key)) {
+      *line = format!("{}={}", name.trim(), keys[i].1);
+      found[i] = true;
+    }
+  }
+
+  let missing = keys.iter().zip(&found).filter(|(_, &found)| !found).map(|((key, value), _)| format!("{}={}", key, value));
+  match section_end {
+    Some(end) => {
+      let tail = lines.split_off(end);
+      lines.extend(missing);
+      lines.extend(tail);
+    }
+    None => {
+      let missing: Vec<String> = missing.collect();
+      if !missing.is_empty() {
+        lines.push(format!("[{}]", section));
+        lines.extend(missing);
+      }
+    }
+  }
+
+  let mut updated = lines.join(newline);
+  if contents.ends_with('\n') {
+    updated.push_str(newline);
+  }
+  updated
+}
+
+/// Checks an environment variable for the game before it's saved or used.
+fn validate_launch_env_var(name: &str, value: &str) -> Result<(), String> {
+  if name.trim().is_empty() {
+    return Err("Environment variable names can't be empty".to_string());
+  }
+  if name.contains('=') || name.contains('\0') {
+    return Err(format!("Invalid environment variable name: {}", name));
+  }
+  if value.contains('\0') {
+    return Err(format!("Invalid value for environment variable {}", name));
+  }
+  Ok(())
+}
+
+/// Environment variables set on the game process only, from the `[env]`
+/// section of config.ini, e.g. DXVK or driver toggles. Invalid entries are
+/// skipped.
+fn load_launch_env() -> Vec<(String, String)> {
+  let Some(config_path) = find_config_file() else { return Vec::new() };
+  let Ok(conf) = Ini::load_from_file(config_path) else { return Vec::new() };
+  let Some(section) = conf.section(Some("env")) else { return Vec::new() };
+  section.iter()
+    .filter(|(name, value)| match validate_launch_env_var(name, value) {
+      Ok(()) => true,
+      Err(e) => {
+        error!("Ignoring [env] entry: {}", e);
+        false
+      }
+    })
+    .map(|(name, value)| (name.to_string(), value.to_string()))
+    .collect()
+}
+
+#[tauri::command]
+fn get_launch_env() -> BTreeMap<String, String> {
+  load_launch_env().into_iter().collect()
+}
+
+/// Replaces the `[env]` section of config.ini.
+#[tauri::command]
+fn set_launch_env(env: BTreeMap<String, String>) -> Result<(), String> {
+  for (name, value) in &env {
+    validate_launch_env_var(name, value)?;
+  }
+  let config_path = find_config_file().ok_or("Config file not found")?;
+  let mut conf = Ini::load_from_file(&config_path).map_err(|e|
+    format!("Failed to load config: {}", e)
+  )?;
+
+  conf.delete(Some("env"));
+  for (name, value) in &env {
+    conf.with_section(Some("env")).set(name.trim(), value);
+  }
+
+  conf.write_to_file(&config_path).map_err(|e| format!("Failed to write config: {}", e))
+}
+
+#[tauri::command]
+fn get_language_from_config() -> Result<String, String> {
+  info!("Attempting to read language from config file");
+  let (_, game_lang) = load_config()?;
+  info!("Language read from config: {}", game_lang);
+  Ok(game_lang)
+}
+
+#[tauri::command]
+fn save_language_to_config(language: String) -> Result<(), String> {
+  info!("Attempting to save language {} to config file", language);
+  let language = normalize_language(&language)?;
+  let config_path = find_config_file().ok_or("Config file not found")?;
+  let mut conf = Ini::load_from_file(&config_path).map_err(|e|
+    format!("Failed to load config: {}", e)
+  )?;
+
+  conf.with_section(Some("game")).set("lang", &language);
+
+  conf.write_to_file(&config_path).map_err(|e| format!("Failed to write config: {}", e))?;
+
+  info!("Language successfully saved to config");
+  Ok(())
+}
+
+const DEFAULT_TERMINATE_GRACE_SECS: u64 = 10;
+
+/// Seconds a game gets to close before it's terminated, from the
+/// `terminate_grace_secs` setting.
+fn terminate_grace_secs() -> u64 {
+  get_launcher_setting("terminate_grace_secs")
+    .and_then(|v| v.parse::<u64>().ok())
+    .unwrap_or(DEFAULT_TERMINATE_GRACE_SECS)
+}
+
+/// Closes a running or hung game: WM_CLOSE first, then the process is
+/// terminated after `timeout_seconds` (default: the `terminate_grace_secs`
+/// setting, 10), then whatever it started that is still running.
+/// `session_id` picks an extra client (see `get_game_sessions`); without it
+/// the first client is closed. With `dry_run` nothing is closed and the
+/// report lists what would be.
+#[tauri::command]
+async fn terminate_game(
+  app_handle: tauri::AppHandle,
+  state: tauri::State<'_, GameState>,
+  timeout_seconds: Option<u64>,
+  session_id: Option<u64>,
+  dry_run: Option<bool>,
+) -> Result<TerminationReport, String> {
+  let grace_period = Duration::from_secs(timeout_seconds.unwrap_or_else(terminate_grace_secs));
+  let dry_run = dry_run.unwrap_or(false);
+  if let Some(session_id) = session_id.filter(|&id| id != PRIMARY_SESSION_ID) {
+    // Set first so the session's launch task, which settles the final
+    // phase, doesn't take the exit for a crash.
+    if !dry_run && get_session_pid(session_id).is_some() {
+      set_session_phase(&app_handle, &state.extra_sessions, session_id, GamePhase::Exiting).await;
+    }
+    let report = terminate_session(session_id, grace_period, dry_run).await.map_err(|e| e.to_string())?;
+    info!("terminate_game: client {}: {:?}", session_id, report);
+    return Ok(report);
+  }
+
+  if !dry_run && get_game_pid().is_some() {
+    set_game_phase(&app_handle, &state.phase, GamePhase::Exiting).await;
+  }
+  let report = terminate_game_process(grace_period, dry_run).await.map_err(|e| e.to_string())?;
+  info!("terminate_game: game process: {:?}", report);
+  if dry_run {
+    return Ok(report);
+  }
+
+  // A terminated game still goes through the launch task, which resets the
+  // launch state and emits `game_ended` once `run_game` returns. Only a
+  // phase left behind with no launch task is settled here.
+  if report.outcome == TerminationOutcome::NotFound && !*state.is_launching.lock().await {
+    set_game_phase(&app_handle, &state.phase, GamePhase::Idle).await;
+  }
+  Ok(report)
+}
+
+/// Brings the game window to the foreground, restoring it if minimized.
+/// `session_id` picks an extra client; without it the first client, or a
+/// game started outside the launcher, is focused.
+#[tauri::command]
+async fn focus_game_window(state: tauri::State<'_, GameState>, session_id: Option<u64>) -> Result<(), String> {
+  let pid = match session_id.filter(|&id| id != PRIMARY_SESSION_ID) {
+    Some(session_id) => get_session_pid(session_id),
+    None => match *state.phase.lock().await {
+      GamePhase::RunningExternal { pid } => Some(pid),
+      _ => get_game_pid(),
+    },
+  };
+  let Some(pid) = pid else {
+    return Err("The game is not running".to_string());
+  };
+  match focus_process_window(pid) {
+    Ok(true) => Ok(()),
+    Ok(false) => Err("The game window is not open yet".to_string()),
+    Err(e) => {
+      error!("Failed to focus the game window of process {}: {}", pid, e);
+      Err(e.to_string())
+    }
+  }
+}
+
+#[tauri::command]
+async fn reset_launch_state(state: tauri::State<'_, GameState>) -> Result<(), String> {
+  let mut is_launching = state.is_launching.lock().await;
+  *is_launching = false;
+  *state.launch_started_at.lock().await = None;
+  Ok(())
+}
+
+#[tauri::command]
+async fn set_auth_info( 
+  auth_key: String, 
+  user_name: String, 
+  user_no: i32, 
+  character_count: String,
+  session_cookie: Option<String>, 
+) -> Result<(), String> { 
+  {
+    let mut auth_info = GLOBAL_AUTH_INFO.write().unwrap();
+    auth_info.auth_key = auth_key;
+    auth_info.user_name = user_name;
+    auth_info.user_no = user_no;
+    auth_info.character_count = character_count;
+
+    info!("Auth info set from frontend:");
+    info!("User Name: {}", auth_info.user_name);
+    info!("User No: {}", auth_info.user_no);
+    info!("Character Count: {}", auth_info.character_count);
+    info!("Auth Key: {}", if auth_info.auth_key.is_empty() { "<empty>" } else { "<set>" });
+  }
+
+  if let Some(cookie_value) = session_cookie {
+    if !cookie_value.is_empty() {
+      info!("Rebuilding authenticated client from stored cookie...");
+      let base_url = &get_launcher_base_url();
+      let url = Url::parse(base_url).map_err(|e| format!("Invalid launcher URL {}: {}", base_url, e))?;
+      let host = url.host_str().ok_or_else(|| format!("The launcher URL {} has no host", base_url))?;
+
+      // Build cookie
+      let cookie_str = format!("launcher.sid={}; Domain={}; Path=/", cookie_value, host);
+      
+      let jar = Arc::new(Jar::default());
+      jar.add_cookie_str(&cookie_str, &url);
+
+      // Build new client using the cookie jar
+      let client = http_client_builder()
+        .cookie_store(true)
+        .cookie_provider(jar)
+        .build()
+        .map_err(|e| format!("Failed to rebuild client: {}", e))?;
+
+      // Store client globally
+      let mut client_guard = AUTHENTICATED_CLIENT.lock().await; // <-- 6. 'await' is now valid
+      *client_guard = Some(client);
+      info!("Authenticated client rebuilt successfully.");
+    } else {
+      info!("No session cookie found to rebuild client.");
+    }
+  }
+  Ok(())
+}
+
+
+/// Handles the complete login process for the TERA launcher.
+///
+/// ### Overview
+/// This function:
+/// 1. Authenticates the user using their credentials.
+/// 2. Retrieves the session cookie and essential account details (account info, auth key, character count).
+/// 3. Fetches and parses the main launcher HTML page to extract `ACTS_MAP` and `PAGES_MAP`.
+/// 4. Stores these maps globally for future use.
+/// 5. Returns a structured JSON response with all relevant login and session data.
+///
+/// The function communicates with the launcher’s backend endpoints, maintains cookies
+/// across requests, and reconstructs necessary URLs dynamically using `get_launcher_base_url`.
+///
+/// ### Arguments
+/// * `username` - The user's login name.
+/// * `password` - The user's password.
+///
+/// ### Returns
+/// * `Ok(String)` - JSON containing authentication results and user data.
+/// * `Err(String)` - A descriptive error message in case of failure.
+#[tauri::command]
+async fn login(username: String, password: String) -> Result<String, String> {
+    // 1. Create an HTTP client with a persistent cookie jar
+    let cookie_jar = Arc::new(Jar::default());
+    let client = http_client_builder()
+        .cookie_store(true)
+        .cookie_provider(Arc::clone(&cookie_jar))
+        .timeout(Duration::from_secs(30))
+        .build()
+        .map_err(|e| {
+          let error_msg = format!("Failed to build HTTP client: {}", e);
+          let _ = log_error_to_file(&error_msg);
+          error_msg
+        })?;
+
+    // --- Step 1: Define Base URL ---
+    // The base launcher URL is obtained once from the global constant.
+    let base_url = &get_launcher_base_url();
+    let login_url = format!("{}/launcher/LoginAction", base_url);
+
+    // --- Step 2: POST to /launcher/LoginAction (Authentication) ---
+    // Use .form() to properly encode parameters and prevent URL injection attacks
+    let login_res = client
+        .post(&login_url)
+        .form(&[("login", username.as_str()), ("password", password.as_str())])
+        .send()
+        .await
+        .map_err(|e| {
+          let detailed_error = format_reqwest_error(&login_url, &e);
+          let error_msg = format!("Login connection failed: {}", detailed_error);
+          let _ = log_error_to_file(&error_msg);
+          error_msg
+        })?;
+
+    // --- Parse the login response and extract session cookie ---
+    if !login_res.status().is_success() {
+        let error_msg = format!("Login request failed with HTTP status: {} (URL: {})", login_res.status(), login_url);
+        let _ = log_error_to_file(&error_msg);
+        return Err(error_msg);
+    }
+
+    let login_body: InitialLoginResponse = login_res
+        .json()
+        .await
+        .map_err(|e| {
+          let error_msg = format!("Failed to parse login response: {} (URL: {})", e, login_url);
+          let _ = log_error_to_file(&error_msg);
+          error_msg
+        })?;
+
+    if !login_body.return_value {
+        return Err(login_body.msg);
+    }
+
+    // Parse the cookies to retrieve the session identifier (launcher.sid)
+    let login_url_parsed = Url::parse(&login_url)
+        .map_err(|e| {
+          let error_msg = format!("Failed to parse login URL: {} (URL: {})", e, login_url);
+          let _ = log_error_to_file(&error_msg);
+          error_msg
+        })?;
+    let cookie_header_value = cookie_jar.cookies(&login_url_parsed);
+
+    let session_cookie: Option<String> = cookie_header_value
+        .and_then(|header_val| header_val.to_str().ok().map(String::from))
+        .and_then(|cookie_str| {
+            cookie_str.split(';').find_map(|cookie_pair| {
+                let cookie_pair = cookie_pair.trim();
+                if cookie_pair.starts_with("launcher.sid=") {
+                    Some(cookie_pair.trim_start_matches("launcher.sid=").to_string())
+                } else {
+                    None
+                }
+            })
+        });
+
+    let success_msg = login_body.msg.clone();
+
+    // --- Step 3: Retrieve account data using the authenticated client ---
+    // These endpoints depend on the valid session cookie.
+    let account_info_url = format!("{}/launcher/GetAccountInfoAction", base_url);
+    let account_info: AccountInfoResponse = client
+        .get(&account_info_url)
+        .send()
+        .await
+        .map_err(|e| {
+          let detailed_error = format_reqwest_error(&account_info_url, &e);
+          let error_msg = format!("Failed to get account info: {}", detailed_error);
+          let _ = log_error_to_file(&error_msg);
+          error_msg
+        })?
+        .json()
+        .await
+        .map_err(|e| {
+          let error_msg = format!("Failed to parse account info: {} (URL: {})", e, account_info_url);
+          let _ = log_error_to_file(&error_msg);
+          error_msg
+        })?;
+
+    let auth_key_url = format!("{}/launcher/GetAuthKeyAction", base_url);
+    let auth_key: AuthKeyResponse = client
+        .get(&auth_key_url)
+        .send()
+        .await
+        .map_err(|e| {
+          let detailed_error = format_reqwest_error(&auth_key_url, &e);
+          let error_msg = format!("Failed to get auth key: {}", detailed_error);
+          let _ = log_error_to_file(&error_msg);
+          error_msg
+        })?
+        .json()
+        .await
+        .map_err(|e| {
+          let error_msg = format!("Failed to parse auth key: {} (URL: {})", e, auth_key_url);
+          let _ = log_error_to_file(&error_msg);
+          error_msg
+        })?;
+
+    let char_count_url = format!("{}/launcher/GetCharacterCountAction", base_url);
+    let char_count: CharCountResponse = client
+        .get(&char_count_url)
+        .send()
+        .await
+        .map_err(|e| {
+          let detailed_error = format_reqwest_error(&char_count_url, &e);
+          let error_msg = format!("Failed to get character count: {}", detailed_error);
+          let _ = log_error_to_file(&error_msg);
+          error_msg
+        })?
+        .json()
+        .await
+        .map_err(|e| {
+          let error_msg = format!("Failed to parse character count: {} (URL: {})", e, char_count_url);
+          let _ = log_error_to_file(&error_msg);
+          error_msg
+        })?;
+
+    // --- Step 4: GET /launcher/Main to extract ActsMap/PagesMap ---
+    // Important: Add locale if the server requires it to serve localized pages.
+    let main_url = format!("{}/launcher/Main?locale=en", base_url);
+
+    let main_res = client
+        .get(&main_url)
+        .send()
+        .await
+        .map_err(|e| {
+          let detailed_error = format_reqwest_error(&main_url, &e);
+          let error_msg = format!("Failed to get launcher main page: {}", detailed_error);
+          let _ = log_error_to_file(&error_msg);
+          error_msg
+        })?;
+
+    if !main_res.status().is_success() {
+        let error_msg = format!(
+            "Launcher main page request failed with HTTP status: {} (URL: {})",
+            main_res.status(), main_url
+        );
+        let _ = log_error_to_file(&error_msg);
+        return Err(error_msg);
+    }
+
+    let main_html = main_res
+        .text()
+        .await
+        .map_err(|e| {
+          let error_msg = format!("Failed to read main page body: {} (URL: {})", e, main_url);
+          let _ = log_error_to_file(&error_msg);
+          error_msg
+        })?;
+
+    // KEY STEP: Pass `base_url` so that `extract_maps_from_html` can rebuild the full URLs in ACTS_MAP.
+    let (acts_map, pages_map) = extract_maps_from_html(&main_html, base_url)?;
+
+    // --- Step 5: Save parsed maps into global state ---
+    if let Some(map_object) = acts_map.as_object() {
+        let mut acts_map_guard = GLOBAL_ACTS_MAP.write().unwrap();
+        acts_map_guard.clear();
+        for (key, value) in map_object {
+            if let Some(url_str) = value.as_str() {
+                acts_map_guard.insert(key.clone(), url_str.to_string());
+            }
+        }
+        info!("Saved GLOBAL_ACTS_MAP with {} entries", acts_map_guard.len());
+    }
+
+    if let Some(map_object) = pages_map.as_object() {
+        let mut pages_map_guard = GLOBAL_PAGES_MAP.write().unwrap();
+        pages_map_guard.clear(); // Clear previous map before inserting new values
+        for (key, value) in map_object {
+            if let Some(url_str) = value.as_str() {
+                pages_map_guard.insert(key.clone(), url_str.to_string());
+            }
+        }
+        info!("Saved GLOBAL_PAGES_MAP with {} entries", pages_map_guard.len());
+    }
+
+    // --- Step 6: Consolidate and return the final JSON response ---
+    let combined_response = CombinedLoginResponse {
+        return_value: true,
+        return_code: login_body.return_code,
+        msg: success_msg,
+        character_count: char_count.character_count,
+        permission: account_info.permission,
+        privilege: account_info.privilege,
+        user_no: account_info.user_no,
+        user_name: account_info.user_name,
+        auth_key: auth_key.auth_key,
         banned: account_info.banned,
 
-        acts_map: Some(acts_map),
-        pages_map: Some(pages_map),
+        acts_map: Some(acts_map),
+        pages_map: Some(pages_map),
+
+        session_cookie: session_cookie,
+    };
+
+    // Store the authenticated client globally for subsequent API calls
+    let mut client_guard = AUTHENTICATED_CLIENT.lock().await;
+    *client_guard = Some(client);
+
+    // Serialize and return the combined response as JSON
+    serde_json::to_string(&combined_response)
+        .map_err(|e| format!("Failed to serialize final login response: {}", e))
+}
+
+#[tauri::command]
+async fn handle_logout(state: tauri::State<'_, GameState>) -> Result<(), String> {
+  let mut is_launching = state.is_launching.lock().await;
+  *is_launching = false;
+
+  // Step 1: Attempt to revoke the session on the server (security best practice)
+  // This ensures the auth_key is invalidated server-side, even if compromised
+  let auth_key = {
+    let auth_info = GLOBAL_AUTH_INFO.read().unwrap();
+    auth_info.auth_key.clone()
+  };  // Lock is released here before the await point
+
+  if !auth_key.is_empty() {
+    let base_url = &get_launcher_base_url();
+    let logout_url = format!("{}/launcher/LogoutAction", base_url);
+    
+    let response = match http_client() {
+      Ok(client) => client.get(&logout_url).send().await.map_err(|e| e.to_string()),
+      Err(e) => Err(e),
+    };
+    if response.is_ok() {
+      info!("Server logout completed");
+    } else {
+      error!("Failed to revoke session on server, proceeding with local logout");
+    }
+  }
+
+  // Step 2: Reset global authentication information locally
+  {
+    let mut auth_info = GLOBAL_AUTH_INFO.write().unwrap();
+    auth_info.auth_key = String::new();
+    auth_info.user_name = String::new();
+    auth_info.user_no = 0;
+    auth_info.character_count = String::new();
+  }
+
+  {
+    let mut pages_map = GLOBAL_PAGES_MAP.write().unwrap();
+    pages_map.clear();
+    info!("GLOBAL_PAGES_MAP cleared.");
+  }
+
+  {
+    let mut pages_map = GLOBAL_ACTS_MAP.write().unwrap();
+    pages_map.clear();
+    info!("GLOBAL_ACTS_MAP cleared.");
+  }
+
+  let mut client_guard = AUTHENTICATED_CLIENT.lock().await;
+  *client_guard = None;
+
+  Ok(())
+}
+
+// Modification: We need to access the launcher base URL inside this function,
+// but it’s not a parameter. The solution is to pass it as an argument to the function,
+// and update the call in `login` accordingly.
+// Move this line if it’s not already at the top of the file.
+// use regex::Regex;
+fn extract_maps_from_html(
+    html: &str,
+    base_url: &str
+) -> Result<(serde_json::Value, serde_json::Value), String> {
+    use regex::Regex;
+    
+    lazy_static! {
+        // Expression to extract the full block of the ACTS_MAP/PAGES_MAP variable (including the braces).
+        static ref RE_ACTSMAP: Regex = 
+            Regex::new(r"var ACTS_MAP\s*=\s*(\{[\s\S]*?\});").expect("Invalid actsMap regex");
+            
+        static ref RE_PAGESMAP: Regex = 
+            Regex::new(r"var PAGES_MAP\s*=\s*(\{[\s\S]*?\});").expect("Invalid pagesMap regex");
+
+        // KEY FIX FOR ACTS_MAP: Captures the Key (Group 1) and the PATH (Group 2)
+        // Pattern looks for: Key: location.protocol + "//HOST:PORT/PATH"
+        // G1 (\w+): The numeric key (e.g., 210)
+        // G2 (/[^"]*?): The path starting with '/' and ending before the closing quote.
+        static ref RE_ACTS_ITEM_PATH: Regex = 
+            Regex::new(r#"(\w+):\s*location\.protocol\s*\+\s*"//\S+?(/[^"]*?)",?"#).expect("Invalid actsMap item path regex");
+
+
+        // --- Regex for PAGES_MAP (General cleanup) ---
+        
+        // Quote all unquoted keys that are tokens (word or number).
+        static ref RE_QUOTE_UNQUOTED_KEYS: Regex = 
+            Regex::new(r#"([,\s{])(\w+)(\s*?:)"#).expect("Invalid quote unquoted keys regex");
+        
+        // Replace JS expressions (if any) with a valid string for PAGES_MAP.
+        static ref RE_JS_PROTOCOL_PAGESMAP: Regex = 
+            Regex::new(r"(location\.protocol[\s\S]+?)(\}|,)").expect("Invalid pagesMap JS value regex");
+            
+        // Remove trailing comma.
+        static ref RE_TRAILING_COMMA: Regex = 
+            Regex::new(r",\s*?\}").expect("Invalid trailing comma regex");
+            
+        // Whitespace normalization
+        static ref RE_NORMALIZE_WHITESPACE: Regex = 
+            Regex::new(r"[\r\n\t ]+").expect("Invalid normalize whitespace regex");
+    }
+
+    // 1. EXTRACT AND BUILD ACTS_MAP (Manual URL reconstruction)
+
+    let acts_map_raw = RE_ACTSMAP.captures(html)
+        .and_then(|cap| cap.get(1))
+        .map(|m| m.as_str())
+        .ok_or("Could not find ACTS_MAP in HTML")?;
+    
+    eprintln!("DEBUG: ACTS_MAP Raw Content:\n{}", acts_map_raw); // <- DEBUG 1
+
+    let mut final_acts_map = serde_json::Map::new();
+
+    // Iterate over all (Key: Path) matches in the raw string
+    for cap in RE_ACTS_ITEM_PATH.captures_iter(acts_map_raw) {
+        // Group 1: Key (e.g., "210")
+        // Group 2: Path (e.g., "/tera/ShopAuth?authKey=%s")
+        let key = cap.get(1).unwrap().as_str().to_string();
+        let path = cap.get(2).unwrap().as_str(); // This path is already just the route
+        
+        // Rebuild the URL: base_url + path
+        let final_url = format!("{}{}", base_url, path);
+        
+        final_acts_map.insert(key, serde_json::Value::String(final_url));
+    }
+    
+    let acts_map = serde_json::Value::Object(final_acts_map);
+    eprintln!("DEBUG: ACTS_MAP Final JSON:\n{}", acts_map.to_string()); // <- DEBUG 2
+
+
+    // 2. EXTRACT AND CLEAN PAGES_MAP (String cleanup)
+    let pages_map_raw = RE_PAGESMAP.captures(html)
+        .and_then(|cap| cap.get(1))
+        .map(|m| m.as_str())
+        .ok_or("Could not find PAGES_MAP in HTML")?;
+    
+    let mut pages_map_str = pages_map_raw.to_string();
+    
+    // Apply cleanup: normalization, JS value replacement, quoting keys, trailing comma removal.
+    pages_map_str = RE_NORMALIZE_WHITESPACE.replace_all(&pages_map_str, " ").to_string();
+    pages_map_str = pages_map_str.trim().replace("{ ", "{").replace(" }", "}");
+    
+    // Replace JS expressions (Group 1) with a placeholder string (if any exist in PAGES_MAP)
+    pages_map_str = RE_JS_PROTOCOL_PAGESMAP.replace_all(&pages_map_str, r#""/JS/EXPRESSION/REMOVED"$2"#).to_string();
+
+    // Quote all unquoted keys.
+    pages_map_str = RE_QUOTE_UNQUOTED_KEYS.replace_all(&pages_map_str, r#"$1"$2"$3"#).to_string();
+
+    // Remove trailing comma.
+    pages_map_str = RE_TRAILING_COMMA.replace_all(&pages_map_str, "}").to_string();
+
+    eprintln!("DEBUG: PAGES_MAP Cleaned Content Final:\n{}", pages_map_str); // <- DEBUG 3
+
+    // 3. PARSE PAGES_MAP
+    let pages_map: serde_json::Value = serde_json::from_str(&pages_map_str)
+        .map_err(|e| format!("Failed to parse pagesMap JSON: {}", e))?;
+
+    Ok((acts_map, pages_map))
+}
+
+#[tauri::command]
+async fn check_server_connection() -> Result<bool, String> {
+  // Step 1: Validate that we have an authenticated session with complete credentials
+  let auth_valid = {
+    let auth_info = GLOBAL_AUTH_INFO.read()
+      .map_err(|e| {
+        let error_msg = format!("Failed to read auth info: {}", e);
+        let _ = log_error_to_file(&error_msg);
+        error_msg
+      })?;
+    
+    let is_complete = 
+      !auth_info.auth_key.is_empty() &&
+      auth_info.user_no > 0 &&
+      !auth_info.user_name.is_empty() &&
+      !auth_info.character_count.is_empty();
+    
+    if !is_complete {
+      let error_msg = format!(
+        "Auth info incomplete: auth_key={}, user_no={}, user_name={}, char_count={}", 
+        !auth_info.auth_key.is_empty(),
+        auth_info.user_no,
+        !auth_info.user_name.is_empty(),
+        !auth_info.character_count.is_empty()
+      );
+      let _ = log_error_to_file(&error_msg);
+      println!("{}", error_msg);
+      return Err("Authentication is incomplete. Please login again.".to_string());
+    }
+    
+    println!("Auth info valid and complete for user: {}", auth_info.user_name);
+    true
+  };
+  
+  if !auth_valid {
+    let error_msg = "Authentication validation failed".to_string();
+    let _ = log_error_to_file(&error_msg);
+    return Err(error_msg);
+  }
+  
+  // Step 2: Verify we have an authenticated HTTP client
+  let client_guard = AUTHENTICATED_CLIENT.lock().await;
+  let client = match &*client_guard {
+    Some(client) => client,
+    None => {
+      let error_msg = "No authenticated HTTP client available".to_string();
+      let _ = log_error_to_file(&error_msg);
+      println!("{}", error_msg);
+      return Err("No authenticated session found. Please login again.".to_string());
+    }
+  };
+  
+  // Step 3: Attempt actual connection to server using a simple request
+  let hash_file_url = get_hash_file_url();
+  println!("Attempting server connection to: {}", hash_file_url);
+  
+  match client.get(&hash_file_url).send().await {
+    Ok(response) => {
+      let status = response.status();
+      println!("Server connection check: status {}", status);
+      
+      if status.is_success() {
+        info!("Server connection successful");
+        Ok(true)
+      } else if status.is_client_error() {
+        let error_msg = format!(
+          "Server returned client error: {} (URL: {})",
+          status, hash_file_url
+        );
+        let _ = log_error_to_file(&error_msg);
+        Err(error_msg)
+      } else if status.is_server_error() {
+        let error_msg = format!(
+          "Server returned server error: {} (URL: {})",
+          status, hash_file_url
+        );
+        let _ = log_error_to_file(&error_msg);
+        Err(error_msg)
+      } else {
+        let error_msg = format!(
+          "Unexpected server response: {} (URL: {})",
+          status, hash_file_url
+        );
+        let _ = log_error_to_file(&error_msg);
+        Err(error_msg)
+      }
+    },
+    Err(e) => {
+      let detailed_error = format_reqwest_error(&hash_file_url, &e);
+      let error_msg = if is_certificate_error(&e) {
+        format!("Server certificate not trusted (set TLS_CA_FILE for self-signed or internal CAs): {}", detailed_error)
+      } else {
+        format!("Server connection check failed: {}", detailed_error)
+      };
+      let _ = log_error_to_file(&error_msg);
+      println!("{}", error_msg);
+      Err(error_msg)
+    }
+  }
+}
+
+#[tauri::command]
+fn get_client_version() -> Result<String, String> {
+  Ok(get_config_value("CLIENT_VERSION"))
+}
+
+#[tauri::command]
+async fn get_fresh_account_info() -> Result<String, String> {
+  let client_guard = AUTHENTICATED_CLIENT.lock().await;
+  let client = match &*client_guard {
+    Some(client) => {
+      println!("get_fresh_account_info: Authenticated client found");
+      client
+    },
+    None => {
+      println!("get_fresh_account_info: No authenticated client available");
+      return Err("User session expired. Please login again.".to_string());
+    }
+  };
+  
+  let base_url = &get_launcher_base_url();
+  
+  // Step 2: Fetch account info from server
+  println!("get_fresh_account_info: Fetching account info");
+  let account_info_url = format!("{}/launcher/GetAccountInfoAction", base_url);
+  let account_info: AccountInfoResponse = client
+    .get(&account_info_url)
+    .send()
+    .await
+    .map_err(|e| format!("Failed to connect to account info endpoint: {}", e))?
+    .json()
+    .await
+    .map_err(|e| format!("Failed to parse account info response: {}", e))?;
+  
+  if account_info.user_no <= 0 {
+    return Err("Invalid user number received from server".to_string());
+  }
+  println!("get_fresh_account_info: Account info retrieved for user_no: {}", account_info.user_no);
+
+    // --- Step 2: GET /launcher/GetAuthKeyAction ---
+    // Request a new AuthKey so that the Node.js backend knows we’re still active
+    // and to ensure we’re using a valid key.
+    let auth_key_url = format!("{}/launcher/GetAuthKeyAction", base_url);
+    let auth_key: AuthKeyResponse = client
+      .get(&auth_key_url)
+      .send()
+      .await
+      .map_err(|e| format!("(Re-check) Failed to get auth key: {}", e))?
+      .json()
+      .await
+      .map_err(|e| format!("(Re-check) Failed to parse auth key: {}", e))?;
+
+    // --- Step 3: GET /launcher/GetCharacterCountAction ---
+    let char_count_url = format!("{}/launcher/GetCharacterCountAction", base_url);
+    let char_count: CharCountResponse = client
+      .get(&char_count_url)
+      .send()
+      .await
+      .map_err(|e| format!("Failed to connect to character count endpoint: {}", e))?
+      .json()
+      .await
+      .map_err(|e| format!("Failed to parse character count response: {}", e))?;
+    
+    if char_count.character_count.is_empty() {
+      return Err("Server returned empty character count".to_string());
+    }
+
+    // actsMap & pagesMap refresh
+    println!("get_fresh_account_info: Refreshing ACTS_MAP and PAGES_MAP");
+    let main_url = format!("{}/launcher/Main?locale=en", base_url);  
+    
+    let main_res = client
+      .get(&main_url)
+      .send()
+      .await
+      .map_err(|e| format!("Failed to fetch launcher main page: {}", e))?;
+
+    if !main_res.status().is_success() {
+      return Err(format!("Launcher main page returned error status: {}", main_res.status()));
+    }
+
+    let main_html = main_res.text().await.map_err(|e| format!("Failed to read launcher main page: {}", e))?;
+    let (acts_map, pages_map) = extract_maps_from_html(&main_html, base_url)?;
+
+    if let Some(map_object) = pages_map.as_object() {
+        let mut pages_map_guard = GLOBAL_PAGES_MAP.write()
+          .map_err(|e| format!("Failed to write PAGES_MAP: {}", e))?;
+        pages_map_guard.clear();
+        for (key, value) in map_object {
+            if let Some(url_str) = value.as_str() {
+                pages_map_guard.insert(key.clone(), url_str.to_string());
+            }
+        }
+        println!("get_fresh_account_info: PAGES_MAP updated with {} entries", pages_map_guard.len());
+    } else {
+      return Err("PAGES_MAP is not a valid object".to_string());
+    }
+
+    if let Some(map_object) = acts_map.as_object() {
+        let mut acts_map_guard = GLOBAL_ACTS_MAP.write()
+          .map_err(|e| format!("Failed to write ACTS_MAP: {}", e))?;
+        acts_map_guard.clear();
+        for (key, value) in map_object {
+            if let Some(url_str) = value.as_str() {
+                acts_map_guard.insert(key.clone(), url_str.to_string());
+            }
+        }
+        println!("get_fresh_account_info: ACTS_MAP updated with {} entries", acts_map_guard.len());
+    } else {
+      return Err("ACTS_MAP is not a valid object".to_string());
+    }
+
+    // --- Step 4: Combine all the data ---
+    let combined_response = CombinedLoginResponse {
+      return_value: true,
+      return_code: 0,
+      msg: "success".to_string(),
+      character_count: char_count.character_count,
+      permission: account_info.permission,
+      privilege: account_info.privilege,
+      user_no: account_info.user_no,
+      user_name: account_info.user_name,
+      auth_key: auth_key.auth_key,
+      banned: account_info.banned,
+      acts_map: None,
+      pages_map: None,
+      session_cookie: None, 
+    };
+    
+    println!("get_fresh_account_info: Success - returning fresh account info");
+    serde_json::to_string(&combined_response)
+      .map_err(|e| format!("Failed to serialize response: {}", e))
+}
+
+// ─── Launcher self-update ────────────────────────────────────────────────────
+
+#[derive(Debug, Serialize)]
+struct LauncherUpdateInfo {
+  update_available: bool,
+  current_version: String,
+  new_version: String,
+  bin_url: String,
+  autoupdater_url: String,
+  /// Linux only: URL to replace launcher-bridge.exe alongside the launcher binary.
+  bridge_url: String,
+}
+
+/// Compare two dot-separated version strings (e.g. "1.0.1.52" vs "0.0.6").
+/// Returns true when `remote` is strictly newer than `local`.
+fn is_newer_version(remote: &str, local: &str) -> bool {
+  let parse = |v: &str| -> Vec<u32> {
+    v.split('.').filter_map(|p| p.parse().ok()).collect()
+  };
+  let r = parse(remote);
+  let l = parse(local);
+  let len = r.len().max(l.len());
+  for i in 0..len {
+    let rv = r.get(i).copied().unwrap_or(0);
+    let lv = l.get(i).copied().unwrap_or(0);
+    if rv > lv { return true; }
+    if rv < lv { return false; }
+  }
+  false
+}
+
+/// Returns the path to `launcher_version.ini` in the same directory as the .exe.
+fn get_launcher_version_file_path() -> Result<PathBuf, String> {
+  let exe_path = env::current_exe()
+    .map_err(|e| format!("Failed to get exe path: {}", e))?;
+  let exe_dir = exe_path.parent()
+    .ok_or("Failed to get exe directory")?;
+  Ok(exe_dir.join("launcher_version.ini"))
+}
+
+/// Always writes `compiled_version` to `launcher_version.ini`, overwriting any
+/// stale value that may have been left by a different (older/newer) binary.
+/// This guarantees the file always reflects what is actually running.
+fn read_or_create_launcher_version(compiled_version: &str) -> Result<String, String> {
+  let ver_path = get_launcher_version_file_path()?;
+
+  // Overwrite unconditionally — the compiled version IS the truth.
+  let mut conf = Ini::new();
+  conf.with_section(Some("LAUNCHER"))
+    .set("version", compiled_version);
+  let mut file = File::create(&ver_path)
+    .map_err(|e| format!("Failed to write launcher_version.ini: {}", e))?;
+  conf.write_to(&mut file)
+    .map_err(|e| format!("Failed to write launcher_version.ini: {}", e))?;
+  info!("launcher_version.ini synced to compiled version: {}", compiled_version);
+
+  Ok(compiled_version.to_string())
+}
+
+/// Return the local launcher version (reads launcher_version.ini, creates if missing).
+#[tauri::command]
+fn get_launcher_version(app: tauri::AppHandle) -> Result<String, String> {
+  let compiled = app.package_info().version.to_string();
+  read_or_create_launcher_version(&compiled)
+}
+
+/// Retrieve launcher configuration URLs embedded from WebLink.json
+#[tauri::command]
+fn get_launcher_urls() -> Result<LauncherUrls, String> {
+  const WEBLINK: &str = include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/WebLink.json"));
+
+  let config: serde_json::Value = serde_json::from_str(WEBLINK)
+    .map_err(|e| format!("Failed to parse WebLink.json: {}", e))?;
+
+  let web_url = config.get("WebUrl")
+    .and_then(|v| v.as_str())
+    .unwrap_or("")
+    .to_string();
+
+  let discord_url = config.get("DiscordUrl")
+    .and_then(|v| v.as_str())
+    .unwrap_or("")
+    .to_string();
+
+  let support_url = config.get("SupportUrl")
+    .and_then(|v| v.as_str())
+    .unwrap_or("")
+    .to_string();
+
+  Ok(LauncherUrls {
+    web_url,
+    discord_url,
+    support_url,
+  })
+}
+
+/// Fetch `{LAUNCHER_ACTION_URL}/public/patch/launcher_info.ini`, parse the
+/// [LAUNCHER] section and compare with the local `launcher_version.ini`.
+/// Creates `launcher_version.ini` from the compiled version if it does not exist.
+#[tauri::command]
+async fn check_launcher_update(app: tauri::AppHandle) -> Result<LauncherUpdateInfo, String> {
+  let base_url = &get_launcher_base_url();
+  let info_url = format!("{}/public/patch/launcher_info.ini", base_url);
+
+  let client = http_client()?;
+  let response = client
+    .get(&info_url)
+    .send()
+    .await
+    .map_err(|e| {
+      let detailed_error = format_reqwest_error(&info_url, &e);
+      let error_msg = format!("Failed to fetch launcher_info.ini: {}", detailed_error);
+      let _ = log_error_to_file(&error_msg);
+      error_msg
+    })?;
+
+  if !response.status().is_success() {
+    let error_msg = format!(
+      "Server returned HTTP status {} for launcher_info.ini (URL: {})",
+      response.status(), info_url
+    );
+    let _ = log_error_to_file(&error_msg);
+    return Err(error_msg);
+  }
+
+  let text = response
+    .text()
+    .await
+    .map_err(|e| {
+      let error_msg = format!("Failed to read launcher_info.ini: {} (URL: {})", e, info_url);
+      let _ = log_error_to_file(&error_msg);
+      error_msg
+    })?;
+
+  let ini = Ini::load_from_str(&text)
+    .map_err(|e| {
+      let error_msg = format!("Failed to parse launcher_info.ini: {} (URL: {})", e, info_url);
+      let _ = log_error_to_file(&error_msg);
+      error_msg
+    })?;
+
+  let section = ini
+    .section(Some("LAUNCHER"))
+    .ok_or_else(|| {
+      let error_msg = "Missing [LAUNCHER] section in launcher_info.ini (URL: {})".to_string();
+      let _ = log_error_to_file(&error_msg);
+      error_msg
+    })?;
+
+  // Platform-specific version and binary keys.
+  // New format:
+  //   win_version / linux_version
+  //   win_bin_url / linux_bin_url
+  //   linux_bridge_url  (Linux only)
+  //   autoupdater_url   (shared)
+  //
+  // Falls back to the legacy `version` / `bin_url` keys so existing
+  // servers that haven't added the new keys keep working.
+  #[cfg(target_os = "windows")]
+  let (version_key, bin_key) = ("win_version", "win_bin_url");
+  #[cfg(not(target_os = "windows"))]
+  let (version_key, bin_key) = ("linux_version", "linux_bin_url");
+
+  let server_version = section
+    .get(version_key)
+    .or_else(|| section.get("version"))
+    .ok_or_else(|| {
+      let error_msg = format!("Missing '{}' (or 'version') key in launcher_info.ini (URL: {})", version_key, info_url);
+      let _ = log_error_to_file(&error_msg);
+      error_msg
+    })?
+    .to_string();
+
+  let bin_url = section
+    .get(bin_key)
+    .or_else(|| section.get("bin_url"))
+    .ok_or_else(|| {
+      let error_msg = format!("Missing '{}' (or 'bin_url') key in launcher_info.ini (URL: {})", bin_key, info_url);
+      let _ = log_error_to_file(&error_msg);
+      error_msg
+    })?
+    .to_string();
+
+  let autoupdater_url = section
+    .get("autoupdater_url")
+    .unwrap_or("")
+    .to_string();
+
+  // Linux bridge binary URL — empty string on Windows (unused)
+  #[cfg(not(target_os = "windows"))]
+  let bridge_url = section.get("linux_bridge_url").unwrap_or("").to_string();
+  #[cfg(target_os = "windows")]
+  let bridge_url = String::new();
+
+  // Use the compiled package version as fallback when creating the local file
+  let compiled_version = app.package_info().version.to_string();
+  let local_version = read_or_create_launcher_version(&compiled_version)?;
+
+  let update_available = is_newer_version(&server_version, &local_version);
+
+  Ok(LauncherUpdateInfo {
+    update_available,
+    current_version: local_version,
+    new_version: server_version,
+    bin_url,
+    autoupdater_url,
+    bridge_url,
+  })
+}
+
+/// Fetch `launcher_info.ini` and download `autoupdater.exe` next to the launcher
+/// if it isn't already present. Called silently in the background at startup.
+async fn ensure_autoupdater() -> Result<(), String> {
+  let exe_path = std::env::current_exe()
+    .map_err(|e| {
+      let error_msg = format!("Failed to get exe path: {}", e);
+      let _ = log_error_to_file(&error_msg);
+      error_msg
+    })?;
+  let exe_dir = exe_path.parent().ok_or_else(|| {
+    let error_msg = "Failed to get exe directory".to_string();
+    let _ = log_error_to_file(&error_msg);
+    error_msg
+  })?;
+  let autoupdater = exe_dir.join("autoupdater.exe");
+
+  if autoupdater.exists() {
+    info!("ensure_autoupdater: already present, skipping download.");
+    return Ok(());
+  }
+
+  let base_url = &get_launcher_base_url();
+  let info_url = format!("{}/public/patch/launcher_info.ini", base_url);
+
+  let client = http_client()?;
+  let text = client
+    .get(&info_url)
+    .send()
+    .await
+    .map_err(|e| {
+      let detailed_error = format_reqwest_error(&info_url, &e);
+      let error_msg = format!("Failed to fetch launcher_info.ini for autoupdater: {}", detailed_error);
+      let _ = log_error_to_file(&error_msg);
+      error_msg
+    })?
+    .text()
+    .await
+    .map_err(|e| {
+      let error_msg = format!("Failed to read launcher_info.ini for autoupdater: {} (URL: {})", e, info_url);
+      let _ = log_error_to_file(&error_msg);
+      error_msg
+    })?;
+
+  let ini = Ini::load_from_str(&text)
+    .map_err(|e| {
+      let error_msg = format!("Failed to parse launcher_info.ini for autoupdater: {} (URL: {})", e, info_url);
+      let _ = log_error_to_file(&error_msg);
+      error_msg
+    })?;
+
+  let autoupdater_url = ini
+    .section(Some("LAUNCHER"))
+    .and_then(|s| s.get("autoupdater_url"))
+    .unwrap_or("")
+    .to_string();
+
+  if autoupdater_url.is_empty() {
+    let error_msg = "launcher_info.ini has no autoupdater_url — skipping download.".to_string();
+    let _ = log_error_to_file(&error_msg);
+    return Err(error_msg);
+  }
+
+  info!("ensure_autoupdater: downloading from {}", autoupdater_url);
+
+  let resp = client
+    .get(&autoupdater_url)
+    .send()
+    .await
+    .map_err(|e| {
+      let detailed_error = format_reqwest_error(&autoupdater_url, &e);
+      let error_msg = format!("Failed to download autoupdater.exe: {}", detailed_error);
+      let _ = log_error_to_file(&error_msg);
+      error_msg
+    })?;
+
+  if !resp.status().is_success() {
+    let error_msg = format!(
+      "autoupdater.exe download returned HTTP status {} (URL: {})",
+      resp.status(), autoupdater_url
+    );
+    let _ = log_error_to_file(&error_msg);
+    return Err(error_msg);
+  }
+
+  let bytes = resp
+    .bytes()
+    .await
+    .map_err(|e| {
+      let error_msg = format!("Failed to read autoupdater.exe bytes: {} (URL: {})", e, autoupdater_url);
+      let _ = log_error_to_file(&error_msg);
+      error_msg
+    })?;
+
+  tokio::fs::write(&autoupdater, &bytes)
+    .await
+    .map_err(|e| {
+      let error_msg = format!("Failed to save autoupdater.exe: {}", e);
+      let _ = log_error_to_file(&error_msg);
+      error_msg
+    })?;
+
+  info!(
+    "ensure_autoupdater: saved {} bytes to {}",
+    bytes.len(),
+    autoupdater.display()
+  );
+  Ok(())
+}
+
+/// Download the new launcher exe, then hand off to autoupdater.exe and exit.
+///
+/// Emits `"launcher_update_progress"` events while downloading:
+/// `{ progress: f64 (0-100), downloaded: u64, total: u64 }`
+#[tauri::command]
+async fn apply_launcher_update(
+  bin_url: String,
+  autoupdater_url: String,
+  new_version: String,
+  #[allow(unused_variables)]
+  bridge_url: String,
+  app: tauri::AppHandle,
+) -> Result<(), String> {
+  let client = http_client()?;
+
+  // ── 1. Resolve path to the launcher exe (this is the file we'll replace) ─
+  let exe_path = std::env::current_exe()
+    .map_err(|e| {
+      let error_msg = format!("Failed to resolve launcher exe path: {}", e);
+      let _ = log_error_to_file(&error_msg);
+      error_msg
+    })?;
+  let exe_dir = exe_path
+    .parent()
+    .ok_or_else(|| {
+      let error_msg = "Failed to get launcher directory".to_string();
+      let _ = log_error_to_file(&error_msg);
+      error_msg
+    })?;
+
+  // ── 2. Ensure autoupdater.exe is present; download it if needed ──────────
+  let autoupdater = exe_dir.join("autoupdater.exe");
+
+  info!("apply_launcher_update: autoupdater path = {}", autoupdater.display());
+  info!("apply_launcher_update: autoupdater_url  = '{}'", autoupdater_url);
+  info!("apply_launcher_update: bin_url          = '{}'", bin_url);
+  info!("apply_launcher_update: new_version      = '{}'", new_version);
+
+  if !autoupdater.exists() {
+    if autoupdater_url.is_empty() {
+      let error_msg = format!(
+        "autoupdater.exe not found at '{}'. Rebuild the launcher so autoupdater_url is passed, or place autoupdater.exe manually.",
+        autoupdater.display()
+      );
+      let _ = log_error_to_file(&error_msg);
+      return Err(error_msg);
+    }
+
+    info!("autoupdater.exe not found — downloading from {}", autoupdater_url);
+    let au_response = client
+      .get(&autoupdater_url)
+      .send()
+      .await
+      .map_err(|e| {
+        let detailed_error = format_reqwest_error(&autoupdater_url, &e);
+        let error_msg = format!("Failed to download autoupdater.exe: {}", detailed_error);
+        let _ = log_error_to_file(&error_msg);
+        error_msg
+      })?;
+
+    if !au_response.status().is_success() {
+      let error_msg = format!(
+        "autoupdater.exe download failed with HTTP status: {} (URL: {})",
+        au_response.status(), autoupdater_url
+      );
+      let _ = log_error_to_file(&error_msg);
+      return Err(error_msg);
+    }
+
+    let au_bytes = au_response
+      .bytes()
+      .await
+      .map_err(|e| {
+        let error_msg = format!("Failed to read autoupdater.exe response: {} (URL: {})", e, autoupdater_url);
+        let _ = log_error_to_file(&error_msg);
+        error_msg
+      })?;
+
+    tokio::fs::write(&autoupdater, &au_bytes)
+      .await
+      .map_err(|e| {
+        let error_msg = format!("Failed to save autoupdater.exe: {}", e);
+        let _ = log_error_to_file(&error_msg);
+        error_msg
+      })?;
+
+    info!("autoupdater.exe downloaded successfully");
+  }
+
+  // ── 3. Download the new launcher exe to %TEMP%\launcher_update\ ──────────
+  let response = client
+    .get(&bin_url)
+    .send()
+    .await
+    .map_err(|e| {
+      let detailed_error = format_reqwest_error(&bin_url, &e);
+      let error_msg = format!("Failed to start launcher download: {}", detailed_error);
+      let _ = log_error_to_file(&error_msg);
+      error_msg
+    })?;
+
+  if !response.status().is_success() {
+    let error_msg = format!(
+      "Launcher download failed with HTTP status: {} (URL: {})",
+      response.status(), bin_url
+    );
+    let _ = log_error_to_file(&error_msg);
+    return Err(error_msg);
+  }
+
+  let total_size = response.content_length().unwrap_or(0);
+
+  let temp_dir = std::env::temp_dir().join("launcher_update");
+  fs::create_dir_all(&temp_dir)
+    .map_err(|e| {
+      let error_msg = format!("Failed to create temp directory: {}", e);
+      let _ = log_error_to_file(&error_msg);
+      error_msg
+    })?;
+
+  let filename = bin_url
+    .split('/')
+    .last()
+    .filter(|s| !s.is_empty())
+    .unwrap_or("TeraLauncher_new.exe");
+
+  let new_launcher_path = temp_dir.join(filename);
+
+  let mut dest = tokio::fs::File::create(&new_launcher_path)
+    .await
+    .map_err(|e| {
+      let error_msg = format!("Failed to create temp launcher file: {}", e);
+      let _ = log_error_to_file(&error_msg);
+      error_msg
+    })?;
+
+  let mut downloaded: u64 = 0;
+  let mut stream = response.bytes_stream();
+
+  while let Some(chunk) = stream.next().await {
+    let chunk = chunk.map_err(|e| {
+      let error_msg = format!("Download stream error: {}", e);
+      let _ = log_error_to_file(&error_msg);
+      error_msg
+    })?;
+    dest
+      .write_all(&chunk)
+      .await
+      .map_err(|e| {
+        let error_msg = format!("Failed to write chunk: {}", e);
+        let _ = log_error_to_file(&error_msg);
+        error_msg
+      })?;
+    downloaded += chunk.len() as u64;
 
-        session_cookie: session_cookie,
+    let progress = if total_size > 0 {
+      (downloaded as f64 / total_size as f64) * 100.0
+    } else {
+      0.0
     };
 
-    // Store the authenticated client globally for subsequent API calls
-    let mut client_guard = AUTHENTICATED_CLIENT.lock().await;
-    *client_guard = Some(client);
+    let _ = app.emit_all(
+      "launcher_update_progress",
+      json!({ "progress": progress, "downloaded": downloaded, "total": total_size }),
+    );
+  }
 
-    // Serialize and return the combined response as JSON
-    serde_json::to_string(&combined_response)
-        .map_err(|e| format!("Failed to serialize final login response: {}", e))
-}
+  dest.flush().await.map_err(|e| {
+    let error_msg = format!("Failed to flush temp launcher file: {}", e);
+    let _ = log_error_to_file(&error_msg);
+    error_msg
+  })?;
+  drop(dest);
 
-#[tauri::command]
-async fn handle_logout(state: tauri::State<'_, GameState>) -> Result<(), String> {
-  let mut is_launching = state.is_launching.lock().await;
-  *is_launching = false;
+  // ── 4. Get current PID so autoupdater can wait for us to exit ─────────────
+  let launcher_pid = std::process::id();
 
-  // Step 1: Attempt to revoke the session on the server (security best practice)
-  // This ensures the auth_key is invalidated server-side, even if compromised
-  let auth_key = {
-    let auth_info = GLOBAL_AUTH_INFO.read().unwrap();
-    auth_info.auth_key.clone()
-  };  // Lock is released here before the await point
+  // ── 4b. On Linux: download the updated launcher-bridge.exe if a URL was given ──
+  #[cfg(not(target_os = "windows"))]
+  if !bridge_url.is_empty() {
+    info!("apply_launcher_update: downloading updated launcher-bridge.exe from {}", bridge_url);
+    let bridge_resp = client
+      .get(&bridge_url)
+      .send()
+      .await
+      .map_err(|e| {
+        let detailed_error = format_reqwest_error(&bridge_url, &e);
+        let error_msg = format!("Failed to download launcher-bridge.exe: {}", detailed_error);
+        let _ = log_error_to_file(&error_msg);
+        error_msg
+      })?;
 
-  if !auth_key.is_empty() {
-    let base_url = &*LAUNCHER_BASE_URL;
-    let logout_url = format!("{}/launcher/LogoutAction", base_url);
-    
-    if let Ok(_response) = reqwest::Client::new().get(&logout_url).send().await {
-      info!("Server logout completed");
-    } else {
-      error!("Failed to revoke session on server, proceeding with local logout");
+    if !bridge_resp.status().is_success() {
+      return Err(format!("launcher-bridge.exe download failed with status: {}", bridge_resp.status()));
     }
-  }
 
-  // Step 2: Reset global authentication information locally
-  {
-    let mut auth_info = GLOBAL_AUTH_INFO.write().unwrap();
-    auth_info.auth_key = String::new();
-    auth_info.user_name = String::new();
-    auth_info.user_no = 0;
-    auth_info.character_count = String::new();
+    let bridge_bytes = bridge_resp
+      .bytes()
+      .await
+      .map_err(|e| format!("Failed to read launcher-bridge.exe bytes: {}", e))?;
+
+    let bridge_path = exe_dir.join("launcher-bridge.exe");
+    tokio::fs::write(&bridge_path, &bridge_bytes)
+      .await
+      .map_err(|e| format!("Failed to save launcher-bridge.exe: {}", e))?;
+
+    info!("apply_launcher_update: launcher-bridge.exe updated ({} bytes)", bridge_bytes.len());
   }
 
+  // ── 5. Spawn autoupdater (hidden window) then exit the launcher ───────────
+  //  Args: <new_exe_path> <launcher_pid> <target_exe_path> <new_version>
+  let new_launcher_str = new_launcher_path
+    .to_str()
+    .ok_or("Invalid temp launcher path (non-UTF-8)")?;
+  let target_exe_str = exe_path
+    .to_str()
+    .ok_or("Invalid launcher exe path (non-UTF-8)")?;
+
+  #[cfg(target_os = "windows")]
   {
-    let mut pages_map = GLOBAL_PAGES_MAP.write().unwrap();
-    pages_map.clear();
-    info!("GLOBAL_PAGES_MAP cleared.");
+    use std::os::windows::process::CommandExt;
+    const CREATE_NO_WINDOW: u32 = 0x08000000;
+    std::process::Command::new(&autoupdater)
+      .arg(new_launcher_str)
+      .arg(launcher_pid.to_string())
+      .arg(target_exe_str)
+      .arg(&new_version)
+      .creation_flags(CREATE_NO_WINDOW)
+      .spawn()
+      .map_err(|e| format!("Failed to spawn autoupdater: {}", e))?;
   }
-
+  #[cfg(not(target_os = "windows"))]
   {
-    let mut pages_map = GLOBAL_ACTS_MAP.write().unwrap();
-    pages_map.clear();
-    info!("GLOBAL_ACTS_MAP cleared.");
+    std::process::Command::new(&autoupdater)
+      .arg(new_launcher_str)
+      .arg(launcher_pid.to_string())
+      .arg(target_exe_str)
+      .arg(&new_version)
+      .spawn()
+      .map_err(|e| format!("Failed to spawn autoupdater: {}", e))?;
   }
 
-  let mut client_guard = AUTHENTICATED_CLIENT.lock().await;
-  *client_guard = None;
-
+  app.exit(0);
   Ok(())
 }
 
-// Modification: We need to access LAUNCHER_BASE_URL inside this function,
-// but it’s not a parameter. The solution is to pass it as an argument to the function,
-// and update the call in `login` accordingly.
-// Move this line if it’s not already at the top of the file.
-// use regex::Regex;
-fn extract_maps_from_html(
-    html: &str,
-    base_url: &str
-) -> Result<(serde_json::Value, serde_json::Value), String> {
-    use regex::Regex;
-    
-    lazy_static! {
-        // Expression to extract the full block of the ACTS_MAP/PAGES_MAP variable (including the braces).
-        static ref RE_ACTSMAP: Regex = 
-            Regex::new(r"var ACTS_MAP\s*=\s*(\{[\s\S]*?\});").expect("Invalid actsMap regex");
-            
-        static ref RE_PAGESMAP: Regex = 
-            Regex::new(r"var PAGES_MAP\s*=\s*(\{[\s\S]*?\});").expect("Invalid pagesMap regex");
+// ── Signup / Captcha commands ─────────────────────────────────────────────────
 
-        // KEY FIX FOR ACTS_MAP: Captures the Key (Group 1) and the PATH (Group 2)
-        // Pattern looks for: Key: location.protocol + "//HOST:PORT/PATH"
-        // G1 (\w+): The numeric key (e.g., 210)
-        // G2 (/[^"]*?): The path starting with '/' and ending before the closing quote.
-        static ref RE_ACTS_ITEM_PATH: Regex = 
-            Regex::new(r#"(\w+):\s*location\.protocol\s*\+\s*"//\S+?(/[^"]*?)",?"#).expect("Invalid actsMap item path regex");
+/// Fetches a new slider captcha from the server.
+/// Initializes a plain session client without calling the captcha endpoint.
+/// Used when captcha is disabled so that signup still has a valid session client.
+#[tauri::command]
+async fn init_signup_session() -> Result<(), String> {
+  let client = http_client_builder()
+    .cookie_store(true)
+    .build()
+    .map_err(|e| e.to_string())?;
 
+  let mut client_guard = SIGNUP_SESSION_CLIENT.lock().await;
+  *client_guard = Some(client);
+  Ok(())
+}
 
-        // --- Regex for PAGES_MAP (General cleanup) ---
-        
-        // Quote all unquoted keys that are tokens (word or number).
-        static ref RE_QUOTE_UNQUOTED_KEYS: Regex = 
-            Regex::new(r#"([,\s{])(\w+)(\s*?:)"#).expect("Invalid quote unquoted keys regex");
-        
-        // Replace JS expressions (if any) with a valid string for PAGES_MAP.
-        static ref RE_JS_PROTOCOL_PAGESMAP: Regex = 
-            Regex::new(r"(location\.protocol[\s\S]+?)(\}|,)").expect("Invalid pagesMap JS value regex");
-            
-        // Remove trailing comma.
-        static ref RE_TRAILING_COMMA: Regex = 
-            Regex::new(r",\s*?\}").expect("Invalid trailing comma regex");
-            
-        // Whitespace normalization
-        static ref RE_NORMALIZE_WHITESPACE: Regex = 
-            Regex::new(r"[\r\n\t ]+").expect("Invalid normalize whitespace regex");
-    }
+/// Creates a fresh session client (with cookie jar) stored globally so that
+/// the subsequent verify_captcha and signup calls share the same session.
+#[tauri::command]
+async fn get_captcha() -> Result<String, String> {
+  let cookie_jar = Arc::new(Jar::default());
+  let client = http_client_builder()
+    .cookie_store(true)
+    .cookie_provider(Arc::clone(&cookie_jar))
+    .build()
+    .map_err(|e| e.to_string())?;
 
-    // 1. EXTRACT AND BUILD ACTS_MAP (Manual URL reconstruction)
+  let base_url = &get_launcher_base_url();
+  let captcha_url = format!("{}/launcher/GetCaptcha", base_url);
 
-    let acts_map_raw = RE_ACTSMAP.captures(html)
-        .and_then(|cap| cap.get(1))
-        .map(|m| m.as_str())
-        .ok_or("Could not find ACTS_MAP in HTML")?;
-    
-    eprintln!("DEBUG: ACTS_MAP Raw Content:\n{}", acts_map_raw); // <- DEBUG 1
+  let res = client
+    .get(&captcha_url)
+    .send()
+    .await
+    .map_err(|e| format!("Failed to get captcha: {}", e))?;
 
-    let mut final_acts_map = serde_json::Map::new();
+  if !res.status().is_success() {
+    return Err(format!("Captcha request failed with status: {}", res.status()));
+  }
 
-    // Iterate over all (Key: Path) matches in the raw string
-    for cap in RE_ACTS_ITEM_PATH.captures_iter(acts_map_raw) {
-        // Group 1: Key (e.g., "210")
-        // Group 2: Path (e.g., "/tera/ShopAuth?authKey=%s")
-        let key = cap.get(1).unwrap().as_str().to_string();
-        let path = cap.get(2).unwrap().as_str(); // This path is already just the route
-        
-        // Rebuild the URL: base_url + path
-        let final_url = format!("{}{}", base_url, path);
-        
-        final_acts_map.insert(key, serde_json::Value::String(final_url));
-    }
-    
-    let acts_map = serde_json::Value::Object(final_acts_map);
-    eprintln!("DEBUG: ACTS_MAP Final JSON:\n{}", acts_map.to_string()); // <- DEBUG 2
+  let body: serde_json::Value = res
+    .json()
+    .await
+    .map_err(|e| format!("Failed to parse captcha response: {}", e))?;
 
+  let mut client_guard = SIGNUP_SESSION_CLIENT.lock().await;
+  *client_guard = Some(client);
 
-    // 2. EXTRACT AND CLEAN PAGES_MAP (String cleanup)
-    let pages_map_raw = RE_PAGESMAP.captures(html)
-        .and_then(|cap| cap.get(1))
-        .map(|m| m.as_str())
-        .ok_or("Could not find PAGES_MAP in HTML")?;
-    
-    let mut pages_map_str = pages_map_raw.to_string();
-    
-    // Apply cleanup: normalization, JS value replacement, quoting keys, trailing comma removal.
-    pages_map_str = RE_NORMALIZE_WHITESPACE.replace_all(&pages_map_str, " ").to_string();
-    pages_map_str = pages_map_str.trim().replace("{ ", "{").replace(" }", "}");
-    
-    // Replace JS expressions (Group 1) with a placeholder string (if any exist in PAGES_MAP)
-    pages_map_str = RE_JS_PROTOCOL_PAGESMAP.replace_all(&pages_map_str, r#""/JS/EXPRESSION/REMOVED"$2"#).to_string();
+  serde_json::to_string(&body)
+    .map_err(|e| format!("Failed to serialize captcha: {}", e))
+}
 
-    // Quote all unquoted keys.
-    pages_map_str = RE_QUOTE_UNQUOTED_KEYS.replace_all(&pages_map_str, r#"$1"$2"$3"#).to_string();
+/// Posts the slider answer to the captcha endpoint, using the same session
+/// created in get_captcha. Returns true if the server accepted the answer.
+#[tauri::command]
+async fn verify_captcha(answer: i32) -> Result<bool, String> {
+  let client_guard = SIGNUP_SESSION_CLIENT.lock().await;
+  let client = client_guard
+    .as_ref()
+    .ok_or_else(|| "No signup session. Call get_captcha first.".to_string())?;
 
-    // Remove trailing comma.
-    pages_map_str = RE_TRAILING_COMMA.replace_all(&pages_map_str, "}").to_string();
+  let base_url = &get_launcher_base_url();
+  let captcha_url = format!("{}/launcher/GetCaptcha", base_url);
+  let answer_str = answer.to_string();
+
+  let res = client
+    .post(&captcha_url)
+    .form(&[("answer", answer_str.as_str())])
+    .send()
+    .await
+    .map_err(|e| format!("Failed to verify captcha: {}", e))?;
 
-    eprintln!("DEBUG: PAGES_MAP Cleaned Content Final:\n{}", pages_map_str); // <- DEBUG 3
+  if !res.status().is_success() {
+    return Err(format!("Captcha verify failed with status: {}", res.status()));
+  }
 
-    // 3. PARSE PAGES_MAP
-    let pages_map: serde_json::Value = serde_json::from_str(&pages_map_str)
-        .map_err(|e| format!("Failed to parse pagesMap JSON: {}", e))?;
+  let verify_res: CaptchaVerifyApiResponse = res
+    .json()
+    .await
+    .map_err(|e| format!("Failed to parse captcha verify response: {}", e))?;
 
-    Ok((acts_map, pages_map))
+  Ok(verify_res.verified)
 }
 
+/// Submits the signup form to the backend. Requires captcha to have been
+/// verified first (the same session client must carry captchaVerified = true).
 #[tauri::command]
-async fn check_server_connection() -> Result<bool, String> {
-  // Step 1: Validate that we have an authenticated session with complete credentials
-  let auth_valid = {
-    let auth_info = GLOBAL_AUTH_INFO.read()
-      .map_err(|e| {
-        let error_msg = format!("Failed to read auth info: {}", e);
-        let _ = log_error_to_file(&error_msg);
-        error_msg
-      })?;
-    
-    let is_complete = 
-      !auth_info.auth_key.is_empty() &&
-      auth_info.user_no > 0 &&
-      !auth_info.user_name.is_empty() &&
-      !auth_info.character_count.is_empty();
-    
-    if !is_complete {
-      let error_msg = format!(
-        "Auth info incomplete: auth_key={}, user_no={}, user_name={}, char_count={}", 
-        !auth_info.auth_key.is_empty(),
-        auth_info.user_no,
-        !auth_info.user_name.is_empty(),
-        !auth_info.character_count.is_empty()
-      );
-      let _ = log_error_to_file(&error_msg);
-      println!("{}", error_msg);
-      return Err("Authentication is incomplete. Please login again.".to_string());
-    }
-    
-    println!("Auth info valid and complete for user: {}", auth_info.user_name);
-    true
-  };
-  
-  if !auth_valid {
-    let error_msg = "Authentication validation failed".to_string();
-    let _ = log_error_to_file(&error_msg);
-    return Err(error_msg);
-  }
-  
-  // Step 2: Verify we have an authenticated HTTP client
-  let client_guard = AUTHENTICATED_CLIENT.lock().await;
-  let client = match &*client_guard {
-    Some(client) => client,
-    None => {
-      let error_msg = "No authenticated HTTP client available".to_string();
-      let _ = log_error_to_file(&error_msg);
-      println!("{}", error_msg);
-      return Err("No authenticated session found. Please login again.".to_string());
-    }
-  };
-  
-  // Step 3: Attempt actual connection to server using a simple request
-  let hash_file_url = get_hash_file_url();
-  println!("Attempting server connection to: {}", hash_file_url);
-  
-  match client.get(&hash_file_url).send().await {
-    Ok(response) => {
-      let status = response.status();
-      println!("Server connection check: status {}", status);
-      
-      if status.is_success() {
-        info!("Server connection successful");
-        Ok(true)
-      } else if status.is_client_error() {
-        let error_msg = format!(
-          "Server returned client error: {} (URL: {})",
-          status, hash_file_url
-        );
-        let _ = log_error_to_file(&error_msg);
-        Err(error_msg)
-      } else if status.is_server_error() {
-        let error_msg = format!(
-          "Server returned server error: {} (URL: {})",
-          status, hash_file_url
-        );
-        let _ = log_error_to_file(&error_msg);
-        Err(error_msg)
-      } else {
-        let error_msg = format!(
-          "Unexpected server response: {} (URL: {})",
-          status, hash_file_url
-        );
-        let _ = log_error_to_file(&error_msg);
-        Err(error_msg)
-      }
-    },
-    Err(e) => {
-      let detailed_error = format_reqwest_error(&hash_file_url, &e);
-      let error_msg = format!("Server connection check failed: {}", detailed_error);
-      let _ = log_error_to_file(&error_msg);
-      println!("{}", error_msg);
-      Err(error_msg)
-    }
+async fn signup(login: String, email: String, password: String) -> Result<String, String> {
+  let client_guard = SIGNUP_SESSION_CLIENT.lock().await;
+  let client = client_guard
+    .as_ref()
+    .ok_or_else(|| "No signup session. Complete captcha first.".to_string())?;
+
+  let base_url = &get_launcher_base_url();
+  let signup_url = format!("{}/launcher/SignupAction", base_url);
+
+  let res = client
+    .post(&signup_url)
+    .form(&[
+      ("login", login.as_str()),
+      ("email", email.as_str()),
+      ("password", password.as_str()),
+    ])
+    .send()
+    .await
+    .map_err(|e| format!("Failed to submit signup: {}", e))?;
+
+  if !res.status().is_success() {
+    return Err(format!("Signup request failed with status: {}", res.status()));
   }
-}
 
-#[tauri::command]
-fn get_client_version() -> Result<String, String> {
-  Ok(get_config_value("CLIENT_VERSION"))
+  let signup_res: SignupApiResponse = res
+    .json()
+    .await
+    .map_err(|e| format!("Failed to parse signup response: {}", e))?;
+
+  serde_json::to_string(&signup_res)
+    .map_err(|e| format!("Failed to serialize signup response: {}", e))
 }
 
 #[tauri::command]
-async fn get_fresh_account_info() -> Result<String, String> {
-  let client_guard = AUTHENTICATED_CLIENT.lock().await;
-  let client = match &*client_guard {
-    Some(client) => {
-      println!("get_fresh_account_info: Authenticated client found");
-      client
-    },
-    None => {
-      println!("get_fresh_account_info: No authenticated client available");
-      return Err("User session expired. Please login again.".to_string());
+async fn get_portal_config() -> Result<String, String> {
+  let client = http_client()?;
+  let base_url = &get_launcher_base_url();
+  let url = format!("{}/launcher/GetPortalConfig", base_url);
+
+  let res = match client.get(&url).send().await {
+    Ok(r) => r,
+    Err(_) => {
+      let default = PortalConfigResponse { registration_disabled: false, captcha_enabled: true, patch_no_check: false };
+      return serde_json::to_string(&default).map_err(|e| e.to_string());
     }
   };
-  
-  let base_url = &*LAUNCHER_BASE_URL;
-  
-  // Step 2: Fetch account info from server
-  println!("get_fresh_account_info: Fetching account info");
-  let account_info_url = format!("{}/launcher/GetAccountInfoAction", base_url);
-  let account_info: AccountInfoResponse = client
-    .get(&account_info_url)
-    .send()
-    .await
-    .map_err(|e| format!("Failed to connect to account info endpoint: {}", e))?
+
+  if !res.status().is_success() {
+    let default = PortalConfigResponse { registration_disabled: false, captcha_enabled: true, patch_no_check: false };
+    return serde_json::to_string(&default).map_err(|e| e.to_string());
+  }
+
+  let config: PortalConfigResponse = res
     .json()
     .await
-    .map_err(|e| format!("Failed to parse account info response: {}", e))?;
-  
-  if account_info.user_no <= 0 {
-    return Err("Invalid user number received from server".to_string());
-  }
-  println!("get_fresh_account_info: Account info retrieved for user_no: {}", account_info.user_no);
+    .map_err(|e| format!("Failed to parse portal config: {}", e))?;
 
-    // --- Step 2: GET /launcher/GetAuthKeyAction ---
-    // Request a new AuthKey so that the Node.js backend knows we’re still active
-    // and to ensure we’re using a valid key.
-    let auth_key_url = format!("{}/launcher/GetAuthKeyAction", base_url);
-    let auth_key: AuthKeyResponse = client
-      .get(&auth_key_url)
-      .send()
-      .await
-      .map_err(|e| format!("(Re-check) Failed to get auth key: {}", e))?
-      .json()
-      .await
-      .map_err(|e| format!("(Re-check) Failed to parse auth key: {}", e))?;
+  serde_json::to_string(&config)
+    .map_err(|e| format!("Failed to serialize portal config: {}", e))
+}
 
-    // --- Step 3: GET /launcher/GetCharacterCountAction ---
-    let char_count_url = format!("{}/launcher/GetCharacterCountAction", base_url);
-    let char_count: CharCountResponse = client
-      .get(&char_count_url)
-      .send()
-      .await
-      .map_err(|e| format!("Failed to connect to character count endpoint: {}", e))?
-      .json()
-      .await
-      .map_err(|e| format!("Failed to parse character count response: {}", e))?;
-    
-    if char_count.character_count.is_empty() {
-      return Err("Server returned empty character count".to_string());
-    }
+// ─── Sleep prevention ────────────────────────────────────────────────────────
 
-    // actsMap & pagesMap refresh
-    println!("get_fresh_account_info: Refreshing ACTS_MAP and PAGES_MAP");
-    let main_url = format!("{}/launcher/Main?locale=en", base_url);  
-    
-    let main_res = client
-      .get(&main_url)
-      .send()
-      .await
-      .map_err(|e| format!("Failed to fetch launcher main page: {}", e))?;
+/// Number of live `SleepGuard`s and, while there are any, the channel keeping
+/// the execution-state thread alive.
+struct SleepInhibitor {
+  count: usize,
+  release: Option<std::sync::mpsc::Sender<()>>,
+}
 
-    if !main_res.status().is_success() {
-      return Err(format!("Launcher main page returned error status: {}", main_res.status()));
-    }
+lazy_static! {
+  static ref SLEEP_INHIBITOR: std::sync::Mutex<SleepInhibitor> = std::sync::Mutex::new(SleepInhibitor {
+    count: 0,
+    release: None,
+  });
+}
 
-    let main_html = main_res.text().await.map_err(|e| format!("Failed to read launcher main page: {}", e))?;
-    let (acts_map, pages_map) = extract_maps_from_html(&main_html, base_url)?;
+/// Keeps the system from going to sleep for as long as it is alive. Disabled
+/// with `prevent_sleep = false` in the `[launcher]` section of config.ini.
+struct SleepGuard {
+  active: bool,
+}
 
-    if let Some(map_object) = pages_map.as_object() {
-        let mut pages_map_guard = GLOBAL_PAGES_MAP.write()
-          .map_err(|e| format!("Failed to write PAGES_MAP: {}", e))?;
-        pages_map_guard.clear();
-        for (key, value) in map_object {
-            if let Some(url_str) = value.as_str() {
-                pages_map_guard.insert(key.clone(), url_str.to_string());
-            }
-        }
-        println!("get_fresh_account_info: PAGES_MAP updated with {} entries", pages_map_guard.len());
-    } else {
-      return Err("PAGES_MAP is not a valid object".to_string());
+impl SleepGuard {
+  fn new() -> Self {
+    let enabled = get_launcher_setting("prevent_sleep")
+      .map(|v| !(v.eq_ignore_ascii_case("false") || v == "0"))
+      .unwrap_or(true);
+    if !enabled {
+      return SleepGuard { active: false };
     }
 
-    if let Some(map_object) = acts_map.as_object() {
-        let mut acts_map_guard = GLOBAL_ACTS_MAP.write()
-          .map_err(|e| format!("Failed to write ACTS_MAP: {}", e))?;
-        acts_map_guard.clear();
-        for (key, value) in map_object {
-            if let Some(url_str) = value.as_str() {
-                acts_map_guard.insert(key.clone(), url_str.to_string());
-            }
-        }
-        println!("get_fresh_account_info: ACTS_MAP updated with {} entries", acts_map_guard.len());
-    } else {
-      return Err("ACTS_MAP is not a valid object".to_string());
+    let mut inhibitor = SLEEP_INHIBITOR.lock().unwrap();
+    if inhibitor.count == 0 {
+      inhibitor.release = Some(spawn_execution_state_thread());
     }
+    inhibitor.count += 1;
+    SleepGuard { active: true }
+  }
+}
 
-    // --- Step 4: Combine all the data ---
-    let combined_response = CombinedLoginResponse {
-      return_value: true,
-      return_code: 0,
-      msg: "success".to_string(),
-      character_count: char_count.character_count,
-      permission: account_info.permission,
-      privilege: account_info.privilege,
-      user_no: account_info.user_no,
-      user_name: account_info.user_name,
-      auth_key: auth_key.auth_key,
-      banned: account_info.banned,
-      acts_map: None,
-      pages_map: None,
-      session_cookie: None, 
-    };
-    
-    println!("get_fresh_account_info: Success - returning fresh account info");
-    serde_json::to_string(&combined_response)
-      .map_err(|e| format!("Failed to serialize response: {}", e))
+impl Drop for SleepGuard {
+  fn drop(&mut self) {
+    if !self.active {
+      return;
+    }
+    let mut inhibitor = SLEEP_INHIBITOR.lock().unwrap();
+    inhibitor.count -= 1;
+    if inhibitor.count == 0 {
+      // Dropping the sender wakes the thread, which clears the request.
+      inhibitor.release = None;
+    }
+  }
 }
 
-// ─── Launcher self-update ────────────────────────────────────────────────────
+/// SetThreadExecutionState applies to the calling thread, and async tasks
+/// move between worker threads, so the request is held by a dedicated thread
+/// until the returned sender is dropped.
+#[cfg(windows)]
+fn spawn_execution_state_thread() -> std::sync::mpsc::Sender<()> {
+  use winapi::um::winbase::SetThreadExecutionState;
+  use winapi::um::winnt::{ES_CONTINUOUS, ES_SYSTEM_REQUIRED};
+
+  let (tx, rx) = std::sync::mpsc::channel::<()>();
+  std::thread::spawn(move || {
+    unsafe { SetThreadExecutionState(ES_CONTINUOUS | ES_SYSTEM_REQUIRED) };
+    let _ = rx.recv();
+    unsafe { SetThreadExecutionState(ES_CONTINUOUS) };
+  });
+  tx
+}
 
-#[derive(Debug, Serialize)]
-struct LauncherUpdateInfo {
-  update_available: bool,
-  current_version: String,
-  new_version: String,
-  bin_url: String,
-  autoupdater_url: String,
-  /// Linux only: URL to replace launcher-bridge.exe alongside the launcher binary.
-  bridge_url: String,
+#[cfg(not(windows))]
+fn spawn_execution_state_thread() -> std::sync::mpsc::Sender<()> {
+  // No-op outside Windows; the sender is only kept so the bookkeeping matches.
+  std::sync::mpsc::channel::<()>().0
 }
 
-/// Compare two dot-separated version strings (e.g. "1.0.1.52" vs "0.0.6").
-/// Returns true when `remote` is strictly newer than `local`.
-fn is_newer_version(remote: &str, local: &str) -> bool {
-  let parse = |v: &str| -> Vec<u32> {
-    v.split('.').filter_map(|p| p.parse().ok()).collect()
-  };
-  let r = parse(remote);
-  let l = parse(local);
-  let len = r.len().max(l.len());
-  for i in 0..len {
-    let rv = r.get(i).copied().unwrap_or(0);
-    let lv = l.get(i).copied().unwrap_or(0);
-    if rv > lv { return true; }
-    if rv < lv { return false; }
+// ─── Incremental manifests ───────────────────────────────────────────────────
+
+lazy_static! {
+  // Paths removed by the last diff manifest, deleted once its version is applied.
+  static ref PENDING_REMOVALS: RwLock<Vec<String>> = RwLock::new(Vec::new());
+}
+
+/// Reads a manifest version, which may be published as a string or a number.
+fn version_string(value: &serde_json::Value) -> Option<String> {
+  match value {
+    serde_json::Value::String(v) => Some(v.clone()),
+    serde_json::Value::Number(v) => Some(v.to_string()),
+    _ => None,
   }
-  false
 }
 
-/// Returns the path to `launcher_version.ini` in the same directory as the .exe.
-fn get_launcher_version_file_path() -> Result<PathBuf, String> {
-  let exe_path = env::current_exe()
-    .map_err(|e| format!("Failed to get exe path: {}", e))?;
-  let exe_dir = exe_path.parent()
-    .ok_or("Failed to get exe directory")?;
-  Ok(exe_dir.join("launcher_version.ini"))
+/// Versions end up in diff file names, so they're limited to plain characters.
+fn is_valid_manifest_version(version: &str) -> bool {
+  !version.is_empty() && version.chars().all(|c| c.is_ascii_alphanumeric() || c == '.' || c == '_')
 }
 
-/// Always writes `compiled_version` to `launcher_version.ini`, overwriting any
-/// stale value that may have been left by a different (older/newer) binary.
-/// This guarantees the file always reflects what is actually running.
-fn read_or_create_launcher_version(compiled_version: &str) -> Result<String, String> {
-  let ver_path = get_launcher_version_file_path()?;
+/// Manifest version the game files were last fully updated to.
+fn get_applied_manifest_version() -> Option<String> {
+  get_launcher_setting("applied_manifest_version").filter(|v| !v.is_empty())
+}
 
-  // Overwrite unconditionally — the compiled version IS the truth.
-  let mut conf = Ini::new();
-  conf.with_section(Some("LAUNCHER"))
-    .set("version", compiled_version);
-  let mut file = File::create(&ver_path)
-    .map_err(|e| format!("Failed to write launcher_version.ini: {}", e))?;
-  conf.write_to(&mut file)
-    .map_err(|e| format!("Failed to write launcher_version.ini: {}", e))?;
-  info!("launcher_version.ini synced to compiled version: {}", compiled_version);
+/// Forgets the applied version, so the next check compares the full hash
+/// file. Needed whenever files may be out of date that a diff wouldn't
+/// list: an incomplete update, or a change that widens the set of files the
+/// launcher manages.
+fn reset_applied_manifest_version() {
+  remove_applied_manifest();
+  if get_applied_manifest_version().is_none() {
+    return;
+  }
+  match save_launcher_setting("applied_manifest_version", "") {
+    Ok(()) => println!("Cleared the applied manifest version, the next check uses the full hash file"),
+    Err(e) => eprintln!("Failed to clear the applied manifest version: {}", e),
+  }
+}
 
-  Ok(compiled_version.to_string())
+fn get_applied_manifest_path() -> Result<PathBuf, String> {
+  get_app_data_path("applied_manifest.json")
 }
 
-/// Return the local launcher version (reads launcher_version.ini, creates if missing).
-#[tauri::command]
-fn get_launcher_version(app: tauri::AppHandle) -> Result<String, String> {
-  let compiled = app.package_info().version.to_string();
-  read_or_create_launcher_version(&compiled)
+/// The manifest the game files were last fully updated to. Written by
+/// `mark_manifest_applied` from manifests that passed
+/// `verify_manifest_signature`, and kept current across diff updates,
+/// unlike the HTTP cache in manifest_cache.json. `None` while the files
+/// aren't known to match any manifest.
+fn load_applied_manifest() -> Option<serde_json::Value> {
+  let contents = fs::read_to_string(get_applied_manifest_path().ok()?).ok()?;
+  serde_json::from_str(&contents).ok()
 }
 
-/// Retrieve launcher configuration URLs embedded from WebLink.json
+fn save_applied_manifest(manifest: &serde_json::Value) -> Result<(), String> {
+  let path = get_applied_manifest_path()?;
+  if let Some(parent) = path.parent() {
+    fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+  }
+  let temp_path = path.with_extension("json.tmp");
+  fs::write(&temp_path, serde_json::to_string(manifest).map_err(|e| e.to_string())?)
+    .map_err(|e| e.to_string())?;
+  fs::rename(&temp_path, &path).map_err(|e| e.to_string())
+}
+
+fn remove_applied_manifest() {
+  let Ok(path) = get_applied_manifest_path() else { return };
+  if let Err(e) = fs::remove_file(&path) {
+    if e.kind() != std::io::ErrorKind::NotFound {
+      eprintln!("Failed to remove {}: {}", path.display(), e);
+    }
+  }
+}
+
+/// Layers a diff's added and changed entries over `base` and drops the
+/// removed paths. Entries keep the algorithm of the diff they came from.
+fn apply_manifest_diff(base: &mut serde_json::Value, entries: &[serde_json::Value], removed: &[String], diff_algo: HashAlgorithm) {
+  let base_algo = HashAlgorithm::for_manifest(base);
+  let mut files: Vec<serde_json::Value> = base["files"].as_array().cloned().unwrap_or_default();
+  let replaced: HashSet<&str> = entries.iter()
+    .filter_map(|entry| entry["path"].as_str())
+    .chain(removed.iter().map(String::as_str))
+    .collect();
+  files.retain(|entry| !entry["path"].as_str().is_some_and(|path| replaced.contains(path)));
+  files.extend(entries.iter().cloned().map(|mut entry| {
+    if entry.get("algo").is_none() && diff_algo != base_algo {
+      entry["algo"] = json!(diff_algo);
+    }
+    entry
+  }));
+  base["files"] = serde_json::Value::Array(files);
+}
+
+/// The manifest version the client is known to match, for display.
 #[tauri::command]
-fn get_launcher_urls() -> Result<LauncherUrls, String> {
-  const WEBLINK: &str = include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/WebLink.json"));
+fn get_client_manifest_version() -> Option<String> {
+  get_applied_manifest_version()
+}
 
-  let config: serde_json::Value = serde_json::from_str(WEBLINK)
-    .map_err(|e| format!("Failed to parse WebLink.json: {}", e))?;
+/// Resolves `relative` against the directory hash-file.json is served from.
+fn manifest_sibling_url(relative: &str) -> Result<Url, String> {
+  Url::parse(&get_hash_file_url())
+    .map_err(|e| format!("Invalid HASH_FILE_URL: {}", e))?
+    .join(relative)
+    .map_err(|e| e.to_string())
+}
 
-  let web_url = config.get("WebUrl")
-    .and_then(|v| v.as_str())
-    .unwrap_or("")
-    .to_string();
+async fn fetch_manifest_json(url: &Url) -> Result<serde_json::Value, String> {
+  let res = http_client()?.get(url.clone()).send().await
+    .map_err(|e| format_reqwest_error(url.as_str(), &e))?;
+  if !res.status().is_success() {
+    return Err(format!("{} returned HTTP status {}", url, res.status()));
+  }
+  let content_type = header_string(&res, reqwest::header::CONTENT_TYPE);
+  let bytes = res.bytes().await.map_err(|e| format_reqwest_error(url.as_str(), &e))?;
+  let text = decode_manifest_body(&bytes, content_type.as_deref())?;
+  let json = serde_json::from_str(&text).map_err(|e| format!("Failed to parse {}: {}", url, e))?;
+  verify_manifest_signature(&json)?;
+  Ok(json)
+}
 
-  let discord_url = config.get("DiscordUrl")
-    .and_then(|v| v.as_str())
-    .unwrap_or("")
-    .to_string();
+/// Whether the game files are known to match the server without hashing
+/// them: the last update finished cleanly (no download session left to
+/// resume) and `version.json` names the applied version. Overlays aren't
+/// versioned, so they always need a full check.
+async fn applied_version_is_current() -> Result<bool, String> {
+  let Some(applied) = get_applied_manifest_version() else { return Ok(false) };
+  if load_download_session().is_ok() || !extra_manifest_urls().is_empty() {
+    return Ok(false);
+  }
+  let latest = fetch_manifest_json(&manifest_sibling_url("version.json")?).await?;
+  let latest = version_string(&latest["version"]).ok_or("version.json has no version")?;
+  if latest == applied {
+    println!("Manifest version {} is already applied, skipping the file check", applied);
+  }
+  Ok(latest == applied)
+}
 
-  let support_url = config.get("SupportUrl")
-    .and_then(|v| v.as_str())
-    .unwrap_or("")
-    .to_string();
+/// Loads only the entries that changed since the applied version, from
+/// `version.json` and `diff/<from>-<to>.json`. Returns `None` whenever the
+/// full hash file has to be used instead.
+async fn fetch_manifest_diff() -> Option<serde_json::Value> {
+  // Diffs only describe hash-file.json, not the overlays on top of it.
+  if !extra_manifest_urls().is_empty() {
+    return None;
+  }
+  let from = get_applied_manifest_version()?;
+  match load_manifest_diff(&from).await {
+    Ok(manifest) => Some(manifest),
+    Err(e) => {
+      println!("No usable diff from manifest version {}, using the full hash file: {}", from, e);
+      None
+    }
+  }
+}
 
-  Ok(LauncherUrls {
-    web_url,
-    discord_url,
-    support_url,
+async fn load_manifest_diff(from: &str) -> Result<serde_json::Value, String> {
+  let latest = fetch_manifest_json(&manifest_sibling_url("version.json")?).await?;
+  let to = version_string(&latest["version"]).ok_or("version.json has no version")?;
+
+  // The diff is applied to the stored manifest, so that has to be there.
+  let mut applied = load_applied_manifest().ok_or("No applied manifest to apply a diff to")?;
+  let (manifest, removed) = if to == from {
+    println!("Manifest version {} is already applied", to);
+    (json!({ "version": to, "files": [] }), Vec::new())
+  } else {
+    if !is_valid_manifest_version(&to) {
+      return Err(format!("Invalid manifest version: {}", to));
+    }
+    let diff = fetch_manifest_json(&manifest_sibling_url(&format!("diff/{}-{}.json", from, to))?).await?;
+    if version_string(&diff["from"]).as_deref() != Some(from) || version_string(&diff["to"]).as_deref() != Some(to.as_str()) {
+      return Err(format!("Diff does not cover {} to {}", from, to));
+    }
+    let files: Vec<serde_json::Value> = ["added", "changed"].iter()
+      .filter_map(|key| diff[*key].as_array())
+      .flatten()
+      .cloned()
+      .collect();
+    let removed: Vec<String> = diff["removed"].as_array()
+      .map(|paths| paths.iter().filter_map(|p| p.as_str().map(str::to_string)).collect())
+      .unwrap_or_default();
+    println!("Using diff {} -> {}: {} added or changed, {} removed", from, to, files.len(), removed.len());
+    (json!({ "version": to, "algo": diff["algo"], "files": files }), removed)
+  };
+
+  apply_manifest_diff(&mut applied, manifest["files"].as_array().map(Vec::as_slice).unwrap_or_default(), &removed, HashAlgorithm::for_manifest(&manifest));
+  applied["version"] = json!(to);
+  *CURRENT_MANIFEST_VERSION.write().unwrap() = Some(to);
+  *CURRENT_PATCH_PACKAGE.write().unwrap() = None;
+  *PENDING_APPLIED_MANIFEST.write().unwrap() = Some(applied);
+  *PENDING_REMOVALS.write().unwrap() = removed;
+  Ok(manifest)
+}
+
+/// How one manifest's entries differ from another's, by path and in path
+/// order. An entry counts as changed only when its hash, algorithm or size
+/// differ, so optional fields an older generator didn't write (mtime, chunks,
+/// quick_hash) don't count. Both the published diffs and `compare_manifests`
+/// are built from this.
+struct ManifestDelta<'a> {
+  added: Vec<&'a serde_json::Value>,
+  /// Old and new entry.
+  changed: Vec<(&'a serde_json::Value, &'a serde_json::Value)>,
+  removed: Vec<&'a serde_json::Value>,
+}
+
+fn manifest_delta<'a>(previous: &'a serde_json::Value, current: &'a serde_json::Value) -> ManifestDelta<'a> {
+  let entries = |manifest: &'a serde_json::Value| -> BTreeMap<&'a str, &'a serde_json::Value> {
+    manifest["files"].as_array()
+      .map(|files| files.iter().filter_map(|f| Some((f["path"].as_str()?, f))).collect())
+      .unwrap_or_default()
+  };
+  let content = |manifest: &serde_json::Value, entry: &'a serde_json::Value| {
+    (entry["hash"].as_str(), entry["size"].as_u64(), HashAlgorithm::for_entry(HashAlgorithm::for_manifest(manifest), entry))
+  };
+  let old = entries(previous);
+  let new = entries(current);
+
+  let mut delta = ManifestDelta { added: Vec::new(), changed: Vec::new(), removed: Vec::new() };
+  for (path, entry) in &new {
+    match old.get(path) {
+      None => delta.added.push(entry),
+      Some(old_entry) if content(previous, old_entry) != content(current, entry) => delta.changed.push((old_entry, entry)),
+      Some(_) => {}
+    }
+  }
+  delta.removed = old.iter().filter(|(path, _)| !new.contains_key(*path)).map(|(_, entry)| *entry).collect();
+  delta
+}
+
+/// Entries added or changed between two generated manifests, plus the paths
+/// that disappeared.
+fn build_manifest_diff(previous: &serde_json::Value, current: &serde_json::Value) -> serde_json::Value {
+  let delta = manifest_delta(previous, current);
+  let changed: Vec<&serde_json::Value> = delta.changed.iter().map(|(_, entry)| *entry).collect();
+  let removed: Vec<&serde_json::Value> = delta.removed.iter().map(|entry| &entry["path"]).collect();
+
+  json!({
+    "from": previous["version"],
+    "to": current["version"],
+    "algo": current["algo"],
+    "generated_at": current["generated_at"],
+    "added": delta.added,
+    "changed": changed,
+    "removed": removed
   })
 }
 
-/// Fetch `{LAUNCHER_ACTION_URL}/public/patch/launcher_info.ini`, parse the
-/// [LAUNCHER] section and compare with the local `launcher_version.ini`.
-/// Creates `launcher_version.ini` from the compiled version if it does not exist.
-#[tauri::command]
-async fn check_launcher_update(app: tauri::AppHandle) -> Result<LauncherUpdateInfo, String> {
-  let base_url = &*LAUNCHER_BASE_URL;
-  let info_url = format!("{}/public/patch/launcher_info.ini", base_url);
+#[derive(Debug, Serialize)]
+struct ManifestFileSize {
+  path: String,
+  size: u64,
+}
 
-  let client = reqwest::Client::new();
-  let response = client
-    .get(&info_url)
-    .send()
-    .await
-    .map_err(|e| {
-      let detailed_error = format_reqwest_error(&info_url, &e);
-      let error_msg = format!("Failed to fetch launcher_info.ini: {}", detailed_error);
-      let _ = log_error_to_file(&error_msg);
-      error_msg
-    })?;
+#[derive(Debug, Serialize)]
+struct ManifestChange {
+  path: String,
+  old_hash: String,
+  new_hash: String,
+  old_size: u64,
+  new_size: u64,
+  size_delta: i64,
+}
 
-  if !response.status().is_success() {
-    let error_msg = format!(
-      "Server returned HTTP status {} for launcher_info.ini (URL: {})",
-      response.status(), info_url
-    );
-    let _ = log_error_to_file(&error_msg);
-    return Err(error_msg);
-  }
+#[derive(Debug, Serialize)]
+struct ManifestComparison {
+  old_version: Option<String>,
+  new_version: Option<String>,
+  added: Vec<ManifestFileSize>,
+  removed: Vec<ManifestFileSize>,
+  changed: Vec<ManifestChange>,
+  /// Change in total game size from the old manifest to the new one.
+  size_delta: i64,
+  /// What a client on the old manifest downloads: added and changed files in full.
+  download_size: u64,
+}
 
-  let text = response
-    .text()
-    .await
-    .map_err(|e| {
-      let error_msg = format!("Failed to read launcher_info.ini: {} (URL: {})", e, info_url);
-      let _ = log_error_to_file(&error_msg);
-      error_msg
-    })?;
+/// Compares two manifests by path, see `manifest_delta`.
+fn compare_manifest_entries(old: &serde_json::Value, new: &serde_json::Value) -> ManifestComparison {
+  let delta = manifest_delta(old, new);
+  let path = |entry: &serde_json::Value| entry["path"].as_str().unwrap_or_default().to_string();
+  let size = |entry: &serde_json::Value| entry["size"].as_u64().unwrap_or(0);
+  let hash = |entry: &serde_json::Value| entry["hash"].as_str().unwrap_or_default().to_string();
+  let listed = |entries: &[&serde_json::Value]| -> Vec<ManifestFileSize> {
+    entries.iter().map(|entry| ManifestFileSize { path: path(entry), size: size(entry) }).collect()
+  };
+  let changed: Vec<ManifestChange> = delta.changed.iter()
+    .map(|(old_entry, entry)| ManifestChange {
+      path: path(entry),
+      old_hash: hash(old_entry),
+      new_hash: hash(entry),
+      old_size: size(old_entry),
+      new_size: size(entry),
+      size_delta: size(entry) as i64 - size(old_entry) as i64,
+    })
+    .collect();
+  let added = listed(&delta.added);
+  let removed = listed(&delta.removed);
+
+  ManifestComparison {
+    old_version: version_string(&old["version"]),
+    new_version: version_string(&new["version"]),
+    size_delta: added.iter().map(|f| f.size as i64).sum::<i64>()
+      - removed.iter().map(|f| f.size as i64).sum::<i64>()
+      + changed.iter().map(|f| f.size_delta).sum::<i64>(),
+    download_size: added.iter().map(|f| f.size).sum::<u64>() + changed.iter().map(|f| f.new_size).sum::<u64>(),
+    added,
+    removed,
+    changed,
+  }
+}
 
-  let ini = Ini::load_from_str(&text)
-    .map_err(|e| {
-      let error_msg = format!("Failed to parse launcher_info.ini: {} (URL: {})", e, info_url);
-      let _ = log_error_to_file(&error_msg);
-      error_msg
-    })?;
+/// Loads a manifest from an http(s) URL or a local file, gzipped or not.
+/// Remote manifests are checked against `MANIFEST_PUBKEY` like the update's.
+async fn load_manifest_source(source: &str) -> Result<serde_json::Value, String> {
+  if source.starts_with("http://") || source.starts_with("https://") {
+    let url = Url::parse(source).map_err(|e| format!("Invalid manifest URL {}: {}", source, e))?;
+    return fetch_manifest_json(&url).await;
+  }
+  let bytes = fs::read(source).map_err(|e| format!("Failed to read {}: {}", source, e))?;
+  let text = decode_manifest_body(&bytes, None)?;
+  serde_json::from_str(&text).map_err(|e| format!("Failed to parse {}: {}", source, e))
+}
 
-  let section = ini
-    .section(Some("LAUNCHER"))
-    .ok_or_else(|| {
-      let error_msg = "Missing [LAUNCHER] section in launcher_info.ini (URL: {})".to_string();
-      let _ = log_error_to_file(&error_msg);
-      error_msg
-    })?;
+/// Lists what changed between two hash files, each given as a local path or
+/// a URL, with the download size a client on the old one would need.
+#[tauri::command]
+async fn compare_manifests(old_source: String, new_source: String) -> Result<ManifestComparison, String> {
+  let old = load_manifest_source(&old_source).await?;
+  let new = load_manifest_source(&new_source).await?;
+  Ok(compare_manifest_entries(&old, &new))
+}
 
-  // Platform-specific version and binary keys.
-  // New format:
-  //   win_version / linux_version
-  //   win_bin_url / linux_bin_url
-  //   linux_bridge_url  (Linux only)
-  //   autoupdater_url   (shared)
-  //
-  // Falls back to the legacy `version` / `bin_url` keys so existing
-  // servers that haven't added the new keys keep working.
-  #[cfg(target_os = "windows")]
-  let (version_key, bin_key) = ("win_version", "win_bin_url");
-  #[cfg(not(target_os = "windows"))]
-  let (version_key, bin_key) = ("linux_version", "linux_bin_url");
+/// Records that the game files now match the last loaded manifest, after
+/// deleting the files its diff removed.
+async fn mark_manifest_applied() {
+  let removed = std::mem::take(&mut *PENDING_REMOVALS.write().unwrap());
+  if !removed.is_empty() {
+    if let Ok(game_path) = get_game_files_path() {
+      let mut deleted = 0;
+      for path in removed.iter().filter(|p| is_safe_path(p)) {
+        let target = game_path.join(path);
+        if !target.is_file() {
+          continue;
+        }
+        if let Err(e) = backup_before_overwrite(&target, path).await {
+          eprintln!("Failed to back up {}: {}", path, e);
+        }
+        match fs::remove_file(&target) {
+          Ok(()) => deleted += 1,
+          Err(e) => eprintln!("Failed to remove {}: {}", path, e),
+        }
+      }
+      flush_backup_manifest().await;
+      invalidate_hash_cache_entries(&removed);
+      println!("Removed {} file(s) dropped from the manifest", deleted);
+    }
+  }
 
-  let server_version = section
-    .get(version_key)
-    .or_else(|| section.get("version"))
-    .ok_or_else(|| {
-      let error_msg = format!("Missing '{}' (or 'version') key in launcher_info.ini (URL: {})", version_key, info_url);
-      let _ = log_error_to_file(&error_msg);
-      error_msg
-    })?
-    .to_string();
+  if let Some(manifest) = PENDING_APPLIED_MANIFEST.read().unwrap().clone() {
+    if let Err(e) = save_applied_manifest(&manifest) {
+      eprintln!("Failed to save the applied manifest: {}", e);
+    }
+  }
 
-  let bin_url = section
-    .get(bin_key)
-    .or_else(|| section.get("bin_url"))
-    .ok_or_else(|| {
-      let error_msg = format!("Missing '{}' (or 'bin_url') key in launcher_info.ini (URL: {})", bin_key, info_url);
-      let _ = log_error_to_file(&error_msg);
-      error_msg
-    })?
-    .to_string();
+  let Some(version) = CURRENT_MANIFEST_VERSION.read().unwrap().clone() else { return };
+  if get_applied_manifest_version().as_deref() == Some(version.as_str()) {
+    return;
+  }
+  match save_launcher_setting("applied_manifest_version", &version) {
+    Ok(()) => println!("Game files are at manifest version {}", version),
+    Err(e) => eprintln!("Failed to save applied manifest version: {}", e),
+  }
+}
 
-  let autoupdater_url = section
-    .get("autoupdater_url")
-    .unwrap_or("")
-    .to_string();
+// ─── Manifest signing ────────────────────────────────────────────────────────
+
+/// Serializes `value` with object keys sorted at every level, so signer and
+/// verifier agree on the bytes regardless of how the file was formatted.
+fn canonical_json(value: &serde_json::Value) -> String {
+  fn sorted(value: &serde_json::Value) -> serde_json::Value {
+    match value {
+      serde_json::Value::Object(map) => {
+        let mut keys: Vec<&String> = map.keys().collect();
+        keys.sort();
+        let mut out = serde_json::Map::new();
+        for key in keys {
+          out.insert(key.clone(), sorted(&map[key]));
+        }
+        serde_json::Value::Object(out)
+      }
+      serde_json::Value::Array(items) => serde_json::Value::Array(items.iter().map(sorted).collect()),
+      other => other.clone(),
+    }
+  }
+  sorted(value).to_string()
+}
 
-  // Linux bridge binary URL — empty string on Windows (unused)
-  #[cfg(not(target_os = "windows"))]
-  let bridge_url = section.get("linux_bridge_url").unwrap_or("").to_string();
-  #[cfg(target_os = "windows")]
-  let bridge_url = String::new();
+/// The bytes a manifest signature covers: the canonical JSON of the manifest
+/// without its `signature` field.
+fn manifest_signing_payload(manifest: &serde_json::Value) -> String {
+  let mut unsigned = manifest.clone();
+  if let Some(map) = unsigned.as_object_mut() {
+    map.remove("signature");
+  }
+  canonical_json(&unsigned)
+}
 
-  // Use the compiled package version as fallback when creating the local file
-  let compiled_version = app.package_info().version.to_string();
-  let local_version = read_or_create_launcher_version(&compiled_version)?;
+fn decode_key_bytes(encoded: &str, what: &str) -> Result<[u8; 32], String> {
+  use base64::Engine;
+  let bytes = base64::engine::general_purpose::STANDARD.decode(encoded.trim())
+    .map_err(|e| format!("Invalid {}: {}", what, e))?;
+  bytes.try_into().map_err(|_| format!("Invalid {}: expected 32 bytes", what))
+}
 
-  let update_available = is_newer_version(&server_version, &local_version);
+/// Loads an ed25519 private key stored as a base64-encoded 32-byte seed.
+fn load_signing_key(path: &Path) -> Result<ed25519_dalek::SigningKey, String> {
+  let encoded = fs::read_to_string(path)
+    .map_err(|e| format!("Failed to read signing key {}: {}", path.display(), e))?;
+  Ok(ed25519_dalek::SigningKey::from_bytes(&decode_key_bytes(&encoded, "signing key")?))
+}
 
-  Ok(LauncherUpdateInfo {
-    update_available,
-    current_version: local_version,
-    new_version: server_version,
-    bin_url,
-    autoupdater_url,
-    bridge_url,
-  })
+/// Writes `manifest`, signed when a key is given, and returns the text written.
+fn write_manifest_file(
+  path: &Path,
+  mut manifest: serde_json::Value,
+  key: Option<&ed25519_dalek::SigningKey>,
+  compact: bool,
+) -> Result<String, String> {
+  if let Some(key) = key {
+    sign_manifest(&mut manifest, key)?;
+  }
+  let json = if compact {
+    serde_json::to_string(&manifest)
+  } else {
+    serde_json::to_string_pretty(&manifest)
+  }.map_err(|e| e.to_string())?;
+  let mut file = File::create(path).map_err(|e| e.to_string())?;
+  file.write_all(json.as_bytes()).map_err(|e| e.to_string())?;
+  Ok(json)
 }
 
-/// Fetch `launcher_info.ini` and download `autoupdater.exe` next to the launcher
-/// if it isn't already present. Called silently in the background at startup.
-async fn ensure_autoupdater() -> Result<(), String> {
-  let exe_path = std::env::current_exe()
-    .map_err(|e| {
-      let error_msg = format!("Failed to get exe path: {}", e);
-      let _ = log_error_to_file(&error_msg);
-      error_msg
-    })?;
-  let exe_dir = exe_path.parent().ok_or_else(|| {
-    let error_msg = "Failed to get exe directory".to_string();
-    let _ = log_error_to_file(&error_msg);
-    error_msg
-  })?;
-  let autoupdater = exe_dir.join("autoupdater.exe");
+/// Adds a base64 `signature` field to `manifest`.
+fn sign_manifest(manifest: &mut serde_json::Value, key: &ed25519_dalek::SigningKey) -> Result<(), String> {
+  use base64::Engine;
+  use ed25519_dalek::Signer;
+  let signature = key.sign(manifest_signing_payload(manifest).as_bytes());
+  manifest.as_object_mut()
+    .ok_or("Manifest is not a JSON object")?
+    .insert("signature".to_string(), json!(base64::engine::general_purpose::STANDARD.encode(signature.to_bytes())));
+  Ok(())
+}
 
-  if autoupdater.exists() {
-    info!("ensure_autoupdater: already present, skipping download.");
+/// Checks the manifest against `MANIFEST_PUBKEY`. Without a configured key
+/// every manifest is accepted, signed or not.
+fn verify_manifest_signature(manifest: &serde_json::Value) -> Result<(), String> {
+  use base64::Engine;
+  use ed25519_dalek::Verifier;
+  let Some(pubkey) = get_optional_config_value("MANIFEST_PUBKEY") else {
     return Ok(());
-  }
+  };
+  let key = ed25519_dalek::VerifyingKey::from_bytes(&decode_key_bytes(&pubkey, "MANIFEST_PUBKEY")?)
+    .map_err(|e| format!("Invalid MANIFEST_PUBKEY: {}", e))?;
+  let encoded = manifest["signature"].as_str().ok_or("Hash file is not signed")?;
+  let signature = base64::engine::general_purpose::STANDARD.decode(encoded)
+    .map_err(|e| format!("Malformed hash file signature: {}", e))?;
+  let signature = ed25519_dalek::Signature::from_slice(&signature)
+    .map_err(|e| format!("Malformed hash file signature: {}", e))?;
+  key.verify(manifest_signing_payload(manifest).as_bytes(), &signature)
+    .map_err(|_| "Hash file signature does not match MANIFEST_PUBKEY".to_string())
+}
 
-  let base_url = &*LAUNCHER_BASE_URL;
-  let info_url = format!("{}/public/patch/launcher_info.ini", base_url);
+#[derive(Debug, Serialize)]
+struct ManifestKeypair {
+  private_key: String,
+  public_key: String,
+}
 
-  let client = reqwest::Client::new();
-  let text = client
-    .get(&info_url)
-    .send()
-    .await
-    .map_err(|e| {
-      let detailed_error = format_reqwest_error(&info_url, &e);
-      let error_msg = format!("Failed to fetch launcher_info.ini for autoupdater: {}", detailed_error);
-      let _ = log_error_to_file(&error_msg);
-      error_msg
-    })?
-    .text()
-    .await
-    .map_err(|e| {
-      let error_msg = format!("Failed to read launcher_info.ini for autoupdater: {} (URL: {})", e, info_url);
-      let _ = log_error_to_file(&error_msg);
-      error_msg
-    })?;
+/// Creates a key pair for `generate_hash_file`'s `signing_key`. The private
+/// key goes in a file on the machine that builds manifests; the public key
+/// is what clients put in `MANIFEST_PUBKEY`.
+#[tauri::command]
+fn generate_manifest_keypair() -> Result<ManifestKeypair, String> {
+  use base64::Engine;
+  let key = ed25519_dalek::SigningKey::from_bytes(&rand::random::<[u8; 32]>());
+  Ok(ManifestKeypair {
+    private_key: base64::engine::general_purpose::STANDARD.encode(key.to_bytes()),
+    public_key: base64::engine::general_purpose::STANDARD.encode(key.verifying_key().to_bytes()),
+  })
+}
 
-  let ini = Ini::load_from_str(&text)
-    .map_err(|e| {
-      let error_msg = format!("Failed to parse launcher_info.ini for autoupdater: {} (URL: {})", e, info_url);
-      let _ = log_error_to_file(&error_msg);
-      error_msg
-    })?;
+// ─── Cancellation ────────────────────────────────────────────────────────────
 
-  let autoupdater_url = ini
-    .section(Some("LAUNCHER"))
-    .and_then(|s| s.get("autoupdater_url"))
-    .unwrap_or("")
-    .to_string();
+const UPDATE_CANCELLED: &str = "Cancelled";
 
-  if autoupdater_url.is_empty() {
-    let error_msg = "launcher_info.ini has no autoupdater_url — skipping download.".to_string();
-    let _ = log_error_to_file(&error_msg);
-    return Err(error_msg);
+/// A cancel request for one launcher operation.
+#[derive(Default)]
+struct CancellationToken(AtomicBool);
+
+impl CancellationToken {
+  fn cancel(&self) {
+    self.0.store(true, Ordering::SeqCst);
   }
 
-  info!("ensure_autoupdater: downloading from {}", autoupdater_url);
+  fn is_cancelled(&self) -> bool {
+    self.0.load(Ordering::SeqCst)
+  }
+}
 
-  let resp = client
-    .get(&autoupdater_url)
-    .send()
-    .await
-    .map_err(|e| {
-      let detailed_error = format_reqwest_error(&autoupdater_url, &e);
-      let error_msg = format!("Failed to download autoupdater.exe: {}", detailed_error);
-      let _ = log_error_to_file(&error_msg);
-      error_msg
-    })?;
+lazy_static! {
+  /// Token of the operation holding the lock. `begin_operation` and the
+  /// guard's drop swap in a fresh one, so a cancel only ever reaches the
+  /// operation it was aimed at.
+  static ref OPERATION_CANCELLATION: RwLock<CancellationToken> = RwLock::new(CancellationToken::default());
+}
 
-  if !resp.status().is_success() {
-    let error_msg = format!(
-      "autoupdater.exe download returned HTTP status {} (URL: {})",
-      resp.status(), autoupdater_url
-    );
-    let _ = log_error_to_file(&error_msg);
-    return Err(error_msg);
+fn is_update_cancelled() -> bool {
+  OPERATION_CANCELLATION.read().unwrap_or_else(|e| e.into_inner()).is_cancelled()
+}
+
+/// Cancels the running operation if it is one of `operations`.
+fn cancel_operation(state: &OperationState, operations: &[LauncherOperation]) -> bool {
+  let current = state.current.lock().unwrap_or_else(|e| e.into_inner());
+  if !operations.contains(&current) {
+    return false;
   }
+  OPERATION_CANCELLATION.read().unwrap_or_else(|e| e.into_inner()).cancel();
+  true
+}
 
-  let bytes = resp
-    .bytes()
-    .await
-    .map_err(|e| {
-      let error_msg = format!("Failed to read autoupdater.exe bytes: {} (URL: {})", e, autoupdater_url);
-      let _ = log_error_to_file(&error_msg);
-      error_msg
-    })?;
+/// Stops the running download or update check, including any file hash in
+/// progress. An interrupted download can be resumed later.
+#[tauri::command]
+fn cancel_update(state: tauri::State<'_, OperationState>) -> Result<(), String> {
+  if cancel_operation(&state, &[LauncherOperation::Updating, LauncherOperation::Verifying]) {
+    println!("Cancellation requested");
+  }
+  Ok(())
+}
 
-  tokio::fs::write(&autoupdater, &bytes)
-    .await
-    .map_err(|e| {
-      let error_msg = format!("Failed to save autoupdater.exe: {}", e);
-      let _ = log_error_to_file(&error_msg);
-      error_msg
-    })?;
+/// Set by `cancel_hash_generation`; cleared when `generate_hash_file` or
+/// `get_local_manifest` starts.
+static HASH_GENERATION_CANCEL_REQUESTED: AtomicBool = AtomicBool::new(false);
 
-  info!(
-    "ensure_autoupdater: saved {} bytes to {}",
-    bytes.len(),
-    autoupdater.display()
-  );
+fn is_hash_generation_cancelled() -> bool {
+  HASH_GENERATION_CANCEL_REQUESTED.load(Ordering::SeqCst)
+}
+
+/// Stops a running `generate_hash_file`. No manifest is written; the command
+/// fails with `Cancelled` after emitting `hash_file_cancelled`.
+#[tauri::command]
+fn cancel_hash_generation() -> Result<(), String> {
+  println!("Hash generation cancellation requested");
+  HASH_GENERATION_CANCEL_REQUESTED.store(true, Ordering::SeqCst);
   Ok(())
 }
 
-/// Download the new launcher exe, then hand off to autoupdater.exe and exit.
-///
-/// Emits `"launcher_update_progress"` events while downloading:
-/// `{ progress: f64 (0-100), downloaded: u64, total: u64 }`
+/// Stops a running file check. Whatever was verified before the stop is kept
+/// in the cache, and the check fails with `Cancelled` after emitting
+/// `file_check_cancelled`. Does nothing when no check is running.
 #[tauri::command]
-async fn apply_launcher_update(
-  bin_url: String,
-  autoupdater_url: String,
-  new_version: String,
-  #[allow(unused_variables)]
-  bridge_url: String,
-  app: tauri::AppHandle,
-) -> Result<(), String> {
-  let client = reqwest::Client::new();
+fn cancel_file_check(state: tauri::State<'_, OperationState>) -> Result<(), String> {
+  if cancel_operation(&state, &[LauncherOperation::Verifying]) {
+    println!("File check cancellation requested");
+  }
+  Ok(())
+}
 
-  // ── 1. Resolve path to the launcher exe (this is the file we'll replace) ─
-  let exe_path = std::env::current_exe()
-    .map_err(|e| {
-      let error_msg = format!("Failed to resolve launcher exe path: {}", e);
-      let _ = log_error_to_file(&error_msg);
-      error_msg
-    })?;
-  let exe_dir = exe_path
-    .parent()
-    .ok_or_else(|| {
-      let error_msg = "Failed to get launcher directory".to_string();
-      let _ = log_error_to_file(&error_msg);
-      error_msg
-    })?;
+// ─── Taskbar progress ────────────────────────────────────────────────────────
 
-  // ── 2. Ensure autoupdater.exe is present; download it if needed ──────────
-  let autoupdater = exe_dir.join("autoupdater.exe");
+/// State shown on the launcher's taskbar button.
+#[derive(Clone, Copy)]
+#[cfg_attr(not(windows), allow(dead_code))]
+enum TaskbarProgress {
+  Normal { completed: u64, total: u64 },
+  Error,
+  Clear,
+}
 
-  info!("apply_launcher_update: autoupdater path = {}", autoupdater.display());
-  info!("apply_launcher_update: autoupdater_url  = '{}'", autoupdater_url);
-  info!("apply_launcher_update: bin_url          = '{}'", bin_url);
-  info!("apply_launcher_update: new_version      = '{}'", new_version);
+/// Channel to the taskbar thread, started on first use, and whether the
+/// current operation shows progress at all. `enabled` is re-read from the
+/// settings after every `Clear` so a toggle applies to the next operation.
+#[cfg(windows)]
+struct TaskbarIndicator {
+  sender: Option<std::sync::mpsc::Sender<(isize, TaskbarProgress)>>,
+  enabled: Option<bool>,
+}
 
-  if !autoupdater.exists() {
-    if autoupdater_url.is_empty() {
-      let error_msg = format!(
-        "autoupdater.exe not found at '{}'. Rebuild the launcher so autoupdater_url is passed, or place autoupdater.exe manually.",
-        autoupdater.display()
-      );
-      let _ = log_error_to_file(&error_msg);
-      return Err(error_msg);
-    }
+#[cfg(windows)]
+lazy_static! {
+  static ref TASKBAR_INDICATOR: std::sync::Mutex<TaskbarIndicator> = std::sync::Mutex::new(TaskbarIndicator {
+    sender: None,
+    enabled: None,
+  });
+}
 
-    info!("autoupdater.exe not found — downloading from {}", autoupdater_url);
-    let au_response = client
-      .get(&autoupdater_url)
-      .send()
-      .await
-      .map_err(|e| {
-        let detailed_error = format_reqwest_error(&autoupdater_url, &e);
-        let error_msg = format!("Failed to download autoupdater.exe: {}", detailed_error);
-        let _ = log_error_to_file(&error_msg);
-        error_msg
-      })?;
+/// Mirrors download and file check progress on the Windows taskbar button.
+/// Disabled with `taskbar_progress = false` in the `[launcher]` section of
+/// config.ini; does nothing on other platforms.
+#[cfg(windows)]
+fn set_taskbar_progress<R: tauri::Runtime>(window: &tauri::Window<R>, state: TaskbarProgress) {
+  let mut indicator = TASKBAR_INDICATOR.lock().unwrap();
+  let enabled = *indicator.enabled.get_or_insert_with(|| {
+    get_launcher_setting("taskbar_progress")
+      .map(|v| !(v.eq_ignore_ascii_case("false") || v == "0"))
+      .unwrap_or(true)
+  });
+  if matches!(state, TaskbarProgress::Clear) {
+    indicator.enabled = None;
+  }
+  if !enabled {
+    return;
+  }
 
-    if !au_response.status().is_success() {
-      let error_msg = format!(
-        "autoupdater.exe download failed with HTTP status: {} (URL: {})",
-        au_response.status(), autoupdater_url
-      );
-      let _ = log_error_to_file(&error_msg);
-      return Err(error_msg);
+  let hwnd = match window.hwnd() {
+    Ok(hwnd) => hwnd.0,
+    Err(_) => return,
+  };
+  let sender = indicator.sender.get_or_insert_with(spawn_taskbar_thread);
+  if sender.send((hwnd, state)).is_err() {
+    // The thread gave up (no taskbar available); don't keep trying.
+    indicator.sender = None;
+    indicator.enabled = Some(false);
+  }
+}
+
+#[cfg(not(windows))]
+fn set_taskbar_progress<R: tauri::Runtime>(_window: &tauri::Window<R>, _state: TaskbarProgress) {}
+
+/// ITaskbarList3 is a COM object tied to the apartment it was created in, so
+/// it lives on its own thread and receives updates over a channel.
+#[cfg(windows)]
+fn spawn_taskbar_thread() -> std::sync::mpsc::Sender<(isize, TaskbarProgress)> {
+  use winapi::shared::windef::HWND;
+  use winapi::shared::winerror::SUCCEEDED;
+  use winapi::shared::wtypesbase::CLSCTX_INPROC_SERVER;
+  use winapi::um::combaseapi::{CoCreateInstance, CoInitializeEx, CoUninitialize};
+  use winapi::um::objbase::COINIT_APARTMENTTHREADED;
+  use winapi::um::shobjidl_core::{CLSID_TaskbarList, ITaskbarList3, TBPF_ERROR, TBPF_NOPROGRESS, TBPF_NORMAL};
+  use winapi::Interface;
+
+  let (tx, rx) = std::sync::mpsc::channel::<(isize, TaskbarProgress)>();
+  std::thread::spawn(move || unsafe {
+    if !SUCCEEDED(CoInitializeEx(std::ptr::null_mut(), COINIT_APARTMENTTHREADED)) {
+      return;
+    }
+    let mut taskbar: *mut ITaskbarList3 = std::ptr::null_mut();
+    let hr = CoCreateInstance(
+      &CLSID_TaskbarList,
+      std::ptr::null_mut(),
+      CLSCTX_INPROC_SERVER,
+      &ITaskbarList3::uuidof(),
+      &mut taskbar as *mut *mut ITaskbarList3 as *mut _,
+    );
+    if !SUCCEEDED(hr) || taskbar.is_null() || !SUCCEEDED((*taskbar).HrInit()) {
+      eprintln!("Taskbar progress unavailable (HRESULT {:#x})", hr);
+      CoUninitialize();
+      return;
     }
 
-    let au_bytes = au_response
-      .bytes()
-      .await
-      .map_err(|e| {
-        let error_msg = format!("Failed to read autoupdater.exe response: {} (URL: {})", e, autoupdater_url);
-        let _ = log_error_to_file(&error_msg);
-        error_msg
-      })?;
+    let mut last_state = None;
+    while let Ok((hwnd, state)) = rx.recv() {
+      let hwnd = hwnd as HWND;
+      match state {
+        TaskbarProgress::Normal { completed, total } => {
+          // The error state sticks until the operation is cleared.
+          if last_state != Some(TBPF_ERROR) {
+            if last_state != Some(TBPF_NORMAL) {
+              (*taskbar).SetProgressState(hwnd, TBPF_NORMAL);
+              last_state = Some(TBPF_NORMAL);
+            }
+            (*taskbar).SetProgressValue(hwnd, completed.min(total), total.max(1));
+          }
+        }
+        TaskbarProgress::Error => {
+          (*taskbar).SetProgressState(hwnd, TBPF_ERROR);
+          last_state = Some(TBPF_ERROR);
+        }
+        TaskbarProgress::Clear => {
+          (*taskbar).SetProgressState(hwnd, TBPF_NOPROGRESS);
+          last_state = None;
+        }
+      }
+    }
+    (*taskbar).Release();
+    CoUninitialize();
+  });
+  tx
+}
+
+// ─── Playtime ────────────────────────────────────────────────────────────────
+
+/// Sessions kept per account; older ones only count towards the total.
+const MAX_PLAYTIME_SESSIONS: usize = 200;
+/// How often a running session's end time is saved, bounding what a launcher
+/// crash loses.
+const PLAYTIME_HEARTBEAT_SECS: u64 = 60;
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+struct PlaytimeSession {
+  /// Unix seconds.
+  started_at: u64,
+  ended_at: u64,
+  duration_seconds: u64,
+  /// Closed out at startup after the launcher went away mid-session; the end
+  /// time is the last heartbeat.
+  #[serde(default)]
+  interrupted: bool,
+}
+
+impl PlaytimeSession {
+  fn new(started_at: u64, ended_at: u64, interrupted: bool) -> Self {
+    PlaytimeSession { started_at, ended_at, duration_seconds: ended_at.saturating_sub(started_at), interrupted }
+  }
+}
+
+#[derive(Debug, Serialize, Deserialize, Default, Clone)]
+struct AccountPlaytime {
+  total_seconds: u64,
+  /// Oldest first.
+  sessions: Vec<PlaytimeSession>,
+}
+
+/// A session that has started but not been recorded yet.
+#[derive(Debug, Serialize, Deserialize)]
+struct OpenPlaytimeSession {
+  /// The launcher's game session id; only unique within one launcher run.
+  session_id: u64,
+  account: String,
+  started_at: u64,
+  last_seen: u64,
+}
+
+/// playtime.json: per-account totals keyed by `user_no`.
+#[derive(Debug, Serialize, Deserialize, Default)]
+struct PlaytimeData {
+  accounts: HashMap<String, AccountPlaytime>,
+  #[serde(default)]
+  open_sessions: Vec<OpenPlaytimeSession>,
+}
 
-    tokio::fs::write(&autoupdater, &au_bytes)
-      .await
-      .map_err(|e| {
-        let error_msg = format!("Failed to save autoupdater.exe: {}", e);
-        let _ = log_error_to_file(&error_msg);
-        error_msg
-      })?;
+impl PlaytimeData {
+  fn take_open_session(&mut self, session_id: u64) -> Option<OpenPlaytimeSession> {
+    let index = self.open_sessions.iter().position(|s| s.session_id == session_id)?;
+    Some(self.open_sessions.remove(index))
+  }
 
-    info!("autoupdater.exe downloaded successfully");
+  fn close_session(&mut self, session: OpenPlaytimeSession, ended_at: u64, interrupted: bool) {
+    let account = self.accounts.entry(session.account).or_default();
+    let session = PlaytimeSession::new(session.started_at, ended_at, interrupted);
+    account.total_seconds += session.duration_seconds;
+    account.sessions.push(session);
+    let excess = account.sessions.len().saturating_sub(MAX_PLAYTIME_SESSIONS);
+    account.sessions.drain(..excess);
   }
+}
 
-  // ── 3. Download the new launcher exe to %TEMP%\launcher_update\ ──────────
-  let response = client
-    .get(&bin_url)
-    .send()
-    .await
-    .map_err(|e| {
-      let detailed_error = format_reqwest_error(&bin_url, &e);
-      let error_msg = format!("Failed to start launcher download: {}", detailed_error);
-      let _ = log_error_to_file(&error_msg);
-      error_msg
-    })?;
+#[derive(Debug, Serialize)]
+struct PlaytimeStats {
+  total_seconds: u64,
+  last_session: Option<PlaytimeSession>,
+  /// Newest first.
+  sessions: Vec<PlaytimeSession>,
+}
 
-  if !response.status().is_success() {
-    let error_msg = format!(
-      "Launcher download failed with HTTP status: {} (URL: {})",
-      response.status(), bin_url
-    );
-    let _ = log_error_to_file(&error_msg);
-    return Err(error_msg);
+impl PlaytimeStats {
+  fn from_account(account: &AccountPlaytime, limit: usize) -> Self {
+    PlaytimeStats {
+      total_seconds: account.total_seconds,
+      last_session: account.sessions.last().cloned(),
+      sessions: account.sessions.iter().rev().take(limit).cloned().collect(),
+    }
   }
+}
 
-  let total_size = response.content_length().unwrap_or(0);
+static PLAYTIME_LOCK: std::sync::Mutex<()> = std::sync::Mutex::new(());
 
-  let temp_dir = std::env::temp_dir().join("launcher_update");
-  fs::create_dir_all(&temp_dir)
-    .map_err(|e| {
-      let error_msg = format!("Failed to create temp directory: {}", e);
-      let _ = log_error_to_file(&error_msg);
-      error_msg
-    })?;
+fn get_playtime_path() -> Result<PathBuf, String> {
+  get_app_data_path("playtime.json")
+}
 
-  let filename = bin_url
-    .split('/')
-    .last()
-    .filter(|s| !s.is_empty())
-    .unwrap_or("TeraLauncher_new.exe");
+fn unix_now() -> u64 {
+  SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap_or_default().as_secs()
+}
 
-  let new_launcher_path = temp_dir.join(filename);
+/// Reads playtime.json; a corrupt file is logged and starts an empty record.
+fn load_playtime(path: &Path) -> Result<PlaytimeData, String> {
+  match fs::read_to_string(path) {
+    Ok(contents) => Ok(serde_json::from_str(&contents).unwrap_or_else(|e| {
+      error!("Failed to parse {}, starting an empty record: {}", path.display(), e);
+      PlaytimeData::default()
+    })),
+    Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(PlaytimeData::default()),
+    Err(e) => Err(format!("Failed to read {}: {}", path.display(), e)),
+  }
+}
 
-  let mut dest = tokio::fs::File::create(&new_launcher_path)
-    .await
-    .map_err(|e| {
-      let error_msg = format!("Failed to create temp launcher file: {}", e);
-      let _ = log_error_to_file(&error_msg);
-      error_msg
-    })?;
+/// Loads playtime.json, applies `update` and writes it back, serialized so a
+/// heartbeat can't overwrite a session being closed.
+fn update_playtime<T>(update: impl FnOnce(&mut PlaytimeData) -> T) -> Result<T, String> {
+  let _guard = PLAYTIME_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+  let path = get_playtime_path()?;
+  let mut data = load_playtime(&path)?;
+  let result = update(&mut data);
+  if let Some(parent) = path.parent() {
+    fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+  }
+  let serialized = serde_json::to_string_pretty(&data).map_err(|e| e.to_string())?;
+  fs::write(&path, serialized).map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+  Ok(result)
+}
 
-  let mut downloaded: u64 = 0;
-  let mut stream = response.bytes_stream();
+/// Records the start of game session `session_id` for `account`.
+fn start_playtime_session(session_id: u64, account: &str) -> Result<(), String> {
+  let now = unix_now();
+  update_playtime(|data| {
+    if let Some(stale) = data.take_open_session(session_id) {
+      let last_seen = stale.last_seen;
+      data.close_session(stale, last_seen, true);
+    }
+    data.open_sessions.push(OpenPlaytimeSession { session_id, account: account.to_string(), started_at: now, last_seen: now });
+  })
+}
 
-  while let Some(chunk) = stream.next().await {
-    let chunk = chunk.map_err(|e| {
-      let error_msg = format!("Download stream error: {}", e);
-      let _ = log_error_to_file(&error_msg);
-      error_msg
-    })?;
-    dest
-      .write_all(&chunk)
-      .await
-      .map_err(|e| {
-        let error_msg = format!("Failed to write chunk: {}", e);
-        let _ = log_error_to_file(&error_msg);
-        error_msg
-      })?;
-    downloaded += chunk.len() as u64;
+fn touch_playtime_session(session_id: u64) -> Result<(), String> {
+  let now = unix_now();
+  update_playtime(|data| {
+    if let Some(session) = data.open_sessions.iter_mut().find(|s| s.session_id == session_id) {
+      session.last_seen = now;
+    }
+  })
+}
 
-    let progress = if total_size > 0 {
-      (downloaded as f64 / total_size as f64) * 100.0
-    } else {
-      0.0
-    };
+/// Closes the session and returns the account's updated stats.
+fn finish_playtime_session(session_id: u64) -> Result<Option<PlaytimeStats>, String> {
+  let now = unix_now();
+  update_playtime(|data| {
+    let session = data.take_open_session(session_id)?;
+    let account = session.account.clone();
+    data.close_session(session, now, false);
+    data.accounts.get(&account).map(|a| PlaytimeStats::from_account(a, 10))
+  })
+}
 
-    let _ = app.emit_all(
-      "launcher_update_progress",
-      json!({ "progress": progress, "downloaded": downloaded, "total": total_size }),
-    );
+/// Records the start of a session and saves its end time every
+/// `PLAYTIME_HEARTBEAT_SECS` until the returned task is aborted.
+fn start_playtime_tracking(session_id: u64, account: &str) -> tokio::task::JoinHandle<()> {
+  if let Err(e) = start_playtime_session(session_id, account) {
+    error!("Failed to record play session start: {}", e);
   }
+  tokio::task::spawn(async move {
+    let mut tick = tokio::time::interval(Duration::from_secs(PLAYTIME_HEARTBEAT_SECS));
+    tick.tick().await;
+    loop {
+      tick.tick().await;
+      if let Err(e) = touch_playtime_session(session_id) {
+        error!("Failed to update play session: {}", e);
+      }
+    }
+  })
+}
 
-  dest.flush().await.map_err(|e| {
-    let error_msg = format!("Failed to flush temp launcher file: {}", e);
-    let _ = log_error_to_file(&error_msg);
-    error_msg
-  })?;
-  drop(dest);
+/// Stops the heartbeat, records the session and emits `playtime_updated`.
+fn finish_playtime_tracking(app_handle: &tauri::AppHandle, session_id: u64, heartbeat: tokio::task::JoinHandle<()>) {
+  heartbeat.abort();
+  match finish_playtime_session(session_id) {
+    Ok(Some(stats)) => {
+      if let Err(e) = app_handle.emit_all("playtime_updated", &stats) {
+        error!("Failed to emit playtime_updated event: {:?}", e);
+      }
+    }
+    Ok(None) => {}
+    Err(e) => error!("Failed to record play session end: {}", e),
+  }
+}
 
-  // ── 4. Get current PID so autoupdater can wait for us to exit ─────────────
-  let launcher_pid = std::process::id();
+/// Run at startup: sessions still open mean the launcher exited while the
+/// game was running, so they are closed at their last heartbeat.
+fn close_interrupted_playtime_session() {
+  let closed = update_playtime(|data| {
+    let sessions = std::mem::take(&mut data.open_sessions);
+    let count = sessions.len();
+    for session in sessions {
+      let last_seen = session.last_seen;
+      data.close_session(session, last_seen, true);
+    }
+    count
+  });
+  match closed {
+    Ok(0) => {}
+    Ok(count) => println!("Closed {} interrupted play session(s)", count),
+    Err(e) => eprintln!("Failed to close interrupted play sessions: {}", e),
+  }
+}
 
-  // ── 4b. On Linux: download the updated launcher-bridge.exe if a URL was given ──
-  #[cfg(not(target_os = "windows"))]
-  if !bridge_url.is_empty() {
-    info!("apply_launcher_update: downloading updated launcher-bridge.exe from {}", bridge_url);
-    let bridge_resp = client
-      .get(&bridge_url)
-      .send()
-      .await
-      .map_err(|e| {
-        let detailed_error = format_reqwest_error(&bridge_url, &e);
-        let error_msg = format!("Failed to download launcher-bridge.exe: {}", detailed_error);
-        let _ = log_error_to_file(&error_msg);
-        error_msg
-      })?;
+/// Time played on the logged-in account, with its `limit` (default 10) most
+/// recent sessions.
+#[tauri::command]
+fn get_playtime_stats(limit: Option<usize>) -> Result<PlaytimeStats, String> {
+  let account = GLOBAL_AUTH_INFO.read().unwrap().user_no;
+  if account <= 0 {
+    return Err("Not logged in".to_string());
+  }
+  let account = account.to_string();
+  let data = load_playtime(&get_playtime_path()?)?;
+  let playtime = data.accounts.get(&account).cloned().unwrap_or_default();
+  Ok(PlaytimeStats::from_account(&playtime, limit.unwrap_or(10)))
+}
 
-    if !bridge_resp.status().is_success() {
-      return Err(format!("launcher-bridge.exe download failed with status: {}", bridge_resp.status()));
-    }
+// ─── Crash history ───────────────────────────────────────────────────────────
 
-    let bridge_bytes = bridge_resp
-      .bytes()
-      .await
-      .map_err(|e| format!("Failed to read launcher-bridge.exe bytes: {}", e))?;
+const MAX_CRASH_RECORDS: usize = 50;
+/// Sessions that fail within this many seconds count as failed starts, which
+/// usually point at damaged game files rather than an in-game crash.
+const FAILED_START_SECS: u64 = 30;
 
-    let bridge_path = exe_dir.join("launcher-bridge.exe");
-    tokio::fs::write(&bridge_path, &bridge_bytes)
-      .await
-      .map_err(|e| format!("Failed to save launcher-bridge.exe: {}", e))?;
+#[derive(Debug, Serialize, Deserialize, Clone)]
+struct CrashRecord {
+  /// Unix seconds.
+  occurred_at: u64,
+  exit_code: Option<i32>,
+  /// Name of a known Windows exception code, e.g. "access violation".
+  classification: Option<String>,
+  session_duration: u64,
+  failed_to_start: bool,
+  #[serde(default)]
+  details: String,
+  /// The last lines the game printed, when output capture is on.
+  #[serde(default, skip_serializing_if = "Vec::is_empty")]
+  output_tail: Vec<String>,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  launch_id: Option<String>,
+}
 
-    info!("apply_launcher_update: launcher-bridge.exe updated ({} bytes)", bridge_bytes.len());
+/// Names the NTSTATUS exception codes TERA typically dies with.
+fn classify_exit_code(exit_code: i32) -> Option<&'static str> {
+  match exit_code as u32 {
+    0xC0000005 => Some("access violation"),
+    0xC000001D => Some("illegal instruction"),
+    0xC0000094 => Some("integer division by zero"),
+    0xC00000FD => Some("stack overflow"),
+    0xC0000135 => Some("missing DLL"),
+    0xC0000142 => Some("DLL initialization failed"),
+    0xC0000374 => Some("heap corruption"),
+    0xC0000409 => Some("stack buffer overrun"),
+    0xC0000017 => Some("out of memory"),
+    0x80000003 => Some("breakpoint"),
+    _ => None,
   }
+}
 
-  // ── 5. Spawn autoupdater (hidden window) then exit the launcher ───────────
-  //  Args: <new_exe_path> <launcher_pid> <target_exe_path> <new_version>
-  let new_launcher_str = new_launcher_path
-    .to_str()
-    .ok_or("Invalid temp launcher path (non-UTF-8)")?;
-  let target_exe_str = exe_path
-    .to_str()
-    .ok_or("Invalid launcher exe path (non-UTF-8)")?;
+fn get_crash_history_path() -> Result<PathBuf, String> {
+  get_app_data_path("crash_history.json")
+}
 
-  #[cfg(target_os = "windows")]
-  {
-    use std::os::windows::process::CommandExt;
-    const CREATE_NO_WINDOW: u32 = 0x08000000;
-    std::process::Command::new(&autoupdater)
-      .arg(new_launcher_str)
-      .arg(launcher_pid.to_string())
-      .arg(target_exe_str)
-      .arg(&new_version)
-      .creation_flags(CREATE_NO_WINDOW)
-      .spawn()
-      .map_err(|e| format!("Failed to spawn autoupdater: {}", e))?;
-  }
-  #[cfg(not(target_os = "windows"))]
-  {
-    std::process::Command::new(&autoupdater)
-      .arg(new_launcher_str)
-      .arg(launcher_pid.to_string())
-      .arg(target_exe_str)
-      .arg(&new_version)
-      .spawn()
-      .map_err(|e| format!("Failed to spawn autoupdater: {}", e))?;
+fn load_crash_history() -> Result<Vec<CrashRecord>, String> {
+  let path = get_crash_history_path()?;
+  match fs::read_to_string(&path) {
+    Ok(contents) => serde_json::from_str(&contents).map_err(|e| format!("Failed to parse {}: {}", path.display(), e)),
+    Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Vec::new()),
+    Err(e) => Err(format!("Failed to read {}: {}", path.display(), e)),
   }
+}
 
-  app.exit(0);
-  Ok(())
+/// Records a crash of the game and emits `game_crashed`.
+fn report_crash(app_handle: &tauri::AppHandle, launch_id: Option<&str>, exit_code: Option<i32>, session_duration: u64, details: String, output_tail: Vec<String>) {
+  let crash = CrashRecord {
+    occurred_at: unix_now(),
+    exit_code,
+    classification: exit_code.and_then(classify_exit_code).map(str::to_string),
+    session_duration,
+    failed_to_start: session_duration < FAILED_START_SECS,
+    details,
+    output_tail,
+    launch_id: launch_id.map(str::to_string),
+  };
+  error!("Game crashed: {:?}", crash);
+  if let Err(e) = record_crash(&crash) {
+    error!("Failed to record crash: {}", e);
+  }
+  if let Err(e) = app_handle.emit_all("game_crashed", &crash) {
+    error!("Failed to emit game_crashed event: {:?}", e);
+  }
 }
 
-// ── Signup / Captcha commands ─────────────────────────────────────────────────
+fn record_crash(record: &CrashRecord) -> Result<(), String> {
+  let mut history = load_crash_history().unwrap_or_default();
+  history.push(record.clone());
+  let excess = history.len().saturating_sub(MAX_CRASH_RECORDS);
+  history.drain(..excess);
+  let path = get_crash_history_path()?;
+  if let Some(parent) = path.parent() {
+    fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+  }
+  let serialized = serde_json::to_string_pretty(&history).map_err(|e| e.to_string())?;
+  fs::write(&path, serialized).map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
 
-/// Fetches a new slider captcha from the server.
-/// Initializes a plain session client without calling the captcha endpoint.
-/// Used when captcha is disabled so that signup still has a valid session client.
+/// Recorded game crashes, newest first.
 #[tauri::command]
-async fn init_signup_session() -> Result<(), String> {
-  let client = Client::builder()
-    .cookie_store(true)
-    .build()
-    .map_err(|e| e.to_string())?;
+fn get_crash_history() -> Result<Vec<CrashRecord>, String> {
+  let mut history = load_crash_history()?;
+  history.reverse();
+  Ok(history)
+}
 
-  let mut client_guard = SIGNUP_SESSION_CLIENT.lock().await;
-  *client_guard = Some(client);
-  Ok(())
+const MAX_GAME_SESSION_RECORDS: usize = 10;
+
+/// A finished game session, for the UI to show after the fact.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+struct GameSessionRecord {
+  session_id: u64,
+  /// Missing in records written before launches had ids.
+  #[serde(default)]
+  launch_id: Option<String>,
+  /// The account's user_no.
+  account: String,
+  /// Unix seconds.
+  started_at: u64,
+  /// Unix seconds.
+  ended_at: u64,
+  duration: u64,
+  exit_code: Option<i32>,
+  classification: Option<String>,
+  crashed: bool,
+  launch_error: Option<String>,
 }
 
-/// Creates a fresh session client (with cookie jar) stored globally so that
-/// the subsequent verify_captcha and signup calls share the same session.
-#[tauri::command]
-async fn get_captcha() -> Result<String, String> {
-  let cookie_jar = Arc::new(Jar::default());
-  let client = Client::builder()
-    .cookie_store(true)
-    .cookie_provider(Arc::clone(&cookie_jar))
-    .build()
-    .map_err(|e| e.to_string())?;
+impl GameSessionRecord {
+  fn new(session_id: u64, launch_id: Option<String>, account: &str, started: Instant, exit_code: Option<i32>, crashed: bool, launch_error: Option<String>) -> Self {
+    let duration = started.elapsed().as_secs();
+    let ended_at = unix_now();
+    GameSessionRecord {
+      session_id,
+      launch_id,
+      account: account.to_string(),
+      started_at: ended_at.saturating_sub(duration),
+      ended_at,
+      duration,
+      exit_code,
+      classification: exit_code.and_then(classify_exit_code).map(str::to_string),
+      crashed,
+      launch_error,
+    }
+  }
+}
 
-  let base_url = &*LAUNCHER_BASE_URL;
-  let captcha_url = format!("{}/launcher/GetCaptcha", base_url);
+fn get_game_sessions_path() -> Result<PathBuf, String> {
+  get_app_data_path("game_sessions.json")
+}
 
-  let res = client
-    .get(&captcha_url)
-    .send()
-    .await
-    .map_err(|e| format!("Failed to get captcha: {}", e))?;
+fn load_game_sessions() -> Result<VecDeque<GameSessionRecord>, String> {
+  let path = get_game_sessions_path()?;
+  match fs::read_to_string(&path) {
+    Ok(contents) => serde_json::from_str(&contents).map_err(|e| format!("Failed to parse {}: {}", path.display(), e)),
+    Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(VecDeque::new()),
+    Err(e) => Err(format!("Failed to read {}: {}", path.display(), e)),
+  }
+}
 
-  if !res.status().is_success() {
-    return Err(format!("Captcha request failed with status: {}", res.status()));
+/// Adds a finished session to the history, dropping the oldest past
+/// `MAX_GAME_SESSION_RECORDS`, and saves it.
+async fn record_game_session(recent_sessions: &Mutex<VecDeque<GameSessionRecord>>, record: GameSessionRecord) {
+  let mut sessions = recent_sessions.lock().await;
+  sessions.push_back(record);
+  while sessions.len() > MAX_GAME_SESSION_RECORDS {
+    sessions.pop_front();
+  }
+  let result = get_game_sessions_path().and_then(|path| {
+    if let Some(parent) = path.parent() {
+      fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    let serialized = serde_json::to_string_pretty(&*sessions).map_err(|e| e.to_string())?;
+    fs::write(&path, serialized).map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+  });
+  if let Err(e) = result {
+    error!("Failed to save game session history: {}", e);
   }
+}
 
-  let body: serde_json::Value = res
-    .json()
-    .await
-    .map_err(|e| format!("Failed to parse captcha response: {}", e))?;
+/// The most recently finished game session, if any.
+#[tauri::command]
+async fn get_last_game_session(state: tauri::State<'_, GameState>) -> Result<Option<GameSessionRecord>, String> {
+  Ok(state.recent_sessions.lock().await.back().cloned())
+}
 
-  let mut client_guard = SIGNUP_SESSION_CLIENT.lock().await;
-  *client_guard = Some(client);
+/// The last finished game sessions, newest first.
+#[tauri::command]
+async fn get_game_session_history(state: tauri::State<'_, GameState>) -> Result<Vec<GameSessionRecord>, String> {
+  Ok(state.recent_sessions.lock().await.iter().rev().cloned().collect())
+}
 
-  serde_json::to_string(&body)
-    .map_err(|e| format!("Failed to serialize captcha: {}", e))
+// ─── Launcher operations ─────────────────────────────────────────────────────
+
+/// What the launcher is doing with the game files. Only one operation runs at
+/// a time, so a launch can't race a download and a repair can't touch files
+/// the game has open.
+#[derive(Debug, Serialize, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+enum LauncherOperation {
+  #[default]
+  Idle,
+  Updating,
+  Verifying,
+  GameRunning,
 }
 
-/// Posts the slider answer to the captcha endpoint, using the same session
-/// created in get_captcha. Returns true if the server accepted the answer.
-#[tauri::command]
-async fn verify_captcha(answer: i32) -> Result<bool, String> {
-  let client_guard = SIGNUP_SESSION_CLIENT.lock().await;
-  let client = client_guard
-    .as_ref()
-    .ok_or_else(|| "No signup session. Call get_captcha first.".to_string())?;
+#[derive(Default)]
+struct OperationState {
+  current: Arc<std::sync::Mutex<LauncherOperation>>,
+}
+
+/// Holds the operation lock; dropping it returns the launcher to `Idle`.
+struct OperationGuard<R: tauri::Runtime = tauri::Wry> {
+  app_handle: tauri::AppHandle<R>,
+  current: Arc<std::sync::Mutex<LauncherOperation>>,
+}
+
+impl<R: tauri::Runtime> OperationGuard<R> {
+  /// Moves to the next step of a multi-step operation, e.g. a repair going
+  /// from checking to downloading, without letting go of the lock.
+  fn switch_to(&self, operation: LauncherOperation) {
+    *self.current.lock().unwrap_or_else(|e| e.into_inner()) = operation;
+    emit_operation_changed(&self.app_handle, operation);
+  }
+}
+
+impl<R: tauri::Runtime> Drop for OperationGuard<R> {
+  fn drop(&mut self) {
+    let mut current = self.current.lock().unwrap_or_else(|e| e.into_inner());
+    *current = LauncherOperation::Idle;
+    *OPERATION_CANCELLATION.write().unwrap_or_else(|e| e.into_inner()) = CancellationToken::default();
+    drop(current);
+    emit_operation_changed(&self.app_handle, LauncherOperation::Idle);
+  }
+}
 
-  let base_url = &*LAUNCHER_BASE_URL;
-  let captcha_url = format!("{}/launcher/GetCaptcha", base_url);
-  let answer_str = answer.to_string();
+fn emit_operation_changed<R: tauri::Runtime>(app_handle: &tauri::AppHandle<R>, operation: LauncherOperation) {
+  if let Err(e) = app_handle.emit_all("launcher_operation_changed", operation) {
+    eprintln!("Failed to emit launcher_operation_changed event: {}", e);
+  }
+}
 
-  let res = client
-    .post(&captcha_url)
-    .form(&[("answer", answer_str.as_str())])
-    .send()
-    .await
-    .map_err(|e| format!("Failed to verify captcha: {}", e))?;
+/// An operation refused because another one holds the lock. Commands
+/// report it as "busy: currently <Operation>".
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct OperationConflict {
+  current: LauncherOperation,
+}
 
-  if !res.status().is_success() {
-    return Err(format!("Captcha verify failed with status: {}", res.status()));
+impl std::fmt::Display for OperationConflict {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    write!(f, "busy: currently {:?}", self.current)
   }
+}
 
-  let verify_res: CaptchaVerifyApiResponse = res
-    .json()
-    .await
-    .map_err(|e| format!("Failed to parse captcha verify response: {}", e))?;
+impl From<OperationConflict> for String {
+  fn from(conflict: OperationConflict) -> String {
+    conflict.to_string()
+  }
+}
 
-  Ok(verify_res.verified)
+/// Takes the operation lock for `operation`, or fails with an
+/// `OperationConflict` while another one holds it.
+fn begin_operation<R: tauri::Runtime>(app_handle: &tauri::AppHandle<R>, operation: LauncherOperation) -> Result<OperationGuard<R>, OperationConflict> {
+  let current = Arc::clone(&app_handle.state::<OperationState>().current);
+  {
+    let mut current = current.lock().unwrap_or_else(|e| e.into_inner());
+    if *current != LauncherOperation::Idle {
+      return Err(OperationConflict { current: *current });
+    }
+    *current = operation;
+    *OPERATION_CANCELLATION.write().unwrap_or_else(|e| e.into_inner()) = CancellationToken::default();
+  }
+  // A failed operation leaves the taskbar button in the error state.
+  if let Some(window) = app_handle.get_window("main") {
+    set_taskbar_progress(&window, TaskbarProgress::Clear);
+  }
+  emit_operation_changed(app_handle, operation);
+  Ok(OperationGuard { app_handle: app_handle.clone(), current })
 }
 
-/// Submits the signup form to the backend. Requires captcha to have been
-/// verified first (the same session client must carry captchaVerified = true).
 #[tauri::command]
-async fn signup(login: String, email: String, password: String) -> Result<String, String> {
-  let client_guard = SIGNUP_SESSION_CLIENT.lock().await;
-  let client = client_guard
-    .as_ref()
-    .ok_or_else(|| "No signup session. Complete captcha first.".to_string())?;
-
-  let base_url = &*LAUNCHER_BASE_URL;
-  let signup_url = format!("{}/launcher/SignupAction", base_url);
+fn get_launcher_operation(state: tauri::State<'_, OperationState>) -> LauncherOperation {
+  *state.current.lock().unwrap_or_else(|e| e.into_inner())
+}
 
-  let res = client
-    .post(&signup_url)
-    .form(&[
-      ("login", login.as_str()),
-      ("email", email.as_str()),
-      ("password", password.as_str()),
-    ])
-    .send()
-    .await
-    .map_err(|e| format!("Failed to submit signup: {}", e))?;
+// ─── Automatic update checks ─────────────────────────────────────────────────
 
-  if !res.status().is_success() {
-    return Err(format!("Signup request failed with status: {}", res.status()));
-  }
+const DEFAULT_AUTO_CHECK_INTERVAL_MINUTES: u64 = 30;
 
-  let signup_res: SignupApiResponse = res
-    .json()
-    .await
-    .map_err(|e| format!("Failed to parse signup response: {}", e))?;
+fn get_auto_check_interval_minutes() -> u64 {
+  get_launcher_setting("auto_check_interval_minutes")
+    .and_then(|v| v.parse::<u64>().ok())
+    .unwrap_or(DEFAULT_AUTO_CHECK_INTERVAL_MINUTES)
+}
 
-  serde_json::to_string(&signup_res)
-    .map_err(|e| format!("Failed to serialize signup response: {}", e))
+/// Returns the automatic update check interval in minutes (0 = disabled).
+#[tauri::command]
+fn get_auto_check_interval() -> Result<u64, String> {
+  Ok(get_auto_check_interval_minutes())
 }
 
 #[tauri::command]
-async fn get_portal_config() -> Result<String, String> {
-  let client = Client::new();
-  let base_url = &*LAUNCHER_BASE_URL;
-  let url = format!("{}/launcher/GetPortalConfig", base_url);
+fn set_auto_check_interval(minutes: u64) -> Result<(), String> {
+  save_launcher_setting("auto_check_interval_minutes", &minutes.to_string())
+}
 
-  let res = match client.get(&url).send().await {
-    Ok(r) => r,
-    Err(_) => {
-      let default = PortalConfigResponse { registration_disabled: false, captcha_enabled: true, patch_no_check: false };
-      return serde_json::to_string(&default).map_err(|e| e.to_string());
-    }
-  };
+/// Whether the launcher should stay off the network on its own, e.g. on a
+/// metered connection. Only background checks honour it.
+fn offline_mode_enabled() -> bool {
+  get_launcher_setting("offline_mode")
+    .map(|v| v.eq_ignore_ascii_case("true") || v == "1")
+    .unwrap_or(false)
+}
 
-  if !res.status().is_success() {
-    let default = PortalConfigResponse { registration_disabled: false, captcha_enabled: true, patch_no_check: false };
-    return serde_json::to_string(&default).map_err(|e| e.to_string());
+/// Latest manifest version with the size of the pending update, as pushed in
+/// `update_available`.
+#[derive(Debug, Serialize, Clone, PartialEq)]
+struct AvailableUpdate {
+  version: Option<String>,
+  files: usize,
+  total_bytes: u64,
+}
+
+/// Looks for an update without touching the frontend's check UI: a matching
+/// `version.json` settles it without a scan, otherwise the hash file is
+/// fetched conditionally and compared with the game directory. Returns
+/// `None` without checking while an operation (or the game) holds the lock.
+/// The check itself doesn't take the lock, so it never keeps the player from
+/// launching, checking or updating.
+async fn prefetch_update<R: tauri::Runtime>(app_handle: &tauri::AppHandle<R>) -> Result<Option<AvailableUpdate>, String> {
+  if *app_handle.state::<OperationState>().current.lock().unwrap_or_else(|e| e.into_inner()) != LauncherOperation::Idle {
+    return Ok(None);
+  }
+  if applied_version_is_current().await? {
+    return Ok(Some(AvailableUpdate { version: get_applied_manifest_version(), files: 0, total_bytes: 0 }));
   }
+  let files = find_files_to_update(None, false, None, false).await?;
+  let pending = files.iter().filter(|f| !f.protected);
+  Ok(Some(AvailableUpdate {
+    version: CURRENT_MANIFEST_VERSION.read().unwrap().clone(),
+    files: pending.clone().count(),
+    total_bytes: pending.map(|f| f.transfer_size()).sum(),
+  }))
+}
 
-  let config: PortalConfigResponse = res
-    .json()
-    .await
-    .map_err(|e| format!("Failed to parse portal config: {}", e))?;
+/// Checks for game updates shortly after startup and then every
+/// `auto_check_interval_minutes`, emitting `update_available` whenever the
+/// latest version or the pending update changes. The interval is re-read
+/// every minute so setting changes apply without a restart. Failed checks
+/// double the wait, up to 16 intervals, so an unreachable server isn't polled
+/// at full rate; nothing runs while another operation holds the lock or
+/// `offline_mode` is on.
+async fn auto_update_check_loop(app_handle: tauri::AppHandle) {
+  let mut last_result: Option<AvailableUpdate> = None;
+  let mut minutes_since_check: Option<u64> = None;
+  let mut failures: u32 = 0;
+
+  tokio::time::sleep(Duration::from_secs(15)).await;
+  loop {
+    let interval = get_auto_check_interval_minutes() << failures.min(4);
+    let due = interval > 0 && !matches!(minutes_since_check, Some(m) if m < interval);
+
+    if due && !offline_mode_enabled() {
+      match prefetch_update(&app_handle).await {
+        Ok(None) => {}
+        Ok(Some(result)) => {
+          minutes_since_check = Some(0);
+          failures = 0;
+          let changed = match &last_result {
+            Some(last) => last != &result,
+            None => result.files > 0,
+          };
+          if changed {
+            let _ = app_handle.emit_all("update_available", &result);
+            last_result = Some(result);
+          }
+        }
+        Err(e) => {
+          minutes_since_check = Some(0);
+          failures = failures.saturating_add(1);
+          eprintln!("Automatic update check failed ({} in a row): {}", failures, e);
+        }
+      }
+    }
 
-  serde_json::to_string(&config)
-    .map_err(|e| format!("Failed to serialize portal config: {}", e))
+    tokio::time::sleep(Duration::from_secs(60)).await;
+    minutes_since_check = minutes_since_check.map(|m| m + 1);
+  }
 }
 
 // ─────────────────────────────────────────────────────────────────────────────
@@ -2990,6 +11277,22 @@ fn main() {
   log::set_boxed_logger(Box::new(tera_logger)).expect("Failed to set logger");
   log::set_max_level(LevelFilter::Info);
 
+  // A panic in a spawned task only ends that task; keep a record of it.
+  let default_panic_hook = std::panic::take_hook();
+  std::panic::set_hook(Box::new(move |panic_info| {
+    error!("Panic: {}", panic_info);
+    let _ = log_error_to_file(&format!("Panic: {}", panic_info));
+    default_panic_hook(panic_info);
+  }));
+
+  if run_as_admin_enabled() && !is_elevated() {
+    match relaunch_elevated() {
+      Ok(true) => std::process::exit(0),
+      Ok(false) => warn!("Continuing without elevation"),
+      Err(e) => error!("Failed to restart elevated: {}", e),
+    }
+  }
+
   // Create an asynchronous channel for logs
   let (log_sender, mut log_receiver) = mpsc::channel::<String>(100);
 
@@ -3007,15 +11310,13 @@ fn main() {
   });
 
 
-  let game_status_receiver = get_game_status_receiver();
-  let game_state = GameState {
-    status_receiver: Arc::new(Mutex::new(game_status_receiver)),
-    is_launching: Arc::new(Mutex::new(false)),
-  };
+  let game_state = GameState::new(get_game_status_receiver());
 
   tauri::Builder
     ::default()
     .manage(game_state)
+    .manage(UpdateState::default())
+    .manage(OperationState::default())
     .setup(|app| {
       let window = app.get_window("main").unwrap();
       let app_handle = app.handle();
@@ -3040,9 +11341,22 @@ fn main() {
       // Sync launcher_version.ini with the compiled version immediately at startup.
       // This overwrites any stale version left by a mismatched binary.
       let compiled_ver = app.package_info().version.to_string();
+      let _ = LAUNCHER_VERSION.set(compiled_ver.clone());
+      if let Some(data_dir) = app.path_resolver().app_data_dir() {
+        let _ = APP_DATA_DIR.set(data_dir);
+      }
       if let Err(e) = read_or_create_launcher_version(&compiled_ver) {
         info!("Failed to sync launcher_version.ini at startup: {}", e);
       }
+      close_interrupted_playtime_session();
+      match load_game_sessions() {
+        Ok(sessions) => {
+          if let Ok(mut recent) = app.state::<GameState>().recent_sessions.try_lock() {
+            *recent = sessions;
+          }
+        }
+        Err(e) => error!("Failed to load game session history: {}", e),
+      }
 
       // Silently ensure autoupdater.exe is present beside the launcher exe.
       // Fetches launcher_info.ini to get autoupdater_url, then downloads if needed.
@@ -3052,6 +11366,12 @@ fn main() {
         }
       });
 
+      let check_handle = app.handle();
+      tauri::async_runtime::spawn(auto_update_check_loop(check_handle));
+
+      tauri::async_runtime::spawn(watch_game_status(app.handle()));
+      tauri::async_runtime::spawn(watch_external_game(app.handle()));
+
       println!("Tauri setup completed");
 
 
@@ -3061,26 +11381,90 @@ fn main() {
       tauri::generate_handler![
         handle_launch_game,
         get_game_status,
+        get_game_phase,
+        get_game_sessions,
+        get_cpu_info,
+        set_game_priority,
+        set_cpu_affinity,
+        focus_game_window,
+        get_game_process_info,
+        get_playtime_stats,
+        get_crash_history,
+        get_launch_args,
+        set_launch_args,
+        get_launch_options,
+        set_launch_options,
+        get_launch_env,
+        set_launch_env,
+        get_post_game_action,
+        set_post_game_action,
+        cancel_post_game_action,
+        get_minimize_on_launch,
+        set_minimize_on_launch,
+        get_last_game_session,
+        get_game_session_history,
+        get_capture_game_output,
+        set_capture_game_output,
+        get_supported_languages,
+        get_game_executable,
+        set_game_executable,
+        detect_game_executables,
+        get_launcher_operation,
         select_game_folder,
         get_game_path_from_config,
         save_game_path_to_config,
         reset_launch_state,
+        terminate_game,
         login,
         set_auth_info,
         get_language_from_config,
         save_language_to_config,
         get_files_to_update,
         get_files_to_update_force,
+        verify_game_files,
+        verify_single_file,
+        repair_game_files,
         update_file,
         handle_logout,
         generate_hash_file,
+        get_local_manifest,
+        get_content_groups,
+        set_selected_groups,
+        remove_deselected_group_files,
+        list_protected_files,
+        add_protected_file,
+        remove_protected_file,
+        get_regions,
+        set_active_region,
+        cancel_hash_generation,
+        get_ignore_rules,
+        export_update_report,
+        compare_manifests,
+        set_ignore_rules,
+        generate_manifest_keypair,
+        get_client_manifest_version,
         check_server_connection,
         check_update_required,
+        get_update_summary,
+        start_cached_download,
         download_all_files,
+        download_files_subset,
+        get_pending_download_session,
+        cancel_update,
+        cancel_file_check,
+        import_from_existing_install,
+        list_backups,
+        rollback_to_backup,
+        list_quarantined_files,
+        get_auto_check_interval,
+        set_auto_check_interval,
+        get_hash_threads,
+        set_hash_threads,
         get_client_version,
         check_maintenance_and_notify,
         get_fresh_account_info,
         clear_update_cache,
+        get_cache_info,
         check_launcher_update,
         apply_launcher_update,
         get_launcher_version,
@@ -3094,4 +11478,926 @@ fn main() {
     )
     .run(tauri::generate_context!())
     .expect("error while running tauri application");
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn mock_app() -> tauri::App<tauri::test::MockRuntime> {
+    let app = tauri::test::mock_app();
+    app.manage(GameState::new(get_game_status_receiver()));
+    app.manage(OperationState::default());
+    app
+  }
+
+  fn mock_window(app: &tauri::App<tauri::test::MockRuntime>) -> tauri::Window<tauri::test::MockRuntime> {
+    app.get_window("main").unwrap()
+  }
+
+  #[tokio::test]
+  async fn panicking_launch_task_resets_launch_state() {
+    let app = mock_app();
+    let game_state = app.state::<GameState>();
+    *game_state.is_launching.lock().await = true;
+    *game_state.phase.lock().await = GamePhase::Launching;
+    let statuses = Arc::new(std::sync::Mutex::new(Vec::new()));
+    let recorded = Arc::clone(&statuses);
+    app.listen_global("game_status_changed", move |event| {
+      let payload: serde_json::Value = serde_json::from_str(event.payload().unwrap()).unwrap();
+      recorded.lock().unwrap().push(payload);
+    });
+
+    let app_handle = app.handle();
+    let result = tokio::spawn(async move {
+      let _guard = LaunchStateGuard::new(app_handle);
+      panic!("launch task failed");
+    }).await;
+    assert!(result.unwrap_err().is_panic());
+
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while *game_state.is_launching.lock().await {
+      assert!(Instant::now() < deadline, "is_launching was never reset");
+      tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    while *game_state.phase.lock().await != GamePhase::Idle {
+      assert!(Instant::now() < deadline, "the phase was never reset");
+      tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    let statuses = statuses.lock().unwrap();
+    let status = statuses.last().expect("game_status_changed is emitted");
+    assert_eq!(status["running"], false);
+    assert_eq!(status["session_id"], PRIMARY_SESSION_ID);
+  }
+
+  #[tokio::test]
+  async fn concurrent_launches_reserve_once() {
+    let game_state = Arc::new(GameState::new(get_game_status_receiver()));
+    let attempts: Vec<_> = (0..8)
+      .map(|_| {
+        let game_state = Arc::clone(&game_state);
+        tokio::spawn(async move { game_state.reserve_launch().await })
+      })
+      .collect();
+
+    let mut reserved = 0;
+    for attempt in attempts {
+      match attempt.await.unwrap() {
+        Ok(additional) => {
+          assert!(!additional);
+          reserved += 1;
+        }
+        Err(e) => assert_eq!(e, "Game is already launching"),
+      }
+    }
+    assert_eq!(reserved, 1);
+    // The lock is free again for the slow checks that follow.
+    assert!(game_state.is_launching.try_lock().is_ok_and(|flag| *flag));
+  }
+
+  /// Counts the writes that reach the underlying sink.
+  #[derive(Default)]
+  struct CountingWriter {
+    writes: usize,
+    bytes: u64,
+  }
+
+  impl tokio::io::AsyncWrite for CountingWriter {
+    fn poll_write(
+      mut self: std::pin::Pin<&mut Self>,
+      _cx: &mut std::task::Context<'_>,
+      buf: &[u8],
+    ) -> std::task::Poll<std::io::Result<usize>> {
+      self.writes += 1;
+      self.bytes += buf.len() as u64;
+      std::task::Poll::Ready(Ok(buf.len()))
+    }
+
+    fn poll_flush(self: std::pin::Pin<&mut Self>, _cx: &mut std::task::Context<'_>) -> std::task::Poll<std::io::Result<()>> {
+      std::task::Poll::Ready(Ok(()))
+    }
+
+    fn poll_shutdown(self: std::pin::Pin<&mut Self>, _cx: &mut std::task::Context<'_>) -> std::task::Poll<std::io::Result<()>> {
+      std::task::Poll::Ready(Ok(()))
+    }
+  }
+
+  #[tokio::test]
+  async fn download_writer_batches_response_chunks() {
+    const TOTAL: u64 = 100 * 1024 * 1024;
+    let chunk = vec![0u8; 16 * 1024];
+
+    let mut direct = CountingWriter::default();
+    let mut buffered = download_writer(CountingWriter::default());
+    let mut written = 0;
+    while written < TOTAL {
+      direct.write_all(&chunk).await.unwrap();
+      buffered.write_all(&chunk).await.unwrap();
+      written += chunk.len() as u64;
+    }
+    buffered.flush().await.unwrap();
+    let buffered = buffered.into_inner();
+
+    assert_eq!(direct.bytes, TOTAL);
+    assert_eq!(buffered.bytes, TOTAL);
+    assert_eq!(direct.writes, 6400);
+    assert_eq!(buffered.writes as u64, TOTAL / (DEFAULT_WRITE_BUFFER_KB as u64 * 1024));
+  }
+
+  /// Plain SHA-256 hashing of every file, nothing extra in the entries.
+  fn hash_options<'a>(ignore_rules: &'a IgnoreRules, previous_entries: &'a HashMap<String, FileInfo>) -> DirectoryHashOptions<'a> {
+    DirectoryHashOptions {
+      algo: HashAlgorithm::Sha256,
+      with_quick_hash: false,
+      chunk_size: 0,
+      include_mtime: false,
+      follow_symlinks: false,
+      url_prefix: None,
+      ignore_rules,
+      priority_rules: &[],
+      group_rules: &[],
+      previous_entries,
+      use_hash_cache: false,
+    }
+  }
+
+  fn server_entry(path: &str, hash: &str, size: u64) -> ServerFileEntry {
+    ServerFileEntry::deserialize(json!({ "path": path, "hash": hash, "size": size, "url": "" })).unwrap()
+  }
+
+  /// A fresh directory under the system temp dir.
+  fn temp_dir() -> PathBuf {
+    let dir = env::temp_dir().join(format!("teralaunch-test-{}", uuid::Uuid::new_v4()));
+    fs::create_dir_all(&dir).unwrap();
+    dir
+  }
+
+  /// Serves `body` over HTTP on a local port, honouring single-range
+  /// requests. Returns the file's URL.
+  async fn serve_file(body: Vec<u8>) -> String {
+    use tokio::io::AsyncReadExt;
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let body = Arc::new(body);
+    tokio::spawn(async move {
+      while let Ok((mut socket, _)) = listener.accept().await {
+        let body = Arc::clone(&body);
+        tokio::spawn(async move {
+          let mut request = Vec::new();
+          let mut buf = [0u8; 1024];
+          while !request.windows(4).any(|w| w == b"\r\n\r\n") {
+            match socket.read(&mut buf).await {
+              Ok(0) | Err(_) => return,
+              Ok(n) => request.extend_from_slice(&buf[..n]),
+            }
+          }
+          let request = String::from_utf8_lossy(&request).to_ascii_lowercase();
+          let range = request.lines()
+            .find_map(|line| line.strip_prefix("range: bytes="))
+            .and_then(|range| range.trim().split_once('-'))
+            .and_then(|(start, end)| Some((start.parse::<usize>().ok()?, end.parse::<usize>().ok()?)));
+          let (status, slice) = match range {
+            Some((start, end)) => ("206 Partial Content", &body[start..=end.min(body.len() - 1)]),
+            None => ("200 OK", &body[..]),
+          };
+          let head = format!("HTTP/1.1 {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n", status, slice.len());
+          let _ = socket.write_all(head.as_bytes()).await;
+          let _ = socket.write_all(slice).await;
+        });
+      }
+    });
+    format!("http://{}/file", addr)
+  }
+
+  fn chunk_list(data: &[u8], chunk_size: usize, algo: HashAlgorithm) -> Vec<FileChunk> {
+    data.chunks(chunk_size)
+      .enumerate()
+      .map(|(i, chunk)| FileChunk {
+        offset: (i * chunk_size) as u64,
+        size: chunk.len() as u64,
+        hash: hash_bytes(chunk, algo),
+      })
+      .collect()
+  }
+
+  fn remote_file(path: &str, url: &str, data: &[u8], chunks: Vec<FileChunk>) -> FileInfo {
+    serde_json::from_value(json!({
+      "path": path,
+      "hash": hash_bytes(data, HashAlgorithm::Sha256),
+      "size": data.len(),
+      "url": url,
+      "chunks": chunks,
+    })).unwrap()
+  }
+
+  #[tokio::test]
+  async fn chunked_update_patches_shrunk_and_grown_files() {
+    let remote: Vec<u8> = (0..4 * 1024 + 300).map(|i| (i % 251) as u8).collect();
+    let chunks = chunk_list(&remote, 1024, HashAlgorithm::Sha256);
+    let url = serve_file(remote.clone()).await;
+    let file_info = remote_file("S1Game/CookedPC/Big.gpk", &url, &remote, chunks.clone());
+    let client = Client::new();
+    let dir = temp_dir();
+
+    let mut shrunk = remote[..2500].to_vec();
+    shrunk[10] ^= 0xff;
+    let mut grown = remote.clone();
+    grown[2100] ^= 0xff;
+    grown.extend_from_slice(b"trailing bytes");
+
+    for (local, expected_valid) in [(shrunk, vec![1]), (grown, vec![0, 1, 3, 4])] {
+      let file_path = dir.join("Big.gpk");
+      fs::write(&file_path, &local).unwrap();
+
+      let (_, valid) = hash_file_with_chunks(&file_path, HashAlgorithm::Sha256, &chunks).unwrap();
+      assert_eq!(valid, expected_valid.into_iter().collect::<HashSet<_>>());
+
+      File::options().write(true).open(&file_path).unwrap().set_len(remote.len() as u64).unwrap();
+      let progress = SessionProgress::new(0, 1);
+      for (index, chunk) in chunks.iter().enumerate().filter(|(i, _)| !valid.contains(i)) {
+        let received = download_chunk(&client, &file_info, &file_path, chunk, false, &progress, &AtomicU64::new(0)).await.unwrap();
+        assert_eq!(received, chunks[index].size);
+      }
+
+      assert_eq!(fs::read(&file_path).unwrap(), remote);
+      assert_eq!(calculate_file_hash_with(&file_path, HashAlgorithm::Sha256).unwrap(), file_info.hash);
+      let fetched: u64 = chunks.iter().enumerate().filter(|(i, _)| !valid.contains(i)).map(|(_, c)| c.size).sum();
+      assert_eq!(progress.downloaded.load(Ordering::SeqCst), fetched);
+    }
+    fs::remove_dir_all(dir).unwrap();
+  }
+
+  fn hash_mapped(path: &Path) -> String {
+    let mut hasher = StreamHasher::new(HashAlgorithm::Sha256);
+    read_mapped_blocks(&File::open(path).unwrap(), &mut |data| hasher.update(data)).unwrap().unwrap();
+    hasher.finish()
+  }
+
+  fn hash_buffered(path: &Path) -> String {
+    let mut hasher = StreamHasher::new(HashAlgorithm::Sha256);
+    read_buffered_blocks(File::open(path).unwrap(), &mut |data| hasher.update(data)).unwrap();
+    hasher.finish()
+  }
+
+  #[test]
+  fn mapped_and_buffered_hashing_agree() {
+    let dir = temp_dir();
+    let sizes = [1, 64 * 1024, MMAP_HASH_SLICE - 1, MMAP_HASH_SLICE + 1, MMAP_HASH_THRESHOLD as usize + 12345];
+    for size in sizes {
+      let data: Vec<u8> = (0..size).map(|i| (i % 253) as u8).collect();
+      let path = dir.join(format!("{}.bin", size));
+      fs::write(&path, &data).unwrap();
+
+      let expected = hash_bytes(&data, HashAlgorithm::Sha256);
+      assert_eq!(hash_mapped(&path), expected, "mapped, {} bytes", size);
+      assert_eq!(hash_buffered(&path), expected, "buffered, {} bytes", size);
+      assert_eq!(calculate_file_hash_with(&path, HashAlgorithm::Sha256).unwrap(), expected);
+    }
+    fs::remove_dir_all(dir).unwrap();
+  }
+
+  /// Run with `cargo test --release -- --ignored hashing_benchmark --nocapture`.
+  #[test]
+  #[ignore]
+  fn hashing_benchmark() {
+    let dir = temp_dir();
+    let path = dir.join("large.bin");
+    let data: Vec<u8> = (0..1024 * 1024 * 1024).map(|i| (i % 253) as u8).collect();
+    fs::write(&path, &data).unwrap();
+    drop(data);
+
+    let start = Instant::now();
+    let buffered = hash_buffered(&path);
+    let buffered_time = start.elapsed();
+    let start = Instant::now();
+    let mapped = hash_mapped(&path);
+    let mapped_time = start.elapsed();
+    assert_eq!(mapped, buffered);
+    println!("1 GiB: buffered {:?}, mapped {:?}", buffered_time, mapped_time);
+    fs::remove_dir_all(dir).unwrap();
+  }
+
+  #[test]
+  fn second_check_hashes_nothing() {
+    let dir = temp_dir();
+    let cache = JsonHashCache::open(dir.join("file_cache.json"));
+    let game_path = dir.join("game");
+    let entries: Vec<ServerFileEntry> = (0..5)
+      .map(|i| {
+        let path = format!("S1Game/CookedPC/File{}.gpk", i);
+        let data = format!("contents of file {}", i).repeat(100);
+        let local_path = game_path.join(&path);
+        fs::create_dir_all(local_path.parent().unwrap()).unwrap();
+        // The last file differs locally, so it stays pending on both checks.
+        fs::write(&local_path, if i == 4 { data.to_uppercase() } else { data.clone() }).unwrap();
+        server_entry(&path, &hash_bytes(data.as_bytes(), HashAlgorithm::Sha256), data.len() as u64)
+      })
+      .collect();
+    let check = || {
+      let counters = FileCheckCounters::default();
+      let pending: Vec<String> = entries.iter()
+        .filter_map(|entry| check_local_file(entry, &game_path.join(&entry.path), &cache, HashAlgorithm::Sha256, false, false, &counters))
+        .map(|entry| entry.path)
+        .collect();
+      (pending, counters)
+    };
+
+    let (pending, first) = check();
+    assert_eq!(pending, ["S1Game/CookedPC/File4.gpk"]);
+    assert_eq!(first.ok.load(Ordering::SeqCst), 4);
+    assert!(first.bytes_checked.load(Ordering::SeqCst) > 0);
+    assert_eq!(cache.len(), 5);
+
+    let (pending, second) = check();
+    assert_eq!(pending, ["S1Game/CookedPC/File4.gpk"]);
+    assert_eq!(second.cached.load(Ordering::SeqCst), 4);
+    assert_eq!(second.bytes_checked.load(Ordering::SeqCst), 0);
+    fs::remove_dir_all(dir).unwrap();
+  }
+
+  #[test]
+  fn cache_file_round_trips() {
+    let dir = temp_dir();
+    let cache_path = dir.join("file_cache.json");
+    let modified = SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+    let mut entries = HashMap::new();
+    entries.insert("S1Game/A.gpk".to_string(), CachedFileInfo::verified("aa".to_string(), modified, HashAlgorithm::Blake3));
+    entries.insert("S1Game/B.gpk".to_string(), CachedFileInfo {
+      hash: "bb".to_string(),
+      last_modified: modified,
+      algo: HashAlgorithm::Sha256,
+      verified_hash: Some("b0".to_string()),
+    });
+
+    save_json_cache(&cache_path, &entries).unwrap();
+    let saved: serde_json::Value = serde_json::from_str(&fs::read_to_string(&cache_path).unwrap()).unwrap();
+    assert_eq!(saved["version"], CACHE_FORMAT_VERSION);
+
+    let loaded = load_json_cache(&cache_path).unwrap();
+    assert_eq!(serde_json::to_value(&loaded).unwrap(), serde_json::to_value(&entries).unwrap());
+    fs::remove_dir_all(dir).unwrap();
+  }
+
+  #[test]
+  fn unversioned_cache_is_migrated() {
+    let contents = json!({
+      "S1Game/A.gpk": {
+        "hash": "aa",
+        "last_modified": { "secs_since_epoch": 1_700_000_000u64, "nanos_since_epoch": 0 },
+      },
+    }).to_string();
+
+    let entries = parse_cache_file(&contents).unwrap();
+    let entry = &entries["S1Game/A.gpk"];
+    assert_eq!(entry.hash, "aa");
+    assert_eq!(entry.algo, HashAlgorithm::Sha256);
+    assert_eq!(entry.last_verified_hash(), "aa");
+    assert_eq!(entry.last_modified, SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000));
+  }
+
+  #[test]
+  fn newer_cache_version_loads_empty() {
+    let dir = temp_dir();
+    let cache_path = dir.join("file_cache.json");
+    let contents = json!({ "version": CACHE_FORMAT_VERSION + 1, "entries": { "S1Game/A.gpk": { "shape": "unknown" } } });
+    fs::write(&cache_path, contents.to_string()).unwrap();
+
+    assert!(load_json_cache(&cache_path).unwrap().is_empty());
+    // Not corrupt, so it isn't moved aside either.
+    assert!(cache_path.exists());
+    assert!(!cache_path.with_extension("json.bad").exists());
+    fs::remove_dir_all(dir).unwrap();
+  }
+
+  #[test]
+  fn ignore_rules_merge_overlapping_and_nested_patterns() {
+    let dir = temp_dir();
+    fs::write(dir.join(IGNORE_FILE), json!(["S1Game/Logs/Old", "Tools\\", "S1Game/Logs"]).to_string()).unwrap();
+    let extra = vec!["Tools/Debug".to_string(), "S1Game/**/*.bak".to_string()];
+    let rules = IgnoreRules::load(&dir, &extra).unwrap();
+
+    // The duplicate of a built-in rule collapses into one entry.
+    assert_eq!(rules.builtin.iter().filter(|rule| *rule == "S1Game/Logs").count(), 1);
+    assert!(rules.builtin.contains(&"Tools/".to_string()));
+    for ignored in ["S1Game/Logs/Old/a.log", "S1Game/Logs/b.log", "Tools/Debug/x.dll", "Tools/y.exe", "S1Game/CookedPC/Art.gpk.bak"] {
+      assert!(rules.is_ignored(ignored), "{} should be ignored", ignored);
+    }
+    assert!(!rules.is_ignored("S1Game/CookedPC/Art.gpk"));
+    assert!(!rules.is_user_ignored("Tools/y.exe"));
+
+    // The update check filters with the same rules, keeping root-level files.
+    let entries: Vec<ServerFileEntry> = ["Tools/y.exe", "S1Game/CookedPC/Art.gpk", "TeraExtra.dll"].iter()
+      .map(|path| server_entry(path, "", 0))
+      .collect();
+    let (kept, user_skipped) = filter_ignored_entries(&entries, &rules, &dir);
+    assert_eq!(kept.iter().map(|entry| entry.path.as_str()).collect::<Vec<_>>(), ["S1Game/CookedPC/Art.gpk", "TeraExtra.dll"]);
+    assert!(user_skipped.is_empty());
+    fs::remove_dir_all(dir).unwrap();
+  }
+
+  #[test]
+  fn ignore_rules_match_whole_components() {
+    let dir = temp_dir();
+    let rules = IgnoreRules::load(&dir, &[]).unwrap();
+
+    // Exact and nested matches.
+    assert!(rules.is_ignored("S1Game/Logs"));
+    assert!(rules.is_ignored("S1Game/Logs/Client.log"));
+    assert!(rules.is_ignored("$Patch/a/b.tmp"));
+    // Siblings that only share a prefix.
+    assert!(!rules.is_ignored("S1Game/Logs_backup/Client.log"));
+    assert!(!rules.is_ignored("S1Game/Logsomething.dat"));
+    assert!(!rules.is_ignored("$Patched_files/a.dat"));
+    // Windows separators and stray components.
+    assert!(rules.is_ignored("S1Game\\Logs\\Client.log"));
+    assert!(rules.is_ignored("./S1Game//Screenshots/shot.png"));
+    assert!(!rules.is_ignored("S1Game\\Logs_backup\\Client.log"));
+    // Root-level files are only left out of the hash file.
+    assert!(!rules.is_ignored("anything.dll"));
+    fs::remove_dir_all(dir).unwrap();
+  }
+
+  #[test]
+  fn ignore_rules_are_kept_for_the_session() {
+    let dir = temp_dir();
+    fs::write(dir.join(USER_IGNORE_FILE), "S1Game/Movies\n").unwrap();
+    reload_ignore_rules(&dir).unwrap();
+    assert!(ensure_not_ignored(&dir, "S1Game/Movies/Intro.bik").is_err());
+    assert!(ensure_not_ignored(&dir, "S1Game/CookedPC/Art.gpk").is_ok());
+    assert!(ensure_not_ignored(&dir, "TeraExtra.dll").is_ok());
+
+    // A new session picks up edits on disk.
+    fs::write(dir.join(USER_IGNORE_FILE), "").unwrap();
+    reload_ignore_rules(&dir).unwrap();
+    assert!(ensure_not_ignored(&dir, "S1Game/Movies/Intro.bik").is_ok());
+    fs::remove_dir_all(dir).unwrap();
+  }
+
+  #[cfg(unix)]
+  fn symlink(target: &Path, link: &Path) -> std::io::Result<()> {
+    std::os::unix::fs::symlink(target, link)
+  }
+
+  #[cfg(windows)]
+  fn symlink(target: &Path, link: &Path) -> std::io::Result<()> {
+    if target.is_dir() {
+      std::os::windows::fs::symlink_dir(target, link)
+    } else {
+      std::os::windows::fs::symlink_file(target, link)
+    }
+  }
+
+  fn hashed_paths(window: &tauri::Window<tauri::test::MockRuntime>, game_path: &Path, follow_symlinks: bool) -> Vec<String> {
+    let ignore_rules = IgnoreRules::load(game_path, &[]).unwrap();
+    let previous_entries = HashMap::new();
+    let options = DirectoryHashOptions { follow_symlinks, ..hash_options(&ignore_rules, &previous_entries) };
+    let hashes = hash_game_directory(window, game_path, &options).unwrap();
+    let alias = hashes.files.iter().find(|f| f.path == "S1Game/alias.dat").expect("symlinked file is hashed");
+    assert_eq!(alias.hash, hash_bytes(b"outside", HashAlgorithm::Sha256));
+    let mut paths: Vec<String> = hashes.files.into_iter().map(|f| f.path).collect();
+    paths.sort();
+    paths
+  }
+
+  #[tokio::test(flavor = "multi_thread")]
+  async fn symlinked_directories_are_followed_only_when_asked() {
+    let dir = temp_dir();
+    let game_path = dir.join("game");
+    let outside = dir.join("outside");
+    fs::create_dir_all(game_path.join("S1Game")).unwrap();
+    fs::create_dir_all(&outside).unwrap();
+    fs::write(game_path.join("S1Game/real.dat"), b"inside").unwrap();
+    fs::write(outside.join("big.dat"), b"outside").unwrap();
+    let linked = symlink(&outside, &game_path.join("S1Game/Linked"))
+      .and_then(|_| symlink(&outside.join("big.dat"), &game_path.join("S1Game/alias.dat")));
+    if let Err(e) = linked {
+      println!("Skipping, symlinks can't be created here: {}", e);
+      fs::remove_dir_all(dir).unwrap();
+      return;
+    }
+
+    let app = mock_app();
+    let window = mock_window(&app);
+    assert_eq!(hashed_paths(&window, &game_path, false), ["S1Game/alias.dat", "S1Game/real.dat"]);
+    assert_eq!(hashed_paths(&window, &game_path, true), ["S1Game/Linked/big.dat", "S1Game/alias.dat", "S1Game/real.dat"]);
+
+    let entries: Vec<ServerFileEntry> = ["S1Game/Linked/big.dat", "S1Game/alias.dat", "S1Game/real.dat"].iter()
+      .map(|path| server_entry(path, "", 0))
+      .collect();
+    let mut files: Vec<&ServerFileEntry> = entries.iter().collect();
+    drop_symlinked_entries(&mut files, &game_path);
+    assert_eq!(files.iter().map(|f| f.path.as_str()).collect::<Vec<_>>(), ["S1Game/alias.dat", "S1Game/real.dat"]);
+    fs::remove_dir_all(dir).unwrap();
+  }
+
+  #[cfg(windows)]
+  #[test]
+  fn extended_length_paths_are_prefixed() {
+    assert_eq!(extended_length_path(Path::new(r"C:\Games\TERA\..\Tera\.\S1Game")), PathBuf::from(r"\\?\C:\Games\Tera\S1Game"));
+    assert_eq!(extended_length_path(Path::new(r"C:/Games/Tera")), PathBuf::from(r"\\?\C:\Games\Tera"));
+    assert_eq!(extended_length_path(Path::new(r"\\server\share\Tera")), PathBuf::from(r"\\?\UNC\server\share\Tera"));
+    assert_eq!(extended_length_path(Path::new(r"\\?\C:\Tera")), PathBuf::from(r"\\?\C:\Tera"));
+    assert_eq!(extended_length_path(Path::new(r"Tera\S1Game")), PathBuf::from(r"Tera\S1Game"));
+  }
+
+  #[tokio::test(flavor = "multi_thread")]
+  async fn files_past_max_path_are_hashed_and_checked() {
+    let dir = temp_dir();
+    let game_path = extended_length_path(&dir.join("game"));
+    let relative_path = format!("S1Game/{}/Deep.gpk", ["a_fairly_long_directory_name"; 10].join("/"));
+    let file_path = game_path.join(&relative_path);
+    assert!(dir.join("game").join(&relative_path).as_os_str().len() > 260);
+    tokio::fs::create_dir_all(file_path.parent().unwrap()).await.unwrap();
+    tokio::fs::write(&file_path, b"deep").await.unwrap();
+
+    let app = mock_app();
+    let ignore_rules = IgnoreRules::load(&game_path, &[]).unwrap();
+    let previous_entries = HashMap::new();
+    let options = hash_options(&ignore_rules, &previous_entries);
+    let hashes = hash_game_directory(&mock_window(&app), &game_path, &options).unwrap();
+    // Manifest paths stay relative and unprefixed.
+    assert_eq!(hashes.files.len(), 1);
+    assert_eq!(hashes.files[0].path, relative_path);
+    assert_eq!(hashes.files[0].hash, hash_bytes(b"deep", HashAlgorithm::Sha256));
+
+    let cache = JsonHashCache::open(dir.join("file_cache.json"));
+    let entry = server_entry(&relative_path, &hashes.files[0].hash, 4);
+    let counters = FileCheckCounters::default();
+    let local_path = game_file_path(&game_path, &relative_path, false);
+    assert!(check_local_file(&entry, &local_path, &cache, HashAlgorithm::Sha256, false, false, &counters).is_none());
+    assert_eq!(cache.entries().into_iter().map(|(path, _)| path).collect::<Vec<_>>(), [relative_path]);
+    fs::remove_dir_all(extended_length_path(&dir)).unwrap();
+  }
+
+  #[test]
+  fn mismatched_casing_resolves_to_the_files_on_disk() {
+    let dir = temp_dir();
+    let game_path = dir.join("game");
+    fs::create_dir_all(game_path.join("S1Game/CookedPC")).unwrap();
+    fs::write(game_path.join("S1Game/CookedPC/Art.gpk"), b"art").unwrap();
+    if game_path.join("S1GAME").exists() {
+      println!("Skipping, the temp directory ignores case already");
+      fs::remove_dir_all(dir).unwrap();
+      return;
+    }
+
+    let on_disk = game_path.join("S1Game/CookedPC/Art.gpk");
+    assert_eq!(game_file_path(&game_path, "s1game/cookedpc/ART.GPK", true), on_disk);
+    assert_eq!(game_file_path(&game_path, "s1game/cookedpc/ART.GPK", false), game_path.join("s1game/cookedpc/ART.GPK"));
+    // Missing parts keep the manifest's casing under the existing directories.
+    assert_eq!(
+      game_file_path(&game_path, "s1game/cookedpc/New/File.gpk", true),
+      game_path.join("S1Game/CookedPC/New/File.gpk"),
+    );
+
+    let cache = JsonHashCache::open(dir.join("file_cache.json"));
+    let entry = server_entry("s1game/cookedpc/ART.GPK", &hash_bytes(b"art", HashAlgorithm::Sha256), 3);
+    let counters = FileCheckCounters::default();
+    let local_path = game_file_path(&game_path, &entry.path, true);
+    assert!(check_local_file(&entry, &local_path, &cache, HashAlgorithm::Sha256, false, false, &counters).is_none());
+    let missing = game_file_path(&game_path, &entry.path, false);
+    assert!(check_local_file(&entry, &missing, &cache, HashAlgorithm::Sha256, false, false, &counters).is_some());
+    fs::remove_dir_all(dir).unwrap();
+  }
+
+  #[tokio::test(flavor = "multi_thread")]
+  async fn empty_directory_reports_full_progress() {
+    let dir = temp_dir();
+    fs::create_dir_all(dir.join("S1Game/Logs")).unwrap();
+    // Only ignored files, so nothing is left to hash.
+    fs::write(dir.join("S1Game/Logs/Client.log"), b"log").unwrap();
+    fs::write(dir.join("config.ini"), b"").unwrap();
+
+    let app = mock_app();
+    let ignore_rules = IgnoreRules::load(&dir, &[]).unwrap();
+    let previous_entries = HashMap::new();
+    let options = hash_options(&ignore_rules, &previous_entries);
+    let hashes = hash_game_directory(&mock_window(&app), &dir, &options).unwrap();
+    assert!(hashes.files.is_empty());
+    assert_eq!(hashes.total_size, 0);
+
+    let payload = hash_progress_payload("", 0, 0, 0, 0);
+    assert_eq!(payload["progress"].as_f64(), Some(100.0));
+    assert_eq!(payload["computed_hashes"], 0);
+    fs::remove_dir_all(dir).unwrap();
+  }
+
+  #[test]
+  fn empty_manifest_reports_finite_progress() {
+    let manifest = parse_server_manifest(&json!({ "files": [] })).unwrap();
+    assert!(manifest.files.is_empty());
+    assert_eq!(percent(0, manifest.files.len() as u64), 100.0);
+    assert_eq!(average_ms_per_file(Duration::from_millis(250), manifest.files.len()), 250.0);
+    assert_eq!(percent(1, 4), 25.0);
+  }
+
+  fn manifest(version: &str, files: serde_json::Value) -> serde_json::Value {
+    json!({ "version": version, "algo": "sha256", "files": files })
+  }
+
+  #[test]
+  fn manifest_comparison_lists_added_changed_and_removed_files() {
+    let old = manifest("1", json!([
+      { "path": "a.gpk", "hash": "aa", "size": 10 },
+      { "path": "b.gpk", "hash": "bb", "size": 20 },
+      { "path": "c.gpk", "hash": "cc", "size": 30 },
+    ]));
+    let new = manifest("2", json!([
+      { "path": "d.gpk", "hash": "dd", "size": 5 },
+      { "path": "b.gpk", "hash": "b2", "size": 25 },
+      { "path": "a.gpk", "hash": "aa", "size": 10 },
+    ]));
+
+    let comparison = compare_manifest_entries(&old, &new);
+    assert_eq!(comparison.old_version.as_deref(), Some("1"));
+    assert_eq!(comparison.new_version.as_deref(), Some("2"));
+    assert_eq!(comparison.added.iter().map(|f| f.path.as_str()).collect::<Vec<_>>(), ["d.gpk"]);
+    assert_eq!(comparison.removed.iter().map(|f| f.path.as_str()).collect::<Vec<_>>(), ["c.gpk"]);
+    assert_eq!(comparison.changed.len(), 1);
+    let change = &comparison.changed[0];
+    assert_eq!((change.path.as_str(), change.old_hash.as_str(), change.new_hash.as_str()), ("b.gpk", "bb", "b2"));
+    assert_eq!(change.size_delta, 5);
+    assert_eq!(comparison.size_delta, 5 + 5 - 30);
+    assert_eq!(comparison.download_size, 5 + 25);
+  }
+
+  #[test]
+  fn fields_an_older_generator_omitted_are_not_changes() {
+    let old = manifest("1", json!([{ "path": "a.gpk", "hash": "aa", "size": 10 }]));
+    let new = manifest("2", json!([{
+      "path": "a.gpk", "hash": "aa", "size": 10, "algo": "sha256", "mtime": 1700000000,
+      "quick_hash": "qq", "chunks": [{ "offset": 0, "size": 10, "hash": "aa" }],
+    }]));
+
+    assert!(compare_manifest_entries(&old, &new).changed.is_empty());
+    assert_eq!(build_manifest_diff(&old, &new)["changed"], json!([]));
+  }
+
+  #[test]
+  fn published_diff_agrees_with_comparison() {
+    let old = manifest("1", json!([
+      { "path": "a.gpk", "hash": "aa", "size": 10 },
+      { "path": "c.gpk", "hash": "cc", "size": 30 },
+    ]));
+    let new = manifest("2", json!([
+      { "path": "a.gpk", "hash": "a2", "size": 10, "mtime": 1 },
+      { "path": "b.gpk", "hash": "bb", "size": 20 },
+    ]));
+
+    let diff = build_manifest_diff(&old, &new);
+    let comparison = compare_manifest_entries(&old, &new);
+    let paths = |entries: &serde_json::Value| -> Vec<String> {
+      entries.as_array().unwrap().iter().map(|e| e["path"].as_str().unwrap().to_string()).collect()
+    };
+    assert_eq!(paths(&diff["added"]), comparison.added.iter().map(|f| f.path.clone()).collect::<Vec<_>>());
+    assert_eq!(paths(&diff["changed"]), comparison.changed.iter().map(|f| f.path.clone()).collect::<Vec<_>>());
+    assert_eq!(diff["removed"], json!(comparison.removed.iter().map(|f| f.path.clone()).collect::<Vec<_>>()));
+    assert_eq!(diff["changed"][0], new["files"][0]);
+    assert_eq!((diff["from"].clone(), diff["to"].clone()), (json!("1"), json!("2")));
+  }
+
+  #[tokio::test]
+  async fn gzipped_diffs_are_decompressed() {
+    use std::io::Write as _;
+
+    let diff = json!({ "from": "1", "to": "2", "added": [], "changed": [], "removed": ["a.gpk"] });
+    let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+    encoder.write_all(diff.to_string().as_bytes()).unwrap();
+    let gzipped = encoder.finish().unwrap();
+
+    let url = Url::parse(&serve_file(gzipped.clone()).await).unwrap();
+    assert_eq!(fetch_manifest_json(&url).await.unwrap(), diff);
+
+    let dir = temp_dir();
+    let path = dir.join("diff.json.gz");
+    fs::write(&path, &gzipped).unwrap();
+    assert_eq!(load_manifest_source(path.to_str().unwrap()).await.unwrap(), diff);
+    fs::remove_dir_all(dir).unwrap();
+  }
+
+  #[tokio::test(flavor = "multi_thread")]
+  async fn generation_is_byte_identical_across_runs() {
+    let app = mock_app();
+    let window = mock_window(&app);
+    let dir = temp_dir();
+    let game_path = dir.join("game");
+    for i in 0..64 {
+      let folder = game_path.join("S1Game").join(format!("Folder{}", i % 7));
+      fs::create_dir_all(&folder).unwrap();
+      fs::write(folder.join(format!("File{}.gpk", i)), vec![i as u8; 1000 + i * 37]).unwrap();
+    }
+    let ignore_rules = IgnoreRules::load(&game_path, &[]).unwrap();
+    let previous_entries = HashMap::new();
+    let options = DirectoryHashOptions {
+      with_quick_hash: true,
+      chunk_size: 512,
+      include_mtime: true,
+      url_prefix: Some("https://cdn.example/"),
+      ..hash_options(&ignore_rules, &previous_entries)
+    };
+
+    let generate = |name: &str, compact: bool| {
+      let hashes = hash_game_directory(&window, &game_path, &options).unwrap();
+      let manifest = build_hash_file_manifest("7", 1_700_000_000, options.algo, &hashes.files, hashes.total_size);
+      let path = dir.join(name);
+      write_manifest_file(&path, manifest, None, compact).unwrap();
+      fs::read(path).unwrap()
+    };
+    let first = generate("first.json", true);
+    assert_eq!(first, generate("second.json", true));
+    assert_eq!(generate("pretty-first.json", false), generate("pretty-second.json", false));
+
+    let manifest: serde_json::Value = serde_json::from_slice(&first).unwrap();
+    let paths: Vec<&str> = manifest["files"].as_array().unwrap().iter().map(|f| f["path"].as_str().unwrap()).collect();
+    let mut sorted = paths.clone();
+    sorted.sort();
+    assert_eq!(paths, sorted);
+    assert_eq!(manifest["total_files"], 64);
+    assert_eq!(manifest["total_size"], (0..64).map(|i| 1000 + i * 37).sum::<u64>());
+    assert_eq!(manifest["generator_version"], env!("CARGO_PKG_VERSION"));
+    assert_eq!(manifest["generated_at"], 1_700_000_000);
+    fs::remove_dir_all(dir).unwrap();
+  }
+
+  #[test]
+  fn valid_server_manifest_is_parsed() {
+    let manifest = parse_server_manifest(&json!({ "files": [
+      { "path": "S1Game/a.gpk", "hash": "aa", "size": 10, "url": "files/S1Game/a.gpk" },
+      {
+        "path": "S1Game/b.gpk", "hash": "bb", "size": 20, "url": "files/S1Game/b.gpk",
+        "mtime": 1700000000, "priority": 1, "critical": true, "extra": "ignored",
+      },
+    ] })).unwrap();
+
+    assert_eq!(manifest.files.len(), 2);
+    assert_eq!((manifest.files[0].path.as_str(), manifest.files[0].size, manifest.files[0].critical), ("S1Game/a.gpk", 10, false));
+    assert_eq!((manifest.files[1].mtime, manifest.files[1].priority, manifest.files[1].critical), (Some(1700000000), Some(1), true));
+  }
+
+  #[test]
+  fn server_manifest_missing_a_field_is_rejected() {
+    let error = parse_server_manifest(&json!({ "files": [
+      { "path": "S1Game/a.gpk", "hash": "aa", "size": 10, "url": "files/S1Game/a.gpk" },
+      { "path": "S1Game/b.gpk", "size": 20, "url": "files/S1Game/b.gpk" },
+    ] })).err().unwrap();
+
+    assert!(error.starts_with("Invalid server hash file entry 1: missing field `hash`"), "{}", error);
+    assert!(error.contains("S1Game/b.gpk"), "{}", error);
+    assert!(parse_server_manifest(&json!({ "version": "1" })).is_err());
+  }
+
+  #[test]
+  fn server_manifest_with_a_wrong_type_is_rejected() {
+    let error = parse_server_manifest(&json!({ "files": [
+      { "path": "S1Game/a.gpk", "hash": "aa", "size": "10", "url": "files/S1Game/a.gpk" },
+    ] })).err().unwrap();
+
+    assert!(error.starts_with("Invalid server hash file entry 0: invalid type: string \"10\""), "{}", error);
+    assert!(parse_server_manifest(&json!({ "files": { "path": "S1Game/a.gpk" } })).is_err());
+  }
+
+  #[tokio::test]
+  async fn operations_conflict_until_the_lock_is_released() {
+    let app = mock_app();
+    let handle = app.handle();
+
+    let operation = begin_operation(&handle, LauncherOperation::Verifying).unwrap();
+    operation.switch_to(LauncherOperation::Updating);
+    let conflict = begin_operation(&handle, LauncherOperation::GameRunning).err().unwrap();
+    assert_eq!(conflict, OperationConflict { current: LauncherOperation::Updating });
+    assert_eq!(String::from(conflict), "busy: currently Updating");
+    assert_eq!(prefetch_update(&handle).await, Ok(None));
+
+    drop(operation);
+    assert_eq!(*handle.state::<OperationState>().current.lock().unwrap(), LauncherOperation::Idle);
+    drop(begin_operation(&handle, LauncherOperation::GameRunning).unwrap());
+  }
+
+  #[test]
+  fn local_modifications_are_judged_by_hash() {
+    let dir = temp_dir();
+    let cache = JsonHashCache::open(dir.join("file_cache.json"));
+    let game_path = dir.join("game");
+    let verified = "verified contents".repeat(10);
+    let server = "new server contents".repeat(20);
+    // Both files were verified at an older mtime; one was only touched since.
+    let long_ago = SystemTime::UNIX_EPOCH + Duration::from_secs(1_600_000_000);
+    let check = |path: &str, local: &str| {
+      let local_path = game_path.join(path);
+      fs::create_dir_all(local_path.parent().unwrap()).unwrap();
+      fs::write(&local_path, local).unwrap();
+      cache.insert(path, CachedFileInfo::verified(hash_bytes(verified.as_bytes(), HashAlgorithm::Sha256), long_ago, HashAlgorithm::Sha256));
+      let entry = server_entry(path, &hash_bytes(server.as_bytes(), HashAlgorithm::Sha256), server.len() as u64);
+      check_local_file(&entry, &local_path, &cache, HashAlgorithm::Sha256, false, false, &FileCheckCounters::default()).unwrap()
+    };
+
+    assert!(!check("S1Game/Touched.gpk", &verified).locally_modified);
+    assert!(check("S1Game/Edited.gpk", &verified.to_uppercase()).locally_modified);
+    fs::remove_dir_all(dir).unwrap();
+  }
+
+  #[tokio::test]
+  async fn bad_chunks_are_rejected_without_counting_progress() {
+    let chunk = |offset: u64, size: u64| FileChunk { offset, size, hash: String::new() };
+    assert!(validate_chunks(&[chunk(0, 1024), chunk(1024, 976)], 2000).is_ok());
+    assert!(validate_chunks(&[chunk(0, 0)], 2000).is_err());
+    assert!(validate_chunks(&[chunk(1024, 1024)], 2000).is_err());
+    assert!(validate_chunks(&[chunk(u64::MAX, 2)], 2000).is_err());
+    assert!(validate_chunks(&[chunk(0, MAX_FILE_CHUNK_SIZE + 1)], u64::MAX).is_err());
+
+    let remote: Vec<u8> = (0..2000).map(|i| (i % 251) as u8).collect();
+    let mut chunks = chunk_list(&remote, 1024, HashAlgorithm::Sha256);
+    chunks[1].hash = hash_bytes(b"something else", HashAlgorithm::Sha256);
+    let file_info = remote_file("S1Game/Big.gpk", &serve_file(remote.clone()).await, &remote, chunks.clone());
+    let dir = temp_dir();
+    let file_path = dir.join("Big.gpk");
+    let client = Client::new();
+    let progress = SessionProgress::new(0, 1);
+    let counted = AtomicU64::new(0);
+
+    assert!(download_chunk(&client, &file_info, &file_path, &chunk(0, 0), false, &progress, &counted).await.is_err());
+    assert_eq!(download_chunk(&client, &file_info, &file_path, &chunks[0], false, &progress, &counted).await, Ok(1024));
+    assert!(download_chunk(&client, &file_info, &file_path, &chunks[1], false, &progress, &counted).await.is_err());
+    assert_eq!(progress.downloaded.load(Ordering::SeqCst), 1024);
+    assert_eq!(counted.load(Ordering::SeqCst), 1024);
+    fs::remove_dir_all(dir).unwrap();
+  }
+
+  #[test]
+  fn regions_with_bad_urls_are_dropped() {
+    let regions: Vec<Region> = serde_json::from_value(json!([
+      { "id": "eu", "name": "Europe", "login_url": "https://eu.example/", "file_server_url": "http://cdn.example/files" },
+      { "id": "na", "name": "America" },
+      { "id": "bad", "name": "No scheme", "login_url": "eu.example/login" },
+      { "id": "ftp", "name": "FTP", "hash_file_url": "ftp://cdn.example/hash-file.json" },
+      { "id": "", "name": "No id" },
+    ])).unwrap();
+
+    let ids: Vec<String> = valid_regions(regions).into_iter().map(|region| region.id).collect();
+    assert_eq!(ids, ["eu", "na"]);
+  }
+
+  #[cfg(unix)]
+  #[tokio::test]
+  async fn background_hook_is_stopped_after_the_game() {
+    let hook = LaunchHook {
+      stage: PRE_LAUNCH_HOOK,
+      command: "sleep".to_string(),
+      args: vec!["30".to_string()],
+      working_dir: None,
+      wait_for_exit: false,
+      required: false,
+      timeout: Duration::from_secs(5),
+    };
+    run_launch_hook(&hook).await.unwrap();
+    let pid = BACKGROUND_HOOK.lock().await.as_ref().and_then(|child| child.id()).unwrap();
+    assert!(Path::new(&format!("/proc/{}", pid)).exists());
+
+    stop_background_hook().await;
+    assert!(BACKGROUND_HOOK.lock().await.is_none());
+    assert!(!Path::new(&format!("/proc/{}", pid)).exists());
+  }
+
+  #[test]
+  fn corrupt_playtime_starts_an_empty_record() {
+    let dir = temp_dir();
+    let path = dir.join("playtime.json");
+    fs::write(&path, "{ not json").unwrap();
+    assert!(load_playtime(&path).unwrap().accounts.is_empty());
+    fs::remove_dir_all(dir).unwrap();
+  }
+
+  #[test]
+  fn full_check_hashes_files_the_cache_vouches_for() {
+    let dir = temp_dir();
+    let local_path = dir.join("S1Game/a.dat");
+    fs::create_dir_all(local_path.parent().unwrap()).unwrap();
+    // Same size as the server's copy, so only a hash tells them apart.
+    fs::write(&local_path, b"rotten").unwrap();
+    let server_hash = hash_bytes(b"intact", HashAlgorithm::Sha256);
+    let cache = JsonHashCache::open(dir.join("file_cache.json"));
+    let last_modified = fs::metadata(&local_path).unwrap().modified().unwrap();
+    cache.insert("S1Game/a.dat", CachedFileInfo::verified(server_hash.clone(), last_modified, HashAlgorithm::Sha256));
+    let entry = server_entry("S1Game/a.dat", &server_hash, 6);
+
+    let counters = FileCheckCounters::default();
+    assert!(check_local_file(&entry, &local_path, &cache, HashAlgorithm::Sha256, false, false, &counters).is_none());
+    assert_eq!(counters.cached.load(Ordering::SeqCst), 1);
+
+    let counters = FileCheckCounters::default();
+    let pending = check_local_file(&entry, &local_path, &cache, HashAlgorithm::Sha256, false, true, &counters).unwrap();
+    // It was verified before, so the difference counts as a local change.
+    assert_eq!(pending.reason, Some(UpdateReason::LocallyModified));
+    assert_eq!(counters.cached.load(Ordering::SeqCst), 0);
+    // The entry now records the file as it is.
+    assert_eq!(cache.get("S1Game/a.dat").unwrap().hash, hash_bytes(b"rotten", HashAlgorithm::Sha256));
+    fs::remove_dir_all(dir).unwrap();
+  }
+
+  #[test]
+  fn only_sharing_violations_count_as_file_in_use() {
+    assert!(!is_file_in_use_error(&std::io::Error::from(std::io::ErrorKind::PermissionDenied)));
+    assert_eq!(is_file_in_use_error(&std::io::Error::from_raw_os_error(32)), cfg!(windows));
+    assert_eq!(is_file_in_use_error(&std::io::Error::from_raw_os_error(33)), cfg!(windows));
+  }
+}
@@ -1,7 +1,7 @@
 const { invoke } = window.__TAURI__.tauri;
 const { listen } = window.__TAURI__.event;
 const { appWindow } = window.__TAURI__.window;
-const { message } = window.__TAURI__.dialog;
+const { message, confirm } = window.__TAURI__.dialog;
 
 const REQUIRED_PRIVILEGE_LEVEL = 3;
 const UPDATE_CHECK_ENABLED = true;
@@ -97,6 +97,8 @@ const App = {
     isCheckingForUpdates: false,
     updateCheckPerformed: false,
     isGameLaunching: false,
+    launchId: null,        // id of the latest launch, from game_status_changed
+    modifiedFiles: [],     // paths from the last modified_files_detected
     isAuthenticated: false,
     isFileCheckComplete: false,
     isFirstLaunch: true,
@@ -272,34 +274,61 @@ const App = {
     this.setupErrorListener();
   },
 
+  /**
+   * Whether a game event belongs to a launch older than the latest one seen, e.g. one
+   * delivered late after a quick relaunch.
+   *
+   * @param {{ launch_id?: string | null }} payload - The event payload.
+   * @returns {boolean} True if the event should be ignored.
+   */
+  isStaleLaunchEvent(payload) {
+    const launchId = payload && payload.launch_id;
+    return Boolean(launchId && this.state.launchId && launchId !== this.state.launchId);
+  },
+
   /**
    * Sets up event listeners for game status events from the game server.
    *
    * Listens for the following events:
    *
-   * - `game_status`: emitted when the game status is updated. The event payload is either
-   * `GAME_STATUS_RUNNING` or `GAME_STATUS_NOT_RUNNING`.
-   * - `game_status_changed`: emitted when the game status changes. The event payload is a
-   * boolean indicating whether the game is running or not.
-   * - `game_ended`: emitted when the game has ended. The event payload is empty.
+   * - `game_status`: emitted when the game status is updated. The event payload is
+   * `{ status, launch_id }`.
+   * - `game_status_changed`: emitted when the game status changes. The event payload is
+   * `{ running, phase, session_id, launch_id }`, where `running` tells whether the game is
+   * launching or running and `phase` is the detailed game phase (see `get_game_phase`). Only
+   * the first client (`session_id` 0) drives the UI; extra clients report their exit through
+   * `game_session_ended`.
+   * - `game_ended`: emitted when the game has ended. The event payload is `{ launch_id }`.
+   *
+   * Every launch gets a new `launch_id` (also returned by `get_game_status` and
+   * `get_game_phase`); events of an earlier launch are ignored. It is not called
+   * `session_id` because that name identifies the client in `game_status_changed`.
    *
    * When any of these events are received, the UI is updated to reflect the new game status.
    */
   setupGameStatusListeners() {
     listen("game_status", async (event) => {
       console.log("Game status update:", event.payload);
-      const isRunning = event.payload === "GAME_STATUS_RUNNING";
+      if (this.isStaleLaunchEvent(event.payload)) return;
+      const isRunning = event.payload.status === "GAME_STATUS_RUNNING";
       if (!isRunning) this.setState({ isGameLaunching: false });
       this.updateUIForGameStatus(isRunning);
     });
 
     listen("game_status_changed", (event) => {
-      const isRunning = event.payload;
+      if (event.payload.session_id) return;
+      if (event.payload.phase.state === "launching") {
+        this.setState({ launchId: event.payload.launch_id });
+      } else if (this.isStaleLaunchEvent(event.payload)) {
+        return;
+      }
+      const isRunning = event.payload.running;
       if (!isRunning) this.setState({ isGameLaunching: false });
       this.updateUIForGameStatus(isRunning);
     });
 
-    listen("game_ended", async () => {
+    listen("game_ended", async (event) => {
+      if (this.isStaleLaunchEvent(event.payload)) return;
       console.log("Game has ended");
       this.setState({ isGameLaunching: false });
       this.updateUIForGameStatus(false);
@@ -363,6 +392,8 @@ const App = {
    * object.
    * - `download_complete`: emitted when the download is complete. The event payload is an empty
    * object.
+   * - `modified_files_detected`: emitted by the file check when pending files were changed by the
+   * player. The event payload is `{ files }`; `runPatchSystem` asks before overwriting them.
    *
    * When any of these events are received, the UI is updated to reflect the new download status.
    */
@@ -377,6 +408,10 @@ const App = {
         currentUpdateMode: "complete",
       });
     });
+    listen("modified_files_detected", (event) => {
+      console.log("modified_files_detected:", event.payload.files);
+      this.setState({ modifiedFiles: event.payload.files || [] });
+    });
   },
 
   /**
@@ -1226,6 +1261,38 @@ const App = {
     }
   },
 
+  /**
+   * Asks whether the player's own changes to pending files may be overwritten.
+   *
+   * Uses the paths from the last `modified_files_detected` event, falling back to
+   * the `locally_modified` flag of the pending files.
+   *
+   * @param {Array.<FileInfo>} filesToUpdate - The list of files to update.
+   *
+   * @returns {Promise<boolean>} Whether to pass `overwriteModified` to the download.
+   */
+  async confirmOverwriteModified(filesToUpdate) {
+    const pending = new Set(filesToUpdate.map((file) => file.path));
+    let modified = this.state.modifiedFiles.filter((path) => pending.has(path));
+    if (modified.length === 0) {
+      modified = filesToUpdate.filter((file) => file.locally_modified).map((file) => file.path);
+    }
+    this.setState({ modifiedFiles: [] });
+    if (modified.length === 0) return false;
+
+    const shown = modified.slice(0, 10).join("\n");
+    const more = modified.length > 10 ? `\n(+${modified.length - 10})` : "";
+    try {
+      return await confirm(`${this.t("MODIFIED_FILES_PROMPT")}\n\n${shown}${more}`, {
+        title: this.t("MODIFIED_FILES_TITLE"),
+        type: "warning",
+      });
+    } catch (e) {
+      console.error("Failed to show modified files prompt:", e);
+      return false;
+    }
+  },
+
   /**
    * Runs the patch system to download and install updates.
    *
@@ -1246,6 +1313,8 @@ const App = {
       return;
     }
     try {
+      const overwriteModified = await this.confirmOverwriteModified(filesToUpdate);
+
       console.log("runPatchSystem: Resetting state");
       // Reset state to clear any previous update data
       this.resetState();
@@ -1282,10 +1351,20 @@ const App = {
 
       // Wait for the download to complete (backend emits progress events)
       console.log("runPatchSystem: CALLING invoke(download_all_files)");
-      const downloadedSizes = await invoke("download_all_files", {
+      const summary = await invoke("download_all_files", {
         filesToUpdate: filesToUpdate,
+        overwriteModified: overwriteModified,
       });
 
+      // Files left alone (kept modifications, protected files) mean the game
+      // isn't fully up to date, so don't report the update as done.
+      if (summary.files_failed > 0 || summary.protected_files > 0 || summary.modified_files > 0) {
+        console.log("runPatchSystem: download finished with skipped files", summary);
+        this.setState({ isUpdateComplete: false, currentUpdateMode: "ready", shouldLaunchAfterUpdate: false });
+        this.showErrorMessage(this.t("UPDATE_INCOMPLETE"));
+        return;
+      }
+
       // Download completed, handled by event listeners for progress updates
       console.log("runPatchSystem: invoke(download_all_files) completed, calling handleCompletion()");
       this.handleCompletion();
@@ -1587,8 +1666,8 @@ const App = {
       await this.loadTranslations();
       await this.updateAllUIElements();
 
-      const isGameRunning = await invoke("get_game_status");
-      this.setState({ isGameRunning: isGameRunning });
+      const { running } = await invoke("get_game_status");
+      this.setState({ isGameRunning: running });
     }
   },
 
@@ -1767,6 +1846,11 @@ const App = {
       if (error.toString().includes("MAINTENANCE_ACTIVE")) {
         console.log("Launch blocked by MAINTENANCE_ACTIVE error from backend.");
         // maintenance modal was already shown by the listener
+      } else if (error.toString().startsWith("NotAuthenticated")) {
+        // The backend refused to start the game with missing login info.
+        this.showErrorModal(this.t("ERROR"), this.t("LOGIN_REQUIRED"));
+      } else if (error.toString().startsWith("ExecutableIntegrityFailed")) {
+        this.showErrorModal(this.t("ERROR"), this.t("EXECUTABLE_INTEGRITY_FAILED"));
       } else {
         // Real game launch error (e.g., missing file, internal game error)
         this.showErrorModal(this.t("ERROR"), game_launch_error);
@@ -1790,16 +1874,18 @@ const App = {
   /**
    * Updates the game status UI based on the current game status.
    *
-   * The game status is retrieved by invoking the "get_game_status" command.
-   * If the command fails, an error is logged and the game status is set to
+   * The game status is retrieved by invoking the "get_game_status" command,
+   * which returns `{ running, launch_id }`; the launch id is kept so later
+   * events of that launch aren't treated as stale. If the command fails, an error is logged and the game status is set to
    * "GAME_STATUS_ERROR".
    *
    * @memberof App
    */
   async updateGameStatus() {
     try {
-      const isRunning = await invoke("get_game_status");
-      this.updateUIForGameStatus(isRunning);
+      const { running, launch_id } = await invoke("get_game_status");
+      if (running && launch_id) this.setState({ launchId: launch_id });
+      this.updateUIForGameStatus(running);
     } catch (error) {
       console.error("Error checking game status:", error);
       if (this.statusEl)
@@ -1922,8 +2008,8 @@ const App = {
    */
   async isGameRunning() {
     try {
-      const isRunning = await invoke("get_game_status");
-      return isRunning;
+      const { running } = await invoke("get_game_status");
+      return running;
     } catch (error) {
       console.error("Error checking game status:", error);
       return false;
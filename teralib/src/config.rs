@@ -15,3 +15,38 @@ pub fn get_config_value(key: &str) -> String {
         .unwrap_or_else(|| panic!("{} must be set in config.json", key))
         .to_string()
 }
+
+/// Like `get_config_value`, but for keys that may be absent. Empty values
+/// count as unset.
+pub fn get_optional_config_value(key: &str) -> Option<String> {
+    CONFIG_JSON[key]
+        .as_str()
+        .filter(|v| !v.is_empty())
+        .map(|v| v.to_string())
+}
+
+/// A list setting, given either as a JSON array of strings or as one
+/// comma-separated string. Absent keys and empty items are dropped.
+pub fn get_config_list(key: &str) -> Vec<String> {
+    match &CONFIG_JSON[key] {
+        Value::Array(items) => items
+            .iter()
+            .filter_map(|v| v.as_str())
+            .map(str::trim)
+            .filter(|v| !v.is_empty())
+            .map(str::to_string)
+            .collect(),
+        Value::String(items) => items
+            .split(',')
+            .map(str::trim)
+            .filter(|v| !v.is_empty())
+            .map(str::to_string)
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// A raw config entry, for structured values such as lists of objects.
+pub fn get_config_json(key: &str) -> Option<Value> {
+    CONFIG_JSON.get(key).filter(|v| !v.is_null()).cloned()
+}
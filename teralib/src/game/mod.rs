@@ -2,12 +2,13 @@
 use crate::config;
 #[cfg(windows)]
 use lazy_static::lazy_static;
-use log::{error, info, warn, Level, Metadata, Record};
+use log::{debug, error, info, warn, Level, Metadata, Record};
 use once_cell::sync::Lazy;
 use std::{
-    collections::HashMap,
+    collections::{HashMap, VecDeque},
     process::ExitStatus,
-    sync::atomic::{AtomicBool, Ordering},
+    sync::atomic::{AtomicBool, AtomicU32, Ordering},
+    time::Duration as StdDuration,
 };
 use tokio::sync::{mpsc as other_mpsc, watch};
 
@@ -24,7 +25,6 @@ use serde_json::Value;
 use std::{
     ffi::OsStr,
     os::windows::ffi::OsStrExt,
-    process::Command,
     ptr::null_mut,
     slice,
     sync::{mpsc, Arc, Mutex, RwLock},
@@ -40,7 +40,9 @@ use winapi::{
     },
     um::{
         errhandlingapi::GetLastError,
+        handleapi::CloseHandle,
         libloaderapi::GetModuleHandleW,
+        shellapi::{ShellExecuteExW, SHELLEXECUTEINFOW},
         winuser::{GetClassInfoExW, *},
     },
 };
@@ -143,6 +145,41 @@ static WINDOW_HANDLE: Lazy<Mutex<Option<SafeHWND>>> = Lazy::new(|| Mutex::new(No
 /// across multiple threads.
 static GAME_RUNNING: Lazy<AtomicBool> = Lazy::new(|| AtomicBool::new(false));
 
+/// PID of the spawned game process (the bridge under Wine), 0 when none.
+static GAME_PID: AtomicU32 = AtomicU32::new(0);
+
+/// Whether the game's stdout and stderr are streamed into the log. See
+/// `set_capture_output`.
+static CAPTURE_OUTPUT: AtomicBool = AtomicBool::new(true);
+
+/// The launcher's configured HTTP client, used for the server list. See
+/// `set_http_client`.
+static HTTP_CLIENT: Lazy<std::sync::Mutex<Option<reqwest::Client>>> =
+    Lazy::new(|| std::sync::Mutex::new(None));
+
+/// The last `GAME_OUTPUT_TAIL_LINES` lines the first client wrote.
+static LAST_GAME_OUTPUT: Lazy<std::sync::Mutex<VecDeque<String>>> =
+    Lazy::new(|| std::sync::Mutex::new(VecDeque::new()));
+
+const GAME_OUTPUT_TAIL_LINES: usize = 50;
+/// Longer lines are cut in the log and the tail.
+const GAME_OUTPUT_MAX_LINE_LEN: usize = 500;
+/// Lines logged per second and stream; the rest only reach the tail.
+const GAME_OUTPUT_LINES_PER_SEC: u32 = 20;
+
+/// Clients started by `run_additional_client`, by session id, with the PID of
+/// their Tera.exe on Windows and of their bridge process elsewhere (0 until
+/// spawned).
+static EXTRA_SESSIONS: Lazy<std::sync::Mutex<HashMap<u64, u32>>> =
+    Lazy::new(|| std::sync::Mutex::new(HashMap::new()));
+
+/// Account name and ticket of each extra client, by the PID of its Tera.exe.
+/// The IPC window answers a client's requests from here, and from
+/// `GLOBAL_CREDENTIALS` for the first client.
+#[cfg(windows)]
+static CLIENT_CREDENTIALS: Lazy<std::sync::Mutex<HashMap<u32, (String, String)>>> =
+    Lazy::new(|| std::sync::Mutex::new(HashMap::new()));
+
 /// Sender for game status updates.
 ///
 /// This channel sender is used to broadcast changes in the game's running state
@@ -248,9 +285,169 @@ pub fn setup_logging() -> (TeraLogger, other_mpsc::Receiver<String>) {
     (TeraLogger { sender }, receiver)
 }
 
+/// Launches the game with the default command line. See `run_game_with_args`.
+pub async fn run_game(
+    account_name: &str,
+    characters_count: &str,
+    ticket: &str,
+    game_lang: &str,
+    game_path: &str,
+    acts_map: HashMap<String, String>,
+    pages_map: HashMap<String, String>,
+) -> Result<ExitStatus, Box<dyn std::error::Error>> {
+    run_game_with_args(
+        account_name,
+        characters_count,
+        ticket,
+        game_lang,
+        game_path,
+        acts_map,
+        pages_map,
+        Vec::new(),
+    )
+    .await
+}
+
+/// Logs the game command line, with the ticket redacted should it ever
+/// appear in an argument.
+fn log_game_command_line(game_path: &str, args: &[String], ticket: &str) {
+    let redacted: Vec<String> = args
+        .iter()
+        .map(|arg| {
+            if ticket.is_empty() {
+                arg.clone()
+            } else {
+                arg.replace(ticket, "<redacted>")
+            }
+        })
+        .collect();
+    info!("Game command line: \"{}\" {}", game_path, redacted.join(" "));
+}
+
+/// Launches the game with `extra_args` appended to its command line. See
+/// `run_game_with_env`.
+#[allow(clippy::too_many_arguments)]
+pub async fn run_game_with_args(
+    account_name: &str,
+    characters_count: &str,
+    ticket: &str,
+    game_lang: &str,
+    game_path: &str,
+    acts_map: HashMap<String, String>,
+    pages_map: HashMap<String, String>,
+    extra_args: Vec<String>,
+) -> Result<ExitStatus, Box<dyn std::error::Error>> {
+    run_game_with_env(
+        account_name,
+        characters_count,
+        ticket,
+        game_lang,
+        game_path,
+        acts_map,
+        pages_map,
+        extra_args,
+        Vec::new(),
+    )
+    .await
+}
+
+/// Logs the names of the variables set on the game process; their values
+/// only at debug level.
+fn log_game_env(env: &[(String, String)]) {
+    if env.is_empty() {
+        return;
+    }
+    let names: Vec<&str> = env.iter().map(|(name, _)| name.as_str()).collect();
+    info!("Game environment overrides: {}", names.join(", "));
+    for (name, value) in env {
+        debug!("Game environment: {}={}", name, value);
+    }
+}
+
+/// Makes the following launches stream the game's stdout and stderr into
+/// the log line by line, prefixed with `[game]`. On by default; when off,
+/// only stderr is kept for `get_last_game_stderr`.
+pub fn set_capture_output(enabled: bool) {
+    CAPTURE_OUTPUT.store(enabled, Ordering::SeqCst);
+}
+
+/// Sets the HTTP client the server list is fetched with, so the request
+/// carries the launcher's User-Agent, headers and TLS settings.
+pub fn set_http_client(client: reqwest::Client) {
+    *HTTP_CLIENT.lock().unwrap() = Some(client);
+}
+
+/// The last lines the first client wrote to stdout or stderr during its
+/// last session, oldest first.
+pub fn get_last_game_output() -> Vec<String> {
+    LAST_GAME_OUTPUT.lock().unwrap().iter().cloned().collect()
+}
+
+/// Logs the lines of one output stream of the game, rate limited so a
+/// client that spews can't flood the log.
+struct GameOutputLog {
+    prefix: String,
+    /// Whether lines go to `LAST_GAME_OUTPUT`; only the first client's do.
+    keep_tail: bool,
+    window_start: std::time::Instant,
+    logged_in_window: u32,
+    suppressed: u64,
+}
+
+impl GameOutputLog {
+    fn new(prefix: String, keep_tail: bool) -> Self {
+        GameOutputLog {
+            prefix,
+            keep_tail,
+            window_start: std::time::Instant::now(),
+            logged_in_window: 0,
+            suppressed: 0,
+        }
+    }
+
+    fn push(&mut self, raw: &[u8]) {
+        let line = String::from_utf8_lossy(raw);
+        let line = line.trim_end();
+        if line.is_empty() {
+            return;
+        }
+        let line: String = line.chars().take(GAME_OUTPUT_MAX_LINE_LEN).collect();
+
+        if self.keep_tail {
+            let mut tail = LAST_GAME_OUTPUT.lock().unwrap();
+            tail.push_back(line.clone());
+            while tail.len() > GAME_OUTPUT_TAIL_LINES {
+                tail.pop_front();
+            }
+        }
+
+        if self.window_start.elapsed() >= StdDuration::from_secs(1) {
+            self.flush_suppressed();
+            self.window_start = std::time::Instant::now();
+            self.logged_in_window = 0;
+        }
+        if self.logged_in_window < GAME_OUTPUT_LINES_PER_SEC {
+            self.logged_in_window += 1;
+            info!("{} {}", self.prefix, line);
+        } else {
+            self.suppressed += 1;
+        }
+    }
+
+    fn flush_suppressed(&mut self) {
+        if self.suppressed > 0 {
+            warn!("{} {} line(s) of output not logged", self.prefix, self.suppressed);
+            self.suppressed = 0;
+        }
+    }
+}
+
 /// Windows implementation: uses Win32 IPC to communicate with Tera.exe.
+/// `extra_args` are appended to the Tera.exe command line and `env` is set
+/// on the Tera.exe process only.
 #[cfg(windows)]
-pub async fn run_game(
+#[allow(clippy::too_many_arguments)]
+pub async fn run_game_with_env(
     account_name: &str,
     characters_count: &str,
     ticket: &str,
@@ -258,6 +455,8 @@ pub async fn run_game(
     game_path: &str,
     acts_map: HashMap<String, String>,
     pages_map: HashMap<String, String>,
+    extra_args: Vec<String>,
+    env: Vec<(String, String)>,
 ) -> Result<ExitStatus, Box<dyn std::error::Error>> {
     info!("Starting run_game function");
 
@@ -297,13 +496,17 @@ pub async fn run_game(
         );
     }
 
-    launch_game().await
+    launch_game(extra_args, env).await
 }
 
 /// Linux implementation: delegates Win32 IPC to launcher-bridge.exe running under Wine.
 /// The native launcher communicates with the bridge via stdin/stdout pipes.
+/// `extra_args` are handed to the bridge, which appends them to the Tera.exe
+/// command line; `env` is set on the Wine process, which passes it on to
+/// Tera.exe.
 #[cfg(not(windows))]
-pub async fn run_game(
+#[allow(clippy::too_many_arguments)]
+pub async fn run_game_with_env(
     account_name: &str,
     characters_count: &str,
     ticket: &str,
@@ -311,181 +514,397 @@ pub async fn run_game(
     game_path: &str,
     acts_map: HashMap<String, String>,
     pages_map: HashMap<String, String>,
+    extra_args: Vec<String>,
+    env: Vec<(String, String)>,
 ) -> Result<ExitStatus, Box<dyn std::error::Error>> {
-    use tokio::{
-        io::{AsyncBufReadExt, AsyncWriteExt, BufReader},
-        process::Command,
-    };
-
     if is_game_running() {
         return Err("Game is already running".into());
     }
 
     GAME_RUNNING.store(true, Ordering::SeqCst);
     let _ = GAME_STATUS_SENDER.send(true);
+    let running = RunningGuard;
+    LAST_GAME_OUTPUT.lock().unwrap().clear();
+
+    let credentials = bridge_credentials(
+        account_name,
+        characters_count,
+        ticket,
+        game_lang,
+        game_path,
+        acts_map,
+        pages_map,
+        extra_args,
+    );
+    let status = run_bridge(credentials, ticket, &env, None, |pid| GAME_PID.store(pid, Ordering::SeqCst)).await;
+    drop(running);
+    status
+}
 
-    // Find launcher-bridge.exe next to the native launcher binary
-    let exe_path = std::env::current_exe()?;
-    let exe_dir = exe_path.parent().ok_or("No parent directory for launcher executable")?;
-    let bridge_path = exe_dir.join("launcher-bridge.exe");
+/// Starts another game client while one is already running, for players
+/// running several accounts at once. The client is tracked under
+/// `session_id` until it exits.
+///
+/// On Windows Tera.exe is spawned directly and talks to the first client's
+/// IPC window, which tells the clients apart by process. Elsewhere each extra
+/// client gets its own launcher-bridge.exe under Wine, which owns its IPC
+/// window.
+#[allow(clippy::too_many_arguments)]
+pub async fn run_additional_client(
+    session_id: u64,
+    account_name: &str,
+    characters_count: &str,
+    ticket: &str,
+    game_lang: &str,
+    game_path: &str,
+    acts_map: HashMap<String, String>,
+    pages_map: HashMap<String, String>,
+    extra_args: Vec<String>,
+    env: Vec<(String, String)>,
+) -> Result<ExitStatus, Box<dyn std::error::Error>> {
+    EXTRA_SESSIONS.lock().unwrap().insert(session_id, 0);
+    #[cfg(windows)]
+    let status = {
+        // The IPC window already serves the first client's maps, and the
+        // character count only matters to the bridge.
+        let _ = (characters_count, acts_map, pages_map);
+        run_native_client(session_id, account_name, ticket, game_lang, game_path, extra_args, &env).await
+    };
+    #[cfg(not(windows))]
+    let status = {
+        let credentials = bridge_credentials(
+            account_name,
+            characters_count,
+            ticket,
+            game_lang,
+            game_path,
+            acts_map,
+            pages_map,
+            extra_args,
+        );
+        run_bridge(credentials, ticket, &env, Some(session_id), |pid| {
+            EXTRA_SESSIONS.lock().unwrap().insert(session_id, pid);
+        })
+        .await
+    };
+    EXTRA_SESSIONS.lock().unwrap().remove(&session_id);
+    info!("Additional client {} exited: {:?}", session_id, status);
+    status
+}
 
-    if !bridge_path.exists() {
-        GAME_RUNNING.store(false, Ordering::SeqCst);
-        let _ = GAME_STATUS_SENDER.send(false);
-        return Err(format!(
-            "launcher-bridge.exe not found at {:?}. Place it next to the launcher binary.",
-            bridge_path
-        ).into());
+/// Windows-only: Spawns an extra Tera.exe and waits for it. The process is
+/// killed if the future is dropped.
+#[cfg(windows)]
+async fn run_native_client(
+    session_id: u64,
+    account_name: &str,
+    ticket: &str,
+    game_lang: &str,
+    game_path: &str,
+    extra_args: Vec<String>,
+    env: &[(String, String)],
+) -> Result<ExitStatus, Box<dyn std::error::Error>> {
+    if WINDOW_HANDLE.lock().unwrap().is_none() {
+        return Err("The launcher IPC window is not open; start the first client first".into());
+    }
+
+    let mut game_args = vec![format!("-LANGUAGEEXT={}", game_lang)];
+    game_args.extend(extra_args);
+    log_game_command_line(game_path, &game_args, ticket);
+    log_game_env(env);
+
+    let capture = CAPTURE_OUTPUT.load(Ordering::SeqCst);
+    let mut command = tokio::process::Command::new(game_path);
+    command
+        .args(&game_args)
+        .envs(env.iter().map(|(name, value)| (name, value)))
+        .kill_on_drop(true);
+    if capture {
+        command
+            .stdout(std::process::Stdio::piped())
+            .stderr(std::process::Stdio::piped());
+    }
+    let mut child = command.spawn()?;
+    let pid = child.id().unwrap_or(0);
+    EXTRA_SESSIONS.lock().unwrap().insert(session_id, pid);
+    CLIENT_CREDENTIALS
+        .lock()
+        .unwrap()
+        .insert(pid, (account_name.to_string(), ticket.to_string()));
+    info!("Additional client {} spawned with PID: {}", session_id, pid);
+
+    let prefix = format!("[game {}]", session_id);
+    let mut output_tasks = Vec::new();
+    if let Some(stdout) = child.stdout.take() {
+        output_tasks.push(spawn_output_reader(stdout, Some(GameOutputLog::new(prefix.clone(), false)), false));
+    }
+    if let Some(stderr) = child.stderr.take() {
+        output_tasks.push(spawn_output_reader(stderr, Some(GameOutputLog::new(prefix, false)), false));
     }
 
+    let status = child.wait().await;
+    CLIENT_CREDENTIALS.lock().unwrap().remove(&pid);
+    for task in output_tasks {
+        let _ = task.await;
+    }
+    Ok(status?)
+}
+
+/// Reads an output pipe of a child process until it closes, into `log` if
+/// given. Resolves with everything read when `collect` is set, otherwise
+/// with an empty string.
+fn spawn_output_reader(
+    stream: impl tokio::io::AsyncRead + Unpin + Send + 'static,
+    mut log: Option<GameOutputLog>,
+    collect: bool,
+) -> tokio::task::JoinHandle<String> {
+    use tokio::io::{AsyncBufReadExt, BufReader};
+
+    tokio::spawn(async move {
+        let mut reader = BufReader::new(stream);
+        let mut collected = String::new();
+        let mut buf = Vec::new();
+        loop {
+            buf.clear();
+            match reader.read_until(b'\n', &mut buf).await {
+                Ok(0) | Err(_) => break,
+                Ok(_) => {
+                    if collect {
+                        collected.push_str(&String::from_utf8_lossy(&buf));
+                    }
+                    if let Some(log) = log.as_mut() {
+                        log.push(&buf);
+                    }
+                }
+            }
+        }
+        if let Some(log) = log.as_mut() {
+            log.flush_suppressed();
+        }
+        collected
+    })
+}
+
+/// The JSON object launcher-bridge.exe reads from stdin.
+#[cfg(not(windows))]
+#[allow(clippy::too_many_arguments)]
+fn bridge_credentials(
+    account_name: &str,
+    characters_count: &str,
+    ticket: &str,
+    game_lang: &str,
+    game_path: &str,
+    acts_map: HashMap<String, String>,
+    pages_map: HashMap<String, String>,
+    extra_args: Vec<String>,
+) -> serde_json::Value {
     // Convert Linux absolute path to Wine Z: path (Wine maps / to Z:\)
-    let wine_game_path = if game_path.starts_with('/') {
+    let bridge_game_path = if game_path.starts_with('/') {
         format!("Z:{}", game_path.replace('/', "\\"))
     } else {
         game_path.to_string()
     };
+    info!("Bridge game path: {}", bridge_game_path);
+
+    let mut game_args = vec![format!("-LANGUAGEEXT={}", game_lang)];
+    game_args.extend(extra_args.iter().cloned());
+    log_game_command_line(&bridge_game_path, &game_args, ticket);
 
     // Get server list URL from embedded config
     let server_list_url = config::get_config_value("SERVER_LIST_URL");
 
-    // Serialize credentials for the bridge
-    let credentials = serde_json::json!({
+    serde_json::json!({
         "account_name": account_name,
         "characters_count": characters_count,
         "ticket": ticket,
         "game_lang": game_lang,
-        "game_path": wine_game_path,
+        "game_path": bridge_game_path,
         "server_list_url": server_list_url,
         "acts_map": acts_map,
         "pages_map": pages_map,
-    });
-
-    // Use WINE env var, or prefer wine64 (launcher-bridge.exe is a 64-bit PE),
-    // falling back to plain "wine" if wine64 is not on PATH.
-    let wine_bin = std::env::var("WINE").unwrap_or_else(|_| {
-        if std::process::Command::new("wine64")
-            .arg("--version")
-            .stdout(std::process::Stdio::null())
-            .stderr(std::process::Stdio::null())
-            .status()
-            .map(|s| s.success())
-            .unwrap_or(false)
-        {
-            "wine64".to_string()
-        } else {
-            "wine".to_string()
-        }
-    });
-    info!("Spawning launcher-bridge.exe via '{}'", wine_bin);
-    info!("Wine game path: {}", wine_game_path);
-
-    // Resolve WINEPREFIX: must be an absolute path.
-    // If the env var is absent or not absolute, fall back to ~/tera-wine.
-    let wine_prefix = std::env::var("WINEPREFIX")
-        .ok()
-        .filter(|p| std::path::Path::new(p).is_absolute())
-        .unwrap_or_else(|| {
-            let home = std::env::var("HOME").unwrap_or_else(|_| "/root".to_string());
-            format!("{}/tera-wine", home)
-        });
+        "extra_args": extra_args,
+    })
+}
 
-    // launcher-bridge.exe is a 64-bit Windows binary; force wine to use a
-    // win64 prefix.  Allow the caller to override via WINEARCH if needed.
-    let wine_arch = std::env::var("WINEARCH").unwrap_or_else(|_| "win64".to_string());
-
-    info!("Using WINEPREFIX: {}", wine_prefix);
-    info!("Using WINEARCH: {}", wine_arch);
-
-    // Ensure the Wine prefix is properly initialised AND is a win64 prefix.
-    // A win64 prefix contains drive_c/windows/syswow64/; a win32 prefix does
-    // not.  Running wine64 against a win32 prefix causes STATUS_DLL_NOT_FOUND
-    // (c0000135) for kernel32.dll.  If we detect a win32 prefix we remove it
-    // and recreate it as win64 — it contains no user data at this stage.
-    let prefix_path = std::path::Path::new(&wine_prefix);
-    let syswow64   = prefix_path.join("drive_c/windows/syswow64");
-    let kernel32   = prefix_path.join("drive_c/windows/system32/kernel32.dll");
-
-    let needs_init = if kernel32.exists() && !syswow64.exists() {
-        // Prefix exists but is win32 — wipe it and start fresh as win64
-        warn!(
-            "Wine prefix at {:?} is a win32 prefix but wine64 needs win64 — removing and recreating …",
-            prefix_path
-        );
-        if let Err(e) = std::fs::remove_dir_all(prefix_path) {
-            return Err(format!(
-                "Failed to remove stale win32 Wine prefix at {:?}: {}. \
-                 Please delete it manually and re-launch.",
-                prefix_path, e
-            ).into());
-        }
-        true
-    } else {
-        !kernel32.exists()
+/// Runs launcher-bridge.exe from next to the launcher binary under Wine,
+/// hands it `credentials` and relays its events until it exits. The bridge
+/// and the game it starts get `env`. `session_id` is `None` for the first
+/// client and names extra ones in logged output. `on_spawn` receives the
+/// bridge's PID.
+#[cfg(not(windows))]
+async fn run_bridge(
+    credentials: serde_json::Value,
+    ticket: &str,
+    env: &[(String, String)],
+    session_id: Option<u64>,
+    on_spawn: impl FnOnce(u32),
+) -> Result<ExitStatus, Box<dyn std::error::Error>> {
+    use tokio::{
+        io::{AsyncBufReadExt, AsyncWriteExt, BufReader},
+        process::Command,
     };
 
-    if needs_init {
-        info!("Initialising Wine prefix as win64 at {:?} …", prefix_path);
-        let display = std::env::var("DISPLAY").unwrap_or_default();
+    // Find launcher-bridge.exe next to the native launcher binary
+    let exe_path = std::env::current_exe()?;
+    let exe_dir = exe_path.parent().ok_or("No parent directory for launcher executable")?;
+    let bridge_path = exe_dir.join("launcher-bridge.exe");
 
-        let boot_status = std::process::Command::new(&wine_bin)
-            .args(["wineboot", "--init"])
-            .env("WINEPREFIX", &wine_prefix)
-            .env("WINEARCH", &wine_arch)
-            .env("DISPLAY", &display)
-            .status();
-        match boot_status {
-            Ok(s) if s.success() => info!("wineboot --init completed successfully"),
-            Ok(s) => warn!("wineboot --init exited with status {}", s),
-            Err(e) => warn!("wineboot --init failed to spawn: {}", e),
-        }
+    if !bridge_path.exists() {
+        return Err(format!(
+            "launcher-bridge.exe not found at {:?}. Place it next to the launcher binary.",
+            bridge_path
+        ).into());
+    }
 
-        // Wait for wineserver to settle before installing components
-        let _ = std::process::Command::new("wineserver")
-            .args(["-w"])
-            .env("WINEPREFIX", &wine_prefix)
-            .status();
-
-        // Install runtime components required by Tera.exe.
-        // winetricks is optional — skip gracefully if not installed.
-        let has_winetricks = std::process::Command::new("winetricks")
-            .arg("--version")
-            .stdout(std::process::Stdio::null())
-            .stderr(std::process::Stdio::null())
-            .status()
-            .map(|s| s.success())
-            .unwrap_or(false);
-
-        if has_winetricks {
-            for component in &["vcrun2013", "vcrun2019", "d3dx9"] {
-                info!("Installing Wine component via winetricks: {} …", component);
-                let st = std::process::Command::new("winetricks")
-                    .args(["-q", component])
-                    .env("WINEPREFIX", &wine_prefix)
-                    .env("WINEARCH", &wine_arch)
-                    .env("DISPLAY", &display)
-                    .status();
-                match st {
-                    Ok(s) if s.success() => info!("winetricks {} installed", component),
-                    Ok(s) => warn!("winetricks {} exited with status {} (may already be present)", component, s),
-                    Err(e) => warn!("winetricks {} failed to spawn: {}", component, e),
-                }
+    let mut command = {
+        // Use WINE env var, or prefer wine64 (launcher-bridge.exe is a 64-bit PE),
+        // falling back to plain "wine" if wine64 is not on PATH.
+        let wine_bin = std::env::var("WINE").unwrap_or_else(|_| {
+            if std::process::Command::new("wine64")
+                .arg("--version")
+                .stdout(std::process::Stdio::null())
+                .stderr(std::process::Stdio::null())
+                .status()
+                .map(|s| s.success())
+                .unwrap_or(false)
+            {
+                "wine64".to_string()
+            } else {
+                "wine".to_string()
             }
-        } else {
+        });
+        info!("Spawning launcher-bridge.exe via '{}'", wine_bin);
+
+        // Resolve WINEPREFIX: must be an absolute path.
+        // If the env var is absent or not absolute, fall back to ~/tera-wine.
+        let wine_prefix = std::env::var("WINEPREFIX")
+            .ok()
+            .filter(|p| std::path::Path::new(p).is_absolute())
+            .unwrap_or_else(|| {
+                let home = std::env::var("HOME").unwrap_or_else(|_| "/root".to_string());
+                format!("{}/tera-wine", home)
+            });
+
+        // launcher-bridge.exe is a 64-bit Windows binary; force wine to use a
+        // win64 prefix.  Allow the caller to override via WINEARCH if needed.
+        let wine_arch = std::env::var("WINEARCH").unwrap_or_else(|_| "win64".to_string());
+
+        info!("Using WINEPREFIX: {}", wine_prefix);
+        info!("Using WINEARCH: {}", wine_arch);
+
+        // Ensure the Wine prefix is properly initialised AND is a win64 prefix.
+        // A win64 prefix contains drive_c/windows/syswow64/; a win32 prefix does
+        // not.  Running wine64 against a win32 prefix causes STATUS_DLL_NOT_FOUND
+        // (c0000135) for kernel32.dll.  If we detect a win32 prefix we remove it
+        // and recreate it as win64 — it contains no user data at this stage.
+        let prefix_path = std::path::Path::new(&wine_prefix);
+        let syswow64   = prefix_path.join("drive_c/windows/syswow64");
+        let kernel32   = prefix_path.join("drive_c/windows/system32/kernel32.dll");
+
+        let needs_init = if kernel32.exists() && !syswow64.exists() {
+            // Prefix exists but is win32 — wipe it and start fresh as win64
             warn!(
-                "winetricks not found — skipping vcrun2013/vcrun2019/d3dx9 install. \
-                 Tera.exe may fail to start. Run `install-linux-deps.sh` to install it."
+                "Wine prefix at {:?} is a win32 prefix but wine64 needs win64 — removing and recreating …",
+                prefix_path
             );
+            if let Err(e) = std::fs::remove_dir_all(prefix_path) {
+                return Err(format!(
+                    "Failed to remove stale win32 Wine prefix at {:?}: {}. \
+                     Please delete it manually and re-launch.",
+                    prefix_path, e
+                ).into());
+            }
+            true
+        } else {
+            !kernel32.exists()
+        };
+
+        if needs_init {
+            info!("Initialising Wine prefix as win64 at {:?} …", prefix_path);
+            let display = std::env::var("DISPLAY").unwrap_or_default();
+
+            let boot_status = std::process::Command::new(&wine_bin)
+                .args(["wineboot", "--init"])
+                .env("WINEPREFIX", &wine_prefix)
+                .env("WINEARCH", &wine_arch)
+                .env("DISPLAY", &display)
+                .status();
+            match boot_status {
+                Ok(s) if s.success() => info!("wineboot --init completed successfully"),
+                Ok(s) => warn!("wineboot --init exited with status {}", s),
+                Err(e) => warn!("wineboot --init failed to spawn: {}", e),
+            }
+
+            // Wait for wineserver to settle before installing components
+            let _ = std::process::Command::new("wineserver")
+                .args(["-w"])
+                .env("WINEPREFIX", &wine_prefix)
+                .status();
+
+            // Install runtime components required by Tera.exe.
+            // winetricks is optional — skip gracefully if not installed.
+            let has_winetricks = std::process::Command::new("winetricks")
+                .arg("--version")
+                .stdout(std::process::Stdio::null())
+                .stderr(std::process::Stdio::null())
+                .status()
+                .map(|s| s.success())
+                .unwrap_or(false);
+
+            if has_winetricks {
+                for component in &["vcrun2013", "vcrun2019", "d3dx9"] {
+                    info!("Installing Wine component via winetricks: {} …", component);
+                    let st = std::process::Command::new("winetricks")
+                        .args(["-q", component])
+                        .env("WINEPREFIX", &wine_prefix)
+                        .env("WINEARCH", &wine_arch)
+                        .env("DISPLAY", &display)
+                        .status();
+                    match st {
+                        Ok(s) if s.success() => info!("winetricks {} installed", component),
+                        Ok(s) => warn!("winetricks {} exited with status {} (may already be present)", component, s),
+                        Err(e) => warn!("winetricks {} failed to spawn: {}", component, e),
+                    }
+                }
+            } else {
+                warn!(
+                    "winetricks not found — skipping vcrun2013/vcrun2019/d3dx9 install. \
+                     Tera.exe may fail to start. Run `install-linux-deps.sh` to install it."
+                );
+            }
         }
-    }
 
-    let mut child = Command::new(&wine_bin)
-        .arg(&bridge_path)
-        .env("WINEPREFIX", &wine_prefix)
-        .env("WINEARCH", &wine_arch)
+        let mut command = Command::new(&wine_bin);
+        command
+            .arg(&bridge_path)
+            .env("WINEPREFIX", &wine_prefix)
+            .env("WINEARCH", &wine_arch);
+        command
+    };
+
+    log_game_env(env);
+    let capture = CAPTURE_OUTPUT.load(Ordering::SeqCst);
+    if capture {
+        command.stderr(std::process::Stdio::piped());
+    }
+    let mut child = command
+        .envs(env.iter().map(|(name, value)| (name, value)))
         .stdin(std::process::Stdio::piped())
         .stdout(std::process::Stdio::piped())
+        .kill_on_drop(true)
         .spawn()
-        .map_err(|e| format!("Failed to spawn wine launcher-bridge.exe: {}", e))?;
+        .map_err(|e| format!("Failed to spawn launcher-bridge.exe: {}", e))?;
+    on_spawn(child.id().unwrap_or(0));
+
+    // The bridge's stdout carries its events; the game's own output comes
+    // through its stderr.
+    let stderr_task = child.stderr.take().map(|stderr| {
+        let prefix = match session_id {
+            Some(id) => format!("[game {}]", id),
+            None => "[game]".to_string(),
+        };
+        spawn_output_reader(stderr, Some(GameOutputLog::new(prefix, session_id.is_none())), false)
+    });
 
     // Write JSON credentials to bridge stdin asynchronously, then close it
     {
@@ -508,7 +927,8 @@ pub async fn run_game(
                                 let _ = std::process::Command::new("xdg-open").arg(url).spawn();
                             }
                         }
-                        _ => info!("Bridge event: {}", line_str),
+                        _ if ticket.is_empty() => info!("Bridge event: {}", line_str),
+                        _ => info!("Bridge event: {}", line_str.replace(ticket, "<redacted>")),
                     }
                 }
             }
@@ -516,17 +936,50 @@ pub async fn run_game(
     }
 
     let status = child.wait().await?;
+    if let Some(task) = stderr_task {
+        let _ = task.await;
+    }
     info!("Game bridge exited with status: {:?}", status);
+    Ok(status)
+}
 
-    GAME_RUNNING.store(false, Ordering::SeqCst);
-    let _ = GAME_STATUS_SENDER.send(false);
+/// Marks the first client as stopped when dropped, including when the
+/// launch future is dropped early (a timed-out launch), and on Windows ends
+/// the IPC window's message loop.
+struct RunningGuard;
 
-    Ok(status)
+impl Drop for RunningGuard {
+    fn drop(&mut self) {
+        GAME_PID.store(0, Ordering::SeqCst);
+        GAME_RUNNING.store(false, Ordering::SeqCst);
+        let _ = GAME_STATUS_SENDER.send(false);
+        info!("Game status set to not running");
+
+        #[cfg(windows)]
+        {
+            SERVER_LIST_SENDER.lock().unwrap().take();
+            if let Ok(handle) = WINDOW_HANDLE.lock() {
+                if let Some(safe_hwnd) = *handle {
+                    let hwnd = safe_hwnd.get();
+                    unsafe {
+                        PostMessageW(hwnd, WM_GAME_EXITED, 0, 0);
+                    }
+                } else {
+                    error!("Window handle not found when trying to post WM_GAME_EXITED message");
+                }
+            } else {
+                error!("Failed to acquire lock on WINDOW_HANDLE");
+            }
+        }
+    }
 }
 
 /// Windows-only: Launches the game and handles the game process lifecycle.
 #[cfg(windows)]
-async fn launch_game() -> Result<ExitStatus, Box<dyn std::error::Error>> {
+async fn launch_game(
+    extra_args: Vec<String>,
+    env: Vec<(String, String)>,
+) -> Result<ExitStatus, Box<dyn std::error::Error>> {
     if GAME_RUNNING.load(Ordering::SeqCst) {
         return Err("Game is already running".into());
     }
@@ -534,6 +987,7 @@ async fn launch_game() -> Result<ExitStatus, Box<dyn std::error::Error>> {
     GAME_RUNNING.store(true, Ordering::SeqCst);
     GAME_STATUS_SENDER.send(true).unwrap();
     info!("Game status set to running");
+    let running = RunningGuard;
 
     if cfg!(debug_assertions) {
         info!(
@@ -553,7 +1007,8 @@ async fn launch_game() -> Result<ExitStatus, Box<dyn std::error::Error>> {
     let handle =
         tokio::task::spawn_blocking(move || unsafe { create_and_run_game_window(tcs_clone) });
 
-    tokio::spawn(async move {
+    // Ends once the guard drops the sender.
+    tokio::task::spawn_blocking(move || {
         while let Ok((w_param, sender)) = rx.recv() {
             unsafe {
                 handle_server_list_request(w_param, sender);
@@ -565,47 +1020,77 @@ async fn launch_game() -> Result<ExitStatus, Box<dyn std::error::Error>> {
 
     // If window creation failed, WINDOW_HANDLE will still be None.
     // Abort cleanly rather than spawning TERA.exe without an IPC window.
-    {
-        let handle_guard = WINDOW_HANDLE.lock().unwrap();
-        if handle_guard.is_none() {
-            GAME_RUNNING.store(false, Ordering::SeqCst);
-            let _ = GAME_STATUS_SENDER.send(false);
-            return Err("Failed to create launcher IPC window — TERA.exe was not started. \
-                        Check logs for the Win32 error code.".into());
-        }
+    if WINDOW_HANDLE.lock().unwrap().is_none() {
+        return Err("Failed to create launcher IPC window — TERA.exe was not started. \
+                    Check logs for the Win32 error code.".into());
     }
 
     // Clear previous stderr before each launch
     if let Ok(mut s) = LAST_GAME_STDERR.lock() { s.clear(); }
+    LAST_GAME_OUTPUT.lock().unwrap().clear();
+
+    let mut game_args = vec![format!(
+        "-LANGUAGEEXT={}",
+        GLOBAL_CREDENTIALS.get_game_lang()
+    )];
+    game_args.extend(extra_args);
+    log_game_command_line(
+        &GLOBAL_CREDENTIALS.get_game_path(),
+        &game_args,
+        &GLOBAL_CREDENTIALS.get_ticket(),
+    );
+
+    log_game_env(&env);
+    let status = spawn_and_wait(&GLOBAL_CREDENTIALS.get_game_path(), &game_args, &env).await;
+    match &status {
+        Ok(status) => info!("Game process exited with status: {:?}", status),
+        Err(e) => error!("Game process failed: {}", e),
+    }
+
+    drop(running);
+    handle.await?;
+
+    status
+}
 
-    let mut child = Command::new(GLOBAL_CREDENTIALS.get_game_path())
-        .arg(format!(
-            "-LANGUAGEEXT={}",
-            GLOBAL_CREDENTIALS.get_game_lang()
-        ))
+/// Windows-only: Spawns Tera.exe and waits for it, keeping its stderr. The
+/// process is killed if the future is dropped.
+#[cfg(windows)]
+async fn spawn_and_wait(
+    game_path: &str,
+    game_args: &[String],
+    env: &[(String, String)],
+) -> Result<ExitStatus, Box<dyn std::error::Error>> {
+    let capture = CAPTURE_OUTPUT.load(Ordering::SeqCst);
+    let mut command = tokio::process::Command::new(game_path);
+    command
+        .args(game_args)
+        .envs(env.iter().map(|(name, value)| (name, value)))
         .stderr(std::process::Stdio::piped())
-        .spawn()?;
+        .kill_on_drop(true);
+    if capture {
+        command.stdout(std::process::Stdio::piped());
+    }
+    let mut child = command.spawn()?;
 
-    let pid = child.id();
+    let pid = child.id().unwrap_or(0);
+    GAME_PID.store(pid, Ordering::SeqCst);
     info!("Game process spawned with PID: {}", pid);
 
-    // Read TERA.exe stderr in a background thread so it doesn't block child.wait().
     // TERA writes human-readable crash info (CrashAddress=, ExceptionCode=, etc.) to stderr.
-    let stderr_thread = child.stderr.take().map(|stderr| {
-        std::thread::spawn(move || {
-            use std::io::Read;
-            let mut output = String::new();
-            let _ = std::io::BufReader::new(stderr).read_to_string(&mut output);
-            output
-        })
-    });
+    let log = |capture: bool| capture.then(|| GameOutputLog::new("[game]".to_string(), true));
+    let stderr_task = child.stderr.take().map(|stderr| spawn_output_reader(stderr, log(capture), true));
+    let stdout_task = child.stdout.take().map(|stdout| spawn_output_reader(stdout, log(true), false));
 
-    let status = child.wait()?;
-    info!("Game process exited with status: {:?}", status);
+    let status = child.wait().await?;
+    if let Some(task) = stdout_task {
+        let _ = task.await;
+    }
 
-    let stderr_output = stderr_thread
-        .and_then(|h| h.join().ok())
-        .unwrap_or_default();
+    let stderr_output = match stderr_task {
+        Some(task) => task.await.unwrap_or_default(),
+        None => String::new(),
+    };
     if !stderr_output.is_empty() {
         info!("Captured {} bytes from TERA.exe stderr", stderr_output.len());
         if let Ok(mut s) = LAST_GAME_STDERR.lock() {
@@ -613,25 +1098,92 @@ async fn launch_game() -> Result<ExitStatus, Box<dyn std::error::Error>> {
         }
     }
 
-    GAME_RUNNING.store(false, Ordering::SeqCst);
-    GAME_STATUS_SENDER.send(false).unwrap();
-    info!("Game status set to not running");
+    Ok(status)
+}
 
-    if let Ok(handle) = WINDOW_HANDLE.lock() {
-        if let Some(safe_hwnd) = *handle {
-            let hwnd = safe_hwnd.get();
-            unsafe {
-                PostMessageW(hwnd, WM_GAME_EXITED, 0, 0);
+/// Whether the current process runs elevated. Always false outside Windows.
+pub fn is_elevated() -> bool {
+    #[cfg(windows)]
+    unsafe {
+        use winapi::um::{
+            processthreadsapi::{GetCurrentProcess, OpenProcessToken},
+            securitybaseapi::GetTokenInformation,
+            winnt::{TokenElevation, HANDLE, TOKEN_ELEVATION, TOKEN_QUERY},
+        };
+
+        let mut token: HANDLE = std::ptr::null_mut();
+        if OpenProcessToken(GetCurrentProcess(), TOKEN_QUERY, &mut token) == 0 {
+            return false;
+        }
+        let mut elevation: TOKEN_ELEVATION = std::mem::zeroed();
+        let mut size = 0;
+        let ok = GetTokenInformation(
+            token,
+            TokenElevation,
+            &mut elevation as *mut _ as *mut _,
+            std::mem::size_of::<TOKEN_ELEVATION>() as u32,
+            &mut size,
+        );
+        CloseHandle(token);
+        ok != 0 && elevation.TokenIsElevated != 0
+    }
+    #[cfg(not(windows))]
+    false
+}
+
+/// Starts a new, elevated copy of the running executable with the same
+/// arguments, through ShellExecuteEx and its `runas` verb. The launcher and
+/// the game it starts then share one integrity level, so the game's
+/// WM_COPYDATA messages still reach the IPC window. Returns false when the
+/// UAC prompt was declined; the caller exits when it returns true.
+pub fn relaunch_elevated() -> Result<bool, Box<dyn std::error::Error>> {
+    #[cfg(windows)]
+    {
+        use winapi::shared::winerror::ERROR_CANCELLED;
+
+        let exe = std::env::current_exe()?;
+        let verb = to_wstring("runas");
+        let file = to_wstring(&exe.to_string_lossy());
+        let parameters = to_wstring(
+            &std::env::args()
+                .skip(1)
+                .map(|arg| quote_windows_arg(&arg))
+                .collect::<Vec<_>>()
+                .join(" "),
+        );
+
+        unsafe {
+            let mut info: SHELLEXECUTEINFOW = std::mem::zeroed();
+            info.cbSize = std::mem::size_of::<SHELLEXECUTEINFOW>() as u32;
+            info.lpVerb = verb.as_ptr();
+            info.lpFile = file.as_ptr();
+            info.lpParameters = parameters.as_ptr();
+            info.nShow = SW_SHOWNORMAL;
+
+            if ShellExecuteExW(&mut info) == 0 {
+                let error_code = GetLastError();
+                if error_code == ERROR_CANCELLED {
+                    warn!("Elevated restart declined at the UAC prompt");
+                    return Ok(false);
+                }
+                return Err(format!("Failed to restart elevated (error {})", error_code).into());
             }
-        } else {
-            error!("Window handle not found when trying to post WM_GAME_EXITED message");
         }
-    } else {
-        error!("Failed to acquire lock on WINDOW_HANDLE");
+        info!("Started an elevated copy of the launcher");
+        Ok(true)
     }
-    handle.await?;
+    #[cfg(not(windows))]
+    Err("Elevation is only supported on Windows".into())
+}
 
-    Ok(status)
+/// Quotes a command-line argument for ShellExecuteEx when it contains
+/// spaces or quotes.
+#[cfg(windows)]
+fn quote_windows_arg(arg: &str) -> String {
+    if !arg.is_empty() && !arg.contains(|c: char| c.is_whitespace() || c == '"') {
+        return arg.to_string();
+    }
+    format!("\"{}\"", arg.replace('"', "\\\""))
 }
 
 /// Converts a Rust string slice to a null-terminated wide string (UTF-16).
@@ -671,6 +1223,596 @@ pub fn is_game_running() -> bool {
     GAME_RUNNING.load(Ordering::SeqCst)
 }
 
+/// Returns the PID of the running game process, if one was spawned.
+pub fn get_game_pid() -> Option<u32> {
+    match GAME_PID.load(Ordering::SeqCst) {
+        0 => None,
+        pid => Some(pid),
+    }
+}
+
+/// Returns the PID of an extra client's Tera.exe (its bridge process outside
+/// Windows), once it is spawned.
+pub fn get_session_pid(session_id: u64) -> Option<u32> {
+    EXTRA_SESSIONS
+        .lock()
+        .unwrap()
+        .get(&session_id)
+        .copied()
+        .filter(|&pid| pid != 0)
+}
+
+/// How `terminate_game` dealt with a game process.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TerminationOutcome {
+    /// The game closed on its own after being asked to.
+    ClosedGracefully,
+    /// The game ignored the request and was terminated.
+    Killed,
+    /// Processes the game had started were still running and were killed
+    /// too.
+    KilledTree,
+    NotFound,
+    /// Nothing was done; see `TerminationReport::children`.
+    DryRun,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct TerminationReport {
+    pub outcome: TerminationOutcome,
+    pub pid: Option<u32>,
+    /// Descendants of the game process (crash handler, anti-cheat helper...)
+    /// when termination started, which are killed if they outlive it.
+    pub children: Vec<u32>,
+}
+
+impl TerminationReport {
+    fn not_found() -> Self {
+        TerminationReport { outcome: TerminationOutcome::NotFound, pid: None, children: Vec::new() }
+    }
+}
+
+/// Terminates the running game process and its children.
+///
+/// The game is first asked to close (WM_CLOSE to its windows on Windows,
+/// SIGTERM elsewhere); if it is still alive after `grace_period`, it is
+/// terminated, and the processes it started that outlive it are killed.
+/// `run_game` then returns as if the game had exited on its own. With
+/// `dry_run` nothing is done and the report lists what would be closed.
+///
+/// # Returns
+///
+/// How the game was closed. When no game is tracked the outcome is
+/// `NotFound` and the global state is reset, so a launcher stuck on "Game is
+/// already running" recovers.
+pub async fn terminate_game(
+    grace_period: StdDuration,
+    dry_run: bool,
+) -> Result<TerminationReport, Box<dyn std::error::Error>> {
+    let Some(pid) = get_game_pid() else {
+        warn!("terminate_game: no game process is tracked");
+        if !dry_run {
+            reset_global_state();
+        }
+        return Ok(TerminationReport::not_found());
+    };
+    terminate_process(pid, grace_period, dry_run, || get_game_pid() == Some(pid)).await
+}
+
+/// Terminates an extra client started by `run_additional_client`, the same
+/// way as `terminate_game`.
+pub async fn terminate_session(
+    session_id: u64,
+    grace_period: StdDuration,
+    dry_run: bool,
+) -> Result<TerminationReport, Box<dyn std::error::Error>> {
+    let Some(pid) = get_session_pid(session_id) else {
+        warn!("terminate_session: session {} has no process", session_id);
+        return Ok(TerminationReport::not_found());
+    };
+    terminate_process(pid, grace_period, dry_run, || get_session_pid(session_id) == Some(pid)).await
+}
+
+/// The escalation behind `terminate_game`: close request, then
+/// `TerminateProcess` (SIGKILL outside Windows) once `still_running` has
+/// held for `grace_period`, then the lingering descendants.
+async fn terminate_process(
+    pid: u32,
+    grace_period: StdDuration,
+    dry_run: bool,
+    still_running: impl Fn() -> bool,
+) -> Result<TerminationReport, Box<dyn std::error::Error>> {
+    let children = process_descendants(pid);
+    if dry_run {
+        info!(
+            "Dry run: would close game process {} and kill {} child process(es) {:?}",
+            pid,
+            children.len(),
+            children
+        );
+        return Ok(TerminationReport { outcome: TerminationOutcome::DryRun, pid: Some(pid), children });
+    }
+
+    // Captured now so a child that exits during the grace period can't be
+    // mistaken for an unrelated process that later reuses its PID.
+    let tracked: Vec<TrackedProcess> = children.iter().filter_map(|&child| TrackedProcess::open(child)).collect();
+
+    info!("Asking game process {} to close", pid);
+    request_close(pid);
+    let deadline = std::time::Instant::now() + grace_period;
+    while still_running() && std::time::Instant::now() < deadline {
+        tokio::time::sleep(StdDuration::from_millis(250)).await;
+    }
+
+    let mut outcome = if !still_running() {
+        info!("Game process {} closed", pid);
+        TerminationOutcome::ClosedGracefully
+    } else {
+        warn!("Game process {} did not close within {:?}, terminating it", pid, grace_period);
+        kill_process(pid)?;
+        info!("Game process {} terminated", pid);
+        TerminationOutcome::Killed
+    };
+
+    let lingering: Vec<&TrackedProcess> = tracked.iter().filter(|child| child.is_alive()).collect();
+    if !lingering.is_empty() {
+        let pids: Vec<u32> = lingering.iter().map(|child| child.pid).collect();
+        warn!("Killing {} lingering child process(es) of {}: {:?}", lingering.len(), pid, pids);
+        for child in lingering {
+            if let Err(e) = child.kill() {
+                warn!("Failed to kill child process {}: {}", child.pid, e);
+            }
+        }
+        outcome = TerminationOutcome::KilledTree;
+    }
+
+    Ok(TerminationReport { outcome, pid: Some(pid), children })
+}
+
+/// Asks a process to close: WM_CLOSE to its top-level windows on Windows,
+/// SIGTERM elsewhere.
+fn request_close(pid: u32) {
+    #[cfg(windows)]
+    unsafe {
+        unsafe extern "system" fn close_window(hwnd: HWND, lparam: LPARAM) -> BOOL {
+            let search = &mut *(lparam as *mut (u32, u32));
+            let mut window_pid = 0;
+            GetWindowThreadProcessId(hwnd, &mut window_pid);
+            if window_pid == search.0 && GetWindow(hwnd, GW_OWNER).is_null() {
+                PostMessageW(hwnd, WM_CLOSE, 0, 0);
+                search.1 += 1;
+            }
+            TRUE
+        }
+
+        let mut search = (pid, 0u32);
+        EnumWindows(Some(close_window), &mut search as *mut (u32, u32) as LPARAM);
+        if search.1 == 0 {
+            warn!("Game process {} has no window to close", pid);
+        }
+    }
+    #[cfg(not(windows))]
+    {
+        let _ = std::process::Command::new("kill").args(["-TERM", &pid.to_string()]).status();
+    }
+}
+
+fn kill_process(pid: u32) -> Result<(), Box<dyn std::error::Error>> {
+    #[cfg(windows)]
+    unsafe {
+        use winapi::um::{processthreadsapi::{OpenProcess, TerminateProcess}, winnt::PROCESS_TERMINATE};
+
+        let process = OpenProcess(PROCESS_TERMINATE, 0, pid);
+        if process.is_null() {
+            return Err(format!("Failed to open process {} (error {})", pid, GetLastError()).into());
+        }
+        let result = TerminateProcess(process, 1);
+        let error_code = GetLastError();
+        CloseHandle(process);
+        if result == 0 {
+            return Err(format!("TerminateProcess failed for process {} (error {})", pid, error_code).into());
+        }
+        Ok(())
+    }
+    #[cfg(not(windows))]
+    {
+        let status = std::process::Command::new("kill").args(["-KILL", &pid.to_string()]).status()?;
+        if !status.success() {
+            return Err(format!("Failed to kill process {}: {}", pid, status).into());
+        }
+        Ok(())
+    }
+}
+
+/// A process that can be checked and killed later without hitting an
+/// unrelated one that reused its PID: on Windows the open handle keeps the
+/// PID from being reused, elsewhere the start time recorded when it was
+/// opened has to still match.
+struct TrackedProcess {
+    pid: u32,
+    #[cfg(windows)]
+    handle: winapi::um::winnt::HANDLE,
+    #[cfg(not(windows))]
+    start_time: u64,
+}
+
+// The handle is only used through thread-safe process APIs.
+#[cfg(windows)]
+unsafe impl Send for TrackedProcess {}
+
+impl TrackedProcess {
+    fn open(pid: u32) -> Option<Self> {
+        #[cfg(windows)]
+        unsafe {
+            use winapi::um::{
+                processthreadsapi::OpenProcess,
+                winnt::{PROCESS_QUERY_LIMITED_INFORMATION, PROCESS_TERMINATE},
+            };
+
+            let handle = OpenProcess(PROCESS_TERMINATE | PROCESS_QUERY_LIMITED_INFORMATION, 0, pid);
+            (!handle.is_null()).then_some(TrackedProcess { pid, handle })
+        }
+        #[cfg(not(windows))]
+        {
+            proc_start_time(pid).map(|start_time| TrackedProcess { pid, start_time })
+        }
+    }
+
+    fn is_alive(&self) -> bool {
+        #[cfg(windows)]
+        unsafe {
+            use winapi::um::{minwinbase::STILL_ACTIVE, processthreadsapi::GetExitCodeProcess};
+
+            let mut exit_code = 0;
+            GetExitCodeProcess(self.handle, &mut exit_code) != 0 && exit_code == STILL_ACTIVE
+        }
+        #[cfg(not(windows))]
+        {
+            // Zombies are dead, just not reaped yet.
+            proc_start_time(self.pid) == Some(self.start_time)
+                && proc_stat(self.pid).is_some_and(|(state, _)| state != 'Z')
+        }
+    }
+
+    fn kill(&self) -> Result<(), Box<dyn std::error::Error>> {
+        #[cfg(windows)]
+        unsafe {
+            use winapi::um::processthreadsapi::TerminateProcess;
+
+            if TerminateProcess(self.handle, 1) == 0 {
+                return Err(format!("TerminateProcess failed for process {} (error {})", self.pid, GetLastError()).into());
+            }
+            Ok(())
+        }
+        #[cfg(not(windows))]
+        {
+            if !self.is_alive() {
+                return Ok(());
+            }
+            kill_process(self.pid)
+        }
+    }
+}
+
+#[cfg(windows)]
+impl Drop for TrackedProcess {
+    fn drop(&mut self) {
+        unsafe {
+            CloseHandle(self.handle);
+        }
+    }
+}
+
+/// Start time of a process from /proc/<pid>/stat, in clock ticks since boot.
+#[cfg(not(windows))]
+fn proc_start_time(pid: u32) -> Option<u64> {
+    let stat = std::fs::read_to_string(format!("/proc/{}/stat", pid)).ok()?;
+    stat[stat.rfind(')')? + 1..].split_whitespace().nth(19)?.parse().ok()
+}
+
+/// State and parent PID from /proc/<pid>/stat.
+#[cfg(not(windows))]
+fn proc_stat(pid: u32) -> Option<(char, u32)> {
+    let stat = std::fs::read_to_string(format!("/proc/{}/stat", pid)).ok()?;
+    // The command name can contain spaces and parentheses; the fields
+    // after it can't.
+    let mut fields = stat[stat.rfind(')')? + 1..].split_whitespace();
+    let state = fields.next()?.chars().next()?;
+    let parent = fields.next()?.parse().ok()?;
+    Some((state, parent))
+}
+
+/// PID, parent PID and executable name of every process.
+fn process_list() -> Vec<(u32, u32, String)> {
+    #[cfg(windows)]
+    unsafe {
+        use winapi::um::tlhelp32::{
+            CreateToolhelp32Snapshot, Process32FirstW, Process32NextW, PROCESSENTRY32W, TH32CS_SNAPPROCESS,
+        };
+
+        let snapshot = CreateToolhelp32Snapshot(TH32CS_SNAPPROCESS, 0);
+        if snapshot == winapi::um::handleapi::INVALID_HANDLE_VALUE {
+            return Vec::new();
+        }
+        let mut processes = Vec::new();
+        let mut entry: PROCESSENTRY32W = std::mem::zeroed();
+        entry.dwSize = std::mem::size_of::<PROCESSENTRY32W>() as u32;
+        let mut more = Process32FirstW(snapshot, &mut entry) != 0;
+        while more {
+            let name_len = entry.szExeFile.iter().position(|&c| c == 0).unwrap_or(entry.szExeFile.len());
+            processes.push((
+                entry.th32ProcessID,
+                entry.th32ParentProcessID,
+                String::from_utf16_lossy(&entry.szExeFile[..name_len]),
+            ));
+            more = Process32NextW(snapshot, &mut entry) != 0;
+        }
+        CloseHandle(snapshot);
+        processes
+    }
+    #[cfg(not(windows))]
+    {
+        let Ok(entries) = std::fs::read_dir("/proc") else { return Vec::new() };
+        entries
+            .flatten()
+            .filter_map(|entry| {
+                let pid: u32 = entry.file_name().to_str()?.parse().ok()?;
+                let (_, parent) = proc_stat(pid)?;
+                let name = std::fs::read_to_string(entry.path().join("comm")).unwrap_or_default();
+                Some((pid, parent, name.trim_end().to_string()))
+            })
+            .collect()
+    }
+}
+
+/// Every process started, directly or not, by `pid`.
+fn process_descendants(pid: u32) -> Vec<u32> {
+    let processes = process_list();
+    let mut descendants = Vec::new();
+    let mut queue = vec![pid];
+    while let Some(parent) = queue.pop() {
+        for &(child, child_parent, _) in &processes {
+            if child_parent == parent && child != pid && !descendants.contains(&child) {
+                descendants.push(child);
+                queue.push(child);
+            }
+        }
+    }
+    descendants
+}
+
+/// Looks for a Tera.exe started from `game_dir` outside the launcher, e.g.
+/// from another shortcut, and returns its PID. The game the launcher runs
+/// itself is skipped.
+pub fn find_external_game_process(game_dir: &std::path::Path) -> Option<u32> {
+    let own_pid = get_game_pid();
+    let game_dir = game_dir.to_string_lossy().replace('\\', "/").to_lowercase();
+    let game_dir = game_dir.trim_end_matches('/');
+    let in_game_dir = |exe_path: &str| {
+        let exe_path = exe_path.replace('\\', "/").to_lowercase();
+        // Under Wine the path is seen through the Z: drive.
+        let exe_path = exe_path.strip_prefix("z:").unwrap_or(&exe_path);
+        exe_path.ends_with("/tera.exe") && exe_path.starts_with(&format!("{}/", game_dir))
+    };
+
+    #[cfg(windows)]
+    unsafe {
+        use winapi::um::{
+            processthreadsapi::OpenProcess, winbase::QueryFullProcessImageNameW,
+            winnt::PROCESS_QUERY_LIMITED_INFORMATION,
+        };
+
+        process_list().into_iter().find_map(|(pid, _, name)| {
+            if !name.eq_ignore_ascii_case("tera.exe") || Some(pid) == own_pid {
+                return None;
+            }
+            let process = OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, 0, pid);
+            if process.is_null() {
+                return None;
+            }
+            let mut path = [0u16; 1024];
+            let mut path_len = path.len() as u32;
+            let ok = QueryFullProcessImageNameW(process, 0, path.as_mut_ptr(), &mut path_len);
+            CloseHandle(process);
+            (ok != 0 && in_game_dir(&String::from_utf16_lossy(&path[..path_len as usize]))).then_some(pid)
+        })
+    }
+    #[cfg(not(windows))]
+    {
+        std::fs::read_dir("/proc").ok()?.flatten().find_map(|entry| {
+            let pid: u32 = entry.file_name().to_str()?.parse().ok()?;
+            if Some(pid) == own_pid {
+                return None;
+            }
+            let cmdline = std::fs::read(entry.path().join("cmdline")).ok()?;
+            cmdline
+                .split(|&b| b == 0)
+                .map(String::from_utf8_lossy)
+                .any(|arg| in_game_dir(&arg))
+                .then_some(pid)
+        })
+    }
+}
+
+/// Whether a process has a visible top-level window yet, i.e. the game got
+/// past its startup. `None` where that can't be told: outside Windows the
+/// tracked PID is Wine's, not the game's.
+pub fn has_visible_window(pid: u32) -> Option<bool> {
+    #[cfg(windows)]
+    {
+        Some(find_main_window(pid).is_some())
+    }
+    #[cfg(not(windows))]
+    {
+        let _ = pid;
+        None
+    }
+}
+
+/// The first visible, unowned top-level window of `pid`.
+#[cfg(windows)]
+fn find_main_window(pid: u32) -> Option<HWND> {
+    unsafe extern "system" fn check_window(hwnd: HWND, lparam: LPARAM) -> BOOL {
+        let search = &mut *(lparam as *mut (u32, HWND));
+        let mut window_pid = 0;
+        GetWindowThreadProcessId(hwnd, &mut window_pid);
+        if window_pid == search.0
+            && IsWindowVisible(hwnd) != 0
+            && GetWindow(hwnd, GW_OWNER).is_null()
+        {
+            search.1 = hwnd;
+            return 0;
+        }
+        TRUE
+    }
+
+    let mut search: (u32, HWND) = (pid, null_mut());
+    unsafe {
+        EnumWindows(Some(check_window), &mut search as *mut (u32, HWND) as LPARAM);
+    }
+    (!search.1.is_null()).then_some(search.1)
+}
+
+/// Restores the main window of `pid` and brings it to the foreground.
+/// Returns `false` when the process has no window yet. Windows only.
+pub fn focus_process_window(pid: u32) -> Result<bool, Box<dyn std::error::Error>> {
+    #[cfg(windows)]
+    unsafe {
+        use winapi::um::processthreadsapi::GetCurrentThreadId;
+
+        let Some(hwnd) = find_main_window(pid) else {
+            return Ok(false);
+        };
+        if IsIconic(hwnd) != 0 {
+            ShowWindow(hwnd, SW_RESTORE);
+        }
+        // The launcher is normally in the foreground when this runs, which
+        // lets it hand the foreground over.
+        AllowSetForegroundWindow(pid);
+        if SetForegroundWindow(hwnd) == 0 {
+            // Otherwise borrow the foreground thread's input state, the
+            // one pattern the foreground lock still allows.
+            let foreground_thread =
+                GetWindowThreadProcessId(GetForegroundWindow(), null_mut());
+            let current_thread = GetCurrentThreadId();
+            AttachThreadInput(current_thread, foreground_thread, TRUE);
+            BringWindowToTop(hwnd);
+            let focused = SetForegroundWindow(hwnd);
+            AttachThreadInput(current_thread, foreground_thread, 0);
+            if focused == 0 {
+                return Err(format!(
+                    "Windows refused to bring the game window of process {} forward",
+                    pid
+                )
+                .into());
+            }
+        }
+        info!("Brought the window of process {} to the foreground", pid);
+        Ok(true)
+    }
+    #[cfg(not(windows))]
+    {
+        let _ = pid;
+        Err("Focusing the game window is only supported on Windows".into())
+    }
+}
+
+/// Scheduling priority for the game process: the Windows priority classes
+/// short of realtime.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProcessPriority {
+    Idle,
+    BelowNormal,
+    Normal,
+    AboveNormal,
+    High,
+}
+
+impl ProcessPriority {
+    /// Parses the snake_case names used by the `process_priority` setting.
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "idle" => Some(Self::Idle),
+            "below_normal" => Some(Self::BelowNormal),
+            "normal" => Some(Self::Normal),
+            "above_normal" => Some(Self::AboveNormal),
+            "high" => Some(Self::High),
+            _ => None,
+        }
+    }
+}
+
+/// Sets the priority class of a running process. Windows only; the change
+/// lasts as long as the process.
+pub fn set_process_priority(pid: u32, priority: ProcessPriority) -> Result<(), Box<dyn std::error::Error>> {
+    #[cfg(windows)]
+    unsafe {
+        use winapi::um::{
+            processthreadsapi::{OpenProcess, SetPriorityClass},
+            winbase::{
+                ABOVE_NORMAL_PRIORITY_CLASS, BELOW_NORMAL_PRIORITY_CLASS, HIGH_PRIORITY_CLASS,
+                IDLE_PRIORITY_CLASS, NORMAL_PRIORITY_CLASS,
+            },
+            winnt::PROCESS_SET_INFORMATION,
+        };
+
+        let class = match priority {
+            ProcessPriority::Idle => IDLE_PRIORITY_CLASS,
+            ProcessPriority::BelowNormal => BELOW_NORMAL_PRIORITY_CLASS,
+            ProcessPriority::Normal => NORMAL_PRIORITY_CLASS,
+            ProcessPriority::AboveNormal => ABOVE_NORMAL_PRIORITY_CLASS,
+            ProcessPriority::High => HIGH_PRIORITY_CLASS,
+        };
+        let process = OpenProcess(PROCESS_SET_INFORMATION, 0, pid);
+        if process.is_null() {
+            return Err(format!("Failed to open process {} (error {})", pid, GetLastError()).into());
+        }
+        let result = SetPriorityClass(process, class);
+        let error_code = GetLastError();
+        CloseHandle(process);
+        if result == 0 {
+            return Err(format!("SetPriorityClass failed for process {} (error {})", pid, error_code).into());
+        }
+        Ok(())
+    }
+    #[cfg(not(windows))]
+    {
+        let _ = (pid, priority);
+        Err("Process priority is only supported on Windows".into())
+    }
+}
+
+/// Restricts a running process to the logical cores set in `mask` (bit 0 =
+/// core 0). Windows only; the change lasts as long as the process.
+pub fn set_process_affinity(pid: u32, mask: u64) -> Result<(), Box<dyn std::error::Error>> {
+    #[cfg(windows)]
+    unsafe {
+        use winapi::um::{
+            processthreadsapi::OpenProcess,
+            winbase::SetProcessAffinityMask,
+            winnt::{PROCESS_QUERY_INFORMATION, PROCESS_SET_INFORMATION},
+        };
+
+        let process = OpenProcess(PROCESS_SET_INFORMATION | PROCESS_QUERY_INFORMATION, 0, pid);
+        if process.is_null() {
+            return Err(format!("Failed to open process {} (error {})", pid, GetLastError()).into());
+        }
+        let result = SetProcessAffinityMask(process, mask as usize);
+        let error_code = GetLastError();
+        CloseHandle(process);
+        if result == 0 {
+            return Err(format!("SetProcessAffinityMask failed for process {} (error {})", pid, error_code).into());
+        }
+        Ok(())
+    }
+    #[cfg(not(windows))]
+    {
+        let _ = (pid, mask);
+        Err("CPU affinity is only supported on Windows".into())
+    }
+}
+
 /// Resets the global state of the application.
 ///
 /// This function performs the following actions:
@@ -962,6 +2104,15 @@ unsafe fn send_response_message(
     info!("SendMessageW result: {}", result);
 }
 
+/// The account name and ticket of the extra client whose window is
+/// `recipient`, or `None` for the first client.
+#[cfg(windows)]
+unsafe fn extra_client_credentials(recipient: WPARAM) -> Option<(String, String)> {
+    let mut pid = 0;
+    GetWindowThreadProcessId(recipient as HWND, &mut pid);
+    CLIENT_CREDENTIALS.lock().unwrap().get(&pid).cloned()
+}
+
 /// Handles the account name request from the game client.
 ///
 /// This function retrieves the account name and sends it back to the game client.
@@ -976,7 +2127,10 @@ unsafe fn send_response_message(
 /// * `sender` - The sender's window handle as a HWND.
 #[cfg(windows)]
 unsafe fn handle_account_name_request(recipient: WPARAM, sender: HWND) {
-    let account_name = GLOBAL_CREDENTIALS.get_account_name();
+    let account_name = match extra_client_credentials(recipient) {
+        Some((account_name, _)) => account_name,
+        None => GLOBAL_CREDENTIALS.get_account_name(),
+    };
     if cfg!(debug_assertions) {
         info!("Account Name Request - Sending: {}", account_name);
     } else {
@@ -1004,7 +2158,10 @@ unsafe fn handle_account_name_request(recipient: WPARAM, sender: HWND) {
 /// * `sender` - The sender's window handle as a HWND.
 #[cfg(windows)]
 unsafe fn handle_session_ticket_request(recipient: WPARAM, sender: HWND) {
-    let session_ticket = GLOBAL_CREDENTIALS.get_ticket();
+    let session_ticket = match extra_client_credentials(recipient) {
+        Some((_, ticket)) => ticket,
+        None => GLOBAL_CREDENTIALS.get_ticket(),
+    };
     if cfg!(debug_assertions) {
         info!("Session Ticket Request - Sending: {}", session_ticket);
     } else {
@@ -1029,8 +2186,13 @@ unsafe fn handle_session_ticket_request(recipient: WPARAM, sender: HWND) {
 #[cfg(windows)]
 unsafe fn handle_server_list_request(recipient: WPARAM, sender: usize) {
     let runtime = Runtime::new().expect("Failed to create Tokio runtime");
-    let server_list_data =
-        runtime.block_on(async { get_server_list().await.expect("Failed to get server list") });
+    let server_list_data = match runtime.block_on(get_server_list()) {
+        Ok(data) => data,
+        Err(e) => {
+            error!("Failed to get server list: {}", e);
+            return;
+        }
+    };
     send_response_message(recipient, sender as HWND, 6, &server_list_data);
     info!("Game event 6 (LAUNCHER_GAME_EVENT_SERVER_LIST_RESPONSE) sended");
 }
@@ -1302,13 +2464,17 @@ unsafe fn handle_game_event(_recipient: WPARAM, _sender: HWND, event_id: usize,
 ///
 /// # Arguments
 ///
-/// * `_recipient` - The HWND of the recipient window as a WPARAM (unused).
+/// * `recipient` - The HWND of the game window as a WPARAM; extra clients are ignored.
 /// * `_sender` - The HWND of the sender window (unused).
 /// * `_payload` - The payload associated with the game exit event (unused).
 #[cfg(windows)]
-unsafe fn handle_game_exit(_recipient: WPARAM, _sender: HWND, payload: &[u8]) {
+unsafe fn handle_game_exit(recipient: WPARAM, _sender: HWND, payload: &[u8]) {
     let event_name = "LAUNCHER_GAME_EVENT_GAME_EXIT";
     info!("Game event 1020 ({}) received", event_name);
+    if extra_client_credentials(recipient).is_some() {
+        info!("Exit notification came from an additional client");
+        return;
+    }
 
     // LauncherGameExitNotification: { length: u32, code: u32, reason: u32 }
     // length must be 12; payload here is the COPYDATASTRUCT data (12 bytes).
@@ -1338,14 +2504,18 @@ unsafe fn handle_game_exit(_recipient: WPARAM, _sender: HWND, payload: &[u8]) {
 ///
 /// # Arguments
 ///
-/// * `_recipient` - The HWND of the recipient window as a WPARAM (unused).
+/// * `recipient` - The HWND of the game window as a WPARAM; extra clients are ignored.
 /// * `_sender` - The HWND of the sender window (unused).
 /// * `_payload` - The payload associated with the game crash event (unused).
 #[cfg(windows)]
-unsafe fn handle_game_crash(_recipient: WPARAM, _sender: HWND, payload: &[u8]) {
+unsafe fn handle_game_crash(recipient: WPARAM, _sender: HWND, payload: &[u8]) {
     let event_name = "LAUNCHER_GAME_EVENT_GAME_CRASH";
     error!("Game crash detected");
     info!("Game event 1021 ({}) received", event_name);
+    if extra_client_credentials(recipient).is_some() {
+        info!("Crash notification came from an additional client");
+        return;
+    }
 
     // LauncherGameCrashNotification: { details: u16string } (not NUL-terminated)
     let details = if payload.len() >= 2 && payload.len() % 2 == 0 {
@@ -1508,7 +2678,11 @@ fn on_world_entered(world_name: &str) {
 #[cfg(windows)]
 async fn get_server_list() -> Result<Vec<u8>, Box<dyn std::error::Error>> {
     let url = config::get_config_value("SERVER_LIST_URL");
-    let client = reqwest::Client::new();
+    let client = HTTP_CLIENT
+        .lock()
+        .unwrap()
+        .clone()
+        .ok_or("The HTTP client is not configured")?;
     let response = client
         .get(url)
         .timeout(Duration::from_secs(10))
@@ -1,6 +1,6 @@
 
 pub mod game;
 
-pub use game::{run_game, get_game_status_receiver, is_game_running, reset_global_state, setup_logging, TeraLogger, GameExitInfo, get_last_exit_info, get_last_crash_details, get_last_game_stderr};
+pub use game::{run_game, run_game_with_args, run_game_with_env, get_game_status_receiver, is_game_running, reset_global_state, setup_logging, TeraLogger, GameExitInfo, get_last_exit_info, get_last_crash_details, get_last_game_stderr, get_game_pid, terminate_game, TerminationOutcome, TerminationReport, run_additional_client, get_session_pid, terminate_session, is_elevated, relaunch_elevated, ProcessPriority, set_process_priority, set_process_affinity, has_visible_window, focus_process_window, set_capture_output, set_http_client, get_last_game_output, find_external_game_process};
 pub mod global_credentials;
 pub mod config;
\ No newline at end of file